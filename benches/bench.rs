@@ -1,11 +1,11 @@
 #![feature(test)]
 
+extern crate convida;
 extern crate test;
-extern crate wasm_game_of_life;
 
 #[bench]
 fn universe_ticks(b: &mut test::Bencher) {
-    let mut universe = wasm_game_of_life::Universe::new();
+    let mut universe = convida::Universe::new();
 
     b.iter(|| {
         universe.tick();
@@ -11,6 +11,12 @@ i=1
 for target in "${targets[@]}"
 do
     echo "Building $i/${#targets[@]}: $target"
-    cargo build --target="$target" --release
+    case "$target" in
+        # No JavaScript host under WASI, so build the plain Rust library and
+        # the command line runner instead of the wasm-bindgen exports.
+        wasm32-wasi*) features=(--no-default-features --features cli) ;;
+        *) features=() ;;
+    esac
+    cargo build --target="$target" --release "${features[@]}"
     ((i+=1))
 done
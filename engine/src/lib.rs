@@ -0,0 +1,150 @@
+//! The core of Convida: cells, rules and the tick, with no dependencies on
+//! `std`, an allocator or JavaScript.
+//!
+//! A board is a row-major slice of `Cell`s. `step` writes the next
+//! generation into a second slice of the same size, so the engine runs
+//! anywhere two buffers fit, such as a microcontroller driving an LED
+//! matrix:
+//!
+//! ```
+//! use convida_engine::{step, Boundary, Cell, LifeLike};
+//!
+//! let (width, height) = (5, 5);
+//! let mut cells = [Cell::Dead; 25];
+//! for &i in &[11, 12, 13] {
+//!     cells[i] = Cell::Alive;
+//! }
+//! let mut next = [Cell::Dead; 25];
+//! step(width, height, Boundary::Torus, &LifeLike::CONWAY, &cells, &mut next);
+//! assert_eq!(next[7], Cell::Alive);
+//! ```
+//!
+//! The `convida` crate builds its universes, storage engines and bindings
+//! on top of this one.
+
+#![no_std]
+
+#[cfg(feature = "wasm-bindgen")]
+use wasm_bindgen::prelude::*;
+
+mod rule;
+
+pub use rule::{Conway, Coupled, LayeredRule, LifeLike, ParseRuleError, Rule};
+
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cell {
+    Dead = 0,
+    Alive = 1,
+}
+
+impl Cell {
+    pub fn toggle(&mut self) {
+        *self = match *self {
+            Cell::Dead => Cell::Alive,
+            Cell::Alive => Cell::Dead,
+        };
+    }
+}
+
+/// What lies beyond the edges of the universe.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Boundary {
+    /// The edges wrap around to the opposite side.
+    Torus = 0,
+    /// Cells outside the universe are always dead.
+    Dead = 1,
+}
+
+/// The number of live cells among the eight neighbours of (`row`, `col`)
+/// on a `width` x `height` board.
+#[inline]
+pub fn neighbor_count(
+    cells: &[Cell],
+    width: u32,
+    height: u32,
+    boundary: Boundary,
+    row: u32,
+    col: u32,
+) -> u8 {
+    let index = |row: u32, col: u32| (row * width + col) as usize;
+
+    if boundary == Boundary::Dead {
+        let mut count = 0;
+        for r in row.saturating_sub(1)..=(row + 1).min(height - 1) {
+            for c in col.saturating_sub(1)..=(col + 1).min(width - 1) {
+                if (r, c) != (row, col) {
+                    count += cells[index(r, c)] as u8;
+                }
+            }
+        }
+        return count;
+    }
+
+    let mut count = 0;
+
+    let north = if row == 0 { height - 1 } else { row - 1 };
+
+    let south = if row == height - 1 { 0 } else { row + 1 };
+
+    let west = if col == 0 { width - 1 } else { col - 1 };
+
+    let east = if col == width - 1 { 0 } else { col + 1 };
+
+    count += cells[index(north, west)] as u8;
+    count += cells[index(north, col)] as u8;
+    count += cells[index(north, east)] as u8;
+    count += cells[index(row, west)] as u8;
+    count += cells[index(row, east)] as u8;
+    count += cells[index(south, west)] as u8;
+    count += cells[index(south, col)] as u8;
+    count += cells[index(south, east)] as u8;
+
+    count
+}
+
+/// Write the generation after `cells` into `next`. Both slices hold
+/// `width * height` cells.
+pub fn step<R: Rule + ?Sized>(
+    width: u32,
+    height: u32,
+    boundary: Boundary,
+    rule: &R,
+    cells: &[Cell],
+    next: &mut [Cell],
+) {
+    assert_eq!(cells.len(), (width * height) as usize);
+    assert_eq!(next.len(), cells.len());
+    for row in 0..height {
+        for col in 0..width {
+            let idx = (row * width + col) as usize;
+            let count = neighbor_count(cells, width, height, boundary, row, col);
+            next[idx] = rule.next_state(cells[idx], count);
+        }
+    }
+}
+
+/// `step` for one layer of a stack, with `other` the cells of the layer its
+/// rule looks at. All three slices hold `width * height` cells.
+pub fn step_layered<R: LayeredRule + ?Sized>(
+    width: u32,
+    height: u32,
+    boundary: Boundary,
+    rule: &R,
+    cells: &[Cell],
+    other: &[Cell],
+    next: &mut [Cell],
+) {
+    assert_eq!(cells.len(), (width * height) as usize);
+    assert_eq!(other.len(), cells.len());
+    assert_eq!(next.len(), cells.len());
+    for row in 0..height {
+        for col in 0..width {
+            let idx = (row * width + col) as usize;
+            let count = neighbor_count(cells, width, height, boundary, row, col);
+            next[idx] = rule.next_state_over(cells[idx], count, other[idx]);
+        }
+    }
+}
@@ -0,0 +1,175 @@
+// Rules deciding the next state of a cell from its neighbourhood.
+
+use core::fmt;
+use core::str::FromStr;
+
+use crate::Cell;
+
+pub trait Rule {
+    /// The state of a cell in the next generation given its `current`
+    /// state and the number of live cells among its eight neighbours.
+    fn next_state(&self, current: Cell, neighborhood: u8) -> Cell;
+}
+
+/// A rule for one layer of a stack of boards, which also sees the cell at
+/// the same place in another layer. Every `Rule` is one that ignores it.
+pub trait LayeredRule {
+    /// The state of a cell in the next generation given its `current`
+    /// state, the number of live cells among its eight neighbours and the
+    /// `other` layer's cell under it.
+    fn next_state_over(&self, current: Cell, neighborhood: u8, other: Cell) -> Cell;
+}
+
+impl<R: Rule + ?Sized> LayeredRule for R {
+    #[inline]
+    fn next_state_over(&self, current: Cell, neighborhood: u8, _other: Cell) -> Cell {
+        self.next_state(current, neighborhood)
+    }
+}
+
+/// A pair of outer-totalistic rules picked by the other layer's cell:
+/// `alive` over a live cell and `dead` over a dead one. With `B3/S23` and
+/// `B3/S`, cells only survive above live cells.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Coupled {
+    pub alive: LifeLike,
+    pub dead: LifeLike,
+}
+
+impl LayeredRule for Coupled {
+    #[inline]
+    fn next_state_over(&self, current: Cell, neighborhood: u8, other: Cell) -> Cell {
+        match other {
+            Cell::Alive => self.alive.next_state(current, neighborhood),
+            Cell::Dead => self.dead.next_state(current, neighborhood),
+        }
+    }
+}
+
+/// Conway's Game of Life, B3/S23.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Conway;
+
+impl Rule for Conway {
+    #[inline]
+    fn next_state(&self, current: Cell, neighborhood: u8) -> Cell {
+        match (current, neighborhood) {
+            // Rule 1: Any live cell with fewer than two neighbors
+            // dies, as if caused by underpopulation.
+            (Cell::Alive, x) if x < 2 => Cell::Dead,
+
+            // Rule 2: Any live cell with two or three live neighbors
+            // lives on to the next generations
+            (Cell::Alive, 2) | (Cell::Alive, 3) => Cell::Alive,
+
+            // Rule 3: Any live cell with more than three live
+            // neighbors dies, as if by overpopulation
+            (Cell::Alive, x) if x > 3 => Cell::Dead,
+
+            // Rule 4 :: Any dead cells with exactly three live nighbors
+            // becomes a live cell, as if by reproduction.
+            (Cell::Dead, 3) => Cell::Alive,
+
+            // All other cells remain in the same state.
+            (otherwise, _) => otherwise,
+        }
+    }
+}
+
+/// An outer-totalistic rule such as HighLife, `B36/S23`. Bit `n` of
+/// `birth` and `survival` is set when a cell is born or survives with `n`
+/// live neighbours.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LifeLike {
+    pub birth: u16,
+    pub survival: u16,
+}
+
+impl LifeLike {
+    pub const CONWAY: LifeLike = LifeLike {
+        birth: 1 << 3,
+        survival: 1 << 2 | 1 << 3,
+    };
+}
+
+impl Default for LifeLike {
+    fn default() -> Self {
+        LifeLike::CONWAY
+    }
+}
+
+impl Rule for LifeLike {
+    #[inline]
+    fn next_state(&self, current: Cell, neighborhood: u8) -> Cell {
+        let mask = match current {
+            Cell::Dead => self.birth,
+            Cell::Alive => self.survival,
+        };
+        if mask & (1 << neighborhood) != 0 {
+            Cell::Alive
+        } else {
+            Cell::Dead
+        }
+    }
+}
+
+/// A rule that isn't in B/S or S/B notation, or has a neighbour count
+/// above 8.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseRuleError;
+
+impl fmt::Display for ParseRuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid rule")
+    }
+}
+
+// Accepts `B36/S23`, `b36s23` and the older survival-first `23/36`.
+impl FromStr for LifeLike {
+    type Err = ParseRuleError;
+
+    fn from_str(text: &str) -> Result<LifeLike, ParseRuleError> {
+        let digits = |part: &str| -> Result<u16, ParseRuleError> {
+            part.chars().try_fold(0u16, |mask, c| match c.to_digit(10) {
+                Some(n) if n <= 8 => Ok(mask | 1 << n),
+                _ => Err(ParseRuleError),
+            })
+        };
+
+        let rule = text.trim();
+        if let Some(rest) = rule.strip_prefix(['B', 'b']) {
+            let (birth, survival) = match rest.find(['S', 's']) {
+                Some(i) => (&rest[..i], &rest[i + 1..]),
+                None => return Err(ParseRuleError),
+            };
+            let birth = birth.strip_suffix('/').unwrap_or(birth);
+            return Ok(LifeLike {
+                birth: digits(birth)?,
+                survival: digits(survival)?,
+            });
+        }
+
+        let mut parts = rule.splitn(2, '/');
+        match (parts.next(), parts.next()) {
+            (Some(survival), Some(birth)) => Ok(LifeLike {
+                birth: digits(birth)?,
+                survival: digits(survival)?,
+            }),
+            _ => Err(ParseRuleError),
+        }
+    }
+}
+
+impl fmt::Display for LifeLike {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = |f: &mut fmt::Formatter, mask: u16| {
+            (0..=8)
+                .filter(|n| mask & (1 << n) != 0)
+                .try_for_each(|n| write!(f, "{}", n))
+        };
+        write!(f, "B")?;
+        digits(f, self.birth)?;
+        write!(f, "/S")?;
+        digits(f, self.survival)
+    }
+}
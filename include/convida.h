@@ -0,0 +1,56 @@
+/* C interface to the convida Game of Life engine.
+ *
+ * Build the library with `cargo build --release --no-default-features
+ * --features capi` and link against target/release/libconvida.so (or the
+ * platform's equivalent). */
+
+#ifndef CONVIDA_H
+#define CONVIDA_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define CONVIDA_OK 0
+#define CONVIDA_ERR_NULL (-1)
+#define CONVIDA_ERR_UTF8 (-2)
+#define CONVIDA_ERR_PARSE (-3)
+#define CONVIDA_ERR_BOUNDS (-4)
+/* The edit would change a wall or go over the universe's limits. */
+#define CONVIDA_ERR_REFUSED (-5)
+
+typedef struct Universe ConvidaUniverse;
+
+/* A width x height universe with every cell dead, or NULL when empty or
+ * too large. */
+ConvidaUniverse *convida_universe_new(uint32_t width, uint32_t height);
+/* A width x height random soup generated from seed, or NULL when empty
+ * or too large. */
+ConvidaUniverse *convida_universe_new_random(uint32_t width, uint32_t height, uint64_t seed);
+void convida_universe_free(ConvidaUniverse *universe);
+
+void convida_universe_tick(ConvidaUniverse *universe, uint32_t generations);
+
+uint32_t convida_universe_width(const ConvidaUniverse *universe);
+uint32_t convida_universe_height(const ConvidaUniverse *universe);
+uint32_t convida_universe_generation(const ConvidaUniverse *universe);
+
+/* width * height bytes in row-major order, 1 for alive. Valid until the
+ * universe is next ticked, edited or freed. */
+const uint8_t *convida_universe_cells(const ConvidaUniverse *universe);
+
+/* Returns CONVIDA_OK, CONVIDA_ERR_NULL for a NULL universe,
+ * CONVIDA_ERR_BOUNDS outside the board or CONVIDA_ERR_REFUSED. */
+int convida_universe_set_cell(ConvidaUniverse *universe, uint32_t row, uint32_t col, int alive);
+/* Stamp a NUL-terminated RLE pattern with its top-left corner at row, col.
+ * Returns CONVIDA_OK, CONVIDA_ERR_NULL, CONVIDA_ERR_UTF8, CONVIDA_ERR_PARSE
+ * or CONVIDA_ERR_REFUSED. */
+int convida_universe_stamp_rle(ConvidaUniverse *universe, const char *rle, uint32_t row, uint32_t col);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,137 @@
+// The active region, for huge boards where the action is local.
+//
+// A cell can only change if it or one of its neighbours changed in the
+// generation before, so once the region holding the latest changes is
+// known, the next tick only has to visit that region and a margin around
+// it. A board that has settled into still lifes visits nothing at all.
+//
+// The window is only trusted for a tick under the universe's own rule,
+// from the very cells the last tick left, with the same boundary and with
+// no rule zones, walls, frozen regions or kernel. Anything else, such as an
+// edit, scans the whole board once and starts tracking again from there.
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::{Boundary, BoundingBox, Cell, LifeLike, Universe};
+
+#[derive(Clone, Debug)]
+pub(crate) struct ActiveRegion {
+    margin: u32,
+    /// The cells, rule and boundary the window was worked out for.
+    cells: Vec<Cell>,
+    rule: LifeLike,
+    boundary: Boundary,
+    /// The region the next tick has to visit, `None` for the whole board.
+    window: Option<BoundingBox>,
+}
+
+// Grow `changed` to hold (`row`, `col`). Cells are visited in reading
+// order, so the rows only ever grow downwards.
+pub(crate) fn include(changed: &mut Option<BoundingBox>, row: u32, col: u32) {
+    match changed {
+        Some(changed) => {
+            let end = (changed.col + changed.width).max(col + 1);
+            changed.col = changed.col.min(col);
+            changed.width = end - changed.col;
+            changed.height = row + 1 - changed.row;
+        }
+        None => {
+            *changed = Some(BoundingBox {
+                row,
+                col,
+                width: 1,
+                height: 1,
+            })
+        }
+    }
+}
+
+// Grow `changed` by `margin` cells on every side. On a torus a window
+// reaching past an edge covers that whole dimension instead of wrapping.
+fn grow(
+    changed: BoundingBox,
+    margin: u32,
+    width: u32,
+    height: u32,
+    boundary: Boundary,
+) -> BoundingBox {
+    let span = |start: u32, len: u32, max: u32| {
+        let end = start + len + margin;
+        if boundary == Boundary::Torus && (start < margin || end > max) {
+            (0, max)
+        } else {
+            let start = start.saturating_sub(margin);
+            (start, end.min(max) - start)
+        }
+    };
+    let (row, height) = span(changed.row, changed.height, height);
+    let (col, width) = span(changed.col, changed.width, width);
+    BoundingBox {
+        row,
+        col,
+        width,
+        height,
+    }
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Universe {
+    /// Only visit the cells near the latest changes on each tick, `margin`
+    /// cells around them, at least 1. The first tick scans the whole board.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = enableActiveRegion))]
+    pub fn enable_active_region(&mut self, margin: u32) {
+        self.active = Some(ActiveRegion {
+            margin: margin.max(1),
+            cells: Vec::new(),
+            rule: self.rule,
+            boundary: self.boundary,
+            window: None,
+        });
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = disableActiveRegion))]
+    pub fn disable_active_region(&mut self) {
+        self.active = None;
+    }
+
+    /// The region the next tick will visit, or `undefined` when it will
+    /// scan the whole board.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = activeWindow))]
+    pub fn active_window(&self) -> Option<BoundingBox> {
+        let active = self.active.as_ref()?;
+        let settled = active.rule == self.rule
+            && active.boundary == self.boundary
+            && active.cells == self.cells
+            && self.rule_zones.is_empty()
+            && self.walls.is_empty()
+            && self.frozen.is_empty()
+            && self.kernel.is_none();
+        if settled {
+            active.window
+        } else {
+            None
+        }
+    }
+}
+
+impl Universe {
+    // Called after a tick under `rule` that changed the cells of `changed`.
+    pub(crate) fn track_activity(&mut self, changed: Option<BoundingBox>, rule: LifeLike) {
+        let (width, height, boundary) = (self.width, self.height, self.boundary);
+        if let Some(active) = &mut self.active {
+            active.window = Some(match changed {
+                Some(changed) => grow(changed, active.margin, width, height, boundary),
+                None => BoundingBox {
+                    row: 0,
+                    col: 0,
+                    width: 0,
+                    height: 0,
+                },
+            });
+            active.cells.clone_from(&self.cells);
+            active.rule = rule;
+            active.boundary = boundary;
+        }
+    }
+}
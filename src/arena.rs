@@ -0,0 +1,250 @@
+// Glider-gun battles between two players.
+//
+// Player one builds in the left half of the arena and player two in the
+// right. Structures, such as guns and eaters, are what each side defends;
+// ships launched at the opponent are owned but not defended. The arena then
+// runs on dead boundaries with `Versus` ownership, so every glider a gun
+// fires belongs to its builder, and a structure is destroyed once no live
+// cell of its builder is left in its bounding box.
+
+#[cfg(feature = "web")]
+use serde::Serialize;
+#[cfg(feature = "web")]
+use tsify_next::Tsify;
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::seed;
+use crate::{Boundary, BoundingBox, ConvidaError, Player, Rle, Versus};
+
+/// How one side fared.
+#[cfg_attr(feature = "web", derive(Serialize, Tsify))]
+#[cfg_attr(feature = "web", tsify(into_wasm_abi), serde(rename_all = "camelCase"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SideReport {
+    /// The structures the side built.
+    pub structures: u32,
+    /// Those still standing.
+    pub standing: u32,
+    /// The live cells the side owns.
+    pub cells: u32,
+}
+
+#[cfg_attr(feature = "web", derive(Serialize, Tsify))]
+#[cfg_attr(feature = "web", tsify(into_wasm_abi), serde(rename_all = "camelCase"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ArenaReport {
+    pub generation: u32,
+    pub player_one: SideReport,
+    pub player_two: SideReport,
+    /// The side that destroyed more of the other's structures, or with
+    /// more live cells if that is even. `None` on a draw.
+    pub winner: Option<Player>,
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub struct Arena {
+    versus: Versus,
+    generations: u32,
+    round: u32,
+    structures: Vec<(Player, BoundingBox)>,
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Arena {
+    /// An empty `width` x `height` arena whose battles run for
+    /// `generations` generations.
+    #[cfg_attr(feature = "web", wasm_bindgen(constructor))]
+    pub fn new(width: u32, height: u32, generations: u32) -> Result<Arena, ConvidaError> {
+        let mut versus = Versus::new(width, height, 0, 0)?;
+        versus.set_boundary(Boundary::Dead);
+        Ok(Arena {
+            versus,
+            generations,
+            round: 0,
+            structures: Vec::new(),
+        })
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(getter))]
+    pub fn width(&self) -> u32 {
+        self.versus.width()
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(getter))]
+    pub fn height(&self) -> u32 {
+        self.versus.height()
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(getter))]
+    pub fn generation(&self) -> u32 {
+        self.versus.generation()
+    }
+
+    /// The number of battles fought since the arena was created.
+    #[cfg_attr(feature = "web", wasm_bindgen(getter))]
+    pub fn round(&self) -> u32 {
+        self.round
+    }
+
+    pub fn cells(&self) -> *const crate::Cell {
+        self.versus.cells()
+    }
+
+    /// The owner of each cell, as in `Versus.owners`.
+    pub fn owners(&self) -> *const u8 {
+        self.versus.owners()
+    }
+
+    /// Build a structure from an RLE pattern for `player` with its
+    /// top-left corner at (`row`, `col`). The whole pattern has to fit in
+    /// the player's half.
+    pub fn place(
+        &mut self,
+        player: Player,
+        rle: &str,
+        row: u32,
+        col: u32,
+    ) -> Result<(), ConvidaError> {
+        let pattern = Rle::parse(rle)?;
+        self.build(player, &pattern, row, col, true)
+    }
+
+    /// `place` for one of the built-in patterns, such as `gosper-gun`.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = placePattern))]
+    pub fn place_pattern(
+        &mut self,
+        player: Player,
+        name: &str,
+        row: u32,
+        col: u32,
+    ) -> Result<(), ConvidaError> {
+        let pattern = seed::pattern(name)?;
+        self.build(player, &pattern, row, col, true)
+    }
+
+    /// Launch a ship from an RLE pattern for `player`. Ships are owned like
+    /// structures but don't count as destroyed when they fly off.
+    pub fn launch(
+        &mut self,
+        player: Player,
+        rle: &str,
+        row: u32,
+        col: u32,
+    ) -> Result<(), ConvidaError> {
+        let pattern = Rle::parse(rle)?;
+        self.build(player, &pattern, row, col, false)
+    }
+
+    /// Run one generation of the battle.
+    pub fn tick(&mut self) {
+        self.versus.tick();
+    }
+
+    /// Run the rest of the battle and report on it.
+    pub fn run(&mut self) -> ArenaReport {
+        while self.generation() < self.generations {
+            self.tick();
+        }
+        self.report()
+    }
+
+    /// The state of the battle so far.
+    pub fn report(&self) -> ArenaReport {
+        let score = self.versus.score();
+        let player_one = self.side(Player::One, score.player_one);
+        let player_two = self.side(Player::Two, score.player_two);
+
+        let destroyed = |side: &SideReport| side.structures - side.standing;
+        let winner = match destroyed(&player_two)
+            .cmp(&destroyed(&player_one))
+            .then(player_one.cells.cmp(&player_two.cells))
+        {
+            std::cmp::Ordering::Greater => Some(Player::One),
+            std::cmp::Ordering::Less => Some(Player::Two),
+            std::cmp::Ordering::Equal => None,
+        };
+
+        ArenaReport {
+            generation: self.generation(),
+            player_one,
+            player_two,
+            winner,
+        }
+    }
+
+    /// Clear the arena for the next round.
+    pub fn reset(&mut self) -> Result<(), ConvidaError> {
+        let mut versus = Versus::new(self.width(), self.height(), 0, 0)?;
+        versus.set_boundary(Boundary::Dead);
+        self.versus = versus;
+        self.structures.clear();
+        self.round += 1;
+        Ok(())
+    }
+}
+
+impl Arena {
+    pub fn versus(&self) -> &Versus {
+        &self.versus
+    }
+
+    fn build(
+        &mut self,
+        player: Player,
+        pattern: &Rle,
+        row: u32,
+        col: u32,
+        structure: bool,
+    ) -> Result<(), ConvidaError> {
+        if self.generation() > 0 {
+            return Err(ConvidaError::BattleStarted);
+        }
+        let half = self.width() / 2;
+        let cols = match player {
+            Player::One => 0..half,
+            Player::Two => half..self.width(),
+        };
+        for &(r, c) in &pattern.cells {
+            let (r, c) = (row.saturating_add(r), col.saturating_add(c));
+            if r >= self.height() || !cols.contains(&c) {
+                return Err(ConvidaError::NotEditable { row: r, col: c });
+            }
+        }
+
+        for &(r, c) in &pattern.cells {
+            self.versus.claim(player, row + r, col + c);
+        }
+        if structure && !pattern.cells.is_empty() {
+            let region = BoundingBox {
+                row,
+                col,
+                width: pattern.width,
+                height: pattern.height,
+            };
+            self.structures.push((player, region));
+        }
+        Ok(())
+    }
+
+    fn side(&self, player: Player, cells: u32) -> SideReport {
+        let built = self
+            .structures
+            .iter()
+            .filter(|&&(owner, _)| owner == player);
+        let standing = built
+            .clone()
+            .filter(|(_, region)| {
+                (region.row..region.row + region.height).any(|row| {
+                    (region.col..region.col + region.width)
+                        .any(|col| self.versus.owner(row, col) == Ok(Some(player)))
+                })
+            })
+            .count();
+        SideReport {
+            structures: built.count() as u32,
+            standing: standing as u32,
+            cells,
+        }
+    }
+}
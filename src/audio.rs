@@ -0,0 +1,249 @@
+// Sonification of the simulation through the Web Audio API.
+//
+// A `Sonifier` owns a bank of oscillators and retunes them after every
+// update. In `statistics` mode three voices follow the population, births
+// and deaths; in `columns` mode the board is split into vertical bands,
+// one voice each, pitched from low on the left to high on the right and
+// as loud as the band is busy.
+
+use wasm_bindgen::prelude::*;
+use web_sys::{AudioContext, GainNode, OscillatorNode, OscillatorType};
+
+use crate::{Cell, Universe};
+
+// Seconds over which frequency and gain changes are smoothed, which avoids
+// clicks when values jump between generations.
+const SMOOTHING: f64 = 0.05;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mapping {
+    Statistics,
+    Columns,
+}
+
+impl Mapping {
+    fn parse(name: &str) -> Option<Mapping> {
+        match name {
+            "statistics" => Some(Mapping::Statistics),
+            "columns" => Some(Mapping::Columns),
+            _ => None,
+        }
+    }
+}
+
+struct Voice {
+    oscillator: OscillatorNode,
+    gain: GainNode,
+}
+
+#[wasm_bindgen]
+pub struct Sonifier {
+    context: AudioContext,
+    voices: Vec<Voice>,
+    mapping: Mapping,
+    columns: u32,
+    low: f32,
+    high: f32,
+    volume: f32,
+    enabled: bool,
+    previous: Vec<Cell>,
+}
+
+#[wasm_bindgen]
+impl Sonifier {
+    /// Create a silent sonifier in `statistics` mode. Browsers only allow
+    /// audio to start after a user gesture, so call `enable` from one.
+    pub fn new() -> Result<Sonifier, JsValue> {
+        let mut sonifier = Sonifier {
+            context: AudioContext::new()?,
+            voices: Vec::new(),
+            mapping: Mapping::Statistics,
+            columns: 8,
+            low: 110.0,
+            high: 880.0,
+            volume: 0.2,
+            enabled: false,
+            previous: Vec::new(),
+        };
+        sonifier.build_voices()?;
+        Ok(sonifier)
+    }
+
+    pub fn enable(&mut self) -> Result<(), JsValue> {
+        self.enabled = true;
+        let _ = self.context.resume()?;
+        Ok(())
+    }
+
+    /// Fade out and suspend the audio context.
+    pub fn disable(&mut self) -> Result<(), JsValue> {
+        self.enabled = false;
+        self.silence();
+        let _ = self.context.suspend()?;
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = isEnabled)]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Either `statistics` or `columns`.
+    #[wasm_bindgen(js_name = setMapping)]
+    pub fn set_mapping(&mut self, mapping: &str) -> Result<(), JsValue> {
+        self.mapping = Mapping::parse(mapping)
+            .ok_or_else(|| JsValue::from_str(&format!("unknown mapping {}", mapping)))?;
+        self.build_voices()
+    }
+
+    /// Number of bands, and so voices, in `columns` mode.
+    #[wasm_bindgen(js_name = setColumns)]
+    pub fn set_columns(&mut self, columns: u32) -> Result<(), JsValue> {
+        self.columns = columns.clamp(1, 64);
+        self.build_voices()
+    }
+
+    /// Frequencies in Hz that the quietest and busiest values map to.
+    #[wasm_bindgen(js_name = setFrequencyRange)]
+    pub fn set_frequency_range(&mut self, low: f32, high: f32) {
+        self.low = low.max(1.0);
+        self.high = high.max(self.low);
+    }
+
+    /// Overall gain, from 0 to 1, shared by all voices.
+    #[wasm_bindgen(js_name = setVolume)]
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume.clamp(0.0, 1.0);
+    }
+
+    /// Retune the voices to the current state of `universe`. Births and
+    /// deaths are counted against the state seen by the previous update.
+    pub fn update(&mut self, universe: &Universe) {
+        let cells = universe.get_cells();
+        if !self.enabled {
+            self.previous.clear();
+            self.previous.extend_from_slice(cells);
+            return;
+        }
+
+        let levels = match self.mapping {
+            Mapping::Statistics => self.statistics(cells),
+            Mapping::Columns => self.column_activity(universe),
+        };
+        let share = self.volume / self.voices.len().max(1) as f32;
+        let now = self.context.current_time();
+
+        for (i, (voice, &(pitch, loudness))) in self.voices.iter().zip(&levels).enumerate() {
+            let pitch = match self.mapping {
+                Mapping::Statistics => pitch,
+                Mapping::Columns => i as f32 / (self.voices.len() - 1).max(1) as f32,
+            };
+            let frequency = self.low * (self.high / self.low).powf(pitch.clamp(0.0, 1.0));
+            let _ = voice
+                .oscillator
+                .frequency()
+                .set_target_at_time(frequency, now, SMOOTHING);
+            let _ = voice.gain.gain().set_target_at_time(
+                share * loudness.clamp(0.0, 1.0),
+                now,
+                SMOOTHING,
+            );
+        }
+
+        self.previous.clear();
+        self.previous.extend_from_slice(cells);
+    }
+}
+
+impl Sonifier {
+    fn build_voices(&mut self) -> Result<(), JsValue> {
+        for voice in self.voices.drain(..) {
+            let _ = voice.oscillator.stop();
+            let _ = voice.gain.disconnect();
+        }
+
+        let count = match self.mapping {
+            Mapping::Statistics => 3,
+            Mapping::Columns => self.columns,
+        };
+        for _ in 0..count {
+            let oscillator = self.context.create_oscillator()?;
+            oscillator.set_type(OscillatorType::Sine);
+            let gain = self.context.create_gain()?;
+            gain.gain().set_value(0.0);
+            oscillator.connect_with_audio_node(&gain)?;
+            gain.connect_with_audio_node(&self.context.destination())?;
+            oscillator.start()?;
+            self.voices.push(Voice { oscillator, gain });
+        }
+
+        Ok(())
+    }
+
+    fn silence(&self) {
+        let now = self.context.current_time();
+        for voice in &self.voices {
+            let _ = voice.gain.gain().set_target_at_time(0.0, now, SMOOTHING);
+        }
+    }
+
+    // (pitch, loudness) of the population, births and deaths voices. The
+    // population is measured against the board, births and deaths against
+    // the population.
+    fn statistics(&self, cells: &[Cell]) -> Vec<(f32, f32)> {
+        let population = cells.iter().filter(|&&cell| cell == Cell::Alive).count();
+        let (mut births, mut deaths) = (0, 0);
+        if self.previous.len() == cells.len() {
+            for (&before, &after) in self.previous.iter().zip(cells) {
+                match (before, after) {
+                    (Cell::Dead, Cell::Alive) => births += 1,
+                    (Cell::Alive, Cell::Dead) => deaths += 1,
+                    _ => {}
+                }
+            }
+        }
+
+        let density = population as f32 / cells.len().max(1) as f32;
+        let rate = |count: usize| count as f32 / population.max(1) as f32;
+        let audible = |count: usize| if count > 0 { 1.0 } else { 0.0 };
+        vec![
+            (density, audible(population)),
+            (rate(births), audible(births)),
+            (rate(deaths), audible(deaths)),
+        ]
+    }
+
+    // Fraction of live cells in each band of columns. The pitch comes from
+    // the band's position, so only the loudness is meaningful here.
+    fn column_activity(&self, universe: &Universe) -> Vec<(f32, f32)> {
+        let (width, height) = (universe.width(), universe.height());
+        let bands = self.voices.len() as u32;
+        let cells = universe.get_cells();
+
+        (0..bands)
+            .map(|band| {
+                let start = band * width / bands;
+                let end = ((band + 1) * width / bands).max(start + 1).min(width);
+                let mut alive = 0;
+                for row in 0..height {
+                    let offset = (row * width) as usize;
+                    alive += cells[offset + start as usize..offset + end as usize]
+                        .iter()
+                        .filter(|&&cell| cell == Cell::Alive)
+                        .count();
+                }
+                let area = ((end - start) * height).max(1) as f32;
+                (0.0, alive as f32 / area)
+            })
+            .collect()
+    }
+}
+
+impl Drop for Sonifier {
+    fn drop(&mut self) {
+        for voice in &self.voices {
+            let _ = voice.oscillator.stop();
+        }
+        let _ = self.context.close();
+    }
+}
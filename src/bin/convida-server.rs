@@ -0,0 +1,876 @@
+// Headless simulation server.
+//
+// Runs universes on this machine for browsers that only render them, so a
+// long search can use a fast CPU while the page stays responsive. Boards
+// travel in the sync protocol's messages:
+//
+//     POST   /universes?width=W&height=H[&rule=R][&boundary=dead][&density=D][&seed=N]
+//                                           create a universe, answers its id
+//     POST   /universes/ID/stamp?row=R&col=C[&file=NAME]
+//                                           stamp the pattern file in the body
+//     POST   /universes/ID/edits            apply `Edit` messages, answers them
+//                                           sequenced
+//     POST   /universes/ID/advance?generations=N
+//                                           run N generations, answers the
+//                                           generation reached
+//     GET    /universes/ID/snapshot         a `Snapshot` message
+//     GET    /universes/ID/stats            statistics CSV since creation
+//     GET    /universes/ID/rle              the board as RLE
+//     GET    /universes/ID/ws               a WebSocket for `SyncSession.connect`
+//     DELETE /universes/ID
+//
+// The WebSocket gets a `Snapshot` message when it opens and after every
+// stamp or advance, and every sequenced edit, one message per frame. The
+// edits and `Resync` requests it sends are handled as the authority of a
+// sync session would: an edit is applied once the universe reaches its
+// generation. Every response allows any origin, so a page served from
+// elsewhere can call the server.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use base64::Engine;
+use convida::{Boundary, Edit, Message, PatternFile, Seed, Universe, UniverseBuilder};
+
+const USAGE: &str = "\
+usage: convida-server [options]
+
+options:
+      --addr ADDR       address to listen on (default 127.0.0.1:8080)
+      --threads N       requests handled at once (default 4)
+      --max-sockets N   WebSockets open at once, each with two threads of
+                        its own (default 64)
+      --max-cells N     largest universe allowed (default 16777216)
+      --max-updates N   most cell updates, cells times generations, one
+                        advance may run (default 4294967296)
+  -h, --help            show this help
+";
+
+// Appended to a WebSocket key to prove the server speaks the protocol.
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+// The largest request head, request body and WebSocket message accepted.
+// Clients only send edits and resync requests over a WebSocket.
+const MAX_HEAD: usize = 16 * 1024;
+const MAX_BODY: u64 = 64 * 1024 * 1024;
+const MAX_MESSAGE: u64 = 64 * 1024;
+
+struct Options {
+    addr: String,
+    threads: usize,
+    max_sockets: usize,
+    max_cells: u64,
+    max_updates: u64,
+}
+
+// What the workers share.
+struct Server {
+    options: Options,
+    universes: Mutex<Universes>,
+    // The WebSockets open now.
+    sockets: AtomicUsize,
+}
+
+// A place taken among the `--max-sockets` WebSockets, given back when the
+// last of its threads is done.
+struct Socket(Arc<Server>);
+
+impl Drop for Socket {
+    fn drop(&mut self) {
+        self.0.sockets.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+// A universe and the sockets following it.
+struct Entry {
+    universe: Universe,
+    // The sequence number of the last edit applied.
+    seq: u32,
+    // Sequenced edits waiting for their generation, by sequence number.
+    pending: BTreeMap<u32, Edit>,
+    subscribers: Vec<Sender<Vec<u8>>>,
+}
+
+impl Entry {
+    fn new(universe: Universe) -> Entry {
+        Entry {
+            universe,
+            seq: 0,
+            pending: BTreeMap::new(),
+            subscribers: Vec::new(),
+        }
+    }
+
+    fn snapshot(&self) -> Vec<u8> {
+        Message::Snapshot {
+            seq: self.seq,
+            data: self.universe.encode_snapshot(),
+        }
+        .encode()
+    }
+
+    // Everything a new or resyncing subscriber needs: a snapshot and the
+    // edits that are sequenced but not part of it yet.
+    fn catch_up(&self) -> Vec<Vec<u8>> {
+        let edits = self.pending.values();
+        std::iter::once(self.snapshot())
+            .chain(edits.map(|edit| Message::Edit(*edit).encode()))
+            .collect()
+    }
+
+    // Give `edit` the next sequence number and send it to every subscriber,
+    // applying it when the universe has reached its generation.
+    fn sequence(&mut self, edit: Edit) -> Edit {
+        let edit = Edit {
+            seq: self.seq + self.pending.len() as u32 + 1,
+            generation: edit.generation.max(self.universe.generation()),
+            ..edit
+        };
+        self.pending.insert(edit.seq, edit);
+        self.broadcast(Message::Edit(edit).encode());
+        self.apply_due();
+        edit
+    }
+
+    // Apply pending edits in sequence order, stopping at one meant for a
+    // later generation.
+    fn apply_due(&mut self) {
+        while let Some(entry) = self.pending.first_entry() {
+            if entry.get().generation > self.universe.generation() {
+                break;
+            }
+            let edit = entry.remove();
+            self.universe.apply_edit(&edit);
+            self.seq = edit.seq;
+        }
+    }
+
+    // Send `message` to every subscriber still listening.
+    fn broadcast(&mut self, message: Vec<u8>) {
+        self.subscribers
+            .retain(|subscriber| subscriber.send(message.clone()).is_ok());
+    }
+}
+
+#[derive(Default)]
+struct Universes {
+    next_id: u32,
+    entries: HashMap<u32, Arc<Mutex<Entry>>>,
+}
+
+struct Request {
+    method: String,
+    path: String,
+    query: HashMap<String, String>,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(field, _)| field.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+struct Reply {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Reply {
+    fn text(status: u16, body: impl Into<String>) -> Reply {
+        Reply {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: body.into().into_bytes(),
+        }
+    }
+
+    fn binary(body: Vec<u8>) -> Reply {
+        Reply {
+            status: 200,
+            content_type: "application/octet-stream",
+            body,
+        }
+    }
+}
+
+fn main() {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("convida-server: {}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let listener = match TcpListener::bind(&options.addr) {
+        Ok(listener) => Arc::new(listener),
+        Err(err) => {
+            eprintln!("convida-server: {}: {}", options.addr, err);
+            process::exit(1);
+        }
+    };
+    eprintln!("listening on http://{}", options.addr);
+
+    let threads = options.threads;
+    let server = Arc::new(Server {
+        options,
+        universes: Mutex::new(Universes::default()),
+        sockets: AtomicUsize::new(0),
+    });
+    let workers: Vec<_> = (0..threads)
+        .map(|_| {
+            let (listener, server) = (Arc::clone(&listener), Arc::clone(&server));
+            thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    if let Err(err) = serve(stream, &server) {
+                        eprintln!("convida-server: {}", err);
+                    }
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        let _ = worker.join();
+    }
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let mut options = Options {
+        addr: "127.0.0.1:8080".to_string(),
+        threads: 4,
+        max_sockets: 64,
+        max_cells: 1 << 24,
+        max_updates: 1 << 32,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{} needs a value", name));
+        match arg.as_str() {
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                process::exit(0);
+            }
+            "--addr" => options.addr = value(&arg)?,
+            "--threads" => options.threads = number::<usize>(&arg, &value(&arg)?)?.max(1),
+            "--max-sockets" => options.max_sockets = number(&arg, &value(&arg)?)?,
+            "--max-cells" => options.max_cells = number(&arg, &value(&arg)?)?,
+            "--max-updates" => options.max_updates = number(&arg, &value(&arg)?)?,
+            _ => return Err(format!("unknown option {}", arg)),
+        }
+    }
+
+    Ok(options)
+}
+
+fn number<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} expects a number, got {}", name, value))
+}
+
+// Answer the one request on `stream`, unless it opens a WebSocket, which
+// gets threads of its own.
+fn serve(mut stream: TcpStream, server: &Arc<Server>) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let request = match read_request(&mut reader)? {
+        Ok(request) => request,
+        Err(reply) => return respond(&mut stream, reply, &[]),
+    };
+    let segments: Vec<&str> = request.path.split('/').filter(|s| !s.is_empty()).collect();
+
+    match (request.method.as_str(), segments.as_slice()) {
+        ("OPTIONS", _) => respond(
+            &mut stream,
+            Reply::text(204, ""),
+            &[
+                ("Access-Control-Allow-Methods", "GET, POST, DELETE"),
+                ("Access-Control-Allow-Headers", "Content-Type"),
+            ],
+        ),
+        ("GET", ["universes", id, "ws"]) => match find(&server.universes, id) {
+            Ok(entry) => subscribe(stream, reader, &request, entry, server),
+            Err(reply) => respond(&mut stream, reply, &[]),
+        },
+        (method, segments) => {
+            let reply = route(method, segments, &request, server).unwrap_or_else(|reply| reply);
+            respond(&mut stream, reply, &[])
+        }
+    }
+}
+
+fn read_request(reader: &mut impl BufRead) -> io::Result<Result<Request, Reply>> {
+    let mut head = Vec::new();
+    while !head.ends_with(b"\r\n\r\n") {
+        // Never read more than one byte past the limit, even from a line
+        // that doesn't end.
+        let left = (MAX_HEAD - head.len() + 1) as u64;
+        if reader.by_ref().take(left).read_until(b'\n', &mut head)? == 0 {
+            return Ok(Err(Reply::text(400, "incomplete request\n")));
+        }
+        if head.len() > MAX_HEAD {
+            return Ok(Err(Reply::text(431, "request head too large\n")));
+        }
+    }
+
+    let mut headers = [httparse::EMPTY_HEADER; 64];
+    let mut parsed = httparse::Request::new(&mut headers);
+    if parsed.parse(&head).is_err() {
+        return Ok(Err(Reply::text(400, "malformed request\n")));
+    }
+    let (path, query) = split_url(parsed.path.unwrap_or("/"));
+    let mut request = Request {
+        method: parsed.method.unwrap_or("GET").to_string(),
+        path: path.to_string(),
+        query,
+        headers: parsed
+            .headers
+            .iter()
+            .map(|h| {
+                let value = String::from_utf8_lossy(h.value).trim().to_string();
+                (h.name.to_string(), value)
+            })
+            .collect(),
+        body: Vec::new(),
+    };
+
+    let length: u64 = match request.header("Content-Length").map(str::parse) {
+        None => 0,
+        Some(Ok(length)) if length <= MAX_BODY => length,
+        Some(_) => return Ok(Err(Reply::text(413, "request body too large\n"))),
+    };
+    reader
+        .take(length)
+        .read_to_end(&mut request.body)
+        .map(|_| Ok(request))
+}
+
+fn route(
+    method: &str,
+    segments: &[&str],
+    request: &Request,
+    server: &Server,
+) -> Result<Reply, Reply> {
+    let (universes, options) = (&server.universes, &server.options);
+    let query = &request.query;
+    match (method, segments) {
+        ("POST", ["universes"]) => create(query, universes, options.max_cells),
+        ("DELETE", ["universes", id]) => {
+            let id = parse_id(id)?;
+            match universes.lock().unwrap().entries.remove(&id) {
+                Some(_) => Ok(Reply::text(200, "deleted\n")),
+                None => Err(not_found()),
+            }
+        }
+        (_, ["universes", id, action]) => {
+            let entry = find(universes, id)?;
+            let mut entry = entry.lock().unwrap();
+            match (method, *action) {
+                ("POST", "stamp") => stamp(&mut entry, query, &request.body),
+                ("POST", "edits") => edits(&mut entry, &request.body),
+                ("POST", "advance") => advance(&mut entry, query, options.max_updates),
+                ("GET", "snapshot") => Ok(Reply::binary(entry.snapshot())),
+                ("GET", "stats") => Ok(Reply {
+                    content_type: "text/csv",
+                    ..Reply::text(200, entry.universe.export_stats_csv())
+                }),
+                ("GET", "rle") => Ok(Reply::text(200, entry.universe.to_rle())),
+                _ => Err(Reply::text(405, "method not allowed\n")),
+            }
+        }
+        _ => Err(Reply::text(404, "not found\n")),
+    }
+}
+
+fn create(
+    query: &HashMap<String, String>,
+    universes: &Mutex<Universes>,
+    max_cells: u64,
+) -> Result<Reply, Reply> {
+    let width: u32 = param(query, "width")?.unwrap_or(128);
+    let height: u32 = param(query, "height")?.unwrap_or(128);
+    if u64::from(width) * u64::from(height) > max_cells {
+        return Err(Reply::text(
+            413,
+            format!("a universe may have at most {} cells\n", max_cells),
+        ));
+    }
+
+    let mut builder = UniverseBuilder::new().width(width).height(height).seed(
+        match param::<f64>(query, "density")? {
+            Some(density) => Seed::Random { density },
+            None => Seed::Empty,
+        },
+    );
+    if let Some(rule) = query.get("rule") {
+        builder = builder.rule(rule);
+    }
+    if let Some(seed) = param(query, "seed")? {
+        builder = builder.rng_seed(seed);
+    }
+    builder = builder.boundary(match query.get("boundary").map(String::as_str) {
+        None | Some("torus") => Boundary::Torus,
+        Some("dead") => Boundary::Dead,
+        Some(other) => return Err(Reply::text(400, format!("unknown boundary {}\n", other))),
+    });
+    let mut universe = builder.build().map_err(bad_request)?;
+    universe.start_stats();
+
+    let mut universes = universes.lock().unwrap();
+    universes.next_id += 1;
+    let id = universes.next_id;
+    universes
+        .entries
+        .insert(id, Arc::new(Mutex::new(Entry::new(universe))));
+    Ok(Reply::text(201, format!("{}\n", id)))
+}
+
+// Run the generations asked for, unless they would hold the universe for
+// more than `max_updates` cell updates.
+fn advance(
+    entry: &mut Entry,
+    query: &HashMap<String, String>,
+    max_updates: u64,
+) -> Result<Reply, Reply> {
+    let generations: u32 = param(query, "generations")?.unwrap_or(1);
+    let universe = &entry.universe;
+    let updates =
+        u128::from(generations) * u128::from(universe.width()) * u128::from(universe.height());
+    if updates > u128::from(max_updates) {
+        return Err(Reply::text(
+            413,
+            format!("an advance may run at most {} cell updates\n", max_updates),
+        ));
+    }
+
+    for _ in 0..generations {
+        entry.universe.tick();
+        entry.apply_due();
+    }
+    let snapshot = entry.snapshot();
+    entry.broadcast(snapshot);
+    Ok(Reply::text(
+        200,
+        format!("{}\n", entry.universe.generation()),
+    ))
+}
+
+fn stamp(entry: &mut Entry, query: &HashMap<String, String>, body: &[u8]) -> Result<Reply, Reply> {
+    let text = std::str::from_utf8(body).map_err(bad_request)?;
+    let file = query.get("file").map_or("pattern.rle", String::as_str);
+    let pattern = PatternFile::parse(file, text).map_err(bad_request)?.pattern;
+    let row = param(query, "row")?.unwrap_or(0);
+    let col = param(query, "col")?.unwrap_or(0);
+    entry
+        .universe
+        .stamp_at(&pattern, row, col)
+        .map_err(bad_request)?;
+    let snapshot = entry.snapshot();
+    entry.broadcast(snapshot);
+    Ok(Reply::text(200, format!("{}\n", pattern.cells.len())))
+}
+
+// Sequence and apply the `Edit` messages in `body`, answering with the
+// sequenced edits.
+fn edits(entry: &mut Entry, mut body: &[u8]) -> Result<Reply, Reply> {
+    let mut sequenced = Vec::new();
+    while !body.is_empty() {
+        match Message::decode_prefix(&mut body).map_err(bad_request)? {
+            Message::Edit(edit) => {
+                let edit = entry.sequence(edit);
+                sequenced.extend_from_slice(&Message::Edit(edit).encode());
+            }
+            other => {
+                return Err(Reply::text(
+                    400,
+                    format!("expected edits, got {:?}\n", other),
+                ))
+            }
+        }
+    }
+    Ok(Reply::binary(sequenced))
+}
+
+// Accept the WebSocket `request` opened on `stream`, then send it `entry`'s
+// messages from one thread and handle the ones it sends on another.
+fn subscribe(
+    mut stream: TcpStream,
+    reader: BufReader<TcpStream>,
+    request: &Request,
+    entry: Arc<Mutex<Entry>>,
+    server: &Arc<Server>,
+) -> io::Result<()> {
+    let key = match request.header("Sec-WebSocket-Key") {
+        Some(key) => key,
+        None => return respond(&mut stream, Reply::text(400, "expected a WebSocket\n"), &[]),
+    };
+    if server.sockets.fetch_add(1, Ordering::SeqCst) >= server.options.max_sockets {
+        server.sockets.fetch_sub(1, Ordering::SeqCst);
+        return respond(&mut stream, Reply::text(503, "too many WebSockets\n"), &[]);
+    }
+    let socket = Arc::new(Socket(Arc::clone(server)));
+    write!(
+        stream,
+        "HTTP/1.1 101 Switching Protocols\r\n\
+         Upgrade: websocket\r\n\
+         Connection: Upgrade\r\n\
+         Sec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(key)
+    )?;
+
+    let (sender, receiver) = mpsc::channel();
+    {
+        let mut entry = entry.lock().unwrap();
+        for message in entry.catch_up() {
+            let _ = sender.send(message);
+        }
+        entry.subscribers.push(sender.clone());
+    }
+    let forwarding = Arc::clone(&socket);
+    thread::spawn(move || {
+        forward(stream, receiver);
+        drop(forwarding);
+    });
+    thread::spawn(move || {
+        listen(reader, &entry, &sender);
+        drop(socket);
+    });
+    Ok(())
+}
+
+// Send `messages` to the client until one is empty, which `listen` sends
+// once the client is gone.
+fn forward(mut stream: TcpStream, messages: Receiver<Vec<u8>>) {
+    for message in messages {
+        if message.is_empty() || stream.write_all(&frame(&message)).is_err() {
+            break;
+        }
+    }
+    let _ = stream.shutdown(Shutdown::Both);
+}
+
+// Handle the messages a subscriber sends until it closes the socket, with
+// `reply` reaching that subscriber alone.
+fn listen(mut reader: BufReader<TcpStream>, entry: &Mutex<Entry>, reply: &Sender<Vec<u8>>) {
+    while let Ok(Some(bytes)) = read_message(&mut reader) {
+        let mut entry = entry.lock().unwrap();
+        match Message::decode(&bytes) {
+            Ok(Message::Edit(edit)) => {
+                entry.sequence(edit);
+            }
+            Ok(Message::Resync { .. }) => {
+                for message in entry.catch_up() {
+                    let _ = reply.send(message);
+                }
+            }
+            _ => {}
+        }
+    }
+    let _ = reply.send(Vec::new());
+    let _ = reader.get_ref().shutdown(Shutdown::Both);
+}
+
+// The next message from a client, put together from its fragments, or
+// `None` once the client closes the socket. Pings go unanswered; browsers
+// don't send them.
+fn read_message(reader: &mut impl Read) -> io::Result<Option<Vec<u8>>> {
+    let mut message = Vec::new();
+    loop {
+        let mut head = [0; 2];
+        reader.read_exact(&mut head)?;
+        let (fin, opcode, masked) = (head[0] & 0x80 != 0, head[0] & 0x0f, head[1] & 0x80 != 0);
+        let length = match head[1] & 0x7f {
+            126 => {
+                let mut length = [0; 2];
+                reader.read_exact(&mut length)?;
+                u64::from(u16::from_be_bytes(length))
+            }
+            127 => {
+                let mut length = [0; 8];
+                reader.read_exact(&mut length)?;
+                u64::from_be_bytes(length)
+            }
+            length => u64::from(length),
+        };
+        if opcode == 0x8 || !masked || length > MAX_MESSAGE - message.len() as u64 {
+            return Ok(None);
+        }
+        let mut mask = [0; 4];
+        reader.read_exact(&mut mask)?;
+        let mut payload = vec![0; length as usize];
+        reader.read_exact(&mut payload)?;
+
+        if opcode <= 0x2 {
+            message.extend(payload.iter().zip(mask.iter().cycle()).map(|(b, m)| b ^ m));
+            if fin {
+                return Ok(Some(message));
+            }
+        }
+    }
+}
+
+fn accept_key(key: &str) -> String {
+    let digest = sha1_smol::Sha1::from(format!("{}{}", key, WEBSOCKET_GUID)).digest();
+    base64::engine::general_purpose::STANDARD.encode(digest.bytes())
+}
+
+// An unmasked, unfragmented binary WebSocket frame.
+fn frame(payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0x82];
+    match payload.len() {
+        len if len < 126 => frame.push(len as u8),
+        len if len <= usize::from(u16::MAX) => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+fn find(universes: &Mutex<Universes>, id: &str) -> Result<Arc<Mutex<Entry>>, Reply> {
+    let id = parse_id(id)?;
+    let universes = universes.lock().unwrap();
+    universes.entries.get(&id).cloned().ok_or_else(not_found)
+}
+
+fn parse_id(id: &str) -> Result<u32, Reply> {
+    id.parse().map_err(|_| not_found())
+}
+
+fn param<T: std::str::FromStr>(
+    query: &HashMap<String, String>,
+    name: &str,
+) -> Result<Option<T>, Reply> {
+    match query.get(name) {
+        Some(value) => value
+            .parse()
+            .map(Some)
+            .map_err(|_| Reply::text(400, format!("{} expects a number, got {}\n", name, value))),
+        None => Ok(None),
+    }
+}
+
+fn split_url(url: &str) -> (&str, HashMap<String, String>) {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let query = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode(name), decode(value))
+        })
+        .collect();
+    (path, query)
+}
+
+// Undo the percent-encoding of a query string component.
+fn decode(component: &str) -> String {
+    let bytes = component.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (b'+', _) => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn bad_request(err: impl std::fmt::Display) -> Reply {
+    Reply::text(400, format!("{}\n", err))
+}
+
+fn not_found() -> Reply {
+    Reply::text(404, "no such universe\n")
+}
+
+fn respond(stream: &mut TcpStream, reply: Reply, headers: &[(&str, &str)]) -> io::Result<()> {
+    let reason = match reply.status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        503 => "Service Unavailable",
+        _ => "",
+    };
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\n\
+         Content-Type: {}\r\n\
+         Content-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\n\
+         Connection: close\r\n",
+        reply.status,
+        reason,
+        reply.content_type,
+        reply.body.len()
+    );
+    for (field, value) in headers {
+        head.push_str(&format!("{}: {}\r\n", field, value));
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(&reply.body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server() -> Server {
+        Server {
+            options: parse_args(std::iter::empty()).unwrap(),
+            universes: Mutex::new(Universes::default()),
+            sockets: AtomicUsize::new(0),
+        }
+    }
+
+    // Answer `head` the way `serve` answers a request without a body.
+    fn handle(server: &Server, head: &str) -> Reply {
+        let request = match read_request(&mut head.as_bytes()).unwrap() {
+            Ok(request) => request,
+            Err(reply) => return reply,
+        };
+        let segments: Vec<&str> = request.path.split('/').filter(|s| !s.is_empty()).collect();
+        route(&request.method, &segments, &request, server).unwrap_or_else(|reply| reply)
+    }
+
+    // A frame as a browser sends it, masked.
+    fn masked(first: u8, payload: &[u8]) -> Vec<u8> {
+        let mask = [0x37, 0xfa, 0x21, 0x3d];
+        let mut frame = vec![first, 0x80 | payload.len() as u8];
+        frame.extend_from_slice(&mask);
+        frame.extend(payload.iter().zip(mask.iter().cycle()).map(|(b, m)| b ^ m));
+        frame
+    }
+
+    #[test]
+    fn test_accept_key() {
+        // The example from RFC 6455, section 1.3.
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    #[test]
+    fn test_frames() {
+        assert_eq!(frame(b"hi"), b"\x82\x02hi");
+        assert_eq!(frame(&[0; 126])[..4], [0x82, 126, 0, 126]);
+        assert_eq!(
+            frame(&[0; 1 << 16])[..10],
+            [0x82, 127, 0, 0, 0, 0, 0, 1, 0, 0]
+        );
+
+        let message = read_message(&mut &masked(0x82, b"edit")[..]).unwrap();
+        assert_eq!(message.as_deref(), Some(&b"edit"[..]));
+
+        // Fragments, with a ping between them.
+        let mut bytes = masked(0x02, b"ed");
+        bytes.extend(masked(0x89, b""));
+        bytes.extend(masked(0x80, b"it"));
+        let message = read_message(&mut &bytes[..]).unwrap();
+        assert_eq!(message.as_deref(), Some(&b"edit"[..]));
+
+        // Closing, unmasked and oversized frames end the socket.
+        assert_eq!(read_message(&mut &masked(0x88, b"")[..]).unwrap(), None);
+        assert_eq!(read_message(&mut &frame(b"edit")[..]).unwrap(), None);
+        let mut huge = vec![0x82, 0xff];
+        huge.extend_from_slice(&u64::MAX.to_be_bytes());
+        assert_eq!(read_message(&mut &huge[..]).unwrap(), None);
+    }
+
+    #[test]
+    fn test_oversized_head() {
+        let head = format!("GET /{} HTTP/1.1", "a".repeat(MAX_HEAD * 4));
+        let mut reader = head.as_bytes();
+        let reply = match read_request(&mut reader).unwrap() {
+            Ok(_) => panic!("expected the head to be refused"),
+            Err(reply) => reply,
+        };
+        assert_eq!(reply.status, 431);
+        // Only one byte past the limit was read.
+        assert_eq!(reader.len(), head.len() - MAX_HEAD - 1);
+    }
+
+    #[test]
+    fn test_split_url() {
+        let (path, query) = split_url("/universes?width=8&rule=B36%2FS23&name=a+b&flag");
+        assert_eq!(path, "/universes");
+        assert_eq!(query["width"], "8");
+        assert_eq!(query["rule"], "B36/S23");
+        assert_eq!(query["name"], "a b");
+        assert_eq!(query["flag"], "");
+        assert!(split_url("/universes").1.is_empty());
+    }
+
+    #[test]
+    fn test_create_advance_snapshot() {
+        let server = server();
+        let reply = handle(
+            &server,
+            "POST /universes?width=8&height=8&boundary=dead HTTP/1.1\r\n\r\n",
+        );
+        assert_eq!((reply.status, &reply.body[..]), (201, &b"1\n"[..]));
+
+        let reply = handle(
+            &server,
+            "POST /universes/1/advance?generations=5 HTTP/1.1\r\n\r\n",
+        );
+        assert_eq!((reply.status, &reply.body[..]), (200, &b"5\n"[..]));
+
+        let reply = handle(&server, "GET /universes/1/snapshot HTTP/1.1\r\n\r\n");
+        match Message::decode(&reply.body) {
+            Ok(Message::Snapshot { seq: 0, data }) => {
+                let universe = Universe::decode_snapshot(&data).unwrap();
+                assert_eq!(universe.generation(), 5);
+                assert_eq!((universe.width(), universe.height()), (8, 8));
+            }
+            other => panic!("expected a snapshot, got {:?}", other),
+        }
+
+        let reply = handle(
+            &server,
+            "POST /universes/1/advance?generations=4294967295 HTTP/1.1\r\n\r\n",
+        );
+        assert_eq!(reply.status, 413);
+        assert_eq!(
+            handle(&server, "GET /universes/2/rle HTTP/1.1\r\n\r\n").status,
+            404
+        );
+    }
+}
@@ -0,0 +1,373 @@
+// Headless command line runner.
+//
+// Loads a pattern, runs it for a number of generations and writes
+// statistics, the final pattern or rendered frames. Handy for timing the
+// simulation and for experiments without a browser.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process;
+use std::time::Instant;
+
+use convida::{
+    encode_png, render_grid_rgba, step, Boundary, Grid, LifeLike, ParseRuleError, Rle, Seed,
+    Universe, UniverseBuilder,
+};
+
+const USAGE: &str = "\
+usage: convida [options] [pattern.rle | snapshot.cvda]
+
+Without a pattern the universe starts as a random soup.
+
+options:
+  -n, --generations N   generations to run (default 100)
+      --size WxH        universe size (default 64x64, or larger to fit the
+                        pattern, which is placed in the centre)
+      --seed N          seed of the random soup
+      --engine NAME     simulation engine: dense (default), bits, sparse or
+                        chunked
+      --boundary KIND   torus (default) or dead
+      --rule RULE       rule in B/S notation (default: the pattern's, or B3/S23)
+      --stats FILE      write generation,population CSV, `-` for stdout
+      --rle FILE        write the final state as RLE, `-` for stdout
+      --png DIR         write PNG frames into DIR
+      --png-every N     generations between frames (default: last only)
+      --cell-size PX    pixels per cell in PNG frames (default 4)
+      --trace           print the time spent in each tick to stderr (needs
+                        the tracing feature)
+  -h, --help            show this help
+";
+
+struct Options {
+    input: Option<PathBuf>,
+    generations: u32,
+    size: Option<(u32, u32)>,
+    seed: Option<u64>,
+    engine: String,
+    boundary: Boundary,
+    rule: Option<LifeLike>,
+    stats: Option<String>,
+    rle: Option<String>,
+    png: Option<PathBuf>,
+    png_every: Option<u32>,
+    cell_size: u32,
+    trace: bool,
+}
+
+fn main() {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("convida: {}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+
+    #[cfg(feature = "tracing")]
+    if options.trace {
+        convida::init_tracing();
+    }
+
+    if let Err(message) = run(&options) {
+        eprintln!("convida: {}", message);
+        process::exit(1);
+    }
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let mut options = Options {
+        input: None,
+        generations: 100,
+        size: None,
+        seed: None,
+        engine: "dense".to_string(),
+        boundary: Boundary::Torus,
+        rule: None,
+        stats: None,
+        rle: None,
+        png: None,
+        png_every: None,
+        cell_size: 4,
+        trace: false,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{} needs a value", name));
+        match arg.as_str() {
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                process::exit(0);
+            }
+            "-n" | "--generations" => options.generations = number(&arg, &value(&arg)?)?,
+            "--size" => options.size = Some(parse_size(&value(&arg)?)?),
+            "--seed" => options.seed = Some(number(&arg, &value(&arg)?)?),
+            "--engine" => {
+                let engine = value(&arg)?;
+                let engines = UniverseBuilder::engines();
+                if !engines.contains(&engine.as_str()) {
+                    return Err(format!(
+                        "unknown engine {}, expected one of: {}",
+                        engine,
+                        engines.join(", ")
+                    ));
+                }
+                options.engine = engine;
+            }
+            "--boundary" => {
+                options.boundary = match value(&arg)?.as_str() {
+                    "torus" => Boundary::Torus,
+                    "dead" => Boundary::Dead,
+                    other => return Err(format!("unknown boundary {}", other)),
+                }
+            }
+            "--rule" => {
+                let rule = value(&arg)?;
+                options.rule = Some(
+                    rule.parse()
+                        .map_err(|_: ParseRuleError| format!("unsupported rule {}", rule))?,
+                );
+            }
+            "--stats" => options.stats = Some(value(&arg)?),
+            "--rle" => options.rle = Some(value(&arg)?),
+            "--png" => options.png = Some(PathBuf::from(value(&arg)?)),
+            "--png-every" => options.png_every = Some(number::<u32>(&arg, &value(&arg)?)?.max(1)),
+            "--cell-size" => options.cell_size = number::<u32>(&arg, &value(&arg)?)?.max(1),
+            "--trace" if cfg!(feature = "tracing") => options.trace = true,
+            "--trace" => return Err("--trace needs the tracing feature".to_string()),
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(format!("unknown option {}", arg))
+            }
+            _ if options.input.is_none() => options.input = Some(PathBuf::from(arg)),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+
+    Ok(options)
+}
+
+fn number<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} expects a number, got {}", name, value))
+}
+
+fn parse_size(value: &str) -> Result<(u32, u32), String> {
+    let mut parts = value.splitn(2, 'x');
+    match (parts.next(), parts.next()) {
+        (Some(width), Some(height)) => {
+            let width = number("--size", width)?;
+            let height = number("--size", height)?;
+            if width == 0 || height == 0 {
+                return Err("--size must not be zero".to_string());
+            }
+            Ok((width, height))
+        }
+        _ => Err(format!("--size expects WIDTHxHEIGHT, got {}", value)),
+    }
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let mut board = load(options)?;
+
+    let mut stats = match &options.stats {
+        Some(path) => {
+            let mut out = create(path)?;
+            writeln!(out, "generation,population").map_err(|err| err.to_string())?;
+            Some(out)
+        }
+        None => None,
+    };
+    if let Some(dir) = &options.png {
+        fs::create_dir_all(dir).map_err(|err| format!("{}: {}", dir.display(), err))?;
+    }
+
+    let mut elapsed = std::time::Duration::default();
+    for generation in 0..=options.generations {
+        if let Some(out) = &mut stats {
+            writeln!(out, "{},{}", board.generation(), board.grid().population())
+                .map_err(|err| err.to_string())?;
+        }
+        if let Some(dir) = &options.png {
+            let frame_due = match options.png_every {
+                Some(every) => generation % every == 0,
+                None => false,
+            };
+            if frame_due || generation == options.generations {
+                write_frame(&board, dir, options.cell_size)?;
+            }
+        }
+
+        if generation < options.generations {
+            let start = Instant::now();
+            board.tick();
+            elapsed += start.elapsed();
+        }
+    }
+
+    if let Some(path) = &options.rle {
+        let mut out = create(path)?;
+        write!(out, "{}", board.to_rle()).map_err(|err| err.to_string())?;
+    }
+
+    let seconds = elapsed.as_secs_f64();
+    eprintln!(
+        "{} generations of {}x{} in {:.3}s ({:.0} generations/s)",
+        options.generations,
+        board.grid().dims().0,
+        board.grid().dims().1,
+        seconds,
+        if seconds > 0.0 {
+            options.generations as f64 / seconds
+        } else {
+            0.0
+        }
+    );
+
+    Ok(())
+}
+
+// What the runner simulates: a `Universe` for the dense engine, or another
+// engine's grid with the rule and boundary kept alongside.
+enum Board {
+    Dense(Box<Universe>),
+    Grid {
+        grid: Box<dyn Grid>,
+        rule: LifeLike,
+        boundary: Boundary,
+        generation: u32,
+    },
+}
+
+impl Board {
+    fn grid(&self) -> &dyn Grid {
+        match self {
+            Board::Dense(universe) => universe.as_ref(),
+            Board::Grid { grid, .. } => grid.as_ref(),
+        }
+    }
+
+    fn grid_mut(&mut self) -> &mut dyn Grid {
+        match self {
+            Board::Dense(universe) => universe.as_mut(),
+            Board::Grid { grid, .. } => grid.as_mut(),
+        }
+    }
+
+    fn generation(&self) -> u32 {
+        match self {
+            Board::Dense(universe) => universe.generation(),
+            Board::Grid { generation, .. } => *generation,
+        }
+    }
+
+    fn tick(&mut self) {
+        match self {
+            Board::Dense(universe) => universe.tick(),
+            Board::Grid {
+                grid,
+                rule,
+                boundary,
+                generation,
+            } => {
+                step(grid.as_mut(), rule, *boundary);
+                *generation += 1;
+            }
+        }
+    }
+
+    fn to_rle(&self) -> String {
+        match self {
+            Board::Dense(universe) => universe.to_rle(),
+            Board::Grid { grid, rule, .. } => Rle {
+                rule: *rule,
+                ..Rle::from_grid(grid.as_ref())
+            }
+            .to_string(),
+        }
+    }
+}
+
+fn load(options: &Options) -> Result<Board, String> {
+    let path = match &options.input {
+        Some(path) => path,
+        None => {
+            let (width, height) = options.size.unwrap_or((64, 64));
+            let mut builder = UniverseBuilder::new().width(width).height(height);
+            if let Some(seed) = options.seed {
+                builder = builder.rng_seed(seed);
+            }
+            return build(options, builder, options.rule.unwrap_or_default());
+        }
+    };
+
+    let bytes = fs::read(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    let error = |err: String| format!("{}: {}", path.display(), err);
+
+    if bytes.starts_with(b"CVDA") {
+        let snapshot = Universe::decode_snapshot(&bytes).map_err(|err| error(err.to_string()))?;
+        let rle = Rle::from_universe(&snapshot);
+        let builder = UniverseBuilder::new()
+            .width(rle.width)
+            .height(rle.height)
+            .seed(Seed::Empty);
+        let mut board = build(options, builder, options.rule.unwrap_or(rle.rule))?;
+        rle.place_grid(board.grid_mut(), 0, 0);
+        return Ok(board);
+    }
+
+    let text = String::from_utf8(bytes).map_err(|err| error(err.to_string()))?;
+    let pattern = Rle::parse(&text).map_err(|err| error(err.to_string()))?;
+    let (width, height) = options
+        .size
+        .unwrap_or((pattern.width.max(64), pattern.height.max(64)));
+
+    let builder = UniverseBuilder::new()
+        .width(width)
+        .height(height)
+        .seed(Seed::Empty);
+    let mut board = build(options, builder, options.rule.unwrap_or(pattern.rule))?;
+    pattern.place_grid(
+        board.grid_mut(),
+        height.saturating_sub(pattern.height) / 2,
+        width.saturating_sub(pattern.width) / 2,
+    );
+    Ok(board)
+}
+
+fn build(options: &Options, builder: UniverseBuilder, rule: LifeLike) -> Result<Board, String> {
+    let builder = builder
+        .engine(&options.engine)
+        .boundary(options.boundary)
+        .rule(&rule.to_string());
+    if options.engine == "dense" {
+        return builder
+            .build()
+            .map(|universe| Board::Dense(Box::new(universe)))
+            .map_err(|err| err.to_string());
+    }
+    let grid = builder.build_grid().map_err(|err| err.to_string())?;
+    Ok(Board::Grid {
+        grid,
+        rule,
+        boundary: options.boundary,
+        generation: 0,
+    })
+}
+
+fn create(path: &str) -> Result<Box<dyn Write>, String> {
+    if path == "-" {
+        return Ok(Box::new(io::stdout()));
+    }
+    fs::File::create(path)
+        .map(|file| Box::new(io::BufWriter::new(file)) as Box<dyn Write>)
+        .map_err(|err| format!("{}: {}", path, err))
+}
+
+fn write_frame(board: &Board, dir: &std::path::Path, cell_size: u32) -> Result<(), String> {
+    let (width, height) = board.grid().dims();
+    let pixels = render_grid_rgba(board.grid(), cell_size);
+    let png = encode_png(width * cell_size, height * cell_size, &pixels);
+    let path = dir.join(format!("frame_{:06}.png", board.generation()));
+    fs::write(&path, png).map_err(|err| format!("{}: {}", path.display(), err))
+}
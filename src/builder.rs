@@ -0,0 +1,162 @@
+// Construction options for a universe.
+//
+//     let universe = UniverseBuilder::new()
+//         .width(256)
+//         .height(128)
+//         .seed(Seed::Random { density: 0.3 })
+//         .rng_seed(7)
+//         .boundary(Boundary::Dead)
+//         .build()?;
+//
+// From JavaScript the setters chain the same way and `build()` throws on
+// invalid options.
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::error::ConvidaError;
+use crate::grid::{self, Grid, ENGINES};
+use crate::rng::Rng;
+#[cfg(feature = "web")]
+use crate::seed::JsSeed;
+use crate::seed::Seed;
+use crate::{Boundary, Cell, LifeLike, Universe};
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+#[derive(Clone, Debug)]
+pub struct UniverseBuilder {
+    width: u32,
+    height: u32,
+    seed: Seed,
+    rng_seed: Option<u64>,
+    rule: String,
+    boundary: Boundary,
+    engine: String,
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl UniverseBuilder {
+    /// A 128x128 random soup on a torus, like `Universe::new()`.
+    #[cfg_attr(feature = "web", wasm_bindgen(constructor))]
+    pub fn new() -> UniverseBuilder {
+        UniverseBuilder {
+            width: 128,
+            height: 128,
+            seed: Seed::Random { density: 0.5 },
+            rng_seed: None,
+            rule: "B3/S23".to_string(),
+            boundary: Boundary::Torus,
+            engine: "dense".to_string(),
+        }
+    }
+
+    pub fn width(mut self, width: u32) -> UniverseBuilder {
+        self.width = width;
+        self
+    }
+
+    pub fn height(mut self, height: u32) -> UniverseBuilder {
+        self.height = height;
+        self
+    }
+
+    /// Seed the random number generator so random soups can be reproduced.
+    /// Without it every build is different.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = rngSeed))]
+    pub fn rng_seed(mut self, rng_seed: u64) -> UniverseBuilder {
+        self.rng_seed = Some(rng_seed);
+        self
+    }
+
+    /// The rule in B/S or S/B notation, `B3/S23` by default.
+    pub fn rule(mut self, rule: &str) -> UniverseBuilder {
+        self.rule = rule.to_string();
+        self
+    }
+
+    pub fn boundary(mut self, boundary: Boundary) -> UniverseBuilder {
+        self.boundary = boundary;
+        self
+    }
+
+    /// The storage engine: `dense`, `bits`, `sparse` or `chunked`. Only
+    /// `build_grid` accepts engines other than `dense`.
+    pub fn engine(mut self, engine: &str) -> UniverseBuilder {
+        self.engine = engine.to_string();
+        self
+    }
+
+    /// The universe, or the first invalid option.
+    pub fn build(self) -> Result<Universe, ConvidaError> {
+        let (rule, cells) = self.prepare()?;
+        if self.engine != "dense" {
+            return Err(ConvidaError::NotDense(self.engine));
+        }
+
+        crate::diagnostics::set_panic_hook();
+        let mut universe = Universe::from_cells(self.width, self.height, cells);
+        universe.boundary = self.boundary;
+        universe.rule = rule;
+        Ok(universe)
+    }
+}
+
+impl UniverseBuilder {
+    /// The names accepted by `engine`.
+    pub fn engines() -> &'static [&'static str] {
+        ENGINES
+    }
+
+    /// The initial cells, a random soup of density 0.5 by default.
+    pub fn seed(mut self, seed: Seed) -> UniverseBuilder {
+        self.seed = seed;
+        self
+    }
+
+    /// Build the initial cells in any engine's grid. The rule and
+    /// boundary aren't part of a grid and are passed to `convida::step`.
+    pub fn build_grid(self) -> Result<Box<dyn Grid>, ConvidaError> {
+        let (_, cells) = self.prepare()?;
+        let mut grid = grid::new_grid(&self.engine, self.width, self.height)
+            .ok_or_else(|| ConvidaError::UnknownEngine(self.engine.clone()))?;
+        for (i, _) in cells
+            .iter()
+            .enumerate()
+            .filter(|&(_, &cell)| cell == Cell::Alive)
+        {
+            let i = i as u32;
+            grid.set(i / self.width, i % self.width, Cell::Alive);
+        }
+        Ok(grid)
+    }
+
+    fn prepare(&self) -> Result<(LifeLike, Vec<Cell>), ConvidaError> {
+        ConvidaError::check_size(self.width, self.height)?;
+        let rule = crate::rule::parse_rule(&self.rule)?;
+        if !ENGINES.contains(&self.engine.as_str()) {
+            return Err(ConvidaError::UnknownEngine(self.engine.clone()));
+        }
+
+        let mut rng = match self.rng_seed {
+            Some(seed) => Rng::new(seed),
+            None => Rng::from_entropy(),
+        };
+        let cells = self.seed.cells(self.width, self.height, &mut rng)?;
+        Ok((rule, cells))
+    }
+}
+
+impl Default for UniverseBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "web")]
+#[wasm_bindgen]
+impl UniverseBuilder {
+    #[wasm_bindgen(js_name = seed)]
+    pub fn seed_js(self, seed: &JsSeed) -> UniverseBuilder {
+        self.seed(seed.0.clone())
+    }
+}
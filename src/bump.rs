@@ -0,0 +1,88 @@
+// Scratch memory for the tick path and pattern parsing.
+//
+// `wee_alloc` keeps the binary small but is slow to allocate and free, and
+// every tick allocates a whole board for the next generation. With the
+// `bump` feature those buffers come from an arena that is reset each
+// generation instead of freed: the board a tick replaces becomes the next
+// tick's scratch, and the list of changed cells and the cells a pattern
+// parses to grow in place and keep their capacity, so a steady run
+// allocates nothing per tick. Without the feature each buffer is allocated
+// as it is needed.
+
+use crate::Cell;
+
+#[cfg(feature = "bump")]
+use std::cell::RefCell;
+
+#[derive(Debug, Default)]
+pub(crate) struct Scratch {
+    #[cfg(feature = "bump")]
+    cells: Vec<Cell>,
+    #[cfg(feature = "bump")]
+    changes: Vec<u32>,
+}
+
+impl Scratch {
+    /// A buffer for the next generation, starting out as a copy of `cells`.
+    pub(crate) fn next_cells(&mut self, cells: &[Cell]) -> Vec<Cell> {
+        #[cfg(feature = "bump")]
+        {
+            let mut next = std::mem::take(&mut self.cells);
+            next.clear();
+            next.extend_from_slice(cells);
+            next
+        }
+        #[cfg(not(feature = "bump"))]
+        cells.to_vec()
+    }
+
+    /// An empty list for the cells a generation changes.
+    pub(crate) fn changes(&mut self) -> Vec<u32> {
+        #[cfg(feature = "bump")]
+        {
+            let mut changes = std::mem::take(&mut self.changes);
+            changes.clear();
+            changes
+        }
+        #[cfg(not(feature = "bump"))]
+        Vec::new()
+    }
+
+    /// Hand back the buffers of a finished generation, for the next one.
+    pub(crate) fn reset(&mut self, cells: Vec<Cell>, changes: Vec<u32>) {
+        #[cfg(feature = "bump")]
+        {
+            self.cells = cells;
+            self.changes = changes;
+        }
+        #[cfg(not(feature = "bump"))]
+        drop((cells, changes));
+    }
+}
+
+#[cfg(feature = "bump")]
+thread_local! {
+    static PATTERN_CELLS: RefCell<Vec<(u32, u32)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The cells `fill` pushes, collected in the arena and copied out at their
+/// exact length.
+pub(crate) fn collect_cells<E>(
+    fill: impl FnOnce(&mut Vec<(u32, u32)>) -> Result<(), E>,
+) -> Result<Vec<(u32, u32)>, E> {
+    #[cfg(feature = "bump")]
+    {
+        PATTERN_CELLS.with(|cells| {
+            let mut cells = cells.borrow_mut();
+            cells.clear();
+            fill(&mut cells)?;
+            Ok(cells.to_vec())
+        })
+    }
+    #[cfg(not(feature = "bump"))]
+    {
+        let mut cells = Vec::new();
+        fill(&mut cells)?;
+        Ok(cells)
+    }
+}
@@ -0,0 +1,159 @@
+// Camera describing which part of the universe is shown in a viewport.
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+// Limits for the size of a cell on screen, in pixels.
+const MIN_SCALE: f64 = 1.0 / 64.0;
+const MAX_SCALE: f64 = 128.0;
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    // Size of one cell in pixels.
+    scale: f64,
+    // World position (in cells) shown at the top-left corner of the viewport.
+    offset_x: f64,
+    offset_y: f64,
+}
+
+/// Range of cells visible through a camera, clamped to the universe.
+///
+/// The start bounds are inclusive and the end bounds are exclusive.
+#[cfg_attr(feature = "web", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VisibleRange {
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = rowStart))]
+    pub row_start: u32,
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = rowEnd))]
+    pub row_end: u32,
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = colStart))]
+    pub col_start: u32,
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = colEnd))]
+    pub col_end: u32,
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Camera {
+    pub fn new(scale: f64) -> Camera {
+        Camera {
+            scale: clamp_scale(scale),
+            offset_x: 0.0,
+            offset_y: 0.0,
+        }
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = offsetX))]
+    pub fn offset_x(&self) -> f64 {
+        self.offset_x
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = offsetY))]
+    pub fn offset_y(&self) -> f64 {
+        self.offset_y
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = setScale))]
+    pub fn set_scale(&mut self, scale: f64) {
+        self.scale = clamp_scale(scale);
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = setOffset))]
+    pub fn set_offset(&mut self, x: f64, y: f64) {
+        self.offset_x = x;
+        self.offset_y = y;
+    }
+
+    /// Zoom by `factor` while keeping the world point under the screen
+    /// position (`x`, `y`) fixed, as when zooming with the mouse wheel.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = zoomAt))]
+    pub fn zoom_at(&mut self, x: f64, y: f64, factor: f64) {
+        let world_x = self.offset_x + x / self.scale;
+        let world_y = self.offset_y + y / self.scale;
+
+        self.scale = clamp_scale(self.scale * factor);
+        self.offset_x = world_x - x / self.scale;
+        self.offset_y = world_y - y / self.scale;
+    }
+
+    /// Move the view by (`dx`, `dy`) screen pixels. Dragging the contents
+    /// to the right moves the camera to the left.
+    pub fn pan(&mut self, dx: f64, dy: f64) {
+        self.offset_x -= dx / self.scale;
+        self.offset_y -= dy / self.scale;
+    }
+
+    /// The column of the cell under the screen position `x`.
+    ///
+    /// May be negative or past the edge of the universe.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = colAt))]
+    pub fn col_at(&self, x: f64) -> i64 {
+        (self.offset_x + x / self.scale).floor() as i64
+    }
+
+    /// The row of the cell under the screen position `y`.
+    ///
+    /// May be negative or past the edge of the universe.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = rowAt))]
+    pub fn row_at(&self, y: f64) -> i64 {
+        (self.offset_y + y / self.scale).floor() as i64
+    }
+
+    /// The cells of a `width` x `height` universe that are visible in a
+    /// viewport of `viewport_width` x `viewport_height` pixels.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = visibleRange))]
+    pub fn visible_range(
+        &self,
+        viewport_width: u32,
+        viewport_height: u32,
+        width: u32,
+        height: u32,
+    ) -> VisibleRange {
+        let clamp = |v: f64, max: u32| v.clamp(0.0, max as f64) as u32;
+
+        let col_start = clamp(self.offset_x.floor(), width);
+        let col_end = clamp(
+            (self.offset_x + viewport_width as f64 / self.scale).ceil(),
+            width,
+        );
+        let row_start = clamp(self.offset_y.floor(), height);
+        let row_end = clamp(
+            (self.offset_y + viewport_height as f64 / self.scale).ceil(),
+            height,
+        );
+
+        VisibleRange {
+            row_start,
+            row_end,
+            col_start,
+            col_end,
+        }
+    }
+}
+
+impl Camera {
+    /// Screen position of the top-left corner of the cell at (`row`, `col`).
+    pub fn cell_to_screen(&self, row: u32, col: u32) -> (f64, f64) {
+        (
+            (col as f64 - self.offset_x) * self.scale,
+            (row as f64 - self.offset_y) * self.scale,
+        )
+    }
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+fn clamp_scale(scale: f64) -> f64 {
+    if scale.is_nan() {
+        return 1.0;
+    }
+    scale.clamp(MIN_SCALE, MAX_SCALE)
+}
@@ -0,0 +1,172 @@
+// C interface to the simulation core, built with the `capi` feature.
+//
+// A universe is an opaque pointer owned by the caller, created with one of
+// the `convida_universe_new*` functions and released with
+// `convida_universe_free`. The declarations are in `include/convida.h`.
+
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+
+use crate::rle::Rle;
+use crate::rng::Rng;
+use crate::{Cell, ConvidaError, Universe};
+
+/// Returned by the functions that can fail.
+pub const CONVIDA_OK: c_int = 0;
+pub const CONVIDA_ERR_NULL: c_int = -1;
+pub const CONVIDA_ERR_UTF8: c_int = -2;
+pub const CONVIDA_ERR_PARSE: c_int = -3;
+pub const CONVIDA_ERR_BOUNDS: c_int = -4;
+pub const CONVIDA_ERR_REFUSED: c_int = -5;
+
+/// A `width` x `height` universe with every cell dead, or null when it
+/// would be empty or too large.
+#[no_mangle]
+pub extern "C" fn convida_universe_new(width: u32, height: u32) -> *mut Universe {
+    match ConvidaError::check_size(width, height) {
+        Ok(size) => {
+            let cells = vec![Cell::Dead; size];
+            Box::into_raw(Box::new(Universe::from_cells(width, height, cells)))
+        }
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// A `width` x `height` random soup generated from `seed`, or null when it
+/// would be empty or too large.
+#[no_mangle]
+pub extern "C" fn convida_universe_new_random(width: u32, height: u32, seed: u64) -> *mut Universe {
+    match ConvidaError::check_size(width, height) {
+        Ok(size) => {
+            let cells = crate::random(size, 0.5, &mut Rng::new(seed));
+            Box::into_raw(Box::new(Universe::from_cells(width, height, cells)))
+        }
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// # Safety
+///
+/// `universe` must be null or a pointer returned by one of the
+/// `convida_universe_new*` functions that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn convida_universe_free(universe: *mut Universe) {
+    if !universe.is_null() {
+        drop(Box::from_raw(universe));
+    }
+}
+
+/// Advance the universe by `generations`.
+///
+/// # Safety
+///
+/// `universe` must be null or a live universe pointer.
+#[no_mangle]
+pub unsafe extern "C" fn convida_universe_tick(universe: *mut Universe, generations: u32) {
+    if let Some(universe) = universe.as_mut() {
+        for _ in 0..generations {
+            universe.tick();
+        }
+    }
+}
+
+/// # Safety
+///
+/// `universe` must be null or a live universe pointer.
+#[no_mangle]
+pub unsafe extern "C" fn convida_universe_width(universe: *const Universe) -> u32 {
+    universe.as_ref().map_or(0, Universe::width)
+}
+
+/// # Safety
+///
+/// `universe` must be null or a live universe pointer.
+#[no_mangle]
+pub unsafe extern "C" fn convida_universe_height(universe: *const Universe) -> u32 {
+    universe.as_ref().map_or(0, Universe::height)
+}
+
+/// # Safety
+///
+/// `universe` must be null or a live universe pointer.
+#[no_mangle]
+pub unsafe extern "C" fn convida_universe_generation(universe: *const Universe) -> u32 {
+    universe.as_ref().map_or(0, Universe::generation)
+}
+
+/// The cells in row-major order, one byte each, 1 for alive. The pointer
+/// stays valid until the universe is next ticked, edited or freed.
+///
+/// # Safety
+///
+/// `universe` must be null or a live universe pointer.
+#[no_mangle]
+pub unsafe extern "C" fn convida_universe_cells(universe: *const Universe) -> *const u8 {
+    match universe.as_ref() {
+        Some(universe) => universe.cells.as_ptr() as *const u8,
+        None => ptr::null(),
+    }
+}
+
+/// Set the cell at (`row`, `col`) to alive when `alive` is non-zero and to
+/// dead otherwise. Refuses to edit a wall or to go over the universe's
+/// limits, like every other edit.
+///
+/// # Safety
+///
+/// `universe` must be null or a live universe pointer.
+#[no_mangle]
+pub unsafe extern "C" fn convida_universe_set_cell(
+    universe: *mut Universe,
+    row: u32,
+    col: u32,
+    alive: c_int,
+) -> c_int {
+    let universe = match universe.as_mut() {
+        Some(universe) => universe,
+        None => return CONVIDA_ERR_NULL,
+    };
+    let cell = if alive != 0 { Cell::Alive } else { Cell::Dead };
+    match universe.set_cell(row, col, cell) {
+        Ok(()) => CONVIDA_OK,
+        Err(ConvidaError::OutOfBounds { .. }) => CONVIDA_ERR_BOUNDS,
+        Err(_) => CONVIDA_ERR_REFUSED,
+    }
+}
+
+/// Bring the RLE pattern in the NUL-terminated string `rle` to life with
+/// its top-left corner at (`row`, `col`), wrapping around the edges. Walls
+/// stay dead, and the whole stamp is refused when it would go over the
+/// universe's limits.
+///
+/// # Safety
+///
+/// `universe` must be null or a live universe pointer, and `rle` null or a
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn convida_universe_stamp_rle(
+    universe: *mut Universe,
+    rle: *const c_char,
+    row: u32,
+    col: u32,
+) -> c_int {
+    let universe = match universe.as_mut() {
+        Some(universe) => universe,
+        None => return CONVIDA_ERR_NULL,
+    };
+    if rle.is_null() {
+        return CONVIDA_ERR_NULL;
+    }
+    let text = match CStr::from_ptr(rle).to_str() {
+        Ok(text) => text,
+        Err(_) => return CONVIDA_ERR_UTF8,
+    };
+    match Rle::parse(text) {
+        Ok(pattern) => match universe.stamp(&pattern, row, col) {
+            Ok(()) => CONVIDA_OK,
+            Err(_) => CONVIDA_ERR_REFUSED,
+        },
+        Err(_) => CONVIDA_ERR_PARSE,
+    }
+}
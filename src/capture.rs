@@ -0,0 +1,137 @@
+// Video capture of a canvas through the MediaRecorder API.
+//
+// A `VideoCapture` records the stream of a canvas, collecting the chunks
+// the recorder hands over, and `stop` resolves to a single `Blob` of the
+// whole run, ready for an `<a download>` link:
+//
+//     const capture = game.videoCapture();
+//     capture.start();
+//     // ...
+//     const video = await capture.stop();
+
+use js_sys::{Array, Function, Promise};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{
+    Blob, BlobEvent, BlobPropertyBag, HtmlCanvasElement, MediaRecorder, MediaRecorderOptions,
+    RecordingState,
+};
+
+const DEFAULT_MIME_TYPE: &str = "video/webm";
+
+#[wasm_bindgen]
+pub struct VideoCapture {
+    recorder: MediaRecorder,
+    mime_type: String,
+    chunks: Array,
+    _on_data: Closure<dyn FnMut(BlobEvent)>,
+}
+
+#[wasm_bindgen]
+impl VideoCapture {
+    /// Prepare to record `canvas` at up to `fps` frames per second, or
+    /// whenever it is drawn without one, as `mime_type`, `video/webm`
+    /// unless given.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        canvas: &HtmlCanvasElement,
+        fps: Option<f64>,
+        mime_type: Option<String>,
+    ) -> Result<VideoCapture, JsValue> {
+        let stream = match fps {
+            Some(fps) => canvas.capture_stream_with_frame_request_rate(fps)?,
+            None => canvas.capture_stream()?,
+        };
+        let mime_type = mime_type.unwrap_or_else(|| DEFAULT_MIME_TYPE.to_string());
+        let options = MediaRecorderOptions::new();
+        options.set_mime_type(&mime_type);
+        let recorder =
+            MediaRecorder::new_with_media_stream_and_media_recorder_options(&stream, &options)?;
+
+        let chunks = Array::new();
+        let on_data = {
+            let chunks = chunks.clone();
+            Closure::wrap(Box::new(move |event: BlobEvent| {
+                if let Some(data) = event.data() {
+                    if data.size() > 0.0 {
+                        chunks.push(&data);
+                    }
+                }
+            }) as Box<dyn FnMut(BlobEvent)>)
+        };
+        recorder.set_ondataavailable(Some(on_data.as_ref().unchecked_ref()));
+
+        Ok(VideoCapture {
+            recorder,
+            mime_type,
+            chunks,
+            _on_data: on_data,
+        })
+    }
+
+    /// Whether the browser can record `mime_type`, such as
+    /// `video/webm;codecs=vp9`.
+    #[wasm_bindgen(js_name = isTypeSupported)]
+    pub fn is_type_supported(mime_type: &str) -> bool {
+        MediaRecorder::is_type_supported(mime_type)
+    }
+
+    /// Start recording, dropping the chunks of any earlier recording.
+    /// With `timeslice` milliseconds, the recorder hands over a chunk that
+    /// often instead of once at the end.
+    pub fn start(&mut self, timeslice: Option<i32>) -> Result<(), JsValue> {
+        self.chunks.set_length(0);
+        match timeslice {
+            Some(timeslice) => self.recorder.start_with_time_slice(timeslice),
+            None => self.recorder.start(),
+        }
+    }
+
+    pub fn pause(&self) -> Result<(), JsValue> {
+        self.recorder.pause()
+    }
+
+    pub fn resume(&self) -> Result<(), JsValue> {
+        self.recorder.resume()
+    }
+
+    /// Stop recording. The promise resolves to a `Blob` of the whole
+    /// recording once the recorder has handed over its last chunk.
+    pub fn stop(&self) -> Result<Promise, JsValue> {
+        let (chunks, mime_type) = (self.chunks.clone(), self.mime_type.clone());
+        let recorder = self.recorder.clone();
+        let promise = Promise::new(&mut |resolve: Function, reject: Function| {
+            let (chunks, mime_type) = (chunks.clone(), mime_type.clone());
+            let on_stop = Closure::once_into_js(move || {
+                let _ = match blob(&chunks, &mime_type) {
+                    Ok(blob) => resolve.call1(&JsValue::NULL, &blob),
+                    Err(err) => reject.call1(&JsValue::NULL, &err),
+                };
+            });
+            recorder.set_onstop(Some(on_stop.unchecked_ref()));
+        });
+        self.recorder.stop()?;
+        Ok(promise)
+    }
+
+    /// `inactive`, `recording` or `paused`.
+    pub fn state(&self) -> String {
+        match self.recorder.state() {
+            RecordingState::Recording => "recording",
+            RecordingState::Paused => "paused",
+            _ => "inactive",
+        }
+        .to_string()
+    }
+
+    /// The chunks handed over so far as one `Blob`.
+    pub fn blob(&self) -> Result<Blob, JsValue> {
+        blob(&self.chunks, &self.mime_type)
+    }
+}
+
+fn blob(chunks: &Array, mime_type: &str) -> Result<Blob, JsValue> {
+    let options = BlobPropertyBag::new();
+    options.set_type(mime_type);
+    Blob::new_with_blob_sequence_and_options(chunks, &options)
+}
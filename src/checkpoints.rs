@@ -0,0 +1,170 @@
+// Named checkpoints, for marking interesting moments of a long run and
+// jumping between them.
+//
+// Each checkpoint is a packed snapshot, one bit per cell, held in memory
+// with the universe. Snapshots are interned in blocks, so the parts of the
+// board that didn't change between them are only stored once. Restoring
+// one brings back its size, cells and generation; the rule, boundary and
+// limits stay as they are.
+//
+// Autosaves are kept the same way in a ring of the last few, taken every so
+// many generations as the universe steps, so a recent state can be
+// recovered after a crash or an accidental clear even if nobody saved it.
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use std::collections::VecDeque;
+
+use crate::intern::{Interned, Store};
+use crate::{ConvidaError, Universe};
+
+// The bytes of snapshot stored together, 512 cells.
+const BLOCK_SIZE: usize = 64;
+
+#[derive(Clone, Debug)]
+struct Autosave {
+    every: u32,
+    keep: usize,
+    /// (generation, snapshot) pairs, oldest first.
+    ring: VecDeque<(u32, Interned)>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Checkpoints {
+    named: Vec<(String, Interned)>,
+    autosave: Option<Autosave>,
+    store: Store,
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Universe {
+    /// Save the current state as `name`, replacing any checkpoint of the
+    /// same name.
+    pub fn checkpoint(&mut self, name: &str) {
+        let snapshot = self.encode_snapshot();
+        let checkpoints = &mut self.checkpoints;
+        let snapshot = checkpoints.store.intern(&snapshot, BLOCK_SIZE);
+        match checkpoints
+            .named
+            .iter_mut()
+            .find(|(existing, _)| existing == name)
+        {
+            Some((_, bytes)) => *bytes = snapshot,
+            None => checkpoints.named.push((name.to_string(), snapshot)),
+        }
+        checkpoints.store.collect();
+    }
+
+    /// Go back, or forward, to the state saved as `name`.
+    pub fn restore(&mut self, name: &str) -> Result<(), ConvidaError> {
+        self.enter("Universe::restore");
+        let bytes = self
+            .checkpoints
+            .named
+            .iter()
+            .find(|(existing, _)| existing == name)
+            .map(|(_, bytes)| bytes.to_vec())
+            .ok_or_else(|| ConvidaError::UnknownCheckpoint(name.to_string()))?;
+        Ok(self.restore_snapshot(&bytes)?)
+    }
+
+    /// The names of the checkpoints, oldest first.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = listCheckpoints))]
+    pub fn list_checkpoints(&self) -> Vec<String> {
+        self.checkpoints
+            .named
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = removeCheckpoint))]
+    pub fn remove_checkpoint(&mut self, name: &str) {
+        self.checkpoints
+            .named
+            .retain(|(existing, _)| existing != name);
+        self.checkpoints.store.collect();
+    }
+
+    /// The bytes the checkpoints and autosaves take up, each distinct block
+    /// of snapshot counted once.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = checkpointMemory))]
+    pub fn checkpoint_memory(&self) -> u32 {
+        self.checkpoints.store.bytes() as u32
+    }
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Universe {
+    /// Save the state every `every_n_generations` generations, keeping the
+    /// last `keep_last_k`. The current state is saved straight away.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = enableAutosave))]
+    pub fn enable_autosave(&mut self, every_n_generations: u32, keep_last_k: u32) {
+        self.checkpoints.autosave = Some(Autosave {
+            every: every_n_generations.max(1),
+            keep: keep_last_k.max(1) as usize,
+            ring: VecDeque::new(),
+        });
+        self.save_autosave();
+    }
+
+    /// Stop autosaving and drop the autosaves.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = disableAutosave))]
+    pub fn disable_autosave(&mut self) {
+        self.checkpoints.autosave = None;
+        self.checkpoints.store.collect();
+    }
+
+    /// The generations of the autosaves, oldest first.
+    pub fn autosaves(&self) -> Vec<u32> {
+        self.checkpoints
+            .autosave
+            .iter()
+            .flat_map(|autosave| autosave.ring.iter().map(|&(generation, _)| generation))
+            .collect()
+    }
+
+    /// Go back to the autosave of `generation`.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = restoreAutosave))]
+    pub fn restore_autosave(&mut self, generation: u32) -> Result<(), ConvidaError> {
+        self.enter("Universe::restore_autosave");
+        let bytes = self
+            .checkpoints
+            .autosave
+            .iter()
+            .flat_map(|autosave| autosave.ring.iter())
+            .find(|&(saved, _)| *saved == generation)
+            .map(|(_, bytes)| bytes.to_vec())
+            .ok_or(ConvidaError::NoAutosave { generation })?;
+        Ok(self.restore_snapshot(&bytes)?)
+    }
+}
+
+impl Universe {
+    // Called after every step.
+    pub(crate) fn autosave(&mut self) {
+        let due = match &self.checkpoints.autosave {
+            Some(autosave) => self.generation.is_multiple_of(autosave.every),
+            None => false,
+        };
+        if due {
+            self.save_autosave();
+        }
+    }
+
+    fn save_autosave(&mut self) {
+        let snapshot = self.encode_snapshot();
+        let generation = self.generation;
+        let checkpoints = &mut self.checkpoints;
+        if let Some(autosave) = &mut checkpoints.autosave {
+            autosave.ring.retain(|(saved, _)| *saved != generation);
+            let snapshot = checkpoints.store.intern(&snapshot, BLOCK_SIZE);
+            autosave.ring.push_back((generation, snapshot));
+            while autosave.ring.len() > autosave.keep {
+                autosave.ring.pop_front();
+            }
+            checkpoints.store.collect();
+        }
+    }
+}
@@ -0,0 +1,552 @@
+// The snake_case names of the JavaScript API, kept as deprecated aliases of
+// the camelCase ones for one release. TypeScript flags their use through
+// the `@deprecated` tag in the generated declarations.
+//
+// `width()`, `height()`, `generation()`, `rule()` and `boundary()` became
+// read-only properties and have no aliases. Setters such as
+// `universe.boundary = ...` have to wait until the aliases are gone, as
+// wasm-bindgen exports a setter under the same symbol as `set_boundary()`.
+
+use js_sys::Function;
+use wasm_bindgen::prelude::*;
+use web_sys::RtcDataChannel;
+
+use crate::audio::Sonifier;
+use crate::camera::{Camera, VisibleRange};
+use crate::game_loop::GameLoop;
+use crate::midi::MidiSequencer;
+use crate::sync::SyncSession;
+use crate::{
+    diagnostics, logging, platform, Boundary, ConvidaError, JsSeed, LogLevel, Renderer, Universe,
+    UniverseBuilder,
+};
+
+#[wasm_bindgen]
+impl Sonifier {
+    /// @deprecated Use `isEnabled`.
+    #[wasm_bindgen(js_name = is_enabled)]
+    pub fn deprecated_is_enabled(&self) -> bool {
+        self.is_enabled()
+    }
+
+    /// @deprecated Use `setMapping`.
+    #[wasm_bindgen(js_name = set_mapping)]
+    pub fn deprecated_set_mapping(&mut self, mapping: &str) -> Result<(), JsValue> {
+        self.set_mapping(mapping)
+    }
+
+    /// @deprecated Use `setColumns`.
+    #[wasm_bindgen(js_name = set_columns)]
+    pub fn deprecated_set_columns(&mut self, columns: u32) -> Result<(), JsValue> {
+        self.set_columns(columns)
+    }
+
+    /// @deprecated Use `setFrequencyRange`.
+    #[wasm_bindgen(js_name = set_frequency_range)]
+    pub fn deprecated_set_frequency_range(&mut self, low: f32, high: f32) {
+        self.set_frequency_range(low, high)
+    }
+
+    /// @deprecated Use `setVolume`.
+    #[wasm_bindgen(js_name = set_volume)]
+    pub fn deprecated_set_volume(&mut self, volume: f32) {
+        self.set_volume(volume)
+    }
+}
+
+#[wasm_bindgen]
+impl UniverseBuilder {
+    /// @deprecated Use `rngSeed`.
+    #[wasm_bindgen(js_name = rng_seed)]
+    pub fn deprecated_rng_seed(self, rng_seed: u64) -> UniverseBuilder {
+        self.rng_seed(rng_seed)
+    }
+}
+
+#[wasm_bindgen]
+impl Camera {
+    /// @deprecated Use `offsetX`.
+    #[wasm_bindgen(js_name = offset_x)]
+    pub fn deprecated_offset_x(&self) -> f64 {
+        self.offset_x()
+    }
+
+    /// @deprecated Use `offsetY`.
+    #[wasm_bindgen(js_name = offset_y)]
+    pub fn deprecated_offset_y(&self) -> f64 {
+        self.offset_y()
+    }
+
+    /// @deprecated Use `setScale`.
+    #[wasm_bindgen(js_name = set_scale)]
+    pub fn deprecated_set_scale(&mut self, scale: f64) {
+        self.set_scale(scale)
+    }
+
+    /// @deprecated Use `setOffset`.
+    #[wasm_bindgen(js_name = set_offset)]
+    pub fn deprecated_set_offset(&mut self, x: f64, y: f64) {
+        self.set_offset(x, y)
+    }
+
+    /// @deprecated Use `zoomAt`.
+    #[wasm_bindgen(js_name = zoom_at)]
+    pub fn deprecated_zoom_at(&mut self, x: f64, y: f64, factor: f64) {
+        self.zoom_at(x, y, factor)
+    }
+
+    /// @deprecated Use `colAt`.
+    #[wasm_bindgen(js_name = col_at)]
+    pub fn deprecated_col_at(&self, x: f64) -> i32 {
+        self.col_at(x).clamp(i32::MIN.into(), i32::MAX.into()) as i32
+    }
+
+    /// @deprecated Use `rowAt`.
+    #[wasm_bindgen(js_name = row_at)]
+    pub fn deprecated_row_at(&self, y: f64) -> i32 {
+        self.row_at(y).clamp(i32::MIN.into(), i32::MAX.into()) as i32
+    }
+
+    /// @deprecated Use `visibleRange`.
+    #[wasm_bindgen(js_name = visible_range)]
+    pub fn deprecated_visible_range(
+        &self,
+        viewport_width: u32,
+        viewport_height: u32,
+        width: u32,
+        height: u32,
+    ) -> VisibleRange {
+        self.visible_range(viewport_width, viewport_height, width, height)
+    }
+}
+
+#[wasm_bindgen]
+impl VisibleRange {
+    /// @deprecated Use `rowStart`.
+    #[wasm_bindgen(getter = row_start)]
+    pub fn deprecated_row_start(&self) -> u32 {
+        self.row_start
+    }
+    /// @deprecated Use `rowEnd`.
+    #[wasm_bindgen(getter = row_end)]
+    pub fn deprecated_row_end(&self) -> u32 {
+        self.row_end
+    }
+    /// @deprecated Use `colStart`.
+    #[wasm_bindgen(getter = col_start)]
+    pub fn deprecated_col_start(&self) -> u32 {
+        self.col_start
+    }
+    /// @deprecated Use `colEnd`.
+    #[wasm_bindgen(getter = col_end)]
+    pub fn deprecated_col_end(&self) -> u32 {
+        self.col_end
+    }
+}
+
+#[wasm_bindgen]
+impl GameLoop {
+    /// @deprecated Use `onLongPress`.
+    #[wasm_bindgen(js_name = on_long_press)]
+    pub fn deprecated_on_long_press(
+        &mut self,
+        handler: Option<Function>,
+    ) -> Result<(), ConvidaError> {
+        self.on_long_press(handler)
+    }
+
+    /// @deprecated Use `enableGamepad`.
+    #[wasm_bindgen(js_name = enable_gamepad)]
+    pub fn deprecated_enable_gamepad(&mut self, enabled: bool) -> Result<(), ConvidaError> {
+        self.enable_gamepad(enabled)
+    }
+
+    /// @deprecated Use `setGamepadStamp`.
+    #[wasm_bindgen(js_name = set_gamepad_stamp)]
+    pub fn deprecated_set_gamepad_stamp(&mut self, cells: &[u32]) -> Result<(), ConvidaError> {
+        self.set_gamepad_stamp(cells)
+    }
+
+    /// @deprecated Use `isRunning`.
+    #[wasm_bindgen(js_name = is_running)]
+    pub fn deprecated_is_running(&self) -> Result<bool, ConvidaError> {
+        self.is_running()
+    }
+
+    /// @deprecated Use `generationsPerFrame`.
+    #[wasm_bindgen(js_name = generations_per_frame)]
+    pub fn deprecated_generations_per_frame(&self) -> Result<u32, ConvidaError> {
+        self.generations_per_frame()
+    }
+
+    /// @deprecated Use `setGenerationsPerFrame`.
+    #[wasm_bindgen(js_name = set_generations_per_frame)]
+    pub fn deprecated_set_generations_per_frame(
+        &mut self,
+        generations: u32,
+    ) -> Result<(), ConvidaError> {
+        self.set_generations_per_frame(generations)
+    }
+
+    /// @deprecated Use `renderEvery`.
+    #[wasm_bindgen(js_name = render_every)]
+    pub fn deprecated_render_every(&self) -> Result<u32, ConvidaError> {
+        self.render_every()
+    }
+
+    /// @deprecated Use `setRenderEvery`.
+    #[wasm_bindgen(js_name = set_render_every)]
+    pub fn deprecated_set_render_every(&mut self, frames: u32) -> Result<(), ConvidaError> {
+        self.set_render_every(frames)
+    }
+
+    /// @deprecated Use `setSonifier`.
+    #[wasm_bindgen(js_name = set_sonifier)]
+    pub fn deprecated_set_sonifier(
+        &mut self,
+        sonifier: Option<Sonifier>,
+    ) -> Result<(), ConvidaError> {
+        self.set_sonifier(sonifier)
+    }
+
+    /// @deprecated Use `setMidi`.
+    #[wasm_bindgen(js_name = set_midi)]
+    pub fn deprecated_set_midi(&mut self, midi: Option<MidiSequencer>) -> Result<(), ConvidaError> {
+        self.set_midi(midi)
+    }
+
+    /// @deprecated Use `toggleCell`.
+    #[wasm_bindgen(js_name = toggle_cell)]
+    pub fn deprecated_toggle_cell(&mut self, row: u32, col: u32) -> Result<(), ConvidaError> {
+        self.toggle_cell(row, col)
+    }
+
+    /// @deprecated Use `setCamera`.
+    #[wasm_bindgen(js_name = set_camera)]
+    pub fn deprecated_set_camera(&mut self, camera: Camera) -> Result<(), ConvidaError> {
+        self.set_camera(camera)
+    }
+
+    /// @deprecated Use `zoomAt`.
+    #[wasm_bindgen(js_name = zoom_at)]
+    pub fn deprecated_zoom_at(&mut self, x: f64, y: f64, factor: f64) -> Result<(), ConvidaError> {
+        self.zoom_at(x, y, factor)
+    }
+}
+
+#[wasm_bindgen]
+impl Universe {
+    /// @deprecated Use `onEvent`.
+    #[wasm_bindgen(js_name = on_event)]
+    pub fn deprecated_on_event(&mut self, handler: Option<Function>) {
+        self.on_event(handler)
+    }
+
+    /// @deprecated Use `watchPattern`.
+    #[wasm_bindgen(js_name = watch_pattern)]
+    pub fn deprecated_watch_pattern(&mut self, name: &str, cells: &[u32]) {
+        self.watch_pattern(name, cells)
+    }
+
+    /// @deprecated Use `unwatchPattern`.
+    #[wasm_bindgen(js_name = unwatch_pattern)]
+    pub fn deprecated_unwatch_pattern(&mut self, name: &str) {
+        self.unwatch_pattern(name)
+    }
+
+    /// @deprecated Use `onTick`.
+    #[wasm_bindgen(js_name = on_tick)]
+    pub fn deprecated_on_tick(&mut self, callback: Option<Function>) {
+        self.on_tick(callback)
+    }
+
+    /// @deprecated Use `onCellChange`.
+    #[wasm_bindgen(js_name = on_cell_change)]
+    pub fn deprecated_on_cell_change(&mut self, callback: Option<Function>) {
+        self.on_cell_change(callback)
+    }
+
+    /// @deprecated Use `setCellChangeBatching`.
+    #[wasm_bindgen(js_name = set_cell_change_batching)]
+    pub fn deprecated_set_cell_change_batching(&mut self, batch: bool) {
+        self.set_cell_change_batching(batch)
+    }
+
+    /// @deprecated Use `newWithSize`.
+    #[wasm_bindgen(js_name = new_with_size)]
+    pub fn deprecated_new_with_size(width: u32, height: u32) -> Result<Universe, ConvidaError> {
+        Self::new_with_size(width, height)
+    }
+
+    /// @deprecated Use `setSize`.
+    #[wasm_bindgen(js_name = set_size)]
+    pub fn deprecated_set_size(&mut self, width: u32, height: u32) -> Result<(), ConvidaError> {
+        self.set_size(width, height)
+    }
+
+    /// @deprecated Use `liveCells`.
+    #[wasm_bindgen(js_name = live_cells)]
+    pub fn deprecated_live_cells(&self) -> Vec<u32> {
+        self.live_cells()
+    }
+
+    /// @deprecated Use `setWidth`.
+    #[wasm_bindgen(js_name = set_width)]
+    pub fn deprecated_set_width(&mut self, width: u32) -> Result<(), ConvidaError> {
+        self.set_width(width)
+    }
+
+    /// @deprecated Use `setHeight`.
+    #[wasm_bindgen(js_name = set_height)]
+    pub fn deprecated_set_height(&mut self, height: u32) -> Result<(), ConvidaError> {
+        self.set_height(height)
+    }
+
+    /// @deprecated Use `toggleCell`.
+    #[wasm_bindgen(js_name = toggle_cell)]
+    pub fn deprecated_toggle_cell(&mut self, row: u32, col: u32) -> Result<(), ConvidaError> {
+        self.toggle_cell(row, col)
+    }
+
+    /// @deprecated Use `renderRgba`.
+    #[wasm_bindgen(js_name = render_rgba)]
+    pub fn deprecated_render_rgba(&self, cell_size: u32) -> Vec<u8> {
+        self.render_rgba(cell_size)
+    }
+
+    /// @deprecated Use `renderMinimap`.
+    #[wasm_bindgen(js_name = render_minimap)]
+    pub fn deprecated_render_minimap(
+        &self,
+        target_width: u32,
+        target_height: u32,
+    ) -> Result<Vec<u8>, ConvidaError> {
+        self.render_minimap(target_width, target_height)
+    }
+
+    /// @deprecated Use `toRle`.
+    #[wasm_bindgen(js_name = to_rle)]
+    pub fn deprecated_to_rle(&self) -> String {
+        self.to_rle()
+    }
+
+    /// @deprecated Use `fromRle`.
+    #[wasm_bindgen(js_name = from_rle)]
+    pub fn deprecated_from_rle(text: &str) -> Result<Universe, ConvidaError> {
+        Self::from_rle(text)
+    }
+
+    /// @deprecated Use `setRule`.
+    #[wasm_bindgen(js_name = set_rule)]
+    pub fn deprecated_set_rule(&mut self, rule: &str) -> Result<(), ConvidaError> {
+        self.set_rule_js(rule)
+    }
+
+    /// @deprecated Use `newWithSeed`.
+    #[wasm_bindgen(js_name = new_with_seed)]
+    pub fn deprecated_new_with_seed(
+        width: u32,
+        height: u32,
+        seed: &JsSeed,
+        rng_seed: u64,
+    ) -> Result<Universe, ConvidaError> {
+        Self::new_with_seed_js(width, height, seed, rng_seed)
+    }
+
+    /// @deprecated Use `resetWith`.
+    #[wasm_bindgen(js_name = reset_with)]
+    pub fn deprecated_reset_with(&mut self, seed: &JsSeed) -> Result<(), ConvidaError> {
+        self.reset_with_js(seed)
+    }
+
+    /// @deprecated Use `toSnapshot`.
+    #[wasm_bindgen(js_name = to_snapshot)]
+    pub fn deprecated_to_snapshot(&self) -> Vec<u8> {
+        self.to_snapshot()
+    }
+
+    /// @deprecated Use `fromSnapshot`.
+    #[wasm_bindgen(js_name = from_snapshot)]
+    pub fn deprecated_from_snapshot(bytes: &[u8]) -> Result<Universe, ConvidaError> {
+        Self::from_snapshot(bytes)
+    }
+
+    /// @deprecated Use `loadSnapshot`.
+    #[wasm_bindgen(js_name = load_snapshot)]
+    pub fn deprecated_load_snapshot(&mut self, bytes: &[u8]) -> Result<(), ConvidaError> {
+        self.load_snapshot(bytes)
+    }
+
+    /// @deprecated Use `fromString`.
+    #[wasm_bindgen(js_name = from_string)]
+    pub fn deprecated_from_string(text: &str) -> Result<Universe, ConvidaError> {
+        Self::from_string_js(text)
+    }
+
+    /// @deprecated Use `setBoundary`.
+    #[wasm_bindgen(js_name = set_boundary)]
+    pub fn deprecated_set_boundary(&mut self, boundary: Boundary) {
+        self.set_boundary(boundary)
+    }
+}
+
+#[wasm_bindgen]
+impl Renderer {
+    /// @deprecated Use `setCamera`.
+    #[wasm_bindgen(js_name = set_camera)]
+    pub fn deprecated_set_camera(&mut self, camera: Camera) {
+        self.set_camera(camera)
+    }
+
+    /// @deprecated Use `zoomAt`.
+    #[wasm_bindgen(js_name = zoom_at)]
+    pub fn deprecated_zoom_at(&mut self, x: f64, y: f64, factor: f64) {
+        self.zoom_at(x, y, factor)
+    }
+
+    /// @deprecated Use `setColors`.
+    #[wasm_bindgen(js_name = set_colors)]
+    pub fn deprecated_set_colors(&mut self, alive: u32, dead: u32) {
+        self.set_colors(alive, dead)
+    }
+}
+
+#[wasm_bindgen]
+impl MidiSequencer {
+    /// @deprecated Use `addTriggerRow`.
+    #[wasm_bindgen(js_name = add_trigger_row)]
+    pub fn deprecated_add_trigger_row(&mut self, row: u32, note: u8, channel: u8) {
+        self.add_trigger_row(row, note, channel)
+    }
+
+    /// @deprecated Use `addTriggerRegion`.
+    #[wasm_bindgen(js_name = add_trigger_region)]
+    pub fn deprecated_add_trigger_region(
+        &mut self,
+        row: u32,
+        col_start: u32,
+        col_end: u32,
+        note: u8,
+        channel: u8,
+    ) {
+        self.add_trigger_region(row, col_start, col_end, note, channel)
+    }
+
+    /// @deprecated Use `removeTriggerRow`.
+    #[wasm_bindgen(js_name = remove_trigger_row)]
+    pub fn deprecated_remove_trigger_row(&mut self, row: u32) {
+        self.remove_trigger_row(row)
+    }
+
+    /// @deprecated Use `clearTriggers`.
+    #[wasm_bindgen(js_name = clear_triggers)]
+    pub fn deprecated_clear_triggers(&mut self) {
+        self.clear_triggers()
+    }
+
+    /// @deprecated Use `allNotesOff`.
+    #[wasm_bindgen(js_name = all_notes_off)]
+    pub fn deprecated_all_notes_off(&mut self) {
+        self.all_notes_off()
+    }
+}
+
+#[wasm_bindgen]
+impl SyncSession {
+    /// @deprecated Use `overDataChannel`.
+    #[wasm_bindgen(js_name = over_data_channel)]
+    pub fn deprecated_over_data_channel(channel: RtcDataChannel, host: bool) -> SyncSession {
+        Self::over_data_channel(channel, host)
+    }
+
+    /// @deprecated Use `isAuthority`.
+    #[wasm_bindgen(js_name = is_authority)]
+    pub fn deprecated_is_authority(&self) -> bool {
+        self.is_authority()
+    }
+
+    /// @deprecated Use `enableCrdt`.
+    #[wasm_bindgen(js_name = enable_crdt)]
+    pub fn deprecated_enable_crdt(&mut self, replica: u32) {
+        self.enable_crdt(replica)
+    }
+
+    /// @deprecated Use `exportCrdt`.
+    #[wasm_bindgen(js_name = export_crdt)]
+    pub fn deprecated_export_crdt(&self) -> Vec<u8> {
+        self.export_crdt()
+    }
+
+    /// @deprecated Use `importCrdt`.
+    #[wasm_bindgen(js_name = import_crdt)]
+    pub fn deprecated_import_crdt(&mut self, bytes: &[u8]) -> Result<(), JsValue> {
+        self.import_crdt(bytes)
+    }
+
+    /// @deprecated Use `proposeEdit`.
+    #[wasm_bindgen(js_name = propose_edit)]
+    pub fn deprecated_propose_edit(
+        &mut self,
+        universe: &Universe,
+        row: u32,
+        col: u32,
+        alive: bool,
+    ) -> Result<(), JsValue> {
+        self.propose_edit(universe, row, col, alive)
+    }
+
+    /// @deprecated Use `setChecksumInterval`.
+    #[wasm_bindgen(js_name = set_checksum_interval)]
+    pub fn deprecated_set_checksum_interval(&mut self, generations: u32) {
+        self.set_checksum_interval(generations)
+    }
+
+    /// @deprecated Use `lastSeq`.
+    #[wasm_bindgen(js_name = last_seq)]
+    pub fn deprecated_last_seq(&self) -> u32 {
+        self.last_seq()
+    }
+
+    /// @deprecated Use `isDesynced`.
+    #[wasm_bindgen(js_name = is_desynced)]
+    pub fn deprecated_is_desynced(&self) -> bool {
+        self.is_desynced()
+    }
+
+    /// @deprecated Use `clearDesync`.
+    #[wasm_bindgen(js_name = clear_desync)]
+    pub fn deprecated_clear_desync(&mut self) {
+        self.clear_desync()
+    }
+}
+
+/// @deprecated Use `setLogLevel`.
+#[wasm_bindgen(js_name = set_log_level)]
+pub fn deprecated_set_log_level(level: LogLevel) {
+    logging::set_log_level(level)
+}
+
+/// @deprecated Use `logLevel`.
+#[wasm_bindgen(js_name = log_level)]
+pub fn deprecated_log_level() -> LogLevel {
+    logging::log_level()
+}
+
+/// @deprecated Use `lastError`.
+#[wasm_bindgen(js_name = last_error)]
+pub fn deprecated_last_error() -> Option<String> {
+    diagnostics::last_error()
+}
+
+/// @deprecated Use `initTracing`.
+#[cfg(feature = "tracing")]
+#[wasm_bindgen(js_name = init_tracing)]
+pub fn deprecated_init_tracing() {
+    crate::init_tracing()
+}
+
+/// @deprecated Use `hasWindow`.
+#[wasm_bindgen(js_name = has_window)]
+pub fn deprecated_has_window() -> bool {
+    platform::has_window()
+}
@@ -0,0 +1,140 @@
+// Conflict-free merging of cell edits.
+//
+// Each cell is a last-writer-wins register stamped with a Lamport
+// timestamp. Ties are broken by replica id, so any two replicas that have
+// seen the same set of edits agree on every cell no matter in which order
+// the edits arrived. This lets peers keep editing while disconnected and
+// converge once they exchange their registers again.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use crate::{Cell, Universe};
+
+/// Lamport timestamp of an edit. Ordered by counter, then replica id.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Stamp {
+    pub counter: u64,
+    pub replica: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CrdtEdit {
+    pub stamp: Stamp,
+    pub row: u32,
+    pub col: u32,
+    pub state: Cell,
+}
+
+#[derive(Clone, Debug)]
+pub struct LwwGrid {
+    replica: u32,
+    clock: u64,
+    registers: HashMap<(u32, u32), (Stamp, Cell)>,
+    // Local edits not handed out by `take_unsent` yet.
+    unsent: Vec<CrdtEdit>,
+}
+
+impl LwwGrid {
+    /// `replica` must be unique among the peers editing the same universe.
+    pub fn new(replica: u32) -> LwwGrid {
+        LwwGrid {
+            replica,
+            clock: 0,
+            registers: HashMap::new(),
+            unsent: Vec::new(),
+        }
+    }
+
+    pub fn replica(&self) -> u32 {
+        self.replica
+    }
+
+    /// Record a local edit and return it stamped for sending to peers.
+    pub fn set(&mut self, row: u32, col: u32, state: Cell) -> CrdtEdit {
+        self.clock += 1;
+        let edit = CrdtEdit {
+            stamp: Stamp {
+                counter: self.clock,
+                replica: self.replica,
+            },
+            row,
+            col,
+            state,
+        };
+        self.merge(&edit);
+        self.unsent.push(edit);
+        edit
+    }
+
+    /// The local edits made since the last call, for sending to peers.
+    pub fn take_unsent(&mut self) -> Vec<CrdtEdit> {
+        std::mem::take(&mut self.unsent)
+    }
+
+    /// Merge an edit from any replica. Returns whether it won, i.e. whether
+    /// the cell has to be updated.
+    pub fn merge(&mut self, edit: &CrdtEdit) -> bool {
+        self.clock = self.clock.max(edit.stamp.counter);
+
+        match self.registers.entry((edit.row, edit.col)) {
+            Entry::Occupied(mut entry) => {
+                if entry.get().0 >= edit.stamp {
+                    return false;
+                }
+                entry.insert((edit.stamp, edit.state));
+            }
+            Entry::Vacant(entry) => {
+                entry.insert((edit.stamp, edit.state));
+            }
+        }
+        true
+    }
+
+    /// The winning state of a cell, if it was ever edited.
+    pub fn get(&self, row: u32, col: u32) -> Option<Cell> {
+        self.registers.get(&(row, col)).map(|&(_, state)| state)
+    }
+
+    /// Every register as an edit, oldest first. Sending these to a peer
+    /// brings it up to date with everything this replica has seen.
+    pub fn edits(&self) -> Vec<CrdtEdit> {
+        let mut edits: Vec<CrdtEdit> = self
+            .registers
+            .iter()
+            .map(|(&(row, col), &(stamp, state))| CrdtEdit {
+                stamp,
+                row,
+                col,
+                state,
+            })
+            .collect();
+        edits.sort_by_key(|edit| edit.stamp);
+        edits
+    }
+
+    /// Write every register's winning state into `universe`.
+    pub fn apply_to(&self, universe: &mut Universe) {
+        for (&(row, col), &(_, state)) in &self.registers {
+            write(universe, row, col, state);
+        }
+    }
+
+    /// Write the winning state of the cells of unsent local edits into
+    /// `universe`, e.g. after loading a peer's snapshot, which can't know
+    /// of them. Edits that went out are left to the board they evolved on.
+    pub fn apply_unsent_to(&self, universe: &mut Universe) {
+        for edit in &self.unsent {
+            if let Some(state) = self.get(edit.row, edit.col) {
+                write(universe, edit.row, edit.col, state);
+            }
+        }
+    }
+}
+
+fn write(universe: &mut Universe, row: u32, col: u32, state: Cell) {
+    if row < universe.height && col < universe.width {
+        let idx = universe.get_index(row, col);
+        universe.cells[idx] = state;
+    }
+}
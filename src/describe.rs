@@ -0,0 +1,290 @@
+// A textual summary of the board, for screen readers and status bars.
+//
+//     1,204 live cells, 12 gliders heading southeast, 40 blocks, population declining
+//
+// Under Conway's rule the common still lifes, oscillators and spaceships are
+// counted wherever they stand isolated, in any phase and orientation, with
+// spaceships grouped by the way they are heading. Other rules only get the
+// population. The trend compares the population with the one recorded by
+// `start_stats` a few generations back, or failing that with the previous
+// generation kept for interpolation.
+
+use std::collections::HashMap;
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::search;
+use crate::{Boundary, Cell, LifeLike, Rle, Universe};
+
+// How many generations back the trend looks, and the change in population
+// over them, as a fraction, that counts as growing or declining.
+const TREND_WINDOW: usize = 16;
+const TREND_THRESHOLD: f64 = 0.02;
+
+struct Object {
+    name: &'static str,
+    plural: &'static str,
+    rle: &'static str,
+}
+
+const OBJECTS: [Object; 12] = [
+    Object {
+        name: "glider",
+        plural: "gliders",
+        rle: "bo$2bo$3o!",
+    },
+    Object {
+        name: "lightweight spaceship",
+        plural: "lightweight spaceships",
+        rle: "bo2bo$o4b$o3bo$4o!",
+    },
+    Object {
+        name: "block",
+        plural: "blocks",
+        rle: "2o$2o!",
+    },
+    Object {
+        name: "beehive",
+        plural: "beehives",
+        rle: "b2o$o2bo$b2o!",
+    },
+    Object {
+        name: "loaf",
+        plural: "loaves",
+        rle: "b2o$o2bo$bobo$2bo!",
+    },
+    Object {
+        name: "boat",
+        plural: "boats",
+        rle: "2o$obo$bo!",
+    },
+    Object {
+        name: "ship",
+        plural: "ships",
+        rle: "2o$obo$b2o!",
+    },
+    Object {
+        name: "tub",
+        plural: "tubs",
+        rle: "bo$obo$bo!",
+    },
+    Object {
+        name: "pond",
+        plural: "ponds",
+        rle: "b2o$o2bo$o2bo$b2o!",
+    },
+    Object {
+        name: "blinker",
+        plural: "blinkers",
+        rle: "3o!",
+    },
+    Object {
+        name: "toad",
+        plural: "toads",
+        rle: "b3o$3o!",
+    },
+    Object {
+        name: "beacon",
+        plural: "beacons",
+        rle: "2o$2o$2b2o$2b2o!",
+    },
+];
+
+// A shape one of the objects takes, and the (row, col) direction it moves
+// in, (0, 0) for objects that stay put.
+struct Shape {
+    object: usize,
+    cells: Vec<(u32, u32)>,
+    velocity: (i64, i64),
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Universe {
+    /// A short description of the board, such as "1,204 live cells, 12
+    /// gliders heading southeast, 40 blocks, population declining".
+    pub fn describe(&self) -> String {
+        let population = self.iter_live().count() as u32;
+        let mut parts = vec![match population {
+            0 => "no live cells".to_string(),
+            1 => "1 live cell".to_string(),
+            n => format!("{} live cells", thousands(n)),
+        }];
+
+        if population > 0 && self.rule == LifeLike::CONWAY {
+            parts.extend(self.census().into_iter().map(|(object, heading, count)| {
+                let object = &OBJECTS[object];
+                let name = if count == 1 {
+                    object.name
+                } else {
+                    object.plural
+                };
+                match heading {
+                    Some(heading) => format!("{} {} heading {}", thousands(count), name, heading),
+                    None => format!("{} {}", thousands(count), name),
+                }
+            }));
+        }
+        if let Some(trend) = self.population_trend(population) {
+            parts.push(format!("population {}", trend));
+        }
+
+        let mut description = parts.join(", ");
+        if let Some(first) = description.get_mut(..1) {
+            first.make_ascii_uppercase();
+        }
+        description
+    }
+}
+
+impl Universe {
+    // The objects found on the board as (object, heading, count), the most
+    // common first.
+    fn census(&self) -> Vec<(usize, Option<&'static str>, u32)> {
+        let mut counts: HashMap<(usize, Option<&'static str>), u32> = HashMap::new();
+        for shape in shapes() {
+            let found = search::occurrences(self, &shape.cells).count() as u32;
+            if found > 0 {
+                *counts
+                    .entry((shape.object, heading(shape.velocity)))
+                    .or_default() += found;
+            }
+        }
+        let mut census: Vec<_> = counts
+            .into_iter()
+            .map(|((object, heading), count)| (object, heading, count))
+            .collect();
+        census.sort_by(|a, b| b.2.cmp(&a.2).then(a.0.cmp(&b.0)).then(a.1.cmp(&b.1)));
+        census
+    }
+
+    fn population_trend(&self, population: u32) -> Option<&'static str> {
+        let rows = self.stats_history();
+        let before = match rows.len() {
+            0 | 1 => {
+                let previous = self.previous.as_ref()?;
+                previous.iter().filter(|&&cell| cell == Cell::Alive).count() as u32
+            }
+            len => rows[len.saturating_sub(TREND_WINDOW)].population,
+        };
+        let change = f64::from(population) - f64::from(before);
+        Some(if change > TREND_THRESHOLD * f64::from(before) {
+            "growing"
+        } else if -change > TREND_THRESHOLD * f64::from(before) {
+            "declining"
+        } else {
+            "steady"
+        })
+    }
+}
+
+// Every phase of every object in each of its orientations, once.
+fn shapes() -> Vec<Shape> {
+    let mut shapes: Vec<Shape> = Vec::new();
+    for (object, entry) in OBJECTS.iter().enumerate() {
+        let pattern = Rle::parse(entry.rle).expect("a valid object pattern");
+        let phases = search::phases(&pattern, LifeLike::CONWAY);
+        let velocity = displacement(&pattern, phases.len() as u32);
+        for &(swap, flip_rows, flip_cols) in &ORIENTATIONS {
+            for phase in &phases {
+                let cells = orient(phase, swap, flip_rows, flip_cols);
+                if shapes.iter().all(|shape| shape.cells != cells) {
+                    let (dr, dc) = if swap {
+                        (velocity.1, velocity.0)
+                    } else {
+                        velocity
+                    };
+                    shapes.push(Shape {
+                        object,
+                        cells,
+                        velocity: (
+                            if flip_rows { -dr } else { dr },
+                            if flip_cols { -dc } else { dc },
+                        ),
+                    });
+                }
+            }
+        }
+    }
+    shapes
+}
+
+// Whether rows and columns swap, and then whether rows or columns reverse.
+const ORIENTATIONS: [(bool, bool, bool); 8] = [
+    (false, false, false),
+    (false, false, true),
+    (false, true, false),
+    (false, true, true),
+    (true, false, false),
+    (true, false, true),
+    (true, true, false),
+    (true, true, true),
+];
+
+fn orient(cells: &[(u32, u32)], swap: bool, flip_rows: bool, flip_cols: bool) -> Vec<(u32, u32)> {
+    let swapped = cells
+        .iter()
+        .map(|&(row, col)| if swap { (col, row) } else { (row, col) });
+    let bottom = swapped.clone().map(|(row, _)| row).max().unwrap_or(0);
+    let right = swapped.clone().map(|(_, col)| col).max().unwrap_or(0);
+    search::normalized(
+        swapped
+            .map(|(row, col)| {
+                (
+                    if flip_rows { bottom - row } else { row },
+                    if flip_cols { right - col } else { col },
+                )
+            })
+            .collect(),
+    )
+}
+
+// How far `pattern` moves in a period of `period` generations.
+fn displacement(pattern: &Rle, period: u32) -> (i64, i64) {
+    let pad = period + 2;
+    let (width, height) = (pattern.width + 2 * pad, pattern.height + 2 * pad);
+    let mut universe =
+        Universe::from_cells(width, height, vec![Cell::Dead; (width * height) as usize]);
+    universe.boundary = Boundary::Dead;
+    universe.rule = LifeLike::CONWAY;
+    pattern.place(&mut universe, pad, pad);
+
+    let corner = |universe: &Universe| {
+        universe
+            .bounding_box()
+            .map_or((0, 0), |b| (i64::from(b.row), i64::from(b.col)))
+    };
+    let start = corner(&universe);
+    for _ in 0..period {
+        universe.tick();
+    }
+    let end = corner(&universe);
+    (end.0 - start.0, end.1 - start.1)
+}
+
+fn heading((dr, dc): (i64, i64)) -> Option<&'static str> {
+    match (dr.signum(), dc.signum()) {
+        (-1, -1) => Some("northwest"),
+        (-1, 0) => Some("north"),
+        (-1, 1) => Some("northeast"),
+        (0, -1) => Some("west"),
+        (0, 1) => Some("east"),
+        (1, -1) => Some("southwest"),
+        (1, 0) => Some("south"),
+        (1, 1) => Some("southeast"),
+        _ => None,
+    }
+}
+
+// `n` with commas between groups of three digits.
+fn thousands(n: u32) -> String {
+    let digits = n.to_string();
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
@@ -0,0 +1,98 @@
+// Panic and error reporting.
+//
+// A panic in wasm surfaces in JavaScript as "unreachable executed" with no
+// hint of what went wrong. The panic hook installed here adds the last API
+// call and the state of the universe it was made on to the message, and
+// keeps the message for `last_error()` so the page can show it. Both are
+// also logged at `LogLevel::Error`.
+
+use std::cell::{Cell, RefCell};
+use std::panic;
+use std::sync::Once;
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::Universe;
+
+#[derive(Clone, Copy)]
+struct Call {
+    name: &'static str,
+    generation: u32,
+    width: u32,
+    height: u32,
+}
+
+thread_local! {
+    static LAST_CALL: Cell<Option<Call>> = const { Cell::new(None) };
+    static LAST_ERROR: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Install the panic hook. Only the first call has an effect, and the hook
+/// that was installed before, if any, still runs after it.
+pub fn set_panic_hook() {
+    static HOOK: Once = Once::new();
+    HOOK.call_once(|| {
+        #[cfg(not(feature = "console_error_panic_hook"))]
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let call = LAST_CALL.with(Cell::get).map(|call| {
+                format!(
+                    "in {} at generation {} of a {}x{} universe",
+                    call.name, call.generation, call.width, call.height
+                )
+            });
+            match &call {
+                Some(call) => record_error(&format!("{}\n{}", info, call)),
+                None => record_error(&info.to_string()),
+            }
+
+            if let Some(call) = call {
+                cfg_if::cfg_if! {
+                    if #[cfg(all(feature = "web", target_arch = "wasm32", target_os = "unknown"))] {
+                        web_sys::console::error_1(&format!("convida: {}", call).into());
+                    } else {
+                        eprintln!("convida: {}", call);
+                    }
+                }
+            }
+
+            // When the `console_error_panic_hook` feature is enabled, log
+            // the panic with a JavaScript stack trace. For more details see
+            // https://github.com/rustwasm/console_error_panic_hook#readme
+            #[cfg(feature = "console_error_panic_hook")]
+            console_error_panic_hook::hook(info);
+            #[cfg(not(feature = "console_error_panic_hook"))]
+            previous(info);
+        }));
+    });
+}
+
+/// Keep `message` for `last_error()`, and log it.
+pub(crate) fn record_error(message: &str) {
+    crate::logging::error(message);
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message.to_string()));
+}
+
+/// The message of the last panic or error thrown to JavaScript, with the
+/// API call that caused it.
+#[cfg_attr(feature = "web", wasm_bindgen(js_name = lastError))]
+pub fn last_error() -> Option<String> {
+    LAST_ERROR.with(|last| last.borrow().clone())
+}
+
+impl Universe {
+    /// Remember `name` as the API call in progress on this universe, for
+    /// the panic message.
+    #[inline]
+    pub(crate) fn enter(&self, name: &'static str) {
+        LAST_CALL.with(|call| {
+            call.set(Some(Call {
+                name,
+                generation: self.generation,
+                width: self.width,
+                height: self.height,
+            }))
+        });
+    }
+}
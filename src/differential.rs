@@ -0,0 +1,236 @@
+// Differential testing of the engines against a reference tick.
+//
+// `reference_step` is slow and written to be obviously correct. Every
+// engine is run next to it from the same random board, generation by
+// generation, and the first cell where one disagrees is reported. New
+// engines only need adding to `run_engines` to be covered.
+
+use std::fmt;
+
+use crate::grid::{self, ENGINES};
+use crate::rng::Rng;
+use crate::webgpu_model;
+use crate::{Boundary, Cell, LifeLike, Rule, Universe};
+
+/// The generation after `cells`, a `width` x `height` board in reading
+/// order.
+pub fn reference_step(
+    width: u32,
+    height: u32,
+    boundary: Boundary,
+    rule: &dyn Rule,
+    cells: &[Cell],
+) -> Vec<Cell> {
+    let (width, height) = (i64::from(width), i64::from(height));
+    let alive = |row: i64, col: i64| {
+        let (row, col) = match boundary {
+            Boundary::Torus => (row.rem_euclid(height), col.rem_euclid(width)),
+            Boundary::Dead if row < 0 || row >= height || col < 0 || col >= width => return false,
+            Boundary::Dead => (row, col),
+        };
+        cells[(row * width + col) as usize] == Cell::Alive
+    };
+
+    let mut next = Vec::with_capacity(cells.len());
+    for row in 0..height {
+        for col in 0..width {
+            let mut neighbors = 0;
+            for dr in -1..=1 {
+                for dc in -1..=1 {
+                    if (dr, dc) != (0, 0) && alive(row + dr, col + dc) {
+                        neighbors += 1;
+                    }
+                }
+            }
+            let cell = cells[(row * width + col) as usize];
+            next.push(rule.next_state(cell, neighbors));
+        }
+    }
+    next
+}
+
+/// Where an engine first disagreed with `reference_step`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Divergence {
+    pub engine: String,
+    /// The first generation at which the engine's board differs.
+    pub generation: u32,
+    /// The first differing cell in reading order.
+    pub row: u32,
+    pub col: u32,
+    pub width: u32,
+    pub height: u32,
+    pub boundary: Boundary,
+    pub rule: LifeLike,
+    /// The seed of the random starting board.
+    pub seed: u64,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} diverges from the reference at generation {}, cell ({}, {}), on a {}x{} {:?} \
+             board under {} from seed {}",
+            self.engine,
+            self.generation,
+            self.row,
+            self.col,
+            self.width,
+            self.height,
+            self.boundary,
+            self.rule,
+            self.seed
+        )
+    }
+}
+
+/// An engine under test: its name and a function advancing its board one
+/// generation and returning the cells in reading order.
+type Engine = (String, Box<dyn FnMut() -> Vec<Cell>>);
+
+fn run_engines(
+    width: u32,
+    height: u32,
+    boundary: Boundary,
+    rule: LifeLike,
+    cells: &[Cell],
+) -> Vec<Engine> {
+    let mut engines: Vec<Engine> = Vec::new();
+
+    let mut universe = Universe::from_cells(width, height, cells.to_vec());
+    universe.boundary = boundary;
+    universe.rule = rule;
+    engines.push((
+        "universe".to_string(),
+        Box::new(move || {
+            universe.tick();
+            universe.cells.clone()
+        }),
+    ));
+
+    let mut current = cells.to_vec();
+    let mut next = cells.to_vec();
+    engines.push((
+        "convida-engine".to_string(),
+        Box::new(move || {
+            convida_engine::step(width, height, boundary, &rule, &current, &mut next);
+            std::mem::swap(&mut current, &mut next);
+            current.clone()
+        }),
+    ));
+
+    // `GpuUniverse`, as its compute shader and as its fallback without a
+    // GPU.
+    let mut gpu = Universe::from_cells(width, height, cells.to_vec());
+    gpu.boundary = boundary;
+    gpu.rule = rule;
+    let params = webgpu_model::params(&gpu);
+    let mut current: Vec<u32> = cells.iter().map(|&cell| cell as u32).collect();
+    let mut next = current.clone();
+    engines.push((
+        "webgpu shader".to_string(),
+        Box::new(move || {
+            webgpu_model::step(&params, &current, &mut next);
+            std::mem::swap(&mut current, &mut next);
+            current
+                .iter()
+                .map(|&value| if value == 0 { Cell::Dead } else { Cell::Alive })
+                .collect()
+        }),
+    ));
+    engines.push((
+        "webgpu fallback".to_string(),
+        Box::new(move || {
+            webgpu_model::cpu_tick(&mut gpu, 1);
+            gpu.cells.clone()
+        }),
+    ));
+
+    for &name in ENGINES {
+        let mut board = grid::new_grid(name, width, height).expect("known engine");
+        for (i, _) in cells
+            .iter()
+            .enumerate()
+            .filter(|&(_, &cell)| cell == Cell::Alive)
+        {
+            let i = i as u32;
+            board.set(i / width, i % width, Cell::Alive);
+        }
+        engines.push((
+            format!("{} grid", name),
+            Box::new(move || {
+                grid::step(board.as_mut(), &rule, boundary);
+                (0..height)
+                    .flat_map(|row| (0..width).map(move |col| (row, col)))
+                    .map(|(row, col)| board.get(row, col))
+                    .collect()
+            }),
+        ));
+    }
+
+    engines
+}
+
+/// Run every engine for `generations` from a random `width` x `height`
+/// board drawn from `seed`, comparing each generation with the reference.
+pub fn check_engines(
+    width: u32,
+    height: u32,
+    boundary: Boundary,
+    rule: LifeLike,
+    seed: u64,
+    generations: u32,
+) -> Result<(), Divergence> {
+    let mut rng = Rng::new(seed);
+    let density = rng.next_f64();
+    let mut expected = crate::random((width * height) as usize, density, &mut rng);
+    let mut engines = run_engines(width, height, boundary, rule, &expected);
+
+    for generation in 1..=generations {
+        expected = reference_step(width, height, boundary, &rule, &expected);
+        for (engine, tick) in &mut engines {
+            let cells = tick();
+            if let Some(i) = (0..expected.len()).find(|&i| cells.get(i) != Some(&expected[i])) {
+                return Err(Divergence {
+                    engine: engine.clone(),
+                    generation,
+                    row: i as u32 / width,
+                    col: i as u32 % width,
+                    width,
+                    height,
+                    boundary,
+                    rule,
+                    seed,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `check_engines` over `runs` random boards of up to 40x40 cells, random
+/// boundaries and random Life-like rules, all drawn from `seed`.
+pub fn fuzz_engines(seed: u64, runs: u32, generations: u32) -> Result<(), Divergence> {
+    let mut rng = Rng::new(seed);
+    for _ in 0..runs {
+        let width = 1 + (rng.next_u64() % 40) as u32;
+        let height = 1 + (rng.next_u64() % 40) as u32;
+        let boundary = if rng.next_u64() & 1 == 0 {
+            Boundary::Torus
+        } else {
+            Boundary::Dead
+        };
+        // Conway's Life takes its own path through `Universe::tick`.
+        let rule = if rng.next_u64() & 3 == 0 {
+            LifeLike::CONWAY
+        } else {
+            LifeLike {
+                birth: (rng.next_u64() & 0x1ff) as u16,
+                survival: (rng.next_u64() & 0x1ff) as u16,
+            }
+        };
+        check_engines(width, height, boundary, rule, rng.next_u64(), generations)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,109 @@
+// Importing pattern files dropped onto the page.
+//
+// `GameLoop::enable_drop` listens for files dragged onto the canvas, or
+// onto any other element standing in as the drop zone, reads the `.rle`,
+// `.cells` and `.lif` ones with a `FileReader` and stamps each centred on
+// the cell it was dropped on, or on the middle of the board when dropped
+// beside the canvas. Each import is reported with a `pattern-imported`
+// event.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{DragEvent, EventTarget, File, FileReader, HtmlCanvasElement};
+
+use crate::diagnostics;
+use crate::game_loop::LoopState;
+use crate::input::{self, Listener};
+
+const EXTENSIONS: [&str; 4] = ["rle", "cells", "lif", "life"];
+
+/// The listeners of a drop zone. Dropping it removes them.
+pub(crate) struct DropZone {
+    _listeners: Vec<Listener>,
+}
+
+impl DropZone {
+    pub(crate) fn attach(
+        zone: &EventTarget,
+        canvas: HtmlCanvasElement,
+        state: &Rc<RefCell<LoopState>>,
+    ) -> Result<DropZone, JsValue> {
+        // Without this the browser opens the file instead of dropping it.
+        let dragover = Listener::new(zone, "dragover", |event: DragEvent| {
+            event.prevent_default();
+            if let Some(transfer) = event.data_transfer() {
+                transfer.set_drop_effect("copy");
+            }
+        })?;
+
+        let state = Rc::clone(state);
+        let drop = Listener::new(zone, "drop", move |event: DragEvent| {
+            event.prevent_default();
+            let files = match event.data_transfer().and_then(|t| t.files()) {
+                Some(files) => files,
+                None => return,
+            };
+            let (x, y) = input::client_point(&event);
+            let (x, y) = input::to_canvas(&canvas, x, y);
+            let cell = {
+                let state = state.borrow();
+                let (width, height) = (state.universe.width(), state.universe.height());
+                state.cell_at(x, y).unwrap_or((height / 2, width / 2))
+            };
+            for i in 0..files.length() {
+                if let Some(file) = files.get(i) {
+                    if let Err(err) = read(file, cell, &state) {
+                        warn(&format!("could not read dropped file: {:?}", err));
+                    }
+                }
+            }
+        })?;
+
+        Ok(DropZone {
+            _listeners: vec![dragover, drop],
+        })
+    }
+}
+
+// Read `file` and import it at `cell` once it has loaded.
+fn read(file: File, cell: (u32, u32), state: &Rc<RefCell<LoopState>>) -> Result<(), JsValue> {
+    let name = file.name();
+    let known = name.rsplit_once('.').is_some_and(|(_, extension)| {
+        EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+    });
+    if !known {
+        warn(&format!("{} is not a pattern file", name));
+        return Ok(());
+    }
+
+    let reader = FileReader::new()?;
+    let onload = {
+        let (reader, state) = (reader.clone(), Rc::clone(state));
+        Closure::once_into_js(move || {
+            let text = match reader.result().ok().and_then(|text| text.as_string()) {
+                Some(text) => text,
+                None => return warn(&format!("could not read {}", name)),
+            };
+            let mut state = state.borrow_mut();
+            let (row, col) = cell;
+            match state.universe.import_pattern(&name, &text, row, col) {
+                Ok(_) => {
+                    let _ = state.draw();
+                }
+                Err(err) => warn(&format!("{}: {}", name, err)),
+            }
+        })
+    };
+    reader.set_onload(Some(onload.unchecked_ref()));
+    reader.read_as_text(&file)
+}
+
+// Report a file that could not be imported, for `last_error()` and the
+// console, since there is no caller to throw to.
+fn warn(message: &str) {
+    diagnostics::record_error(message);
+    web_sys::console::error_1(&format!("convida: {}", message).into());
+}
@@ -0,0 +1,157 @@
+// The `<con-vida>` custom element.
+//
+//     <con-vida width="96" height="64" seed="7" speed="20"></con-vida>
+//
+// The element is registered as soon as the module is loaded, so a page
+// only has to include the package. Each connected element gets a canvas
+// and its own running `GameLoop` with input attached, and is rebuilt when
+// its attributes change.
+//
+// Custom elements have to be ES classes, which can't be declared through
+// web-sys alone, hence the small shim below that forwards the lifecycle
+// callbacks to Rust.
+
+use std::cell::RefCell;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlCanvasElement, HtmlElement};
+
+use crate::builder::UniverseBuilder;
+use crate::game_loop::GameLoop;
+use crate::ConvidaError;
+
+const TAG: &str = "con-vida";
+const DEFAULT_SIZE: u32 = 64;
+// Canvas pixels per cell.
+const CELL_SIZE: u32 = 4;
+
+#[wasm_bindgen(inline_js = r#"
+export function define_element(tag, attributes, connect, disconnect, change) {
+    if (typeof customElements === "undefined" || customElements.get(tag)) {
+        return;
+    }
+    customElements.define(tag, class extends HTMLElement {
+        static get observedAttributes() { return attributes; }
+        connectedCallback() { connect(this); }
+        disconnectedCallback() { disconnect(this); }
+        attributeChangedCallback(name, previous, value) {
+            if (this.isConnected && previous !== value) { change(this, name); }
+        }
+    });
+}
+"#)]
+extern "C" {
+    fn define_element(
+        tag: &str,
+        attributes: js_sys::Array,
+        connect: &JsValue,
+        disconnect: &JsValue,
+        change: &JsValue,
+    );
+}
+
+thread_local! {
+    static ELEMENTS: RefCell<Vec<(HtmlElement, GameLoop)>> = const { RefCell::new(Vec::new()) };
+}
+
+#[wasm_bindgen(start)]
+pub fn register_element() {
+    if !crate::platform::has_window() {
+        return;
+    }
+
+    let attributes: js_sys::Array = ["width", "height", "rule", "seed", "speed"]
+        .iter()
+        .map(|&name| JsValue::from_str(name))
+        .collect();
+    // Registration happens once per page, so the callbacks are leaked.
+    let on_connect = Closure::wrap(Box::new(connect) as Box<dyn FnMut(HtmlElement)>);
+    let on_disconnect = Closure::wrap(Box::new(disconnect) as Box<dyn FnMut(HtmlElement)>);
+    let on_change = Closure::wrap(Box::new(|element: HtmlElement, name: String| {
+        if name == "speed" {
+            ELEMENTS.with(|elements| {
+                for (el, game_loop) in elements.borrow_mut().iter_mut() {
+                    if el == &element {
+                        let _ = apply_speed(&element, game_loop);
+                    }
+                }
+            });
+        } else {
+            disconnect(element.clone());
+            connect(element);
+        }
+    }) as Box<dyn FnMut(HtmlElement, String)>);
+
+    define_element(
+        TAG,
+        attributes,
+        &on_connect.into_js_value(),
+        &on_disconnect.into_js_value(),
+        &on_change.into_js_value(),
+    );
+}
+
+fn connect(element: HtmlElement) {
+    if let Err(err) = build(&element) {
+        web_sys::console::error_2(&JsValue::from_str("con-vida:"), &err);
+    }
+}
+
+fn disconnect(element: HtmlElement) {
+    ELEMENTS.with(|elements| elements.borrow_mut().retain(|(el, _)| el != &element));
+    element.set_inner_html("");
+}
+
+fn build(element: &HtmlElement) -> Result<(), JsValue> {
+    let width = number_attribute(element, "width").unwrap_or(DEFAULT_SIZE as f64) as u32;
+    let height = number_attribute(element, "height").unwrap_or(DEFAULT_SIZE as f64) as u32;
+    let (width, height) = (width.max(1), height.max(1));
+
+    let mut builder = UniverseBuilder::new().width(width).height(height);
+    if let Some(rule) = element.get_attribute("rule") {
+        builder = builder.rule(&rule);
+    }
+    if let Some(seed) = number_attribute(element, "seed") {
+        builder = builder.rng_seed(seed as u64);
+    }
+    let universe = builder.build()?;
+
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or_else(|| JsValue::from_str("no document"))?;
+    let canvas = document
+        .create_element("canvas")?
+        .dyn_into::<HtmlCanvasElement>()?;
+    canvas.set_width(width * CELL_SIZE);
+    canvas.set_height(height * CELL_SIZE);
+    element.set_inner_html("");
+    element.append_child(&canvas)?;
+
+    let mut game_loop = GameLoop::new(universe, canvas.clone())?;
+    let mut camera = game_loop.camera()?;
+    camera.set_scale(CELL_SIZE as f64);
+    game_loop.set_camera(camera)?;
+    game_loop.attach(&canvas)?;
+    apply_speed(element, &mut game_loop)?;
+    game_loop.play()?;
+
+    ELEMENTS.with(|elements| elements.borrow_mut().push((element.clone(), game_loop)));
+    Ok(())
+}
+
+// `speed` is in generations per second; without it the loop runs one
+// generation per frame.
+fn apply_speed(element: &HtmlElement, game_loop: &mut GameLoop) -> Result<(), ConvidaError> {
+    match number_attribute(element, "speed") {
+        Some(speed) => game_loop.set_speed(speed),
+        None => game_loop.set_generations_per_frame(1),
+    }
+}
+
+fn number_attribute(element: &HtmlElement, name: &str) -> Option<f64> {
+    element
+        .get_attribute(name)
+        .and_then(|value| value.trim().parse::<f64>().ok())
+        .filter(|value| value.is_finite() && *value >= 0.0)
+}
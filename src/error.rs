@@ -0,0 +1,322 @@
+// The error type of the public API.
+//
+// In JavaScript a `ConvidaError` is thrown as an `Error` whose `name` is
+// `ConvidaError`, so callers can `catch` it instead of the module aborting
+// on a panic.
+
+use std::error::Error;
+use std::fmt;
+
+#[cfg(feature = "web")]
+use wasm_bindgen::JsValue;
+
+use crate::grid::ENGINES;
+use crate::protocol::DecodeError;
+use crate::rle::RleError;
+use crate::seed::Seed;
+use crate::text::TextError;
+use crate::{BoundingBox, Universe};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConvidaError {
+    OutOfBounds {
+        row: u32,
+        col: u32,
+        width: u32,
+        height: u32,
+    },
+    EmptySize,
+    /// `width * height` cells don't fit in memory.
+    SizeOverflow {
+        width: u32,
+        height: u32,
+    },
+    UnsupportedRule(String),
+    UnknownPattern(String),
+    UnknownEngine(String),
+    UnknownCheckpoint(String),
+    /// A buffer with one value per cell has the wrong length.
+    BufferLength {
+        expected: u32,
+        actual: u32,
+    },
+    /// A `Multiverse` index past its last universe.
+    NoSuchUniverse {
+        index: u32,
+        count: u32,
+    },
+    /// No autosave of that generation is kept.
+    NoAutosave {
+        generation: u32,
+    },
+    /// A `Universe` was asked for an engine other than `dense`, which only
+    /// `UniverseBuilder::build_grid` can provide.
+    NotDense(String),
+    /// Two universes that have to be the same size aren't.
+    SizeMismatch {
+        width: u32,
+        height: u32,
+        other_width: u32,
+        other_height: u32,
+    },
+    /// Text, RLE or a snapshot that couldn't be read.
+    ParseError(String),
+    /// A player tried to place a cell on a live one.
+    CellOccupied {
+        row: u32,
+        col: u32,
+    },
+    /// An edit needs more cells than are left of the budget.
+    BudgetSpent {
+        budget: u32,
+    },
+    /// An edit would leave more live cells than the `Limits` allow.
+    PopulationCap {
+        cap: u32,
+    },
+    /// A stamp costs more mana than is available.
+    NotEnoughMana {
+        needed: u32,
+        available: u32,
+    },
+    /// A `Versus` game has already been won or drawn.
+    GameOver,
+    /// An `Arena` can't be built on once the battle has started.
+    BattleStarted,
+    /// A cell that may not be edited, such as a wall, one outside a
+    /// player's half of an `Arena` or one on a puzzle's given board.
+    NotEditable {
+        row: u32,
+        col: u32,
+    },
+    /// A soup with rotational or diagonal symmetry was asked for in a
+    /// region that isn't square.
+    NotSquare {
+        width: u32,
+        height: u32,
+    },
+    /// A neighbourhood kernel without a centre cell, because its width or
+    /// height is even or zero.
+    KernelSize {
+        width: u32,
+        height: u32,
+    },
+    /// World coordinates outside the board's window, with dead boundaries.
+    OutsideWindow {
+        row: i64,
+        col: i64,
+    },
+    /// A `GameLoop` was called from its universe's callbacks, in the middle
+    /// of a tick.
+    Busy,
+    /// A random seed's density outside 0 to 1, as written.
+    InvalidDensity(String),
+}
+
+impl ConvidaError {
+    /// Check that (`row`, `col`) is inside a `width` x `height` universe.
+    pub(crate) fn check_bounds(
+        row: u32,
+        col: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), ConvidaError> {
+        if row < height && col < width {
+            Ok(())
+        } else {
+            Err(ConvidaError::OutOfBounds {
+                row,
+                col,
+                width,
+                height,
+            })
+        }
+    }
+
+    /// Check that `region` is non-empty and inside a `width` x `height`
+    /// universe.
+    pub(crate) fn check_region(
+        region: &BoundingBox,
+        width: u32,
+        height: u32,
+    ) -> Result<(), ConvidaError> {
+        if region.width == 0 || region.height == 0 {
+            return Err(ConvidaError::EmptySize);
+        }
+        ConvidaError::check_bounds(
+            region.row.saturating_add(region.height - 1),
+            region.col.saturating_add(region.width - 1),
+            width,
+            height,
+        )
+    }
+
+    /// Check that two universes are the same size.
+    pub(crate) fn check_same_size(
+        universe: &Universe,
+        other: &Universe,
+    ) -> Result<(), ConvidaError> {
+        if (universe.width, universe.height) == (other.width, other.height) {
+            Ok(())
+        } else {
+            Err(ConvidaError::SizeMismatch {
+                width: universe.width,
+                height: universe.height,
+                other_width: other.width,
+                other_height: other.height,
+            })
+        }
+    }
+
+    /// Check that `density` is a probability, from 0 to 1.
+    pub(crate) fn check_density(density: f64) -> Result<f64, ConvidaError> {
+        if (0.0..=1.0).contains(&density) {
+            Ok(density)
+        } else {
+            Err(ConvidaError::InvalidDensity(density.to_string()))
+        }
+    }
+
+    /// The number of cells in a `width` x `height` universe, which must
+    /// have at least one.
+    pub(crate) fn check_size(width: u32, height: u32) -> Result<usize, ConvidaError> {
+        if width == 0 || height == 0 {
+            return Err(ConvidaError::EmptySize);
+        }
+        width
+            .checked_mul(height)
+            .map(|size| size as usize)
+            .ok_or(ConvidaError::SizeOverflow { width, height })
+    }
+}
+
+impl fmt::Display for ConvidaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConvidaError::OutOfBounds {
+                row,
+                col,
+                width,
+                height,
+            } => write!(
+                f,
+                "cell ({}, {}) is outside the {}x{} universe",
+                row, col, width, height
+            ),
+            ConvidaError::EmptySize => write!(f, "width and height must not be zero"),
+            ConvidaError::SizeOverflow { width, height } => {
+                write!(f, "a {}x{} universe is too large", width, height)
+            }
+            ConvidaError::UnsupportedRule(rule) => write!(f, "unsupported rule {}", rule),
+            ConvidaError::UnknownPattern(name) => write!(
+                f,
+                "unknown pattern {}, expected one of: {}",
+                name,
+                Seed::patterns().join(", ")
+            ),
+            ConvidaError::UnknownEngine(engine) => write!(
+                f,
+                "unknown engine {}, expected one of: {}",
+                engine,
+                ENGINES.join(", ")
+            ),
+            ConvidaError::UnknownCheckpoint(name) => write!(f, "no checkpoint named {}", name),
+            ConvidaError::BufferLength { expected, actual } => write!(
+                f,
+                "expected one value per cell, {} in all, but got {}",
+                expected, actual
+            ),
+            ConvidaError::NoSuchUniverse { index, count } => write!(
+                f,
+                "there is no universe {} in a multiverse of {}",
+                index, count
+            ),
+            ConvidaError::NoAutosave { generation } => {
+                write!(f, "no autosave of generation {} is kept", generation)
+            }
+            ConvidaError::NotDense(engine) => write!(
+                f,
+                "a Universe always uses the dense engine, build a {} grid with build_grid",
+                engine
+            ),
+            ConvidaError::SizeMismatch {
+                width,
+                height,
+                other_width,
+                other_height,
+            } => write!(
+                f,
+                "a {}x{} universe can't be combined with a {}x{} one",
+                width, height, other_width, other_height
+            ),
+            ConvidaError::ParseError(message) => write!(f, "{}", message),
+            ConvidaError::CellOccupied { row, col } => {
+                write!(f, "cell ({}, {}) is already alive", row, col)
+            }
+            ConvidaError::BudgetSpent { budget } => {
+                write!(f, "the budget of {} cells has been spent", budget)
+            }
+            ConvidaError::PopulationCap { cap } => {
+                write!(f, "the population is capped at {} cells", cap)
+            }
+            ConvidaError::NotEnoughMana { needed, available } => write!(
+                f,
+                "the stamp needs {} mana but only {} is available",
+                needed, available
+            ),
+            ConvidaError::GameOver => write!(f, "the game is over"),
+            ConvidaError::BattleStarted => write!(f, "the battle has already started"),
+            ConvidaError::NotEditable { row, col } => {
+                write!(f, "cell ({}, {}) is not editable", row, col)
+            }
+            ConvidaError::NotSquare { width, height } => write!(
+                f,
+                "the symmetry needs a square region, not {}x{}",
+                width, height
+            ),
+            ConvidaError::KernelSize { width, height } => write!(
+                f,
+                "a kernel needs an odd width and height, not {}x{}",
+                width, height
+            ),
+            ConvidaError::OutsideWindow { row, col } => {
+                write!(f, "world cell ({}, {}) is outside the board", row, col)
+            }
+            ConvidaError::Busy => write!(f, "the game loop is busy ticking"),
+            ConvidaError::InvalidDensity(density) => {
+                write!(f, "a density must be from 0 to 1, not {}", density)
+            }
+        }
+    }
+}
+
+impl Error for ConvidaError {}
+
+impl From<RleError> for ConvidaError {
+    fn from(err: RleError) -> ConvidaError {
+        ConvidaError::ParseError(err.to_string())
+    }
+}
+
+impl From<TextError> for ConvidaError {
+    fn from(err: TextError) -> ConvidaError {
+        ConvidaError::ParseError(err.to_string())
+    }
+}
+
+impl From<DecodeError> for ConvidaError {
+    fn from(err: DecodeError) -> ConvidaError {
+        ConvidaError::ParseError(err.to_string())
+    }
+}
+
+#[cfg(feature = "web")]
+impl From<ConvidaError> for JsValue {
+    fn from(err: ConvidaError) -> JsValue {
+        let message = err.to_string();
+        crate::diagnostics::record_error(&message);
+        let error = js_sys::Error::new(&message);
+        error.set_name("ConvidaError");
+        error.into()
+    }
+}
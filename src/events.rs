@@ -0,0 +1,419 @@
+// Event bus reporting simulation milestones to a single JavaScript handler,
+// or to a queue drained from Rust.
+//
+// Detection only runs while someone is listening, since hashing the board
+// for cycle detection costs a full pass over the cells.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+
+#[cfg(feature = "web")]
+use js_sys::{Function, Object, Reflect};
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::milestones::Milestones;
+use crate::search;
+use crate::{Cell, Player, Universe, WinCondition};
+
+// How many past generations are remembered when looking for cycles.
+const CYCLE_HISTORY: usize = 256;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// The last live cell died.
+    Extinction { generation: u32 },
+    /// The population is higher than it has ever been.
+    PopulationRecord { generation: u32, population: u32 },
+    /// A watched pattern appeared, isolated, with its top-left corner at
+    /// (`row`, `col`).
+    PatternDetected {
+        generation: u32,
+        name: String,
+        row: u32,
+        col: u32,
+    },
+    /// A live cell reached the edge of the grid.
+    BoundaryReached { generation: u32, row: u32, col: u32 },
+    /// The board repeated a state seen `period` generations ago.
+    CycleDetected { generation: u32, period: u32 },
+    /// A milestone added with `add_milestone` was reached for the first
+    /// time.
+    Milestone { generation: u32, name: String },
+    /// A `Versus` game met one of its win conditions. `winner` is `None`
+    /// on a draw.
+    GameOver {
+        generation: u32,
+        winner: Option<Player>,
+        condition: WinCondition,
+    },
+    /// A pattern file was stamped onto the board centred on (`row`, `col`),
+    /// by `import_pattern` or by dropping it on the board.
+    PatternImported {
+        generation: u32,
+        file: String,
+        name: Option<String>,
+        row: u32,
+        col: u32,
+        width: u32,
+        height: u32,
+        population: u32,
+    },
+}
+
+impl Event {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Event::Extinction { .. } => "extinction",
+            Event::PopulationRecord { .. } => "population-record",
+            Event::PatternDetected { .. } => "pattern-detected",
+            Event::BoundaryReached { .. } => "boundary-reached",
+            Event::CycleDetected { .. } => "cycle-detected",
+            Event::Milestone { .. } => "milestone",
+            Event::GameOver { .. } => "game-over",
+            Event::PatternImported { .. } => "pattern-imported",
+        }
+    }
+
+    /// Convert into a plain object such as
+    /// `{ type: "cycle-detected", generation: 120, period: 2 }`.
+    #[cfg(feature = "web")]
+    pub fn to_js(&self) -> JsValue {
+        let object = Object::new();
+        let set = |key: &str, value: JsValue| {
+            let _ = Reflect::set(&object, &JsValue::from_str(key), &value);
+        };
+
+        set("type", JsValue::from_str(self.kind()));
+        match self {
+            Event::Extinction { generation } => {
+                set("generation", (*generation).into());
+            }
+            Event::PopulationRecord {
+                generation,
+                population,
+            } => {
+                set("generation", (*generation).into());
+                set("population", (*population).into());
+            }
+            Event::PatternDetected {
+                generation,
+                name,
+                row,
+                col,
+            } => {
+                set("generation", (*generation).into());
+                set("name", JsValue::from_str(name));
+                set("row", (*row).into());
+                set("col", (*col).into());
+            }
+            Event::BoundaryReached {
+                generation,
+                row,
+                col,
+            } => {
+                set("generation", (*generation).into());
+                set("row", (*row).into());
+                set("col", (*col).into());
+            }
+            Event::CycleDetected { generation, period } => {
+                set("generation", (*generation).into());
+                set("period", (*period).into());
+            }
+            Event::Milestone { generation, name } => {
+                set("generation", (*generation).into());
+                set("name", JsValue::from_str(name));
+            }
+            Event::GameOver {
+                generation,
+                winner,
+                condition,
+            } => {
+                set("generation", (*generation).into());
+                set(
+                    "winner",
+                    winner.map_or(JsValue::NULL, |player| (player as u8).into()),
+                );
+                set("condition", JsValue::from_str(condition.name()));
+            }
+            Event::PatternImported {
+                generation,
+                file,
+                name,
+                row,
+                col,
+                width,
+                height,
+                population,
+            } => {
+                set("generation", (*generation).into());
+                set("file", JsValue::from_str(file));
+                set(
+                    "name",
+                    name.as_deref().map_or(JsValue::NULL, JsValue::from_str),
+                );
+                set("row", (*row).into());
+                set("col", (*col).into());
+                set("width", (*width).into());
+                set("height", (*height).into());
+                set("population", (*population).into());
+            }
+        }
+
+        object.into()
+    }
+}
+
+// A pattern given as live cell offsets from its top-left corner.
+struct WatchedPattern {
+    name: String,
+    cells: Vec<(u32, u32)>,
+    seen: bool,
+}
+
+impl WatchedPattern {
+    fn new(name: &str, cells: Vec<(u32, u32)>) -> WatchedPattern {
+        WatchedPattern {
+            name: name.to_string(),
+            cells,
+            seen: false,
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct EventBus {
+    #[cfg(feature = "web")]
+    handler: Option<Function>,
+    queue: Option<Vec<Event>>,
+    patterns: Vec<WatchedPattern>,
+    population_record: u32,
+    extinct: bool,
+    on_boundary: bool,
+    in_cycle: bool,
+    history: VecDeque<(u64, u32)>,
+    generations: HashMap<u64, u32>,
+    pub(crate) milestones: Milestones,
+}
+
+impl EventBus {
+    pub(crate) fn is_listening(&self) -> bool {
+        #[cfg(feature = "web")]
+        {
+            if self.handler.is_some() {
+                return true;
+            }
+        }
+        self.queue.is_some()
+    }
+
+    pub(crate) fn emit(&mut self, event: Event) {
+        #[cfg(feature = "web")]
+        {
+            if let Some(handler) = &self.handler {
+                let _ = handler.call1(&JsValue::NULL, &event.to_js());
+            }
+        }
+        if let Some(queue) = &mut self.queue {
+            queue.push(event);
+        }
+    }
+
+    /// Look for milestones in the state of `universe` after a tick.
+    pub(crate) fn observe(&mut self, universe: &Universe) {
+        if !self.is_listening() {
+            return;
+        }
+
+        for event in self.detect(universe) {
+            self.emit(event);
+        }
+    }
+
+    fn detect(&mut self, universe: &Universe) -> Vec<Event> {
+        let generation = universe.generation;
+        let mut events = Vec::new();
+
+        let population = universe
+            .cells
+            .iter()
+            .filter(|&&cell| cell == Cell::Alive)
+            .count() as u32;
+
+        if population == 0 {
+            if !self.extinct {
+                events.push(Event::Extinction { generation });
+            }
+            self.extinct = true;
+        } else {
+            self.extinct = false;
+        }
+
+        if population > self.population_record {
+            self.population_record = population;
+            events.push(Event::PopulationRecord {
+                generation,
+                population,
+            });
+        }
+
+        match boundary_cell(universe) {
+            Some((row, col)) => {
+                if !self.on_boundary {
+                    events.push(Event::BoundaryReached {
+                        generation,
+                        row,
+                        col,
+                    });
+                }
+                self.on_boundary = true;
+            }
+            None => self.on_boundary = false,
+        }
+
+        let hash = state_hash(universe);
+        match self.generations.get(&hash) {
+            Some(&previous) => {
+                if !self.in_cycle {
+                    events.push(Event::CycleDetected {
+                        generation,
+                        period: generation.wrapping_sub(previous),
+                    });
+                }
+                self.in_cycle = true;
+            }
+            None => self.in_cycle = false,
+        }
+        self.remember(hash, generation);
+
+        for pattern in &mut self.patterns {
+            match find_isolated(universe, pattern) {
+                Some((row, col)) => {
+                    if !pattern.seen {
+                        events.push(Event::PatternDetected {
+                            generation,
+                            name: pattern.name.clone(),
+                            row,
+                            col,
+                        });
+                    }
+                    pattern.seen = true;
+                }
+                None => pattern.seen = false,
+            }
+        }
+
+        self.milestones.detect(universe, population, &mut events);
+
+        events
+    }
+
+    fn remember(&mut self, hash: u64, generation: u32) {
+        self.generations.insert(hash, generation);
+        self.history.push_back((hash, generation));
+        if self.history.len() > CYCLE_HISTORY {
+            if let Some((oldest, seen_at)) = self.history.pop_front() {
+                // Keep the entry if the state was seen again since.
+                if self.generations.get(&oldest) == Some(&seen_at) {
+                    self.generations.remove(&oldest);
+                }
+            }
+        }
+    }
+
+    /// Forget everything observed so far, e.g. after the board was edited.
+    pub(crate) fn reset(&mut self) {
+        self.population_record = 0;
+        self.extinct = false;
+        self.on_boundary = false;
+        self.in_cycle = false;
+        self.history.clear();
+        self.generations.clear();
+        for pattern in &mut self.patterns {
+            pattern.seen = false;
+        }
+        self.milestones.reset();
+    }
+}
+
+impl Universe {
+    /// Collect milestone events in a queue drained by `take_events`, the
+    /// Rust-side counterpart of `on_event`.
+    pub fn queue_events(&mut self, enabled: bool) {
+        self.events.queue = if enabled { Some(Vec::new()) } else { None };
+        self.events.reset();
+    }
+
+    /// Events raised since the last call, oldest first.
+    pub fn take_events(&mut self) -> Vec<Event> {
+        match &mut self.events.queue {
+            Some(queue) => std::mem::take(queue),
+            None => Vec::new(),
+        }
+    }
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Universe {
+    /// Deliver milestone events to `handler` as plain objects with a `type`
+    /// field: `extinction`, `population-record`, `pattern-detected`,
+    /// `boundary-reached`, `cycle-detected`, `milestone`, `pattern-imported`
+    /// for a file dropped on a `GameLoop` or, in a `Versus` game, `game-over`.
+    /// Pass `null` to unregister.
+    #[cfg(feature = "web")]
+    #[wasm_bindgen(js_name = onEvent)]
+    pub fn on_event(&mut self, handler: Option<Function>) {
+        self.events.handler = handler;
+        self.events.reset();
+    }
+
+    /// Raise `pattern-detected` whenever `name` appears on the board,
+    /// surrounded by dead cells. `cells` holds flat `[row, col, ...]` pairs
+    /// of live cells relative to the pattern's top-left corner.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = watchPattern))]
+    pub fn watch_pattern(&mut self, name: &str, cells: &[u32]) {
+        let cells: Vec<(u32, u32)> = cells.chunks_exact(2).map(|c| (c[0], c[1])).collect();
+        self.events.patterns.push(WatchedPattern::new(name, cells));
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = unwatchPattern))]
+    pub fn unwatch_pattern(&mut self, name: &str) {
+        self.events.patterns.retain(|pattern| pattern.name != name);
+    }
+}
+
+fn state_hash(universe: &Universe) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    universe.width.hash(&mut hasher);
+    for cell in &universe.cells {
+        (*cell as u8).hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+fn boundary_cell(universe: &Universe) -> Option<(u32, u32)> {
+    let (width, height) = (universe.width, universe.height);
+    let alive = |row: u32, col: u32| universe.cells[universe.get_index(row, col)] == Cell::Alive;
+
+    for col in 0..width {
+        for &row in &[0, height - 1] {
+            if alive(row, col) {
+                return Some((row, col));
+            }
+        }
+    }
+    for row in 0..height {
+        for &col in &[0, width - 1] {
+            if alive(row, col) {
+                return Some((row, col));
+            }
+        }
+    }
+
+    None
+}
+
+fn find_isolated(universe: &Universe, pattern: &WatchedPattern) -> Option<(u32, u32)> {
+    search::occurrences(universe, &pattern.cells).next()
+}
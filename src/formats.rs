@@ -0,0 +1,223 @@
+// Reading pattern files in the formats pattern collections come in.
+//
+// `.rle` is Golly's run length encoding, `.cells` the plaintext format of
+// the LifeWiki, with `O` for live and `.` for dead cells under `!` comment
+// lines, and `.lif` or `.life` either Life 1.06, one `x y` pair per live
+// cell, or Life 1.05, blocks of `*` and `.` placed by `#P x y` lines. Every
+// format comes out as an `Rle` with its top-left live cell at the origin,
+// along with the name and comments the file gives.
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::{ConvidaError, Event, LifeLike, Rle, Universe};
+
+/// A pattern read from a file, with the name and comments it gives.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PatternFile {
+    pub name: Option<String>,
+    pub comments: Vec<String>,
+    pub pattern: Rle,
+}
+
+impl PatternFile {
+    /// Read `text` in the format the extension of `file_name` names. A file
+    /// without a known extension is read as RLE.
+    pub fn parse(file_name: &str, text: &str) -> Result<PatternFile, ConvidaError> {
+        let extension = file_name
+            .rsplit_once('.')
+            .map(|(_, extension)| extension.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("cells") => parse_plaintext(text),
+            Some("lif") | Some("life") => parse_life(text),
+            _ => parse_rle(text),
+        }
+    }
+}
+
+fn parse_rle(text: &str) -> Result<PatternFile, ConvidaError> {
+    let mut file = PatternFile {
+        name: None,
+        comments: Vec::new(),
+        pattern: Rle::parse(text)?,
+    };
+    for line in text.lines() {
+        let line = line.trim();
+        if let Some(name) = line.strip_prefix("#N") {
+            file.name = Some(name.trim().to_string());
+        } else if let Some(comment) = ["#C", "#c", "#O"]
+            .iter()
+            .find_map(|prefix| line.strip_prefix(prefix))
+        {
+            file.comments.push(comment.trim().to_string());
+        }
+    }
+    Ok(file)
+}
+
+fn parse_plaintext(text: &str) -> Result<PatternFile, ConvidaError> {
+    let (mut name, mut comments, mut cells) = (None, Vec::new(), Vec::new());
+    let mut row = 0;
+    for line in text.lines() {
+        if let Some(comment) = line.strip_prefix('!') {
+            match comment.strip_prefix("Name:") {
+                Some(found) => name = Some(found.trim().to_string()),
+                None => comments.push(comment.trim().to_string()),
+            }
+            continue;
+        }
+        for (col, c) in line.trim_end().chars().enumerate() {
+            match c {
+                'O' | '*' => cells.push((row, col as i64)),
+                '.' => {}
+                c => return Err(unexpected(c, "plaintext")),
+            }
+        }
+        row += 1;
+    }
+    Ok(PatternFile {
+        name,
+        comments,
+        pattern: normalize(cells, LifeLike::default()),
+    })
+}
+
+fn parse_life(text: &str) -> Result<PatternFile, ConvidaError> {
+    let (mut name, mut comments, mut cells) = (None, Vec::new(), Vec::new());
+    let mut rule = LifeLike::default();
+    // The top-left corner of the current Life 1.05 block and the row
+    // reached within it.
+    let mut block: Option<(i64, i64)> = None;
+    let mut row = 0;
+
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with("#Life") {
+            continue;
+        }
+        if let Some(offset) = line.strip_prefix("#P") {
+            let mut numbers = offset.split_whitespace().map(str::parse::<i64>);
+            match (numbers.next(), numbers.next()) {
+                (Some(Ok(x)), Some(Ok(y))) => block = Some((y, x)),
+                _ => return Err(bad_line(line, "Life 1.05")),
+            }
+            row = 0;
+        } else if let Some(found) = line.strip_prefix("#R") {
+            rule = found
+                .trim()
+                .parse()
+                .map_err(|_| ConvidaError::UnsupportedRule(found.trim().to_string()))?;
+        } else if let Some(found) = line.strip_prefix("#N") {
+            if !found.trim().is_empty() {
+                name = Some(found.trim().to_string());
+            }
+        } else if let Some(comment) = ["#D", "#C"]
+            .iter()
+            .find_map(|prefix| line.strip_prefix(prefix))
+        {
+            comments.push(comment.trim().to_string());
+        } else if line.starts_with('#') {
+            continue;
+        } else if let Some((top, left)) = block {
+            for (col, c) in line.chars().enumerate() {
+                match c {
+                    '*' => cells.push((top + row, left + col as i64)),
+                    '.' => {}
+                    c => return Err(unexpected(c, "Life 1.05")),
+                }
+            }
+            row += 1;
+        } else {
+            let mut numbers = line.split_whitespace().map(str::parse::<i64>);
+            match (numbers.next(), numbers.next(), numbers.next()) {
+                (Some(Ok(x)), Some(Ok(y)), None) => cells.push((y, x)),
+                _ => return Err(bad_line(line, "Life 1.06")),
+            }
+        }
+    }
+    Ok(PatternFile {
+        name,
+        comments,
+        pattern: normalize(cells, rule),
+    })
+}
+
+// The pattern of live cells at the (row, col) positions of `cells`, moved
+// so that its bounding box starts at the origin.
+fn normalize(mut cells: Vec<(i64, i64)>, rule: LifeLike) -> Rle {
+    cells.sort_unstable();
+    cells.dedup();
+    let top = cells.iter().map(|&(row, _)| row).min().unwrap_or(0);
+    let left = cells.iter().map(|&(_, col)| col).min().unwrap_or(0);
+    let cells: Vec<(u32, u32)> = cells
+        .into_iter()
+        .map(|(row, col)| ((row - top) as u32, (col - left) as u32))
+        .collect();
+    Rle {
+        width: cells.iter().map(|&(_, col)| col + 1).max().unwrap_or(0),
+        height: cells.last().map_or(0, |&(row, _)| row + 1),
+        cells,
+        rule,
+    }
+}
+
+fn unexpected(c: char, format: &str) -> ConvidaError {
+    ConvidaError::ParseError(format!(
+        "unexpected character {:?} in {} pattern",
+        c, format
+    ))
+}
+
+fn bad_line(line: &str, format: &str) -> ConvidaError {
+    ConvidaError::ParseError(format!("malformed {} line \"{}\"", format, line))
+}
+
+impl Universe {
+    /// Read the pattern file `file_name` and stamp it centred on board cell
+    /// (`row`, `col`), if the limits allow it, reporting it with a
+    /// `PatternImported` event.
+    pub fn import_pattern(
+        &mut self,
+        file_name: &str,
+        text: &str,
+        row: u32,
+        col: u32,
+    ) -> Result<PatternFile, ConvidaError> {
+        let file = PatternFile::parse(file_name, text)?;
+        let pattern = &file.pattern;
+        let (row, col) = (row % self.height.max(1), col % self.width.max(1));
+        let (world_row, world_col) = self.to_world(row, col);
+        self.stamp_at(
+            pattern,
+            world_row - i64::from(pattern.height / 2),
+            world_col - i64::from(pattern.width / 2),
+        )?;
+        self.events.emit(Event::PatternImported {
+            generation: self.generation,
+            file: file_name.to_string(),
+            name: file.name.clone(),
+            row,
+            col,
+            width: pattern.width,
+            height: pattern.height,
+            population: pattern.cells.len() as u32,
+        });
+        Ok(file)
+    }
+}
+
+#[cfg(feature = "web")]
+#[wasm_bindgen]
+impl Universe {
+    /// `import_pattern` for the text of a `.rle`, `.cells` or `.lif` file.
+    #[wasm_bindgen(js_name = importPattern)]
+    pub fn import_pattern_js(
+        &mut self,
+        file_name: &str,
+        text: &str,
+        row: u32,
+        col: u32,
+    ) -> Result<(), ConvidaError> {
+        self.import_pattern(file_name, text, row, col)?;
+        Ok(())
+    }
+}
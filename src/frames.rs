@@ -0,0 +1,85 @@
+// Frames for archiving a run one generation at a time.
+//
+// A `FrameEncoder` turns each generation into a self-contained chunk of
+// bytes, so a long run can be written out as it goes instead of being held
+// in memory. An RLE frame is the whole board as an RLE pattern headed by a
+// `#C generation` comment. A delta frame uses the sync protocol: the first
+// frame is a `Snapshot` message and every later one the `Edit` messages of
+// the cells that changed, stamped with the generation they belong to, so
+// replaying is `restore_snapshot` and then `apply_edit` for each edit.
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::{Cell, Edit, Message, Universe};
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameFormat {
+    Rle,
+    Delta,
+}
+
+#[derive(Clone, Debug)]
+pub struct FrameEncoder {
+    format: FrameFormat,
+    /// The cells of the last frame, `None` before the first one.
+    previous: Option<Vec<Cell>>,
+}
+
+impl FrameEncoder {
+    pub fn new(format: FrameFormat) -> FrameEncoder {
+        FrameEncoder {
+            format,
+            previous: None,
+        }
+    }
+
+    pub fn format(&self) -> FrameFormat {
+        self.format
+    }
+
+    /// The frame of `universe` as it is now.
+    pub fn frame(&mut self, universe: &Universe) -> Vec<u8> {
+        match self.format {
+            FrameFormat::Rle => format!(
+                "#C generation {}\n{}",
+                universe.generation(),
+                universe.to_rle()
+            )
+            .into_bytes(),
+            FrameFormat::Delta => self.delta(universe),
+        }
+    }
+
+    fn delta(&mut self, universe: &Universe) -> Vec<u8> {
+        let previous = match &mut self.previous {
+            Some(previous) if previous.len() == universe.cells.len() => previous,
+            _ => {
+                self.previous = Some(universe.cells.clone());
+                return Message::Snapshot {
+                    seq: 0,
+                    data: universe.encode_snapshot(),
+                }
+                .encode();
+            }
+        };
+
+        let width = universe.width() as usize;
+        let mut bytes = Vec::new();
+        for (idx, (before, &after)) in previous.iter_mut().zip(&universe.cells).enumerate() {
+            if *before != after {
+                *before = after;
+                let edit = Edit {
+                    seq: 0,
+                    generation: universe.generation(),
+                    row: (idx / width) as u32,
+                    col: (idx % width) as u32,
+                    state: after,
+                };
+                bytes.extend_from_slice(&Message::Edit(edit).encode());
+            }
+        }
+        bytes
+    }
+}
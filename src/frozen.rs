@@ -0,0 +1,117 @@
+// Frozen regions, for protecting a carefully built structure while testing
+// what a soup does next to it.
+//
+// A frozen cell is left out of every update and keeps its state, live or
+// dead, until its region is thawed. Its neighbours still see it, and it can
+// still be edited.
+//
+//     universe.freeze_region(BoundingBox { row: 0, col: 0, width: 32, height: 32 })?;
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::{BoundingBox, ConvidaError, Universe};
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Frozen {
+    /// 1 for each frozen cell, empty while there are none.
+    mask: Vec<u8>,
+}
+
+impl Frozen {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.mask.is_empty()
+    }
+
+    pub(crate) fn contains(&self, idx: usize) -> bool {
+        self.mask.get(idx) == Some(&1)
+    }
+
+    // Thaw everything, for a board of a different size.
+    pub(crate) fn clear(&mut self) {
+        self.mask.clear();
+    }
+}
+
+impl Universe {
+    /// Stop updating the cells of `region`.
+    pub fn freeze_region(&mut self, region: BoundingBox) -> Result<(), ConvidaError> {
+        self.set_frozen(region, true)
+    }
+
+    /// Update the cells of `region` again.
+    pub fn thaw_region(&mut self, region: BoundingBox) -> Result<(), ConvidaError> {
+        self.set_frozen(region, false)
+    }
+
+    fn set_frozen(&mut self, region: BoundingBox, frozen: bool) -> Result<(), ConvidaError> {
+        ConvidaError::check_region(&region, self.width, self.height)?;
+        if self.frozen.mask.len() != self.cells.len() {
+            self.frozen.mask = vec![0; self.cells.len()];
+        }
+        for row in region.row..region.row + region.height {
+            let start = self.get_index(row, region.col);
+            self.frozen.mask[start..start + region.width as usize].fill(frozen as u8);
+        }
+        if !self.frozen.mask.contains(&1) {
+            self.frozen.clear();
+        }
+        Ok(())
+    }
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Universe {
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = isFrozen))]
+    pub fn is_frozen(&self, row: u32, col: u32) -> bool {
+        row < self.height && col < self.width && self.frozen.contains(self.get_index(row, col))
+    }
+
+    /// One byte per cell, 1 for frozen ones, or null while there are none.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = frozenMask))]
+    pub fn frozen_mask(&self) -> *const u8 {
+        if self.frozen.is_empty() {
+            std::ptr::null()
+        } else {
+            self.frozen.mask.as_ptr()
+        }
+    }
+}
+
+#[cfg(feature = "web")]
+#[wasm_bindgen]
+impl Universe {
+    /// Freeze the `width` x `height` region with its top-left corner at
+    /// (`row`, `col`).
+    #[wasm_bindgen(js_name = freezeRegion)]
+    pub fn freeze_region_js(
+        &mut self,
+        row: u32,
+        col: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), ConvidaError> {
+        self.freeze_region(BoundingBox {
+            row,
+            col,
+            width,
+            height,
+        })
+    }
+
+    #[wasm_bindgen(js_name = thawRegion)]
+    pub fn thaw_region_js(
+        &mut self,
+        row: u32,
+        col: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), ConvidaError> {
+        self.thaw_region(BoundingBox {
+            row,
+            col,
+            width,
+            height,
+        })
+    }
+}
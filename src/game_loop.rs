@@ -0,0 +1,806 @@
+// Animation loop driven by `requestAnimationFrame`.
+//
+// The loop owns the universe, the renderer and the rAF callback, so
+// JavaScript only has to hand over a canvas and call play/pause/step.
+// While the tab is hidden the loop stops, as its `HiddenPolicy` says, so
+// background tabs don't drain the battery. Browsers don't run animation
+// frames in hidden tabs, so a loop that keeps running ticks from a timer
+// then, without drawing.
+
+use std::cell::{RefCell, RefMut};
+use std::convert::TryFrom;
+use std::rc::Rc;
+
+use js_sys::{Date, Function};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::{Clamped, JsCast};
+use web_sys::{Blob, CanvasRenderingContext2d, Event, EventTarget, HtmlCanvasElement, ImageData};
+
+use crate::audio::Sonifier;
+use crate::camera::Camera;
+use crate::capture::VideoCapture;
+use crate::dropzone::DropZone;
+use crate::gamepad::{Action, GamepadControl};
+use crate::input::{Input, Listener};
+use crate::midi::MidiSequencer;
+use crate::render::Renderer;
+use crate::rle::Rle;
+use crate::{Cell, ConvidaError, Universe};
+
+pub(crate) type FrameCallback = Rc<RefCell<Option<Closure<dyn FnMut(f64)>>>>;
+
+// Upper bound on generations computed in one frame, so a slow frame (or a
+// very high speed) can't lock up the page trying to catch up.
+const MAX_GENERATIONS_PER_FRAME: u32 = 1000;
+
+// Assumed frame rate when a gamepad trigger first turns a fixed number of
+// generations per frame into a speed.
+const NOMINAL_FPS: f64 = 60.0;
+const CURSOR_COLOR: [u8; 4] = [0xFF, 0x40, 0x40, 0xFF];
+
+// Upper bound on the generations `HiddenPolicy::CatchUp` runs when the tab
+// is shown again, so coming back after hours doesn't freeze the page.
+const MAX_CATCH_UP: u32 = 10_000;
+
+// How often `HiddenPolicy::KeepRunning` ticks while the tab is hidden, in
+// milliseconds. Browsers run timers in hidden tabs about this often at most.
+const HIDDEN_TICK_INTERVAL: i32 = 1000;
+
+/// What the loop does while the page is hidden.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HiddenPolicy {
+    /// Stop ticking and drawing, and carry on where it left off when shown.
+    Pause,
+    /// Stop ticking and drawing, and when shown run the generations the
+    /// hidden time was worth in one go, so the simulation seems to have
+    /// kept going.
+    CatchUp,
+    /// Keep ticking about once a second, running the generations the time
+    /// since was worth, and draw again when shown.
+    KeepRunning,
+}
+
+// Decides how many generations to run and whether to draw on each frame.
+//
+// Without a speed, every frame runs a fixed number of generations. With a
+// speed, elapsed time is turned into a generation budget, so speeds below
+// one generation per frame tick only on some frames.
+struct Pacer {
+    speed: Option<f64>,
+    render_every: u32,
+    budget: f64,
+    last_timestamp: Option<f64>,
+    frames_since_render: u32,
+}
+
+impl Pacer {
+    fn new() -> Pacer {
+        Pacer {
+            speed: None,
+            render_every: 1,
+            budget: 0.0,
+            last_timestamp: None,
+            frames_since_render: 0,
+        }
+    }
+
+    // Forget the last frame so resuming doesn't try to catch up on the
+    // time spent paused.
+    fn restart(&mut self) {
+        self.budget = 0.0;
+        self.last_timestamp = None;
+    }
+
+    fn generations_due(&mut self, timestamp: f64, generations_per_frame: u32) -> u32 {
+        let elapsed = match self.last_timestamp {
+            Some(last) => (timestamp - last).max(0.0),
+            None => 0.0,
+        };
+        self.last_timestamp = Some(timestamp);
+
+        let speed = match self.speed {
+            Some(speed) => speed,
+            None => return generations_per_frame.min(MAX_GENERATIONS_PER_FRAME),
+        };
+
+        self.budget += elapsed / 1000.0 * speed;
+        let due = self.budget.floor().min(MAX_GENERATIONS_PER_FRAME as f64);
+        self.budget = (self.budget - due).min(MAX_GENERATIONS_PER_FRAME as f64);
+        due as u32
+    }
+
+    // How far, from 0 to 1, the time since the last tick is towards the
+    // next one. Without a speed every frame ticks, so frames are always
+    // drawn at the latest generation.
+    fn progress(&self) -> f64 {
+        match self.speed {
+            Some(_) => self.budget.clamp(0.0, 1.0),
+            None => 1.0,
+        }
+    }
+
+    // Only every `render_every`th frame that advanced the universe is drawn.
+    fn should_render(&mut self, generations: u32) -> bool {
+        if generations == 0 {
+            return false;
+        }
+
+        self.frames_since_render += 1;
+        if self.frames_since_render >= self.render_every {
+            self.frames_since_render = 0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+pub(crate) struct LoopState {
+    pub(crate) universe: Universe,
+    pub(crate) renderer: Renderer,
+    context: CanvasRenderingContext2d,
+    generations_per_frame: u32,
+    pacer: Pacer,
+    pub(crate) running: bool,
+    frame_id: Option<i32>,
+    sonifier: Option<Sonifier>,
+    midi: Option<MidiSequencer>,
+    pub(crate) long_press: Option<Function>,
+    gamepad: Option<GamepadControl>,
+    // Whether clicks and drags draw walls rather than cells.
+    draw_walls: bool,
+    // Whether frames between ticks fade from the previous generation.
+    interpolate: bool,
+    hidden_policy: HiddenPolicy,
+    // When the page was hidden, or last ticked while hidden.
+    hidden_since: Option<f64>,
+    // The interval ticking the loop while hidden, under `KeepRunning`.
+    hidden_timer: Option<i32>,
+}
+
+impl LoopState {
+    fn advance(&mut self, generations: u32) {
+        self.universe.tick_many(generations);
+        if generations > 0 {
+            if let Some(sonifier) = &mut self.sonifier {
+                sonifier.update(&self.universe);
+            }
+            if let Some(midi) = &mut self.midi {
+                midi.update(&self.universe);
+            }
+        }
+    }
+
+    fn frame(&mut self, timestamp: f64) {
+        let moved = self.poll_gamepad(timestamp);
+        let generations = self
+            .pacer
+            .generations_due(timestamp, self.generations_per_frame);
+        self.advance(generations);
+        let between_ticks = self.interpolate && self.pacer.speed.is_some();
+        if self.pacer.should_render(generations) || moved || between_ticks {
+            let _ = self.draw();
+        }
+    }
+
+    // Generations per second, assuming the nominal frame rate without a
+    // speed.
+    fn speed(&self) -> f64 {
+        self.pacer
+            .speed
+            .unwrap_or(self.generations_per_frame as f64 * NOMINAL_FPS)
+    }
+
+    // The generations `elapsed` milliseconds are worth at the current
+    // speed.
+    fn generations_in(&self, elapsed: f64) -> u32 {
+        (elapsed.max(0.0) / 1000.0 * self.speed())
+            .floor()
+            .min(MAX_CATCH_UP as f64) as u32
+    }
+
+    // Run the generations due since `hidden_since`, keeping the time short
+    // of a whole generation for the next call.
+    fn run_hidden(&mut self, now: f64) {
+        let since = match self.hidden_since {
+            Some(since) => since,
+            None => return,
+        };
+        let generations = self.generations_in(now - since);
+        self.advance(generations);
+        if generations == MAX_CATCH_UP {
+            self.hidden_since = Some(now);
+        } else if generations > 0 {
+            self.hidden_since = Some(since + generations as f64 * 1000.0 / self.speed());
+        }
+    }
+
+    fn stop_hidden_timer(&mut self) {
+        if let Some(id) = self.hidden_timer.take() {
+            if let Some(window) = web_sys::window() {
+                window.clear_interval_with_handle(id);
+            }
+        }
+    }
+
+    fn cancel_frame(&mut self) {
+        if let Some(id) = self.frame_id.take() {
+            if let Some(window) = web_sys::window() {
+                let _ = window.cancel_animation_frame(id);
+            }
+        }
+    }
+
+    // Apply what the gamepad asks for. Returns whether anything visible
+    // changed.
+    fn poll_gamepad(&mut self, timestamp: f64) -> bool {
+        let (width, height) = (self.universe.width(), self.universe.height());
+        let generations_per_frame = self.generations_per_frame;
+        let gamepad = match &mut self.gamepad {
+            Some(gamepad) => gamepad,
+            None => return false,
+        };
+
+        let cursor = gamepad.cursor(width, height);
+        let actions = gamepad.poll(timestamp, width, height);
+        let mut changed = cursor != gamepad.cursor(width, height);
+        for action in actions {
+            match action {
+                Action::Toggle(row, col) => {
+                    let _ = self.universe.toggle_cell(row, col);
+                    changed = true;
+                }
+                Action::Stamp(row, col) => {
+                    let _ = self.universe.stamp(&gamepad.stamp, row, col);
+                    changed = true;
+                }
+                Action::Speed(factor) => {
+                    let speed = self
+                        .pacer
+                        .speed
+                        .unwrap_or(generations_per_frame as f64 * NOMINAL_FPS);
+                    self.pacer.speed = Some((speed * factor).clamp(0.1, 10_000.0));
+                }
+            }
+        }
+        changed
+    }
+
+    /// The cell under the canvas position (`x`, `y`), if there is one.
+    pub(crate) fn cell_at(&self, x: f64, y: f64) -> Option<(u32, u32)> {
+        let camera = self.renderer.camera();
+        let row = u32::try_from(camera.row_at(y)).ok()?;
+        let col = u32::try_from(camera.col_at(x)).ok()?;
+        if row < self.universe.height() && col < self.universe.width() {
+            Some((row, col))
+        } else {
+            None
+        }
+    }
+
+    // Toggle the cell, or the wall, at (`row`, `col`) and return what a drag
+    // from there should paint, `Alive` standing for a wall.
+    pub(crate) fn toggle_at(&mut self, row: u32, col: u32) -> Cell {
+        let idx = self.universe.get_index(row, col);
+        if self.draw_walls {
+            let _ = self.universe.toggle_wall(row, col);
+            return if self.universe.is_wall(row, col) {
+                Cell::Alive
+            } else {
+                Cell::Dead
+            };
+        }
+        let _ = self.universe.toggle_cell(row, col);
+        self.universe.cells[idx]
+    }
+
+    pub(crate) fn set_cell(&mut self, row: u32, col: u32, cell: Cell) {
+        if self.draw_walls {
+            let _ = self.universe.set_wall(row, col, cell == Cell::Alive);
+        } else {
+            let _ = self.universe.set_cell(row, col, cell);
+        }
+    }
+
+    pub(crate) fn draw(&mut self) -> Result<(), JsValue> {
+        if self.interpolate {
+            let alpha = self.pacer.progress();
+            self.renderer.render_interpolated(&self.universe, alpha);
+        } else {
+            self.renderer.render(&self.universe);
+        }
+        if let Some(gamepad) = &self.gamepad {
+            let (row, col) = gamepad.cursor(self.universe.width(), self.universe.height());
+            self.renderer.outline_cell(row, col, CURSOR_COLOR);
+        }
+        let image = ImageData::new_with_u8_clamped_array_and_sh(
+            Clamped(self.renderer.get_pixels()),
+            self.renderer.width(),
+            self.renderer.height(),
+        )?;
+        // The unstable bindings, which the `webgpu` feature needs, take
+        // integer offsets here.
+        #[cfg(not(web_sys_unstable_apis))]
+        return self.context.put_image_data(&image, 0.0, 0.0);
+        #[cfg(web_sys_unstable_apis)]
+        return self.context.put_image_data(&image, 0, 0);
+    }
+}
+
+#[wasm_bindgen]
+pub struct GameLoop {
+    state: Rc<RefCell<LoopState>>,
+    callback: FrameCallback,
+    input: Option<Input>,
+    drop_zone: Option<DropZone>,
+    visibility: Option<Listener>,
+    // Called by the interval that ticks the loop while the page is hidden.
+    _hidden_tick: Closure<dyn FnMut()>,
+}
+
+#[wasm_bindgen]
+impl GameLoop {
+    /// Create a paused loop that draws `universe` onto `canvas`.
+    ///
+    /// Needs a browser window for `requestAnimationFrame`. Under Node.js,
+    /// call `Universe::tick` directly and use the text or RGBA renderers.
+    ///
+    /// While the loop ticks, its methods throw `ConvidaError::Busy`, so
+    /// `onTick` and `onCellChange` callbacks on `universe` can't call it.
+    pub fn new(universe: Universe, canvas: HtmlCanvasElement) -> Result<GameLoop, JsValue> {
+        if !crate::platform::has_window() {
+            return Err(JsValue::from_str(
+                "GameLoop needs a browser window; drive Universe::tick directly instead",
+            ));
+        }
+
+        let context = canvas
+            .get_context("2d")?
+            .ok_or_else(|| JsValue::from_str("canvas has no 2d context"))?
+            .dyn_into::<CanvasRenderingContext2d>()?;
+
+        let state = Rc::new(RefCell::new(LoopState {
+            universe,
+            renderer: Renderer::new(canvas.width(), canvas.height())?,
+            context,
+            generations_per_frame: 1,
+            pacer: Pacer::new(),
+            running: false,
+            frame_id: None,
+            sonifier: None,
+            midi: None,
+            long_press: None,
+            gamepad: None,
+            draw_walls: false,
+            interpolate: false,
+            hidden_policy: HiddenPolicy::Pause,
+            hidden_since: None,
+            hidden_timer: None,
+        }));
+
+        let callback: FrameCallback = Rc::new(RefCell::new(None));
+        let frame_state = Rc::clone(&state);
+        let frame_callback = Rc::clone(&callback);
+        *callback.borrow_mut() = Some(Closure::wrap(Box::new(move |timestamp: f64| {
+            let mut state = frame_state.borrow_mut();
+            state.frame_id = None;
+            if !state.running || state.hidden_since.is_some() {
+                return;
+            }
+
+            state.frame(timestamp);
+            state.frame_id = request_frame(&frame_callback);
+        }) as Box<dyn FnMut(f64)>));
+
+        let tick_state = Rc::clone(&state);
+        let hidden_tick = Closure::wrap(Box::new(move || {
+            let mut state = tick_state.borrow_mut();
+            if state.running {
+                state.run_hidden(Date::now());
+            } else {
+                state.hidden_since = Some(Date::now());
+            }
+        }) as Box<dyn FnMut()>);
+
+        let visibility = match web_sys::window().and_then(|window| window.document()) {
+            Some(document) => {
+                let (state, callback) = (Rc::clone(&state), Rc::clone(&callback));
+                let tick: Function = hidden_tick.as_ref().unchecked_ref::<Function>().clone();
+                let target = document.clone();
+                Some(Listener::new(
+                    &target,
+                    "visibilitychange",
+                    move |_: Event| visibility_changed(&state, &callback, &tick, document.hidden()),
+                )?)
+            }
+            None => None,
+        };
+
+        let game_loop = GameLoop {
+            state,
+            callback,
+            input: None,
+            drop_zone: None,
+            visibility,
+            _hidden_tick: hidden_tick,
+        };
+        game_loop.render()?;
+        Ok(game_loop)
+    }
+
+    pub fn play(&mut self) -> Result<(), ConvidaError> {
+        play(&mut *self.state()?, &self.callback);
+        Ok(())
+    }
+
+    pub fn pause(&mut self) -> Result<(), ConvidaError> {
+        pause(&mut *self.state()?);
+        Ok(())
+    }
+
+    /// Handle mouse, wheel, keyboard and touch input on `canvas`: click or
+    /// tap to toggle a cell, drag to paint, wheel or pinch to zoom,
+    /// middle-drag or two fingers to pan and space to pause or resume.
+    /// Replaces any previously attached canvas.
+    pub fn attach(&mut self, canvas: &HtmlCanvasElement) -> Result<(), JsValue> {
+        drop(self.state()?);
+        self.input = None;
+        self.input = Some(Input::attach(canvas, &self.state, &self.callback)?);
+        Ok(())
+    }
+
+    /// Call `handler(row, col, clientX, clientY)` when a finger rests on a
+    /// cell of the attached canvas. Pass `null` to unregister.
+    #[wasm_bindgen(js_name = onLongPress)]
+    pub fn on_long_press(&mut self, handler: Option<Function>) -> Result<(), ConvidaError> {
+        self.state()?.long_press = handler;
+        Ok(())
+    }
+
+    /// Poll the first connected gamepad on every frame while running: the
+    /// left stick or d-pad moves a cursor, A toggles the cell under it, B
+    /// stamps the pattern set with `set_gamepad_stamp` and the triggers
+    /// change the speed.
+    #[wasm_bindgen(js_name = enableGamepad)]
+    pub fn enable_gamepad(&mut self, enabled: bool) -> Result<(), ConvidaError> {
+        let mut state = self.state()?;
+        state.gamepad = if enabled {
+            Some(GamepadControl::new())
+        } else {
+            None
+        };
+        let _ = state.draw();
+        Ok(())
+    }
+
+    /// The pattern stamped by the B button, as flat `[row, col, ...]`
+    /// pairs of live cells. Defaults to a glider.
+    #[wasm_bindgen(js_name = setGamepadStamp)]
+    pub fn set_gamepad_stamp(&mut self, cells: &[u32]) -> Result<(), ConvidaError> {
+        let cells: Vec<(u32, u32)> = cells.chunks_exact(2).map(|c| (c[0], c[1])).collect();
+        let stamp = Rle {
+            width: cells.iter().map(|&(_, col)| col + 1).max().unwrap_or(0),
+            height: cells.iter().map(|&(row, _)| row + 1).max().unwrap_or(0),
+            cells,
+            ..Rle::default()
+        };
+        if let Some(gamepad) = &mut self.state()?.gamepad {
+            gamepad.stamp = stamp;
+        }
+        Ok(())
+    }
+
+    /// Remove the listeners added by `attach`.
+    pub fn detach(&mut self) {
+        self.input = None;
+    }
+
+    /// Import `.rle`, `.cells` and `.lif` files dropped onto `zone`, the
+    /// canvas unless given, stamping each centred on the cell it lands on
+    /// and reporting it with a `pattern-imported` event. Replaces any
+    /// previous drop zone.
+    #[wasm_bindgen(js_name = enableDrop)]
+    pub fn enable_drop(&mut self, zone: Option<EventTarget>) -> Result<(), JsValue> {
+        self.drop_zone = None;
+        let canvas = self
+            .state()?
+            .context
+            .canvas()
+            .ok_or_else(|| JsValue::from_str("the context has no canvas"))?;
+        let zone = zone.unwrap_or_else(|| canvas.clone().into());
+        self.drop_zone = Some(DropZone::attach(&zone, canvas, &self.state)?);
+        Ok(())
+    }
+
+    /// Stop importing dropped files.
+    #[wasm_bindgen(js_name = disableDrop)]
+    pub fn disable_drop(&mut self) {
+        self.drop_zone = None;
+    }
+
+    #[wasm_bindgen(js_name = hiddenPolicy)]
+    pub fn hidden_policy(&self) -> Result<HiddenPolicy, ConvidaError> {
+        Ok(self.state()?.hidden_policy)
+    }
+
+    /// What to do while the page is hidden. Defaults to
+    /// `HiddenPolicy::Pause`.
+    #[wasm_bindgen(js_name = setHiddenPolicy)]
+    pub fn set_hidden_policy(&mut self, policy: HiddenPolicy) -> Result<(), ConvidaError> {
+        self.state()?.hidden_policy = policy;
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = isRunning)]
+    pub fn is_running(&self) -> Result<bool, ConvidaError> {
+        Ok(self.state()?.running)
+    }
+
+    /// Advance a single generation and draw the result.
+    pub fn step(&mut self) -> Result<(), JsValue> {
+        let mut state = self.state()?;
+        state.advance(1);
+        state.draw()
+    }
+
+    /// Redraw without advancing, e.g. after editing cells.
+    pub fn render(&self) -> Result<(), JsValue> {
+        self.state()?.draw()
+    }
+
+    #[wasm_bindgen(js_name = generationsPerFrame)]
+    pub fn generations_per_frame(&self) -> Result<u32, ConvidaError> {
+        Ok(self.state()?.generations_per_frame)
+    }
+
+    /// Run a fixed number of generations on every frame. This clears any
+    /// speed set with `set_speed`.
+    #[wasm_bindgen(js_name = setGenerationsPerFrame)]
+    pub fn set_generations_per_frame(&mut self, generations: u32) -> Result<(), ConvidaError> {
+        let mut state = self.state()?;
+        state.generations_per_frame = generations;
+        state.pacer.speed = None;
+        Ok(())
+    }
+
+    /// The speed in generations per second, or `undefined` when running a
+    /// fixed number of generations per frame.
+    pub fn speed(&self) -> Result<Option<f64>, ConvidaError> {
+        Ok(self.state()?.pacer.speed)
+    }
+
+    /// Run at `generations_per_second` regardless of the frame rate.
+    ///
+    /// Fractional speeds are supported: at 0.5 a generation happens every
+    /// other second. A speed of zero or less pauses the simulation without
+    /// stopping the loop.
+    pub fn set_speed(&mut self, generations_per_second: f64) -> Result<(), ConvidaError> {
+        let mut state = self.state()?;
+        state.pacer.speed = Some(generations_per_second.max(0.0));
+        state.pacer.restart();
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = renderEvery)]
+    pub fn render_every(&self) -> Result<u32, ConvidaError> {
+        Ok(self.state()?.pacer.render_every)
+    }
+
+    /// Only draw every `frames`th frame that advanced the universe. Useful
+    /// at high speeds, where drawing costs more than ticking.
+    #[wasm_bindgen(js_name = setRenderEvery)]
+    pub fn set_render_every(&mut self, frames: u32) -> Result<(), ConvidaError> {
+        self.state()?.pacer.render_every = frames.max(1);
+        Ok(())
+    }
+
+    /// Play the simulation through `sonifier`, updated after every frame
+    /// that advanced the universe. Pass `null` to stop.
+    #[wasm_bindgen(js_name = setSonifier)]
+    pub fn set_sonifier(&mut self, sonifier: Option<Sonifier>) -> Result<(), ConvidaError> {
+        self.state()?.sonifier = sonifier;
+        Ok(())
+    }
+
+    /// Send MIDI notes for births in the trigger regions of `midi` after
+    /// every frame that advanced the universe. Pass `null` to stop.
+    #[wasm_bindgen(js_name = setMidi)]
+    pub fn set_midi(&mut self, midi: Option<MidiSequencer>) -> Result<(), ConvidaError> {
+        self.state()?.midi = midi;
+        Ok(())
+    }
+
+    /// Fade dead cells by how many of the last `generations` generations ago
+    /// they were alive, so moving patterns leave trails. 0 turns trails off.
+    #[wasm_bindgen(js_name = setTrails)]
+    pub fn set_trails(&mut self, generations: u8) -> Result<(), ConvidaError> {
+        let mut state = self.state()?;
+        if generations == 0 {
+            state.universe.disable_trails();
+        } else if state.universe.trail_ages().is_null() {
+            state.universe.enable_trails();
+        }
+        state.renderer.set_trails(generations);
+        Ok(())
+    }
+
+    /// Animate between ticks when running slower than the frame rate, with
+    /// `setSpeed`: births fade in and deaths fade out over the frames until
+    /// the next tick, which keeps the display a generation behind.
+    #[wasm_bindgen(js_name = setInterpolate)]
+    pub fn set_interpolate(&mut self, interpolate: bool) -> Result<(), ConvidaError> {
+        let mut state = self.state()?;
+        if !interpolate {
+            state.universe.disable_interpolation();
+        } else if state.universe.previous_cells().is_null() {
+            state.universe.enable_interpolation();
+        }
+        state.interpolate = interpolate;
+        Ok(())
+    }
+
+    /// The canvas as last drawn, as an `image/png` `Blob`.
+    pub fn screenshot(&self) -> Result<Blob, JsValue> {
+        self.state()?.renderer.screenshot_blob()
+    }
+
+    /// A `VideoCapture` of the loop's canvas, recording at up to `fps`
+    /// frames per second as `mime_type`.
+    #[wasm_bindgen(js_name = videoCapture)]
+    pub fn video_capture(
+        &self,
+        fps: Option<f64>,
+        mime_type: Option<String>,
+    ) -> Result<VideoCapture, JsValue> {
+        let canvas = self
+            .state()?
+            .context
+            .canvas()
+            .ok_or_else(|| JsValue::from_str("the context has no canvas"))?;
+        VideoCapture::new(&canvas, fps, mime_type)
+    }
+
+    /// Make clicks and drags on the canvas draw walls instead of cells.
+    #[wasm_bindgen(js_name = setDrawWalls)]
+    pub fn set_draw_walls(&mut self, draw_walls: bool) -> Result<(), ConvidaError> {
+        self.state()?.draw_walls = draw_walls;
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = toggleCell)]
+    pub fn toggle_cell(&mut self, row: u32, col: u32) -> Result<(), ConvidaError> {
+        self.state()?.universe.toggle_cell(row, col)
+    }
+
+    pub fn reset(&mut self) -> Result<(), ConvidaError> {
+        self.state()?.universe.reset();
+        Ok(())
+    }
+
+    pub fn clear(&mut self) -> Result<(), ConvidaError> {
+        self.state()?.universe.clear();
+        Ok(())
+    }
+
+    pub fn camera(&self) -> Result<Camera, ConvidaError> {
+        Ok(self.state()?.renderer.camera())
+    }
+
+    #[wasm_bindgen(js_name = setCamera)]
+    pub fn set_camera(&mut self, camera: Camera) -> Result<(), ConvidaError> {
+        self.state()?.renderer.set_camera(camera);
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = zoomAt)]
+    pub fn zoom_at(&mut self, x: f64, y: f64, factor: f64) -> Result<(), ConvidaError> {
+        self.state()?.renderer.zoom_at(x, y, factor);
+        Ok(())
+    }
+
+    pub fn pan(&mut self, dx: f64, dy: f64) -> Result<(), ConvidaError> {
+        self.state()?.renderer.pan(dx, dy);
+        Ok(())
+    }
+
+    /// Match the renderer to the canvas size after the canvas was resized.
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<(), ConvidaError> {
+        self.state()?.renderer.resize(width, height)
+    }
+}
+
+impl GameLoop {
+    // The loop's state, unless it is ticking. Only the universe's callbacks
+    // can call the loop then, and they get `ConvidaError::Busy`.
+    fn state(&self) -> Result<RefMut<'_, LoopState>, ConvidaError> {
+        self.state.try_borrow_mut().map_err(|_| ConvidaError::Busy)
+    }
+}
+
+impl Drop for GameLoop {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.state.try_borrow_mut() {
+            pause(&mut state);
+            state.stop_hidden_timer();
+        }
+        self.input = None;
+        self.drop_zone = None;
+        self.visibility = None;
+        // The closure holds a reference to its own cell, so it has to be
+        // dropped explicitly to break the cycle.
+        self.callback.borrow_mut().take();
+    }
+}
+
+pub(crate) fn play(state: &mut LoopState, callback: &FrameCallback) {
+    if state.running {
+        return;
+    }
+
+    state.running = true;
+    state.pacer.restart();
+    if state.frame_id.is_none() && state.hidden_since.is_none() {
+        state.frame_id = request_frame(callback);
+    }
+}
+
+pub(crate) fn pause(state: &mut LoopState) {
+    state.running = false;
+    state.cancel_frame();
+}
+
+// Hold the loop when the page is hidden, ticking it with `tick` under
+// `KeepRunning`, and release it when it is shown, as the policy says.
+// Playing and pausing while hidden take effect then.
+fn visibility_changed(
+    state: &Rc<RefCell<LoopState>>,
+    callback: &FrameCallback,
+    tick: &Function,
+    hidden: bool,
+) {
+    let mut state = state.borrow_mut();
+    if hidden {
+        if state.hidden_since.is_none() {
+            state.hidden_since = Some(Date::now());
+            state.cancel_frame();
+            if state.hidden_policy == HiddenPolicy::KeepRunning {
+                state.hidden_timer = web_sys::window().and_then(|window| {
+                    window
+                        .set_interval_with_callback_and_timeout_and_arguments_0(
+                            tick,
+                            HIDDEN_TICK_INTERVAL,
+                        )
+                        .ok()
+                });
+            }
+        }
+        return;
+    }
+
+    state.stop_hidden_timer();
+    if state.hidden_since.is_none() {
+        return;
+    }
+    if state.running && state.hidden_policy != HiddenPolicy::Pause {
+        state.run_hidden(Date::now());
+        let _ = state.draw();
+    }
+    state.hidden_since = None;
+    if !state.running {
+        return;
+    }
+    state.pacer.restart();
+    if state.frame_id.is_none() {
+        state.frame_id = request_frame(callback);
+    }
+}
+
+fn request_frame(callback: &FrameCallback) -> Option<i32> {
+    let window = web_sys::window()?;
+    let callback = callback.borrow();
+    let closure = callback.as_ref()?;
+    window
+        .request_animation_frame(closure.as_ref().unchecked_ref())
+        .ok()
+}
@@ -0,0 +1,138 @@
+// Gamepad control of a `GameLoop`, polled once per frame.
+//
+// Uses the standard mapping: the left stick or the d-pad moves a cursor
+// cell, A toggles it, B stamps the selected pattern at it, and the right
+// and left triggers speed the simulation up and slow it down.
+
+use js_sys::Array;
+use wasm_bindgen::JsCast;
+use web_sys::{Gamepad, GamepadButton};
+
+use crate::rle::Rle;
+
+const BUTTON_A: u32 = 0;
+const BUTTON_B: u32 = 1;
+const TRIGGER_LEFT: u32 = 6;
+const TRIGGER_RIGHT: u32 = 7;
+const DPAD_UP: u32 = 12;
+const DPAD_DOWN: u32 = 13;
+const DPAD_LEFT: u32 = 14;
+const DPAD_RIGHT: u32 = 15;
+
+// Stick deflection ignored as drift.
+const DEAD_ZONE: f64 = 0.25;
+// Cursor speed at full stick deflection, in cells per second.
+const CURSOR_SPEED: f64 = 20.0;
+// Speed change per second with a trigger fully pressed, as a factor.
+const SPEED_RATE: f64 = 4.0;
+
+pub(crate) enum Action {
+    Toggle(u32, u32),
+    Stamp(u32, u32),
+    /// Multiply the speed by this factor.
+    Speed(f64),
+}
+
+pub(crate) struct GamepadControl {
+    // Position of the cursor in cells. Fractional so slow stick movements
+    // still add up.
+    cursor: (f64, f64),
+    pressed: Vec<bool>,
+    last_timestamp: Option<f64>,
+    pub(crate) stamp: Rle,
+}
+
+impl GamepadControl {
+    pub(crate) fn new() -> GamepadControl {
+        GamepadControl {
+            cursor: (0.0, 0.0),
+            pressed: Vec::new(),
+            last_timestamp: None,
+            stamp: Rle::parse("bo$2bo$3o!").unwrap(),
+        }
+    }
+
+    /// The cell under the cursor, kept inside a `width` x `height` board.
+    pub(crate) fn cursor(&self, width: u32, height: u32) -> (u32, u32) {
+        let clamp = |value: f64, max: u32| value.max(0.0).min(max.saturating_sub(1) as f64) as u32;
+        (clamp(self.cursor.0, height), clamp(self.cursor.1, width))
+    }
+
+    /// Read the first connected gamepad and return what it asks for.
+    pub(crate) fn poll(&mut self, timestamp: f64, width: u32, height: u32) -> Vec<Action> {
+        let elapsed = match self.last_timestamp {
+            Some(last) => ((timestamp - last) / 1000.0).clamp(0.0, 0.1),
+            None => 0.0,
+        };
+        self.last_timestamp = Some(timestamp);
+
+        let gamepad = match first_gamepad() {
+            Some(gamepad) => gamepad,
+            None => return Vec::new(),
+        };
+        let buttons = gamepad.buttons();
+        let button = |index: u32| {
+            buttons
+                .get(index)
+                .dyn_into::<GamepadButton>()
+                .map(|button| (button.pressed(), button.value()))
+                .unwrap_or((false, 0.0))
+        };
+        let pressed: Vec<bool> = (0..buttons.length()).map(|i| button(i).0).collect();
+        let previous = std::mem::replace(&mut self.pressed, pressed.clone());
+        let just_pressed = |index: u32| {
+            pressed.get(index as usize) == Some(&true)
+                && previous.get(index as usize) != Some(&true)
+        };
+
+        let axes = gamepad.axes();
+        let axis = |index: u32| {
+            let value = axes.get(index).as_f64().unwrap_or(0.0);
+            if value.abs() < DEAD_ZONE {
+                0.0
+            } else {
+                value
+            }
+        };
+        self.cursor.0 += axis(1) * CURSOR_SPEED * elapsed;
+        self.cursor.1 += axis(0) * CURSOR_SPEED * elapsed;
+        if just_pressed(DPAD_UP) {
+            self.cursor.0 -= 1.0;
+        }
+        if just_pressed(DPAD_DOWN) {
+            self.cursor.0 += 1.0;
+        }
+        if just_pressed(DPAD_LEFT) {
+            self.cursor.1 -= 1.0;
+        }
+        if just_pressed(DPAD_RIGHT) {
+            self.cursor.1 += 1.0;
+        }
+        let max = |size: u32| size.saturating_sub(1) as f64;
+        self.cursor.0 = self.cursor.0.clamp(0.0, max(height));
+        self.cursor.1 = self.cursor.1.clamp(0.0, max(width));
+
+        let (row, col) = self.cursor(width, height);
+        let mut actions = Vec::new();
+        if just_pressed(BUTTON_A) {
+            actions.push(Action::Toggle(row, col));
+        }
+        if just_pressed(BUTTON_B) {
+            actions.push(Action::Stamp(row, col));
+        }
+        let throttle = button(TRIGGER_RIGHT).1 - button(TRIGGER_LEFT).1;
+        if throttle != 0.0 {
+            actions.push(Action::Speed(SPEED_RATE.powf(throttle * elapsed)));
+        }
+
+        actions
+    }
+}
+
+fn first_gamepad() -> Option<Gamepad> {
+    let gamepads: Array = web_sys::window()?.navigator().get_gamepads().ok()?;
+    gamepads
+        .iter()
+        .filter_map(|gamepad| gamepad.dyn_into::<Gamepad>().ok())
+        .find(|gamepad| gamepad.connected())
+}
@@ -0,0 +1,429 @@
+// Cell storage behind a common `Grid` trait.
+//
+// `Universe` keeps its cells in a dense `Vec<Cell>`, which is what the
+// JavaScript API exposes through `cells()`. The other backends trade that
+// for less memory or for work proportional to the live cells:
+//
+// * `BitGrid` packs one cell per bit.
+// * `SparseGrid` stores only the coordinates of live cells.
+// * `ChunkedGrid` stores 64x64 bit-packed chunks, leaving empty ones out.
+//
+// `step` runs a generation on any of them, and renderers and exporters
+// that take a `&dyn Grid` work with all of them.
+
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Range;
+
+use fixedbitset::FixedBitSet;
+
+use crate::{Boundary, Cell, Rule, Universe};
+
+/// The names accepted by `new_grid` and `UniverseBuilder::engine`.
+pub const ENGINES: &[&str] = &["dense", "bits", "sparse", "chunked"];
+
+pub trait Grid {
+    /// The width and height in cells.
+    fn dims(&self) -> (u32, u32);
+
+    fn get(&self, row: u32, col: u32) -> Cell;
+
+    fn set(&mut self, row: u32, col: u32, cell: Cell);
+
+    /// Kill every cell.
+    fn clear(&mut self);
+
+    /// The live cells inside the region, in reading order.
+    fn iter_region(
+        &self,
+        rows: Range<u32>,
+        cols: Range<u32>,
+    ) -> Box<dyn Iterator<Item = (u32, u32)> + '_>;
+
+    /// The number of live cells inside the region.
+    fn count_region(&self, rows: Range<u32>, cols: Range<u32>) -> usize {
+        self.iter_region(rows, cols).count()
+    }
+
+    fn population(&self) -> usize {
+        let (width, height) = self.dims();
+        self.count_region(0..height, 0..width)
+    }
+
+    /// Whether `step` should only visit the neighbourhoods of live cells
+    /// instead of every cell.
+    fn is_sparse(&self) -> bool {
+        false
+    }
+}
+
+/// An empty `width` x `height` grid of the named engine.
+pub fn new_grid(engine: &str, width: u32, height: u32) -> Option<Box<dyn Grid>> {
+    let grid: Box<dyn Grid> = match engine {
+        "dense" => Box::new(Universe::from_cells(
+            width,
+            height,
+            vec![Cell::Dead; (width * height) as usize],
+        )),
+        "bits" => Box::new(BitGrid::new(width, height)),
+        "sparse" => Box::new(SparseGrid::new(width, height)),
+        "chunked" => Box::new(ChunkedGrid::new(width, height)),
+        _ => return None,
+    };
+    Some(grid)
+}
+
+/// Advance `grid` one generation under `rule`.
+///
+/// `Universe::tick` is faster for the dense engine, since it doesn't go
+/// through the trait for every neighbour.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+pub fn step(grid: &mut dyn Grid, rule: &dyn Rule, boundary: Boundary) {
+    let (width, height) = grid.dims();
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    // A rule where dead cells with no live neighbours come alive changes
+    // the whole grid, so every cell has to be visited.
+    let births_from_nothing = rule.next_state(Cell::Dead, 0) == Cell::Alive;
+    let next: Vec<(u32, u32)> = if grid.is_sparse() && !births_from_nothing {
+        let mut counts: HashMap<(u32, u32), u8> = HashMap::new();
+        for (row, col) in grid.iter_region(0..height, 0..width) {
+            counts.entry((row, col)).or_insert(0);
+            for neighbor in neighbors(row, col, width, height, boundary) {
+                *counts.entry(neighbor).or_insert(0) += 1;
+            }
+        }
+        let mut next: Vec<(u32, u32)> = counts
+            .into_iter()
+            .filter(|&((row, col), count)| {
+                rule.next_state(grid.get(row, col), count) == Cell::Alive
+            })
+            .map(|(cell, _)| cell)
+            .collect();
+        next.sort_unstable();
+        next
+    } else {
+        let mut next = Vec::new();
+        for row in 0..height {
+            for col in 0..width {
+                let count = neighbors(row, col, width, height, boundary)
+                    .filter(|&(r, c)| grid.get(r, c) == Cell::Alive)
+                    .count() as u8;
+                if rule.next_state(grid.get(row, col), count) == Cell::Alive {
+                    next.push((row, col));
+                }
+            }
+        }
+        next
+    };
+
+    grid.clear();
+    for (row, col) in next {
+        grid.set(row, col, Cell::Alive);
+    }
+}
+
+const OFFSETS: [(i64, i64); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+// The up to eight neighbours of a cell.
+pub(crate) fn neighbors(
+    row: u32,
+    col: u32,
+    width: u32,
+    height: u32,
+    boundary: Boundary,
+) -> impl Iterator<Item = (u32, u32)> {
+    OFFSETS.iter().filter_map(move |&(dr, dc)| {
+        let r = row as i64 + dr;
+        let c = col as i64 + dc;
+        match boundary {
+            Boundary::Torus => Some((
+                r.rem_euclid(height as i64) as u32,
+                c.rem_euclid(width as i64) as u32,
+            )),
+            Boundary::Dead if r < 0 || c < 0 || r >= height as i64 || c >= width as i64 => None,
+            Boundary::Dead => Some((r as u32, c as u32)),
+        }
+    })
+}
+
+fn clamp_region(
+    rows: Range<u32>,
+    cols: Range<u32>,
+    width: u32,
+    height: u32,
+) -> (Range<u32>, Range<u32>) {
+    (
+        rows.start.min(height)..rows.end.min(height),
+        cols.start.min(width)..cols.end.min(width),
+    )
+}
+
+impl Grid for Universe {
+    fn dims(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn get(&self, row: u32, col: u32) -> Cell {
+        self.cells[self.get_index(row, col)]
+    }
+
+    fn set(&mut self, row: u32, col: u32, cell: Cell) {
+        let idx = self.get_index(row, col);
+        self.cells[idx] = cell;
+    }
+
+    fn clear(&mut self) {
+        Universe::clear(self);
+    }
+
+    fn iter_region(
+        &self,
+        rows: Range<u32>,
+        cols: Range<u32>,
+    ) -> Box<dyn Iterator<Item = (u32, u32)> + '_> {
+        let (rows, cols) = clamp_region(rows, cols, self.width, self.height);
+        Box::new(rows.flat_map(move |row| {
+            let cols = cols.clone();
+            cols.filter(move |&col| self.cells[self.get_index(row, col)] == Cell::Alive)
+                .map(move |col| (row, col))
+        }))
+    }
+
+    fn count_region(&self, rows: Range<u32>, cols: Range<u32>) -> usize {
+        let (rows, cols) = clamp_region(rows, cols, self.width, self.height);
+        rows.map(|row| {
+            let start = self.get_index(row, cols.start);
+            self.cells[start..start + cols.len()]
+                .iter()
+                .filter(|&&cell| cell == Cell::Alive)
+                .count()
+        })
+        .sum()
+    }
+}
+
+/// One bit per cell in row-major order.
+#[derive(Clone, Debug)]
+pub struct BitGrid {
+    width: u32,
+    height: u32,
+    bits: FixedBitSet,
+}
+
+impl BitGrid {
+    pub fn new(width: u32, height: u32) -> BitGrid {
+        BitGrid {
+            width,
+            height,
+            bits: FixedBitSet::with_capacity((width * height) as usize),
+        }
+    }
+}
+
+impl Grid for BitGrid {
+    fn dims(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn get(&self, row: u32, col: u32) -> Cell {
+        if self.bits.contains((row * self.width + col) as usize) {
+            Cell::Alive
+        } else {
+            Cell::Dead
+        }
+    }
+
+    fn set(&mut self, row: u32, col: u32, cell: Cell) {
+        self.bits
+            .set((row * self.width + col) as usize, cell == Cell::Alive);
+    }
+
+    fn clear(&mut self) {
+        self.bits.clear();
+    }
+
+    fn iter_region(
+        &self,
+        rows: Range<u32>,
+        cols: Range<u32>,
+    ) -> Box<dyn Iterator<Item = (u32, u32)> + '_> {
+        let (rows, cols) = clamp_region(rows, cols, self.width, self.height);
+        let width = self.width;
+        Box::new(rows.flat_map(move |row| {
+            let cols = cols.clone();
+            cols.filter(move |&col| self.bits.contains((row * width + col) as usize))
+                .map(move |col| (row, col))
+        }))
+    }
+
+    fn count_region(&self, rows: Range<u32>, cols: Range<u32>) -> usize {
+        let (rows, cols) = clamp_region(rows, cols, self.width, self.height);
+        rows.map(|row| {
+            let start = (row * self.width + cols.start) as usize;
+            self.bits.count_ones(start..start + cols.len())
+        })
+        .sum()
+    }
+}
+
+/// The coordinates of the live cells, for mostly empty universes.
+#[derive(Clone, Debug)]
+pub struct SparseGrid {
+    width: u32,
+    height: u32,
+    live: BTreeSet<(u32, u32)>,
+}
+
+impl SparseGrid {
+    pub fn new(width: u32, height: u32) -> SparseGrid {
+        SparseGrid {
+            width,
+            height,
+            live: BTreeSet::new(),
+        }
+    }
+}
+
+impl Grid for SparseGrid {
+    fn dims(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn get(&self, row: u32, col: u32) -> Cell {
+        if self.live.contains(&(row, col)) {
+            Cell::Alive
+        } else {
+            Cell::Dead
+        }
+    }
+
+    fn set(&mut self, row: u32, col: u32, cell: Cell) {
+        if cell == Cell::Alive {
+            self.live.insert((row, col));
+        } else {
+            self.live.remove(&(row, col));
+        }
+    }
+
+    fn clear(&mut self) {
+        self.live.clear();
+    }
+
+    fn iter_region(
+        &self,
+        rows: Range<u32>,
+        cols: Range<u32>,
+    ) -> Box<dyn Iterator<Item = (u32, u32)> + '_> {
+        let (rows, cols) = clamp_region(rows, cols, self.width, self.height);
+        if rows.start >= rows.end {
+            return Box::new(std::iter::empty());
+        }
+        Box::new(
+            self.live
+                .range((rows.start, 0)..(rows.end, 0))
+                .copied()
+                .filter(move |&(_, col)| cols.contains(&col)),
+        )
+    }
+
+    fn population(&self) -> usize {
+        self.live.len()
+    }
+
+    fn is_sparse(&self) -> bool {
+        true
+    }
+}
+
+const CHUNK: u32 = 64;
+
+/// 64x64 chunks of bits, one `u64` per chunk row. Chunks without live
+/// cells aren't stored.
+#[derive(Clone, Debug)]
+pub struct ChunkedGrid {
+    width: u32,
+    height: u32,
+    chunks: HashMap<(u32, u32), Box<[u64; CHUNK as usize]>>,
+}
+
+impl ChunkedGrid {
+    pub fn new(width: u32, height: u32) -> ChunkedGrid {
+        ChunkedGrid {
+            width,
+            height,
+            chunks: HashMap::new(),
+        }
+    }
+}
+
+impl Grid for ChunkedGrid {
+    fn dims(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn get(&self, row: u32, col: u32) -> Cell {
+        match self.chunks.get(&(row / CHUNK, col / CHUNK)) {
+            Some(chunk) if chunk[(row % CHUNK) as usize] & (1 << (col % CHUNK)) != 0 => Cell::Alive,
+            _ => Cell::Dead,
+        }
+    }
+
+    fn set(&mut self, row: u32, col: u32, cell: Cell) {
+        let key = (row / CHUNK, col / CHUNK);
+        let bit = 1 << (col % CHUNK);
+        if cell == Cell::Alive {
+            self.chunks
+                .entry(key)
+                .or_insert_with(|| Box::new([0; CHUNK as usize]))[(row % CHUNK) as usize] |= bit;
+        } else if let Some(chunk) = self.chunks.get_mut(&key) {
+            chunk[(row % CHUNK) as usize] &= !bit;
+            if chunk.iter().all(|&line| line == 0) {
+                self.chunks.remove(&key);
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.chunks.clear();
+    }
+
+    fn iter_region(
+        &self,
+        rows: Range<u32>,
+        cols: Range<u32>,
+    ) -> Box<dyn Iterator<Item = (u32, u32)> + '_> {
+        let (rows, cols) = clamp_region(rows, cols, self.width, self.height);
+        let mut live = Vec::new();
+        for (&(chunk_row, chunk_col), chunk) in &self.chunks {
+            for (i, &line) in chunk.iter().enumerate() {
+                let row = chunk_row * CHUNK + i as u32;
+                if line == 0 || !rows.contains(&row) {
+                    continue;
+                }
+                for bit in 0..CHUNK {
+                    let col = chunk_col * CHUNK + bit;
+                    if line & (1 << bit) != 0 && cols.contains(&col) {
+                        live.push((row, col));
+                    }
+                }
+            }
+        }
+        live.sort_unstable();
+        Box::new(live.into_iter())
+    }
+
+    fn is_sparse(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,100 @@
+// A heat field, for the glowing-trails effect.
+//
+// While heat is on, every live cell deposits heat into its square of an
+// `f32` field each generation. The field then spreads: each square moves
+// part of its heat towards the average of its neighbours, and loses part of
+// it. Where life has passed the field stays warm for a while and fades.
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::grid::neighbors;
+use crate::{Boundary, Cell, Universe};
+
+#[derive(Clone, Debug)]
+pub(crate) struct HeatField {
+    deposit: f32,
+    diffusion: f32,
+    decay: f32,
+    values: Vec<f32>,
+}
+
+impl HeatField {
+    // Spread the heat one generation on, with `cells` depositing into it.
+    pub(crate) fn step(&mut self, cells: &[Cell], width: u32, height: u32, boundary: Boundary) {
+        if self.values.len() != cells.len() {
+            self.values = vec![0.0; cells.len()];
+        }
+        let mut values = Vec::with_capacity(cells.len());
+        for (i, &cell) in cells.iter().enumerate() {
+            let (row, col) = (i as u32 / width, i as u32 % width);
+            let (mut sum, mut count) = (0.0, 0);
+            for (r, c) in neighbors(row, col, width, height, boundary) {
+                sum += self.values[(r * width + c) as usize];
+                count += 1;
+            }
+            let mut heat = self.values[i];
+            if count > 0 {
+                heat += self.diffusion * (sum / count as f32 - heat);
+            }
+            heat *= 1.0 - self.decay;
+            if cell == Cell::Alive {
+                heat += self.deposit;
+            }
+            values.push(heat);
+        }
+        self.values = values;
+    }
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Universe {
+    /// Start a heat field, every square cold. Each generation a live cell
+    /// adds `deposit`, then each square moves the fraction `diffusion` of
+    /// the way towards its neighbours' average and loses the fraction
+    /// `decay`. Both fractions are clamped to 0..=1.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = enableHeat))]
+    pub fn enable_heat(&mut self, deposit: f32, diffusion: f32, decay: f32) {
+        self.heat = Some(HeatField {
+            deposit,
+            diffusion: diffusion.clamp(0.0, 1.0),
+            decay: decay.clamp(0.0, 1.0),
+            values: vec![0.0; self.cells.len()],
+        });
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = disableHeat))]
+    pub fn disable_heat(&mut self) {
+        self.heat = None;
+    }
+
+    /// The heat of each square, one `f32` per cell like `cells`, or null
+    /// while heat is off.
+    pub fn heat(&self) -> *const f32 {
+        match &self.heat {
+            Some(heat) => heat.values.as_ptr(),
+            None => std::ptr::null(),
+        }
+    }
+}
+
+impl Universe {
+    /// The heat of each square, while heat is on.
+    pub fn heat_values(&self) -> Option<&[f32]> {
+        self.heat.as_ref().map(|heat| &heat.values[..])
+    }
+
+    // Called after every step.
+    pub(crate) fn spread_heat(&mut self) {
+        if let Some(heat) = &mut self.heat {
+            heat.step(&self.cells, self.width, self.height, self.boundary);
+        }
+    }
+
+    // Start the field cold again on a board of a different size.
+    pub(crate) fn resize_heat(&mut self, len: usize) {
+        if let Some(heat) = &mut self.heat {
+            heat.values = vec![0.0; len];
+        }
+    }
+}
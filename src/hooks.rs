@@ -0,0 +1,80 @@
+// JavaScript callbacks invoked by the universe as it evolves.
+//
+// Callbacks run while the universe is still borrowed by `tick`, so they
+// must not call back into the same universe. A `GameLoop` running it is
+// borrowed for the whole frame and answers them with `ConvidaError::Busy`.
+
+use js_sys::{Function, Uint32Array};
+use wasm_bindgen::prelude::*;
+
+use crate::{Cell, Universe};
+
+#[derive(Default)]
+pub(crate) struct Hooks {
+    on_tick: Option<Function>,
+    on_cell_change: Option<Function>,
+    batch_cell_changes: bool,
+}
+
+impl Hooks {
+    pub(crate) fn wants_cell_changes(&self) -> bool {
+        self.on_cell_change.is_some()
+    }
+
+    // `changes` holds flat (row, col, new state) triples.
+    pub(crate) fn cell_changes(&self, changes: &[u32]) {
+        let callback = match &self.on_cell_change {
+            Some(callback) => callback,
+            None => return,
+        };
+
+        if self.batch_cell_changes {
+            let _ = callback.call1(&JsValue::NULL, &Uint32Array::from(changes));
+            return;
+        }
+
+        for change in changes.chunks_exact(3) {
+            let _ = callback.call3(
+                &JsValue::NULL,
+                &JsValue::from(change[0]),
+                &JsValue::from(change[1]),
+                &JsValue::from(change[2] == Cell::Alive as u32),
+            );
+        }
+    }
+
+    pub(crate) fn tick(&self, generation: u32) {
+        if let Some(callback) = &self.on_tick {
+            let _ = callback.call1(&JsValue::NULL, &JsValue::from(generation));
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Universe {
+    /// Call `callback(generation)` after every tick. Pass `null` to
+    /// unregister.
+    ///
+    /// The callback runs inside the tick, so calling this universe or a
+    /// `GameLoop` running it from there throws.
+    #[wasm_bindgen(js_name = onTick)]
+    pub fn on_tick(&mut self, callback: Option<Function>) {
+        self.hooks.on_tick = callback;
+    }
+
+    /// Call `callback(row, col, alive)` for every cell that changes state
+    /// during a tick. Pass `null` to unregister.
+    ///
+    /// With batching enabled the callback is instead called once per tick
+    /// with a `Uint32Array` of flat `[row, col, state, ...]` triples. As with
+    /// `on_tick`, calling the universe or its loop from the callback throws.
+    #[wasm_bindgen(js_name = onCellChange)]
+    pub fn on_cell_change(&mut self, callback: Option<Function>) {
+        self.hooks.on_cell_change = callback;
+    }
+
+    #[wasm_bindgen(js_name = setCellChangeBatching)]
+    pub fn set_cell_change_batching(&mut self, batch: bool) {
+        self.hooks.batch_cell_changes = batch;
+    }
+}
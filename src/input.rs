@@ -0,0 +1,465 @@
+// Mouse, wheel, keyboard and touch input for a `GameLoop`.
+//
+// The listeners are attached from Rust, so embedding only takes a call to
+// `GameLoop::attach(canvas)`. Clicking toggles a cell and dragging paints
+// with the state the first cell was given, the wheel zooms around the
+// pointer, a middle-button drag pans and the space bar pauses or resumes.
+//
+// On touch screens a tap toggles, a one-finger drag paints, two fingers
+// pan and pinch to zoom, and a long press is reported to the handler set
+// with `GameLoop::on_long_press`, e.g. to open a stamp menu.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use js_sys::{Array, Function};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{
+    Event, EventTarget, HtmlCanvasElement, KeyboardEvent, MouseEvent, TouchEvent, WheelEvent,
+};
+
+use crate::game_loop::{self, FrameCallback, LoopState};
+use crate::Cell;
+
+// Pixels a wheel "line" scrolls in Firefox, which reports lines instead of
+// pixels.
+const LINE_HEIGHT: f64 = 16.0;
+// Zoom factor per pixel scrolled.
+const WHEEL_ZOOM: f64 = 0.002;
+
+const BUTTON_PRIMARY: i16 = 0;
+const BUTTON_MIDDLE: i16 = 1;
+
+// How long a finger has to rest to count as a long press, in milliseconds,
+// and how far in CSS pixels it may wander before it counts as a drag.
+const LONG_PRESS_DELAY: i32 = 500;
+const TAP_SLOP: f64 = 10.0;
+
+/// An event listener, removed again when dropped.
+pub(crate) struct Listener {
+    target: EventTarget,
+    kind: &'static str,
+    closure: Closure<dyn FnMut(Event)>,
+}
+
+impl Listener {
+    /// Call `handler` with each `kind` event on `target` that is an `E`.
+    pub(crate) fn new<E, F>(
+        target: &EventTarget,
+        kind: &'static str,
+        mut handler: F,
+    ) -> Result<Listener, JsValue>
+    where
+        E: JsCast,
+        F: FnMut(E) + 'static,
+    {
+        let closure = Closure::wrap(Box::new(move |event: Event| {
+            if let Ok(event) = event.dyn_into::<E>() {
+                handler(event);
+            }
+        }) as Box<dyn FnMut(Event)>);
+        target.add_event_listener_with_callback(kind, closure.as_ref().unchecked_ref())?;
+        Ok(Listener {
+            target: target.clone(),
+            kind,
+            closure,
+        })
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        let _ = self
+            .target
+            .remove_event_listener_with_callback(self.kind, self.closure.as_ref().unchecked_ref());
+    }
+}
+
+/// The listeners of an attached canvas. Dropping it detaches them.
+pub(crate) struct Input {
+    listeners: Vec<Listener>,
+    touch: Rc<RefCell<Touch>>,
+    long_press: Closure<dyn FnMut()>,
+}
+
+#[derive(Default)]
+struct Pointer {
+    // State painted while the primary button is held, and the last
+    // painted cell so a drag within one cell doesn't repaint it.
+    painting: Option<Cell>,
+    last_cell: Option<(u32, u32)>,
+    // Last client position while panning with the middle button.
+    panning: Option<(f64, f64)>,
+}
+
+enum Touch {
+    Idle,
+    // One finger down that is not yet a tap, a drag or a long press. The
+    // position is in client coordinates.
+    Pending {
+        client: (f64, f64),
+        timer: Option<i32>,
+    },
+    Painting {
+        paint: Cell,
+        last_cell: Option<(u32, u32)>,
+    },
+    // Distance between and midpoint of two fingers, in canvas pixels.
+    Pinching {
+        distance: f64,
+        center: (f64, f64),
+    },
+    // Ignore the remaining fingers until all of them are lifted, e.g.
+    // after a long press or a pinch.
+    Finished,
+}
+
+impl Touch {
+    fn cancel_timer(&mut self) {
+        if let Touch::Pending { timer, .. } = self {
+            if let (Some(id), Some(window)) = (timer.take(), web_sys::window()) {
+                window.clear_timeout_with_handle(id);
+            }
+        }
+    }
+}
+
+impl Input {
+    pub(crate) fn attach(
+        canvas: &HtmlCanvasElement,
+        state: &Rc<RefCell<LoopState>>,
+        callback: &FrameCallback,
+    ) -> Result<Input, JsValue> {
+        let touch = Rc::new(RefCell::new(Touch::Idle));
+        let long_press = {
+            let (canvas, state, touch) = (canvas.clone(), Rc::clone(state), Rc::clone(&touch));
+            Closure::wrap(Box::new(move || {
+                let (x, y) = match *touch.borrow() {
+                    Touch::Pending { client, .. } => client,
+                    _ => return,
+                };
+                *touch.borrow_mut() = Touch::Finished;
+
+                // Release the borrow before calling out, since the handler
+                // may well call back into the loop.
+                let (cx, cy) = to_canvas(&canvas, x, y);
+                let (cell, handler) = {
+                    let state = state.borrow();
+                    (state.cell_at(cx, cy), state.long_press.clone())
+                };
+                if let (Some((row, col)), Some(handler)) = (cell, handler) {
+                    let args = Array::of4(&row.into(), &col.into(), &x.into(), &y.into());
+                    let _ = handler.apply(&JsValue::NULL, &args);
+                }
+            }) as Box<dyn FnMut()>)
+        };
+        let mut input = Input {
+            listeners: Vec::new(),
+            touch,
+            long_press,
+        };
+        let pointer = Rc::new(RefCell::new(Pointer::default()));
+        let target: &EventTarget = canvas.as_ref();
+
+        {
+            let (canvas, state, pointer) = (canvas.clone(), Rc::clone(state), Rc::clone(&pointer));
+            input.listen(target, "mousedown", move |event: MouseEvent| {
+                let mut pointer = pointer.borrow_mut();
+                match event.button() {
+                    BUTTON_PRIMARY => {
+                        let (x, y) = canvas_point(&canvas, &event);
+                        let mut state = state.borrow_mut();
+                        if let Some((row, col)) = state.cell_at(x, y) {
+                            pointer.painting = Some(state.toggle_at(row, col));
+                            pointer.last_cell = Some((row, col));
+                            let _ = state.draw();
+                        }
+                    }
+                    BUTTON_MIDDLE => {
+                        event.prevent_default();
+                        pointer.panning = Some(client_point(&event));
+                    }
+                    _ => {}
+                }
+            })?;
+        }
+
+        {
+            let (canvas, state, pointer) = (canvas.clone(), Rc::clone(state), Rc::clone(&pointer));
+            input.listen(target, "mousemove", move |event: MouseEvent| {
+                let mut pointer = pointer.borrow_mut();
+                if let Some((last_x, last_y)) = pointer.panning {
+                    let (x, y) = client_point(&event);
+                    let (sx, sy) = canvas_scale(&canvas);
+                    let mut state = state.borrow_mut();
+                    state.renderer.pan((x - last_x) * sx, (y - last_y) * sy);
+                    pointer.panning = Some((x, y));
+                    let _ = state.draw();
+                    return;
+                }
+
+                if let Some(paint) = pointer.painting {
+                    let (x, y) = canvas_point(&canvas, &event);
+                    let mut state = state.borrow_mut();
+                    match state.cell_at(x, y) {
+                        Some((row, col)) if pointer.last_cell != Some((row, col)) => {
+                            state.set_cell(row, col, paint);
+                            pointer.last_cell = Some((row, col));
+                            let _ = state.draw();
+                        }
+                        _ => {}
+                    }
+                }
+            })?;
+        }
+
+        for &kind in &["mouseup", "mouseleave"] {
+            let pointer = Rc::clone(&pointer);
+            input.listen(target, kind, move |_: MouseEvent| {
+                *pointer.borrow_mut() = Pointer::default();
+            })?;
+        }
+
+        {
+            let (canvas, state) = (canvas.clone(), Rc::clone(state));
+            input.listen(target, "wheel", move |event: WheelEvent| {
+                event.prevent_default();
+                let delta = match event.delta_mode() {
+                    WheelEvent::DOM_DELTA_LINE => event.delta_y() * LINE_HEIGHT,
+                    _ => event.delta_y(),
+                };
+                let (x, y) = canvas_point(&canvas, &event);
+                let mut state = state.borrow_mut();
+                state.renderer.zoom_at(x, y, (-delta * WHEEL_ZOOM).exp());
+                let _ = state.draw();
+            })?;
+        }
+
+        if let Some(window) = web_sys::window() {
+            let (state, callback) = (Rc::clone(state), Rc::clone(callback));
+            input.listen(window.as_ref(), "keydown", move |event: KeyboardEvent| {
+                if event.key() != " " || typing(&event) {
+                    return;
+                }
+                event.prevent_default();
+                let mut state = state.borrow_mut();
+                if state.running {
+                    game_loop::pause(&mut state);
+                } else {
+                    game_loop::play(&mut state, &callback);
+                }
+            })?;
+        }
+
+        input.attach_touch(canvas, state)?;
+        Ok(input)
+    }
+
+    fn attach_touch(
+        &mut self,
+        canvas: &HtmlCanvasElement,
+        state: &Rc<RefCell<LoopState>>,
+    ) -> Result<(), JsValue> {
+        let target: &EventTarget = canvas.as_ref();
+        let long_press: Function = self.long_press.as_ref().unchecked_ref::<Function>().clone();
+
+        {
+            let (canvas, touch) = (canvas.clone(), Rc::clone(&self.touch));
+            self.listen(target, "touchstart", move |event: TouchEvent| {
+                event.prevent_default();
+                let mut touch = touch.borrow_mut();
+                touch.cancel_timer();
+                let touches = event.touches();
+                *touch = match touches.length() {
+                    1 => {
+                        let finger = touches.get(0).unwrap();
+                        let timer = web_sys::window().and_then(|window| {
+                            window
+                                .set_timeout_with_callback_and_timeout_and_arguments_0(
+                                    &long_press,
+                                    LONG_PRESS_DELAY,
+                                )
+                                .ok()
+                        });
+                        Touch::Pending {
+                            client: (finger.client_x() as f64, finger.client_y() as f64),
+                            timer,
+                        }
+                    }
+                    2 => {
+                        let (distance, center) = pinch(&canvas, &event);
+                        Touch::Pinching { distance, center }
+                    }
+                    _ => Touch::Finished,
+                };
+            })?;
+        }
+
+        {
+            let (canvas, state, touch) = (canvas.clone(), Rc::clone(state), Rc::clone(&self.touch));
+            self.listen(target, "touchmove", move |event: TouchEvent| {
+                event.prevent_default();
+                let mut touch = touch.borrow_mut();
+                let mut state = state.borrow_mut();
+                let finger = match event.touches().get(0) {
+                    Some(finger) => finger,
+                    None => return,
+                };
+                let client = (finger.client_x() as f64, finger.client_y() as f64);
+
+                if let Touch::Pending { client: start, .. } = *touch {
+                    let (dx, dy) = (client.0 - start.0, client.1 - start.1);
+                    if dx.hypot(dy) < TAP_SLOP {
+                        return;
+                    }
+                    touch.cancel_timer();
+                    let (x, y) = to_canvas(&canvas, start.0, start.1);
+                    *touch = match state.cell_at(x, y) {
+                        Some((row, col)) => Touch::Painting {
+                            paint: state.toggle_at(row, col),
+                            last_cell: Some((row, col)),
+                        },
+                        None => Touch::Finished,
+                    };
+                }
+
+                match &mut *touch {
+                    Touch::Painting { paint, last_cell } => {
+                        let (x, y) = to_canvas(&canvas, client.0, client.1);
+                        if let Some(cell) = state.cell_at(x, y) {
+                            if *last_cell != Some(cell) {
+                                state.set_cell(cell.0, cell.1, *paint);
+                                *last_cell = Some(cell);
+                            }
+                        }
+                    }
+                    Touch::Pinching { distance, center } if event.touches().length() >= 2 => {
+                        let (new_distance, new_center) = pinch(&canvas, &event);
+                        state
+                            .renderer
+                            .pan(new_center.0 - center.0, new_center.1 - center.1);
+                        if *distance > 0.0 {
+                            state.renderer.zoom_at(
+                                new_center.0,
+                                new_center.1,
+                                new_distance / *distance,
+                            );
+                        }
+                        *distance = new_distance;
+                        *center = new_center;
+                    }
+                    _ => return,
+                }
+                let _ = state.draw();
+            })?;
+        }
+
+        for &kind in &["touchend", "touchcancel"] {
+            let (canvas, state, touch) = (canvas.clone(), Rc::clone(state), Rc::clone(&self.touch));
+            self.listen(target, kind, move |event: TouchEvent| {
+                event.prevent_default();
+                let mut touch = touch.borrow_mut();
+                touch.cancel_timer();
+                if event.touches().length() > 0 {
+                    // Lifting one finger of a pinch mustn't start painting.
+                    *touch = Touch::Finished;
+                    return;
+                }
+
+                if let (Touch::Pending { client, .. }, "touchend") = (&*touch, kind) {
+                    let (x, y) = to_canvas(&canvas, client.0, client.1);
+                    let mut state = state.borrow_mut();
+                    if let Some((row, col)) = state.cell_at(x, y) {
+                        state.toggle_at(row, col);
+                        let _ = state.draw();
+                    }
+                }
+                *touch = Touch::Idle;
+            })?;
+        }
+
+        Ok(())
+    }
+
+    fn listen<E, F>(
+        &mut self,
+        target: &EventTarget,
+        kind: &'static str,
+        handler: F,
+    ) -> Result<(), JsValue>
+    where
+        E: JsCast,
+        F: FnMut(E) + 'static,
+    {
+        self.listeners.push(Listener::new(target, kind, handler)?);
+        Ok(())
+    }
+}
+
+impl Drop for Input {
+    fn drop(&mut self) {
+        self.touch.borrow_mut().cancel_timer();
+    }
+}
+
+// Canvas pixels per CSS pixel, which differ when the canvas is scaled by
+// the page.
+fn canvas_scale(canvas: &HtmlCanvasElement) -> (f64, f64) {
+    let rect = canvas.get_bounding_client_rect();
+    let scale = |pixels: u32, css: f64| if css > 0.0 { pixels as f64 / css } else { 1.0 };
+    (
+        scale(canvas.width(), rect.width()),
+        scale(canvas.height(), rect.height()),
+    )
+}
+
+// Convert a position in client coordinates to canvas pixels.
+pub(crate) fn to_canvas(canvas: &HtmlCanvasElement, x: f64, y: f64) -> (f64, f64) {
+    let rect = canvas.get_bounding_client_rect();
+    let (sx, sy) = canvas_scale(canvas);
+    ((x - rect.left()) * sx, (y - rect.top()) * sy)
+}
+
+fn canvas_point(canvas: &HtmlCanvasElement, event: &MouseEvent) -> (f64, f64) {
+    let (x, y) = client_point(event);
+    to_canvas(canvas, x, y)
+}
+
+// The client position of `event`, which the unstable bindings the `webgpu`
+// feature builds with already give as `f64`s.
+#[allow(clippy::unnecessary_cast)]
+pub(crate) fn client_point(event: &MouseEvent) -> (f64, f64) {
+    (event.client_x() as f64, event.client_y() as f64)
+}
+
+// Distance between and midpoint of the first two fingers, in canvas pixels.
+fn pinch(canvas: &HtmlCanvasElement, event: &TouchEvent) -> (f64, (f64, f64)) {
+    let touches = event.touches();
+    let point = |i| {
+        touches
+            .get(i)
+            .map(|t| to_canvas(canvas, t.client_x() as f64, t.client_y() as f64))
+            .unwrap_or((0.0, 0.0))
+    };
+    let (a, b) = (point(0), point(1));
+    (
+        (b.0 - a.0).hypot(b.1 - a.1),
+        ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0),
+    )
+}
+
+// Whether a key press is meant for a form field rather than the game.
+fn typing(event: &KeyboardEvent) -> bool {
+    let element = match event
+        .target()
+        .and_then(|t| t.dyn_into::<web_sys::Element>().ok())
+    {
+        Some(element) => element,
+        None => return false,
+    };
+    match element.tag_name().as_str() {
+        "INPUT" | "TEXTAREA" | "SELECT" => true,
+        _ => element.has_attribute("contenteditable"),
+    }
+}
@@ -0,0 +1,27 @@
+// Reporting `tracing` spans, built with the `tracing` feature.
+//
+// Each tick is a `Universe::tick` span with child spans for its phases, and
+// the editing calls are spans of their own. Any subscriber sees them; this
+// module only installs a default one where the embedder has none.
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+/// Report spans to the browser console, with `performance` marks for its
+/// profiler, or natively print each span to stderr as it closes. Does
+/// nothing if a subscriber is already installed.
+#[cfg_attr(feature = "web", wasm_bindgen(js_name = initTracing))]
+pub fn init_tracing() {
+    cfg_if::cfg_if! {
+        if #[cfg(all(feature = "web", target_arch = "wasm32", target_os = "unknown"))] {
+            let _ = tracing_wasm::try_set_as_global_default();
+        } else if #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))] {
+            use tracing_subscriber::fmt::format::FmtSpan;
+
+            let _ = tracing_subscriber::fmt()
+                .with_span_events(FmtSpan::CLOSE)
+                .with_writer(std::io::stderr)
+                .try_init();
+        }
+    }
+}
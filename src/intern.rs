@@ -0,0 +1,55 @@
+// Content-addressed storage, for keeping many snapshots of a board that
+// barely changes between them.
+//
+// Bytes are cut into blocks and each distinct block is stored once, so the
+// checkpoints of a mostly static board, or the time-lapse frames of an
+// oscillator, share nearly all of their memory. A stored value is a list
+// of shared blocks; the store forgets a block once no value holds it.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Bytes held as blocks shared with the other values of their store.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Interned {
+    blocks: Vec<Arc<[u8]>>,
+}
+
+impl Interned {
+    pub(crate) fn to_vec(&self) -> Vec<u8> {
+        self.blocks.concat()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Store {
+    blocks: HashSet<Arc<[u8]>>,
+}
+
+impl Store {
+    /// Store `bytes` as blocks of `block_size` bytes, the last one shorter.
+    pub(crate) fn intern(&mut self, bytes: &[u8], block_size: usize) -> Interned {
+        let blocks = bytes
+            .chunks(block_size.max(1))
+            .map(|block| match self.blocks.get(block) {
+                Some(shared) => Arc::clone(shared),
+                None => {
+                    let shared: Arc<[u8]> = block.into();
+                    self.blocks.insert(Arc::clone(&shared));
+                    shared
+                }
+            })
+            .collect();
+        Interned { blocks }
+    }
+
+    /// Forget the blocks no value holds any more.
+    pub(crate) fn collect(&mut self) {
+        self.blocks.retain(|block| Arc::strong_count(block) > 1);
+    }
+
+    /// The bytes of the distinct blocks, what the values really take up.
+    pub(crate) fn bytes(&self) -> usize {
+        self.blocks.iter().map(|block| block.len()).sum()
+    }
+}
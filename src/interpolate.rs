@@ -0,0 +1,67 @@
+// The generation before the current one, for animating between ticks.
+//
+// While interpolation is on, each tick keeps the cells it replaced, so a
+// renderer running faster than the simulation can fade births in and deaths
+// out with `Renderer::render_interpolated` instead of jumping from one
+// generation to the next.
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::{Cell, Universe};
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Universe {
+    /// Keep the previous generation alongside the current one, starting out
+    /// the same as the current one.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = enableInterpolation))]
+    pub fn enable_interpolation(&mut self) {
+        self.previous = Some(self.cells.clone());
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = disableInterpolation))]
+    pub fn disable_interpolation(&mut self) {
+        self.previous = None;
+    }
+
+    /// The cells before the last tick, laid out like `cells`, or null while
+    /// interpolation is off.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = previousCells))]
+    pub fn previous_cells(&self) -> *const Cell {
+        match &self.previous {
+            Some(previous) => previous.as_ptr(),
+            None => std::ptr::null(),
+        }
+    }
+}
+
+impl Universe {
+    /// (`row`, `col`) before the last tick, while interpolation is on. Every
+    /// cell reads as dead after a resize until the next tick.
+    pub fn previous_cell(&self, row: u32, col: u32) -> Option<Cell> {
+        if row >= self.height || col >= self.width {
+            return None;
+        }
+        let previous = self.previous.as_ref()?;
+        if previous.len() != self.cells.len() {
+            return None;
+        }
+        previous.get(self.get_index(row, col)).copied()
+    }
+
+    // Called when the board is resized to `len` cells, which leaves no
+    // previous generation to fade from.
+    pub(crate) fn resize_previous(&mut self, len: usize) {
+        if let Some(previous) = &mut self.previous {
+            *previous = vec![Cell::Dead; len];
+        }
+    }
+
+    // Called by every step with the cells it replaced.
+    pub(crate) fn keep_previous(&mut self, cells: &[Cell]) {
+        if let Some(previous) = &mut self.previous {
+            previous.clear();
+            previous.extend_from_slice(cells);
+        }
+    }
+}
@@ -0,0 +1,170 @@
+// Weighted neighbourhood kernels, for weighted Life variants and
+// anisotropic rules.
+//
+// A kernel gives each offset around a cell a weight, the centre included.
+// Instead of counting live neighbours the universe sums the weights of the
+// live cells under the kernel, and the cell is born or survives when that
+// sum falls within the rule's range. Conway's Game of Life is the 3x3
+// kernel of ones with a 0 in the centre, born on 3 and surviving on 2 to 3.
+//
+//     let kernel = Kernel::new(vec![1.0, 1.0, 1.0, 1.0, 0.0, 1.0, 1.0, 1.0, 1.0], 3, 3)?;
+//     universe.set_kernel_rule(KernelRule { kernel, birth: (3.0, 3.0), survival: (2.0, 3.0) });
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::{Boundary, Cell, ConvidaError, Universe};
+
+/// Weights in reading order over a `width` x `height` window centred on
+/// the cell.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Kernel {
+    width: u32,
+    height: u32,
+    weights: Vec<f32>,
+}
+
+impl Kernel {
+    pub fn new(weights: Vec<f32>, width: u32, height: u32) -> Result<Kernel, ConvidaError> {
+        if width.is_multiple_of(2) || height.is_multiple_of(2) {
+            return Err(ConvidaError::KernelSize { width, height });
+        }
+        let expected = ConvidaError::check_size(width, height)?;
+        if weights.len() != expected {
+            return Err(ConvidaError::BufferLength {
+                expected: expected as u32,
+                actual: weights.len() as u32,
+            });
+        }
+        Ok(Kernel {
+            width,
+            height,
+            weights,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn weights(&self) -> &[f32] {
+        &self.weights
+    }
+
+    // The sum of the weights over the live cells around (`row`, `col`).
+    fn sum(
+        &self,
+        cells: &[Cell],
+        width: u32,
+        height: u32,
+        boundary: Boundary,
+        row: u32,
+        col: u32,
+    ) -> f32 {
+        let (dy, dx) = ((self.height / 2) as i64, (self.width / 2) as i64);
+        let mut sum = 0.0;
+        for (i, &weight) in self.weights.iter().enumerate() {
+            if weight == 0.0 {
+                continue;
+            }
+            let r = row as i64 + i as i64 / self.width as i64 - dy;
+            let c = col as i64 + i as i64 % self.width as i64 - dx;
+            let (r, c) = match boundary {
+                Boundary::Torus => (r.rem_euclid(height as i64), c.rem_euclid(width as i64)),
+                Boundary::Dead if r < 0 || c < 0 || r >= height as i64 || c >= width as i64 => {
+                    continue
+                }
+                Boundary::Dead => (r, c),
+            };
+            if cells[(r * width as i64 + c) as usize] == Cell::Alive {
+                sum += weight;
+            }
+        }
+        sum
+    }
+}
+
+/// A threshold rule over a `Kernel`: a dead cell is born when the sum lies
+/// within `birth` and a live one survives when it lies within `survival`,
+/// both ranges inclusive.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KernelRule {
+    pub kernel: Kernel,
+    pub birth: (f32, f32),
+    pub survival: (f32, f32),
+}
+
+impl KernelRule {
+    pub(crate) fn next_state(&self, universe: &Universe, row: u32, col: u32) -> Cell {
+        let sum = self.kernel.sum(
+            &universe.cells,
+            universe.width,
+            universe.height,
+            universe.boundary,
+            row,
+            col,
+        );
+        let (min, max) = match universe.cells[universe.get_index(row, col)] {
+            Cell::Dead => self.birth,
+            Cell::Alive => self.survival,
+        };
+        if (min..=max).contains(&sum) {
+            Cell::Alive
+        } else {
+            Cell::Dead
+        }
+    }
+}
+
+impl Universe {
+    /// Tick under `rule` instead of the universe's life-like rule, until
+    /// `clear_kernel`. Rule zones still run under their own rules.
+    pub fn set_kernel_rule(&mut self, rule: KernelRule) {
+        self.kernel = Some(rule);
+    }
+
+    pub fn kernel_rule(&self) -> Option<&KernelRule> {
+        self.kernel.as_ref()
+    }
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Universe {
+    /// Go back to the universe's life-like rule.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = clearKernel))]
+    pub fn clear_kernel(&mut self) {
+        self.kernel = None;
+    }
+}
+
+#[cfg(feature = "web")]
+#[wasm_bindgen]
+impl Universe {
+    /// Tick under a kernel of `weights` in reading order over a `width` x
+    /// `height` window, born when the weighted sum is within `birth_min`
+    /// to `birth_max` and surviving within `survival_min` to
+    /// `survival_max`.
+    #[wasm_bindgen(js_name = setKernel)]
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_kernel_js(
+        &mut self,
+        weights: Vec<f32>,
+        width: u32,
+        height: u32,
+        birth_min: f32,
+        birth_max: f32,
+        survival_min: f32,
+        survival_max: f32,
+    ) -> Result<(), ConvidaError> {
+        self.set_kernel_rule(KernelRule {
+            kernel: Kernel::new(weights, width, height)?,
+            birth: (birth_min, birth_max),
+            survival: (survival_min, survival_max),
+        });
+        Ok(())
+    }
+}
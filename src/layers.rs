@@ -0,0 +1,155 @@
+// Stacks of universes evolved together, each layer's rule able to look at
+// another layer's cells.
+//
+// Every layer has the same size. An uncoupled layer ticks under its own
+// rule. A coupled one steps under a `LayeredRule` that also sees the cell
+// at the same place in its source layer, as it was before the tick, so the
+// order of the layers doesn't matter. With a `Coupled` rule of `B3/S23`
+// over live cells and `B3/S` over dead ones, the cells of layer 1 only
+// survive above live cells of layer 0:
+//
+//     stack.couple(1, 0, Coupled { alive: "B3/S23".parse()?, dead: "B3/S".parse()? })?;
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "web")]
+use crate::rule::parse_rule;
+use crate::{Cell, ConvidaError, Coupled, Universe};
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub struct LayeredUniverse {
+    layers: Vec<Universe>,
+    /// The source layer and rule of each coupled layer.
+    couplings: Vec<Option<(usize, Coupled)>>,
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl LayeredUniverse {
+    /// A stack of `count` dead `width` x `height` layers, none coupled.
+    #[cfg_attr(feature = "web", wasm_bindgen(constructor))]
+    pub fn new(width: u32, height: u32, count: u32) -> Result<LayeredUniverse, ConvidaError> {
+        let size = ConvidaError::check_size(width, height)?;
+        let layer = Universe::from_cells(width, height, vec![Cell::Dead; size]);
+        Ok(LayeredUniverse {
+            layers: vec![layer; count as usize],
+            couplings: vec![None; count as usize],
+        })
+    }
+
+    /// The number of layers.
+    #[cfg_attr(feature = "web", wasm_bindgen(getter))]
+    pub fn length(&self) -> u32 {
+        self.layers.len() as u32
+    }
+
+    /// A copy of layer `index`, without its callbacks.
+    pub fn layer(&self, index: u32) -> Result<Universe, ConvidaError> {
+        self.check_index(index)?;
+        Ok(self.layers[index as usize].clone())
+    }
+
+    /// Put `universe` in the place of layer `index`, keeping its coupling.
+    pub fn replace(&mut self, index: u32, universe: Universe) -> Result<(), ConvidaError> {
+        self.check_index(index)?;
+        ConvidaError::check_same_size(&self.layers[0], &universe)?;
+        self.layers[index as usize] = universe;
+        Ok(())
+    }
+
+    /// Let layer `index` tick under its own rule again.
+    pub fn decouple(&mut self, index: u32) -> Result<(), ConvidaError> {
+        self.check_index(index)?;
+        self.couplings[index as usize] = None;
+        Ok(())
+    }
+
+    /// Advance every layer one generation.
+    pub fn tick(&mut self) {
+        let sources: Vec<Option<Vec<Cell>>> = (0..self.layers.len())
+            .map(|i| {
+                self.couplings
+                    .iter()
+                    .any(|coupling| matches!(coupling, Some((source, _)) if *source == i))
+                    .then(|| self.layers[i].cells.clone())
+            })
+            .collect();
+        for (layer, coupling) in self.layers.iter_mut().zip(&self.couplings) {
+            match coupling {
+                Some((source, rule)) => layer.step_over(rule, sources[*source].as_deref()),
+                None => layer.tick(),
+            }
+        }
+    }
+
+    /// Advance every layer `generations` generations.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = tickMany))]
+    pub fn tick_many(&mut self, generations: u32) {
+        for _ in 0..generations {
+            self.tick();
+        }
+    }
+
+    pub fn cells(&self, index: u32) -> Result<*const Cell, ConvidaError> {
+        self.check_index(index)?;
+        Ok(self.layers[index as usize].cells())
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = toggleCell))]
+    pub fn toggle_cell(&mut self, index: u32, row: u32, col: u32) -> Result<(), ConvidaError> {
+        self.check_index(index)?;
+        self.layers[index as usize].toggle_cell(row, col)
+    }
+}
+
+impl LayeredUniverse {
+    /// Step layer `index` under `rule`, which sees the cells of layer
+    /// `source`. A layer may look at itself.
+    pub fn couple(&mut self, index: u32, source: u32, rule: Coupled) -> Result<(), ConvidaError> {
+        self.check_index(index)?;
+        self.check_index(source)?;
+        self.couplings[index as usize] = Some((source as usize, rule));
+        Ok(())
+    }
+
+    pub fn get(&self, index: u32) -> Option<&Universe> {
+        self.layers.get(index as usize)
+    }
+
+    pub fn get_mut(&mut self, index: u32) -> Option<&mut Universe> {
+        self.layers.get_mut(index as usize)
+    }
+
+    fn check_index(&self, index: u32) -> Result<(), ConvidaError> {
+        if (index as usize) < self.layers.len() {
+            Ok(())
+        } else {
+            Err(ConvidaError::NoSuchUniverse {
+                index,
+                count: self.layers.len() as u32,
+            })
+        }
+    }
+}
+
+#[cfg(feature = "web")]
+#[wasm_bindgen]
+impl LayeredUniverse {
+    /// Step layer `index` under `alive_rule` above live cells of layer
+    /// `source` and under `dead_rule` above dead ones, both in B/S
+    /// notation.
+    #[wasm_bindgen(js_name = couple)]
+    pub fn couple_js(
+        &mut self,
+        index: u32,
+        source: u32,
+        alive_rule: &str,
+        dead_rule: &str,
+    ) -> Result<(), ConvidaError> {
+        let rule = Coupled {
+            alive: parse_rule(alive_rule)?,
+            dead: parse_rule(dead_rule)?,
+        };
+        self.couple(index, source, rule)
+    }
+}
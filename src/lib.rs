@@ -1,147 +1,357 @@
 // Main file for Convida.
 // Source: https://rustwasm.github.io/docs/book/game-of-life/setup.html
 
-mod utils;
-extern crate js_sys;
-extern crate web_sys;
-use web_sys::console;
+// The simulation core builds for any target. Everything that talks to
+// JavaScript lives behind the `web` feature.
+
+mod active;
+mod arena;
+#[cfg(feature = "web")]
+mod audio;
+mod builder;
+mod bump;
+mod camera;
+#[cfg(feature = "capi")]
+pub mod capi;
+#[cfg(feature = "web")]
+mod capture;
+mod checkpoints;
+#[cfg(feature = "web")]
+mod compat;
+mod crdt;
+mod describe;
+mod diagnostics;
+mod differential;
+#[cfg(feature = "web")]
+mod dropzone;
+#[cfg(feature = "web")]
+mod element;
+mod error;
+mod events;
+mod formats;
+mod frames;
+mod frozen;
+#[cfg(feature = "web")]
+mod game_loop;
+#[cfg(feature = "web")]
+mod gamepad;
+mod grid;
+mod heat;
+#[cfg(feature = "web")]
+mod hooks;
+#[cfg(feature = "web")]
+mod input;
+#[cfg(feature = "tracing")]
+mod instrument;
+mod intern;
+mod interpolate;
+mod kernel;
+mod layers;
+mod limits;
+mod lineage;
+mod logging;
+mod metadata;
+mod metrics;
+#[cfg(feature = "web")]
+mod midi;
+mod milestones;
+mod multiverse;
+mod mutation;
+mod noise;
+mod overlay;
+#[cfg(feature = "web")]
+mod platform;
+mod png;
+mod protocol;
+mod provenance;
+mod puzzle;
+#[cfg(feature = "pyo3")]
+mod python;
+mod render;
+mod rle;
+mod rng;
+mod rule;
+mod search;
+mod seed;
+mod setops;
+mod snapshot;
+mod soup;
+mod stats;
+#[cfg(feature = "web")]
+mod stream;
+#[cfg(feature = "web")]
+mod sync;
+mod text;
+mod timelapse;
+mod trails;
+mod versus;
+mod walls;
+#[cfg(all(feature = "webgpu", web_sys_unstable_apis))]
+mod webgpu;
+mod webgpu_model;
+mod world;
+mod zones;
+
+#[cfg(all(feature = "webgpu", not(web_sys_unstable_apis)))]
+compile_error!("the `webgpu` feature needs RUSTFLAGS=--cfg=web_sys_unstable_apis");
 
 use std::fmt;
+#[cfg(feature = "web")]
 use wasm_bindgen::prelude::*;
-const DEBUG: bool = false;
 
-// A macro to provide 'println!(..)'-style syntax for 'console.log' logging.
-macro_rules! log{
-    ( $( $t:tt )* ) => {
-        web_sys::console::log_1(&format!( $( $t )* ).into());
+pub use arena::{Arena, ArenaReport, SideReport};
+#[cfg(feature = "web")]
+pub use audio::Sonifier;
+pub use builder::UniverseBuilder;
+pub use camera::{Camera, VisibleRange};
+#[cfg(feature = "web")]
+pub use capture::VideoCapture;
+pub use convida_engine::{
+    Boundary, Cell, Conway, Coupled, LayeredRule, LifeLike, ParseRuleError, Rule,
+};
+pub use crdt::{CrdtEdit, LwwGrid, Stamp};
+pub use diagnostics::last_error;
+pub use differential::{check_engines, fuzz_engines, reference_step, Divergence};
+pub use error::ConvidaError;
+pub use events::Event;
+pub use formats::PatternFile;
+pub use frames::{FrameEncoder, FrameFormat};
+#[cfg(feature = "web")]
+pub use game_loop::{GameLoop, HiddenPolicy};
+pub use grid::{new_grid, step, BitGrid, ChunkedGrid, Grid, SparseGrid, ENGINES};
+#[cfg(feature = "tracing")]
+pub use instrument::init_tracing;
+pub use kernel::{Kernel, KernelRule};
+pub use layers::LayeredUniverse;
+pub use limits::Limits;
+pub use logging::{log_level, set_log_level, LogLevel};
+pub use metadata::MetadataWidth;
+pub use metrics::{BoundingBox, Metrics};
+#[cfg(feature = "web")]
+pub use midi::MidiSequencer;
+pub use milestones::Milestone;
+pub use multiverse::{AbGeneration, AbReport, Multiverse};
+pub use mutation::{MutationLog, RuleMutation};
+pub use overlay::OverlayMode;
+#[cfg(feature = "web")]
+pub use platform::has_window;
+pub use png::encode_png;
+pub use protocol::{DecodeError, Edit, Message};
+pub use puzzle::{Puzzle, PuzzleResult};
+pub use render::{render_grid_rgba, Renderer};
+pub use rle::{Rle, RleError};
+pub use rng::Rng;
+#[cfg(feature = "web")]
+pub use seed::JsSeed;
+pub use seed::Seed;
+pub use setops::{DiffCells, DiffReport};
+pub use soup::{SoupSpec, Symmetry};
+pub use stats::StatsRow;
+#[cfg(feature = "web")]
+pub use stream::{GenerationItem, GenerationStream};
+#[cfg(feature = "web")]
+pub use sync::SyncSession;
+pub use text::TextError;
+pub use timelapse::Timelapse;
+pub use versus::{Player, Score, Versus, WinCondition};
+#[cfg(all(feature = "webgpu", web_sys_unstable_apis))]
+pub use webgpu::GpuUniverse;
+
+cfg_if::cfg_if! {
+    if #[cfg(all(feature = "web", target_arch = "wasm32", target_os = "unknown"))] {
+        // A macro to provide 'println!(..)'-style syntax for 'console.log'
+        // logging, for messages at or below the level set with
+        // `set_log_level`.
+        macro_rules! log{
+            ( $level:expr, $( $t:tt )* ) => {
+                if logging::enabled($level) {
+                    web_sys::console::log_1(&format!( $( $t )* ).into());
+                }
+            }
+        }
+    } else {
+        // Outside of JavaScript, log to stderr instead.
+        macro_rules! log{
+            ( $level:expr, $( $t:tt )* ) => {
+                if logging::enabled($level) {
+                    eprintln!( $( $t )* );
+                }
+            }
+        }
     }
 }
 
+// An `info` span lasting to the end of the enclosing block, with the
+// `tracing` feature. `#[tracing::instrument]` can't be used on methods
+// exported with `wasm_bindgen`, which copies it onto the generated glue.
+macro_rules! span {
+    ( $( $t:tt )* ) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!( $( $t )* ).entered();
+    };
+}
+
 cfg_if::cfg_if! {
     // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
-    // allocator.
-    if #[cfg(feature = "wee_alloc")] {
+    // allocator. Native builds keep the system allocator.
+    if #[cfg(all(feature = "wee_alloc", target_arch = "wasm32"))] {
         #[global_allocator]
         static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
     }
 }
 
-pub struct Timer<'a> {
-    name: &'a str,
-}
+cfg_if::cfg_if! {
+    if #[cfg(all(feature = "web", target_arch = "wasm32", target_os = "unknown"))] {
+        use web_sys::console;
+
+        // With the `performance` feature a timer is a pair of
+        // `performance` marks and a measure between them, which show up on
+        // the profiler's timeline, instead of a `console.time` report.
+        pub struct Timer<'a> {
+            name: &'a str,
+            enabled: bool,
+            #[cfg(feature = "performance")]
+            performance: Option<web_sys::Performance>,
+        }
 
-impl<'a> Timer<'a> {
-    pub fn new(name: &'a str) -> Timer<'a> {
-        console::time_with_label(name);
-        Timer { name }
-    }
-}
+        impl<'a> Timer<'a> {
+            pub fn new(name: &'a str) -> Timer<'a> {
+                #[cfg(feature = "performance")]
+                {
+                    let performance = platform::performance();
+                    if let Some(performance) = &performance {
+                        let _ = performance.mark(&format!("{} start", name));
+                    }
+                    Timer { name, enabled: false, performance }
+                }
+                #[cfg(not(feature = "performance"))]
+                {
+                    let enabled = platform::has_console_timers();
+                    if enabled {
+                        console::time_with_label(name);
+                    }
+                    Timer { name, enabled }
+                }
+            }
+        }
+
+        impl<'a> Drop for Timer<'a> {
+            fn drop(&mut self) {
+                #[cfg(feature = "performance")]
+                if let Some(performance) = &self.performance {
+                    let (start, end) = (format!("{} start", self.name), format!("{} end", self.name));
+                    // The unstable bindings, which the `webgpu` feature needs,
+                    // name the measure call differently. Either way a failed
+                    // measurement only leaves a gap on the timeline.
+                    let _ = performance.mark(&end);
+                    #[cfg(not(web_sys_unstable_apis))]
+                    let _ = performance.measure_with_start_mark_and_end_mark(self.name, &start, &end);
+                    #[cfg(web_sys_unstable_apis)]
+                    let _ = performance.measure_with_str_and_end_mark(self.name, &start, &end);
+                    performance.clear_marks_with_mark_name(&start);
+                    performance.clear_marks_with_mark_name(&end);
+                }
+                if self.enabled {
+                    console::time_end_with_label(self.name);
+                }
+            }
+        }
+    } else if #[cfg(all(target_arch = "wasm32", target_os = "unknown"))] {
+        // Bare wasm has neither a console nor a clock.
+        pub struct Timer<'a> {
+            _name: &'a str,
+        }
 
-impl<'a> Drop for Timer<'a> {
-    fn drop(&mut self) {
-        console::time_end_with_label(self.name);
+        impl<'a> Timer<'a> {
+            pub fn new(name: &'a str) -> Timer<'a> {
+                Timer { _name: name }
+            }
+        }
+    } else {
+        pub struct Timer<'a> {
+            name: &'a str,
+            start: std::time::Instant,
+        }
+
+        impl<'a> Timer<'a> {
+            pub fn new(name: &'a str) -> Timer<'a> {
+                Timer {
+                    name,
+                    start: std::time::Instant::now(),
+                }
+            }
+        }
+
+        impl<'a> Drop for Timer<'a> {
+            fn drop(&mut self) {
+                eprintln!("{}: {:?}", self.name, self.start.elapsed());
+            }
+        }
     }
 }
 
+#[cfg(feature = "web")]
 #[wasm_bindgen]
 extern "C" {
     fn alert(s: &str);
 }
 
-#[wasm_bindgen]
-#[repr(u8)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-
-pub enum Cell {
-    Dead = 0,
-    Alive = 1,
-}
-
-#[wasm_bindgen]
+#[cfg_attr(feature = "web", wasm_bindgen)]
 pub struct Universe {
     width: u32,
     height: u32,
     cells: Vec<Cell>,
+    generation: u32,
+    boundary: Boundary,
+    rule: LifeLike,
+    #[cfg(feature = "web")]
+    hooks: hooks::Hooks,
+    events: events::EventBus,
+    limits: limits::Limits,
+    checkpoints: checkpoints::Checkpoints,
+    timelapse: Option<timelapse::Recorder>,
+    stats: stats::Stats,
+    lineage: Option<lineage::Lineage>,
+    provenance: Option<provenance::Provenance>,
+    rule_zones: zones::RuleZones,
+    mutations: Option<mutation::Experiment>,
+    walls: walls::Walls,
+    frozen: frozen::Frozen,
+    metadata: Option<metadata::Metadata>,
+    heat: Option<heat::HeatField>,
+    active: Option<active::ActiveRegion>,
+    kernel: Option<kernel::KernelRule>,
+    trails: Option<Vec<u8>>,
+    previous: Option<Vec<Cell>>,
+    /// The world (row, col) of the board's top-left cell.
+    origin: (i64, i64),
+    scratch: bump::Scratch,
 }
 
 // Public methods, exported to JavaScript.
-#[wasm_bindgen]
+#[cfg_attr(feature = "web", wasm_bindgen)]
 impl Universe {
     pub fn tick(&mut self) {
-        if DEBUG {
-            let _timer = Timer::new("Universe::tick");
-        }
-
-        let mut next = {
-            if DEBUG {
-                let _timer = Timer::new("allocate next cells");
-            }
-            self.cells.clone()
-        };
-
-        if DEBUG {
-            let _timer = Timer::new("new generation");
+        if self.rule == LifeLike::CONWAY {
+            self.advance(&Conway, None, true);
+        } else {
+            let rule = self.rule;
+            self.advance(&rule, None, true);
         }
-        for row in 0..self.height {
-            for col in 0..self.width {
-                let idx = self.get_index(row, col);
-                let cell = self.cells[idx];
-                let live_neighbors = self.live_neighbor_count(row, col);
-
-                if DEBUG {
-                    log!(
-                        "cell[{}, {}] is initially {:?} and has {} live neighbors",
-                        row,
-                        col,
-                        cell,
-                        live_neighbors
-                    );
-                }
-
-                let next_cell = match (cell, live_neighbors) {
-                    // Rule 1: Any live cell with fewer than two neighbors
-                    // dies, as if caused by underpopulation.
-                    (Cell::Alive, x) if x < 2 => Cell::Dead,
-
-                    // Rule 2: Any live cell with two or three live neighbors
-                    // lives on to the next generations
-                    (Cell::Alive, 2) | (Cell::Alive, 3) => Cell::Alive,
-
-                    // Rule 3: Any live cell with more than three live
-                    // neighbors dies, as if by overpopulation
-                    (Cell::Alive, x) if x > 3 => Cell::Dead,
-
-                    // Rule 4 :: Any dead cells with exactly three live nighbors
-                    // becomes a live cell, as if by reproduction.
-                    (Cell::Dead, 3) => Cell::Alive,
-
-                    // All other cells remain in the same state.
-                    (otherwise, _) => otherwise,
-                };
-
-                // logging that records the row and column of each cell
-                // that transitioned states from live to dead or vice versa.
-                if DEBUG {
-                    if cell != next_cell {
-                        log!(
-                            "trans cell: row: {}, col: {}, now {:?}",
-                            row,
-                            col,
-                            next_cell
-                        );
-                    }
-
-                    log!("    it becomes {:?}", next_cell);
-                }
+    }
 
-                next[idx] = next_cell;
-            }
+    /// Advance `generations` generations.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = tickMany))]
+    pub fn tick_many(&mut self, generations: u32) {
+        for _ in 0..generations {
+            self.tick();
         }
+    }
 
-        if DEBUG {
-            let _timer = Timer::new("free old cells");
-        }
-        self.cells = next;
+    /// The rule in B/S notation, such as `B3/S23`.
+    #[cfg_attr(feature = "web", wasm_bindgen(getter))]
+    pub fn rule(&self) -> String {
+        self.rule.to_string()
     }
 
     fn get_index(&self, row: u32, col: u32) -> usize {
@@ -149,219 +359,549 @@ impl Universe {
     }
 
     fn live_neighbor_count(&self, row: u32, col: u32) -> u8 {
-        let mut count = 0;
-
-        let north = if row == 0 { self.height - 1 } else { row - 1 };
-
-        let south = if row == self.height - 1 { 0 } else { row + 1 };
-
-        let west = if col == 0 { self.width - 1 } else { col - 1 };
-
-        let east = if col == self.width - 1 { 0 } else { col + 1 };
-
-        let nw = self.get_index(north, west);
-        count += self.cells[nw] as u8;
-
-        let n = self.get_index(north, col);
-        count += self.cells[n] as u8;
-
-        let ne = self.get_index(north, east);
-        count += self.cells[ne] as u8;
-
-        let w = self.get_index(row, west);
-        count += self.cells[w] as u8;
-
-        let e = self.get_index(row, east);
-        count += self.cells[e] as u8;
-
-        let sw = self.get_index(south, west);
-        count += self.cells[sw] as u8;
-
-        let s = self.get_index(south, col);
-        count += self.cells[s] as u8;
-
-        let se = self.get_index(south, east);
-        count += self.cells[se] as u8;
-
-        count
+        convida_engine::neighbor_count(
+            &self.cells,
+            self.width,
+            self.height,
+            self.boundary,
+            row,
+            col,
+        )
     }
 
     pub fn new() -> Universe {
-        utils::set_panic_hook();
+        Universe::new_with_size(128, 128).expect("128x128 fits in memory")
+    }
 
-        let width = 128;
-        let height = 128;
-        let size = (width * height) as usize;
+    /// A `width` x `height` random soup.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = newWithSize))]
+    pub fn new_with_size(width: u32, height: u32) -> Result<Universe, ConvidaError> {
+        diagnostics::set_panic_hook();
 
-        // default, random, glider
-        let cells = create_cells("random", size, width as usize);
+        let cells = SoupSpec::new().cells(width, height)?;
 
-        Universe {
-            width,
-            height,
-            cells,
-        }
+        Ok(Universe::from_cells(width, height, cells))
     }
 
-    pub fn set_size(&mut self, width: u32, height: u32) -> Universe {
+    /// Resize the universe in place and fill it with a new random soup.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = setSize))]
+    pub fn set_size(&mut self, width: u32, height: u32) -> Result<(), ConvidaError> {
+        self.cells = SoupSpec::new().cells(width, height)?;
+        self.resize_layers(width, height);
         self.width = width;
         self.height = height;
-        let size = (width * height) as usize;
-
-        // default, random, glider
-        let cells = create_cells("random", size, width as usize);
-
-        Universe {
-            width,
-            height,
-            cells,
-        }
+        Ok(())
     }
 
     pub fn render(&self) -> String {
         self.to_string()
     }
 
+    #[cfg_attr(feature = "web", wasm_bindgen(getter))]
     pub fn width(&self) -> u32 {
         self.width
     }
 
+    /// The number of ticks since the universe was created.
+    #[cfg_attr(feature = "web", wasm_bindgen(getter))]
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(getter))]
     pub fn height(&self) -> u32 {
         self.height
     }
 
+    #[cfg_attr(feature = "web", wasm_bindgen(getter))]
+    pub fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = setBoundary))]
+    pub fn set_boundary(&mut self, boundary: Boundary) {
+        self.boundary = boundary;
+    }
+
     pub fn cells(&self) -> *const Cell {
         self.cells.as_ptr()
     }
 
+    /// The live cells as flat `[row, col, row, col, ...]` pairs in reading
+    /// order, a `Uint32Array` in JavaScript.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = liveCells))]
+    pub fn live_cells(&self) -> Vec<u32> {
+        let mut pairs = Vec::new();
+        for (row, col) in self.iter_live() {
+            pairs.push(row);
+            pairs.push(col);
+        }
+        pairs
+    }
+
+    /// An independent copy of the universe, without its hooks and event
+    /// listeners.
+    pub fn duplicate(&self) -> Universe {
+        self.clone()
+    }
+
+    /// Indices of the cells that differ between the two universes. Cells
+    /// past the end of the smaller one count as dead.
+    pub fn diff(&self, other: &Universe) -> Vec<u32> {
+        let len = self.cells.len().max(other.cells.len());
+        let cell = |cells: &[Cell], i: usize| cells.get(i).copied().unwrap_or(Cell::Dead);
+        (0..len)
+            .filter(|&i| cell(&self.cells, i) != cell(&other.cells, i))
+            .map(|i| i as u32)
+            .collect()
+    }
+
     /// Set the width of the universe.
     ///
-    /// Resets all cells to the dead state.
-    pub fn set_width(&mut self, width: u32) {
+    /// Resets all cells to the dead state. A width of zero fails with
+    /// `EmptySize` and leaves the universe as it was.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = setWidth))]
+    pub fn set_width(&mut self, width: u32) -> Result<(), ConvidaError> {
+        let size = ConvidaError::check_size(width, self.height)?;
+        self.resize_layers(width, self.height);
         self.width = width;
-        self.cells = (0..width * self.height).map(|_1| Cell::Dead).collect();
+        self.cells = vec![Cell::Dead; size];
+        Ok(())
     }
 
     /// Set the height of the universe.
     ///
-    /// Resets all cells to the dead state.
-    pub fn set_height(&mut self, height: u32) {
+    /// Resets all cells to the dead state. A height of zero fails with
+    /// `EmptySize` and leaves the universe as it was.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = setHeight))]
+    pub fn set_height(&mut self, height: u32) -> Result<(), ConvidaError> {
+        let size = ConvidaError::check_size(self.width, height)?;
+        self.resize_layers(self.width, height);
         self.height = height;
-        self.cells = (0..self.width * height).map(|_1| Cell::Dead).collect();
+        self.cells = vec![Cell::Dead; size];
+        Ok(())
     }
 
-    pub fn toggle_cell(&mut self, row: u32, col: u32) {
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = toggleCell))]
+    pub fn toggle_cell(&mut self, row: u32, col: u32) -> Result<(), ConvidaError> {
+        span!("Universe::toggle_cell", row, col);
+        self.enter("Universe::toggle_cell");
+        ConvidaError::check_bounds(row, col, self.width, self.height)?;
+        self.check_not_wall(row, col)?;
         let idx = self.get_index(row, col);
+        if self.cells[idx] == Cell::Dead {
+            self.charge(1, false)?;
+        }
         self.cells[idx].toggle();
+        Ok(())
     }
 
     pub fn reset(&mut self) {
-        let size = (self.width * self.height) as usize;
-
-        // default, random, glider
-        self.cells = create_cells("random", size, self.width as usize);
+        span!("Universe::reset");
+        self.enter("Universe::reset");
+        self.cells = SoupSpec::new()
+            .cells(self.width, self.height)
+            .expect("the universe's own size fits");
     }
 
     pub fn clear(&mut self) {
+        span!("Universe::clear");
+        self.enter("Universe::clear");
         self.cells = (0..self.width * self.height).map(|_1| Cell::Dead).collect();
     }
 
-    // create a glider.
-    pub fn glider(&mut self, row: u32, col: u32) {
-        let limit = self.width * self.height;
-        self.cells[((1 + col + self.width * (0 + row)) % limit) as usize] = Cell::Alive;
-        self.cells[((2 + col + self.width * (1 + row)) % limit) as usize] = Cell::Alive;
-        for i in 0..3 {
-            self.cells[((i + col + self.width * (2 + row)) % limit) as usize] = Cell::Alive;
-        }
+    /// Place a glider with its top-left corner at (`row`, `col`),
+    /// wrapping around the edges.
+    pub fn glider(&mut self, row: u32, col: u32) -> Result<(), ConvidaError> {
+        self.enter("Universe::glider");
+        self.place_pattern("glider", row, col)
+    }
+
+    /// Place a pulsar with its top-left corner at (`row`, `col`),
+    /// wrapping around the edges.
+    pub fn pulsar(&mut self, row: u32, col: u32) -> Result<(), ConvidaError> {
+        self.enter("Universe::pulsar");
+        self.place_pattern("pulsar", row, col)
+    }
+}
+
+impl Universe {
+    pub fn set_rule(&mut self, rule: LifeLike) {
+        self.rule = rule;
+    }
+
+    /// Advance one generation under `rule` instead of the universe's own
+    /// rule.
+    pub fn step<R: Rule>(&mut self, rule: &R) {
+        self.step_over(rule, None);
+    }
+
+    /// `step` for a layer of a stack, with `other` the cells of the layer
+    /// `rule` looks at, dead cells standing in without one.
+    pub(crate) fn step_over<R: LayeredRule + ?Sized>(&mut self, rule: &R, other: Option<&[Cell]>) {
+        self.advance(rule, other, false);
     }
 
-    // create a pulsar.
-    pub fn pulsar(&mut self, row: u32, col: u32) {
-        let top = [2, 3, 4, 8, 9, 10];
-        let side = [0, 5, 7, 12];
-
-        let mut idx = 0;
-        let pulsar_width = 13;
-        let pulsar_height = 13;
-        let limit = self.width * self.height;
-        while idx < pulsar_height {
-            let start = idx * pulsar_width;
-            let end = start + pulsar_width;
-
-            let row_translate = row * self.width;
-            let col_translate = col + (idx * (self.width - pulsar_width));
-            match idx {
-                // top/bottom rows
-                0 | 5 | 7 | 12 => {
-                    self.cells_from_pattern(&top, start, end, row_translate, col_translate, limit)
+    // The tick itself. `own_rule` says that `rule` is the universe's rule,
+    // so the active region can be used and tracked.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "Universe::tick", skip_all, fields(generation = self.generation))
+    )]
+    fn advance<R: LayeredRule + ?Sized>(
+        &mut self,
+        rule: &R,
+        other: Option<&[Cell]>,
+        own_rule: bool,
+    ) {
+        self.enter("Universe::tick");
+        let timing = logging::enabled(LogLevel::Info);
+        let trace = logging::enabled(LogLevel::Trace);
+        // The phases are always measured for the profiler's timeline with
+        // `performance`, and otherwise only reported along with the logs.
+        let timed = timing || cfg!(all(feature = "performance", target_arch = "wasm32"));
+        let _tick_timer = timed.then(|| Timer::new("Universe::tick"));
+
+        let mut next = {
+            let _timer = timed.then(|| Timer::new("allocate next cells"));
+            #[cfg(feature = "tracing")]
+            let _span = tracing::info_span!("allocate next cells").entered();
+            self.scratch.next_cells(&self.cells)
+        };
+
+        #[cfg(feature = "web")]
+        let track_changes = self.hooks.wants_cell_changes();
+        #[cfg(not(feature = "web"))]
+        let track_changes = false;
+        let mut changes = self.scratch.changes();
+        let (mut births, mut deaths) = (0, 0);
+        let zoned = !self.rule_zones.is_empty();
+        let walled = !self.walls.is_empty();
+        let wall = self.walls.cell();
+        let frozen = !self.frozen.is_empty();
+        let own = self.rule;
+        let kernel = self.kernel.as_ref().filter(|_| own_rule);
+        let window = own_rule.then(|| self.active_window()).flatten();
+        let rows = window.map_or(0..self.height, |window| {
+            window.row..window.row + window.height
+        });
+        let cols = window.map_or(0..self.width, |window| {
+            window.col..window.col + window.width
+        });
+        let mut changed: Option<BoundingBox> = None;
+
+        let generation_timer = timed.then(|| Timer::new("new generation"));
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!("new generation").entered();
+        for row in rows {
+            for col in cols.clone() {
+                let idx = self.get_index(row, col);
+                if walled && self.walls.contains(idx) {
+                    next[idx] = wall;
+                    continue;
                 }
+                if frozen && self.frozen.contains(idx) {
+                    continue;
+                }
+                let cell = self.cells[idx];
+                let live_neighbors = self.live_neighbor_count(row, col);
 
-                // side rows
-                1 | 6 | 11 => {
-                    self.cells_from_pattern(&[], start, end, row_translate, col_translate, limit)
+                if trace {
+                    log!(
+                        LogLevel::Trace,
+                        "cell[{}, {}] is initially {:?} and has {} live neighbors",
+                        row,
+                        col,
+                        cell,
+                        live_neighbors
+                    );
                 }
 
-                // empty rows
-                2 | 3 | 4 | 8 | 9 | 10 => {
-                    self.cells_from_pattern(&side, start, end, row_translate, col_translate, limit)
+                let next_cell = match zoned.then(|| self.rule_zones.rule_at(row, col)) {
+                    Some(Some(zone)) => zone.next_state(cell, live_neighbors),
+                    _ => match kernel {
+                        Some(kernel) => kernel.next_state(self, row, col),
+                        None => {
+                            let below = other.map_or(Cell::Dead, |other| other[idx]);
+                            rule.next_state_over(cell, live_neighbors, below)
+                        }
+                    },
+                };
+
+                // logging that records the row and column of each cell
+                // that transitioned states from live to dead or vice versa.
+                if trace {
+                    if cell != next_cell {
+                        log!(
+                            LogLevel::Trace,
+                            "trans cell: row: {}, col: {}, now {:?}",
+                            row,
+                            col,
+                            next_cell
+                        );
+                    }
+
+                    log!(LogLevel::Trace, "    it becomes {:?}", next_cell);
+                }
+
+                if cell != next_cell {
+                    active::include(&mut changed, row, col);
+                    if next_cell == Cell::Alive {
+                        births += 1;
+                    } else {
+                        deaths += 1;
+                    }
+                    if track_changes {
+                        changes.extend_from_slice(&[row, col, next_cell as u32]);
+                    }
                 }
-                _ => panic!("Invalid row number."),
+
+                next[idx] = next_cell;
             }
-            idx += 1;
         }
 
-        /* pulsar pattern and x,y coordinates
-                           111
-                 0123456789012
-                 :::::::::::::
-
-             0:  ..OOO...OOO..
-             1:  .............
-             2:  O....O.O....O
-             3:  O....O.O....O
-             4:  O....O.O....O
-             5:  ..OOO...OOO..
-             6:  .............
-             7:  ..OOO...OOO..
-             8:  O....O.O....O
-             9:  O....O.O....O
-            10:  O....O.O....O
-            11:  .............
-            12:  ..OOO...OOO..
-        */
-    }
-
-    pub fn cells_from_pattern(
-        &mut self,
-        arr: &[u32],
-        min: u32,
-        max: u32,
-        row_translate: u32,
-        col_translate: u32,
-        limit: u32,
-    ) {
-        for i in min..max {
-            if arr.contains(&(i - min)) {
-                self.cells[((i + row_translate + col_translate) % limit) as usize] = Cell::Alive;
-            }
+        #[cfg(feature = "tracing")]
+        drop(span);
+
+        drop(generation_timer);
+        let free_timer = timed.then(|| Timer::new("free old cells"));
+        if let Some(lineage) = &mut self.lineage {
+            lineage.step(&self.cells, &next, self.width, self.height, self.boundary);
+        }
+        if let Some(provenance) = &mut self.provenance {
+            provenance.step(&self.cells, &next, self.width, self.height, self.boundary);
+        }
+        let old = std::mem::replace(&mut self.cells, next);
+        self.keep_previous(&old);
+        drop(free_timer);
+        if own_rule {
+            self.track_activity(changed, own);
+        }
+        self.generation = self.generation.wrapping_add(1);
+        self.limits.regenerate();
+        self.autosave();
+        self.record_timelapse();
+        self.record_stats(births, deaths);
+        self.mutate();
+        self.spread_heat();
+        self.age_trails();
+
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!("notify", changes = changes.len() / 3).entered();
+
+        #[cfg(feature = "web")]
+        {
+            self.hooks.cell_changes(&changes);
+            self.hooks.tick(self.generation);
+        }
+
+        if self.events.is_listening() {
+            let mut events = std::mem::take(&mut self.events);
+            events.observe(self);
+            self.events = events;
+        }
+        self.scratch.reset(old, changes);
+    }
+
+    // Build a universe at generation zero around an existing cell buffer.
+    fn from_cells(width: u32, height: u32, cells: Vec<Cell>) -> Universe {
+        Universe {
+            width,
+            height,
+            cells,
+            generation: 0,
+            boundary: Boundary::Torus,
+            rule: LifeLike::CONWAY,
+            #[cfg(feature = "web")]
+            hooks: hooks::Hooks::default(),
+            events: events::EventBus::default(),
+            limits: limits::Limits::default(),
+            checkpoints: checkpoints::Checkpoints::default(),
+            timelapse: None,
+            stats: stats::Stats::default(),
+            lineage: None,
+            provenance: None,
+            rule_zones: zones::RuleZones::default(),
+            mutations: None,
+            walls: walls::Walls::default(),
+            frozen: frozen::Frozen::default(),
+            metadata: None,
+            heat: None,
+            active: None,
+            kernel: None,
+            trails: None,
+            previous: None,
+            origin: (0, 0),
+            scratch: bump::Scratch::default(),
         }
     }
-}
 
-impl Universe {
     /// Get the dead and alive values of the entire universe.
     pub fn get_cells(&self) -> &[Cell] {
         &self.cells
     }
 
+    /// The (row, column) of each live cell in reading order.
+    pub fn iter_live(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|&(_, &cell)| cell == Cell::Alive)
+            .map(move |(i, _)| (i as u32 / width, i as u32 % width))
+    }
+
     /// Set cells to be alive in a universe by passing the row and column
-    /// of each cell as an array.
-    pub fn set_cells(&mut self, cells: &[(u32, u32)]) {
-        for (row, col) in cells.iter().cloned() {
-            let idx = self.get_index(row, col);
+    /// of each cell as an array. Changes nothing if a cell is outside the
+    /// universe or the limits refuse the edit.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(cells = cells.len()))
+    )]
+    pub fn set_cells(&mut self, cells: &[(u32, u32)]) -> Result<(), ConvidaError> {
+        self.enter("Universe::set_cells");
+        for &(row, col) in cells {
+            ConvidaError::check_bounds(row, col, self.width, self.height)?;
+        }
+        let indices = cells.iter().map(|&(row, col)| self.get_index(row, col));
+        self.bring_to_life(indices.collect(), false)
+    }
+
+    /// Stamp `pattern` with its top-left corner at (`row`, `col`), wrapping
+    /// around the edges, if the limits allow it.
+    pub(crate) fn stamp(&mut self, pattern: &Rle, row: u32, col: u32) -> Result<(), ConvidaError> {
+        let (width, height) = (u64::from(self.width), u64::from(self.height));
+        let wrap = |start: u32, offset: u32, len: u64| {
+            ((u64::from(start) + u64::from(offset)) % len) as u32
+        };
+        let indices = pattern
+            .cells
+            .iter()
+            .map(|&(r, c)| self.get_index(wrap(row, r, height), wrap(col, c, width)));
+        self.bring_to_life(indices.collect(), true)
+    }
+
+    /// Set a single cell, charging the limits if it comes to life.
+    #[cfg(any(feature = "web", feature = "capi"))]
+    pub(crate) fn set_cell(&mut self, row: u32, col: u32, cell: Cell) -> Result<(), ConvidaError> {
+        ConvidaError::check_bounds(row, col, self.width, self.height)?;
+        self.check_not_wall(row, col)?;
+        let idx = self.get_index(row, col);
+        if cell == Cell::Alive && self.cells[idx] == Cell::Dead {
+            self.charge(1, false)?;
+        }
+        self.cells[idx] = cell;
+        Ok(())
+    }
+
+    fn bring_to_life(&mut self, mut indices: Vec<usize>, stamp: bool) -> Result<(), ConvidaError> {
+        indices.sort_unstable();
+        indices.dedup();
+        indices.retain(|&idx| !self.walls.contains(idx));
+        let births = indices
+            .iter()
+            .filter(|&&idx| self.cells[idx] == Cell::Dead)
+            .count();
+        self.charge(births as u32, stamp)?;
+        for idx in indices {
             self.cells[idx] = Cell::Alive;
         }
+        Ok(())
+    }
+
+    // Fit the layers kept alongside the cells to a board about to be resized
+    // to `width` x `height`.
+    pub(crate) fn resize_layers(&mut self, width: u32, height: u32) {
+        if (width, height) == (self.width, self.height) {
+            return;
+        }
+        self.walls.clear();
+        self.frozen.clear();
+        self.resize_metadata(width, height);
+        let len = (width * height) as usize;
+        self.resize_heat(len);
+        self.resize_trails(len);
+        self.resize_previous(len);
+        self.resize_provenance(len);
+        self.resize_lineage(len);
+    }
+
+    fn charge(&mut self, births: u32, stamp: bool) -> Result<(), ConvidaError> {
+        let cells = &self.cells;
+        let population = || cells.iter().filter(|&&cell| cell == Cell::Alive).count() as u32;
+        self.limits.charge(births, population, stamp)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(self)))]
+    fn place_pattern(&mut self, name: &str, row: u32, col: u32) -> Result<(), ConvidaError> {
+        ConvidaError::check_bounds(row, col, self.width, self.height)?;
+        self.stamp(&seed::pattern(name)?, row, col)
+    }
+}
+
+impl Default for Universe {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Hooks and event listeners belong to the original universe and aren't
+// copied.
+impl Clone for Universe {
+    fn clone(&self) -> Self {
+        Universe {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.clone(),
+            generation: self.generation,
+            boundary: self.boundary,
+            rule: self.rule,
+            #[cfg(feature = "web")]
+            hooks: hooks::Hooks::default(),
+            events: events::EventBus::default(),
+            limits: self.limits.clone(),
+            checkpoints: self.checkpoints.clone(),
+            timelapse: self.timelapse.clone(),
+            stats: self.stats.clone(),
+            lineage: self.lineage.clone(),
+            provenance: self.provenance.clone(),
+            rule_zones: self.rule_zones.clone(),
+            mutations: self.mutations.clone(),
+            walls: self.walls.clone(),
+            frozen: self.frozen.clone(),
+            metadata: self.metadata.clone(),
+            heat: self.heat.clone(),
+            active: self.active.clone(),
+            kernel: self.kernel.clone(),
+            trails: self.trails.clone(),
+            previous: self.previous.clone(),
+            origin: self.origin,
+            scratch: bump::Scratch::default(),
+        }
+    }
+}
+
+// Universes are equal when their size, boundary, rule and cells match,
+// whatever their generation.
+impl PartialEq for Universe {
+    fn eq(&self, other: &Self) -> bool {
+        self.width == other.width
+            && self.height == other.height
+            && self.boundary == other.boundary
+            && self.rule == other.rule
+            && self.cells == other.cells
+    }
+}
+
+impl fmt::Debug for Universe {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Universe")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("generation", &self.generation)
+            .field("boundary", &self.boundary)
+            .field("rule", &self.rule)
+            .finish_non_exhaustive()
     }
 }
 
@@ -374,31 +914,13 @@ impl fmt::Display for Universe {
                 let symbol = if cell == Cell::Dead { '◻' } else { '◼' };
                 write!(f, "{}", symbol)?;
             }
-            write!(f, "\n")?;
+            writeln!(f)?;
         }
 
         Ok(())
     }
 }
 
-impl Cell {
-    fn toggle(&mut self) {
-        *self = match *self {
-            Cell::Dead => Cell::Alive,
-            Cell::Alive => Cell::Dead,
-        };
-    }
-}
-
-fn create_cells(cell_type: &str, size: usize, width: usize) -> Vec<Cell> {
-    match cell_type {
-        "default" => return default(size),
-        "glider" => return glider(size, width),
-        "random" => return random(size),
-        _ => panic!("Unknown cell type."),
-    }
-}
-
 // Returns a vector of cells.
 // Cells at even positions within the vector are alive,
 // as are cells at multiples of 7.
@@ -414,37 +936,20 @@ fn default(size: usize) -> Vec<Cell> {
         })
         .collect();
 
-    return cells;
-}
-
-// Returns a vector of cells.
-// The vector contains a single glider.
-fn glider(size: usize, width: usize) -> Vec<Cell> {
-    let mut cells = Vec::with_capacity(size);
-    for _i in 0..size {
-        cells.push(Cell::Dead);
-    }
-
-    cells[1 + width as usize * 0] = Cell::Alive;
-    cells[2 + width as usize * 1] = Cell::Alive;
-    for i in 0..3 {
-        cells[i + width as usize * 2] = Cell::Alive;
-    }
-
-    return cells;
+    cells
 }
 
 // Returns a vector of cells.
-// Half of cells within the vector are alive, half are dead.
-fn random(size: usize) -> Vec<Cell> {
+// Each cell is alive with probability `density`.
+pub(crate) fn random(size: usize, density: f64, rng: &mut Rng) -> Vec<Cell> {
     let mut cells = Vec::with_capacity(size);
     for _i in 0..size {
-        if js_sys::Math::random() < 0.5 {
+        if rng.next_f64() < density {
             cells.push(Cell::Alive);
         } else {
             cells.push(Cell::Dead);
         }
     }
 
-    return cells;
+    cells
 }
@@ -0,0 +1,132 @@
+// Limits on how much a player may edit a universe, for sandbox games.
+//
+//     universe.set_limits(Limits::new().edit_budget(50).population_cap(400).mana(30, 1));
+//
+// Every edit that brings cells to life (`toggle_cell`, `set_cells`,
+// `glider`, `pulsar` and the `GameLoop`'s painting and stamps) is charged
+// here before it is applied, so a UI can't get around them. Killing cells
+// is free, and edits arriving from peers through `apply_edit` are not
+// charged, since the peer that made them already was.
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::{ConvidaError, Universe};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Mana {
+    available: u32,
+    max: u32,
+    regen: u32,
+}
+
+/// A set of limits, none by default.
+#[cfg_attr(feature = "web", wasm_bindgen)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Limits {
+    edit_budget: Option<u32>,
+    remaining: u32,
+    population_cap: Option<u32>,
+    mana: Option<Mana>,
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Limits {
+    #[cfg_attr(feature = "web", wasm_bindgen(constructor))]
+    pub fn new() -> Limits {
+        Limits::default()
+    }
+
+    /// Allow at most `cells` cells to be brought to life by edits, ever.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = editBudget))]
+    pub fn edit_budget(mut self, cells: u32) -> Limits {
+        self.edit_budget = Some(cells);
+        self.remaining = cells;
+        self
+    }
+
+    /// Refuse edits that would leave more than `cells` live cells.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = populationCap))]
+    pub fn population_cap(mut self, cells: u32) -> Limits {
+        self.population_cap = Some(cells);
+        self
+    }
+
+    /// A pool of up to `max` mana, starting full and refilling by `regen`
+    /// every generation. Stamping a pattern costs one mana per cell it
+    /// brings to life.
+    pub fn mana(mut self, max: u32, regen: u32) -> Limits {
+        self.mana = Some(Mana {
+            available: max,
+            max,
+            regen,
+        });
+        self
+    }
+}
+
+impl Limits {
+    // Charge an edit bringing `births` cells to life in a universe of
+    // `population` live cells. Nothing is charged if any limit refuses it.
+    pub(crate) fn charge(
+        &mut self,
+        births: u32,
+        population: impl FnOnce() -> u32,
+        stamp: bool,
+    ) -> Result<(), ConvidaError> {
+        if births == 0 {
+            return Ok(());
+        }
+        if let Some(budget) = self.edit_budget {
+            if births > self.remaining {
+                return Err(ConvidaError::BudgetSpent { budget });
+            }
+        }
+        if let Some(cap) = self.population_cap {
+            if population().saturating_add(births) > cap {
+                return Err(ConvidaError::PopulationCap { cap });
+            }
+        }
+        if let (true, Some(mana)) = (stamp, &mut self.mana) {
+            if births > mana.available {
+                return Err(ConvidaError::NotEnoughMana {
+                    needed: births,
+                    available: mana.available,
+                });
+            }
+            mana.available -= births;
+        }
+        if self.edit_budget.is_some() {
+            self.remaining -= births;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn regenerate(&mut self) {
+        if let Some(mana) = &mut self.mana {
+            mana.available = mana.available.saturating_add(mana.regen).min(mana.max);
+        }
+    }
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Universe {
+    /// Replace the universe's limits, refilling the edit budget and mana.
+    /// `new Limits()` lifts them all.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = setLimits))]
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// The cells edits may still bring to life, or `undefined` without an
+    /// edit budget.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = remainingBudget))]
+    pub fn remaining_budget(&self) -> Option<u32> {
+        self.limits.edit_budget.map(|_| self.limits.remaining)
+    }
+
+    /// The mana available for stamps, or `undefined` without a mana pool.
+    pub fn mana(&self) -> Option<u32> {
+        self.limits.mana.map(|mana| mana.available)
+    }
+}
@@ -0,0 +1,204 @@
+// Birth lineage, for colouring the family trees of structures.
+//
+// While lineage is on every live cell carries an id. The cells alive when
+// it is turned on become roots, and each birth gets a fresh id recording
+// the live neighbours it was born from as its parents. A surviving cell
+// keeps its id, so following parents back from any live cell leads to the
+// parts of the initial soup it descends from. Cells brought to life by an
+// edit rather than a birth become new roots.
+//
+// Only the births a live cell can still reach are remembered, and at most
+// `MAX_RECORDS` of them: past that the oldest are forgotten, and read as
+// roots. When the ids are about to run out they are renumbered from 1,
+// keeping their order.
+
+use std::collections::BTreeMap;
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::grid::neighbors;
+use crate::{Boundary, Cell, Universe};
+
+// The most births remembered, and the fewest worth collecting.
+const MAX_RECORDS: usize = 1 << 22;
+const MIN_COLLECT: usize = 1 << 16;
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Lineage {
+    /// The id of each cell, 0 for dead ones.
+    ids: Vec<u32>,
+    /// The parents of each remembered birth. Roots have none.
+    parents: BTreeMap<u32, Box<[u32]>>,
+    /// The last id handed out.
+    last_id: u32,
+    /// How many births were remembered after the last collection.
+    kept: usize,
+}
+
+impl Lineage {
+    fn new_id(&mut self, parents: &[u32]) -> u32 {
+        self.last_id += 1;
+        if !parents.is_empty() {
+            self.parents.insert(self.last_id, parents.into());
+        }
+        self.last_id
+    }
+
+    fn parents(&self, id: u32) -> &[u32] {
+        self.parents.get(&id).map_or(&[], |parents| parents)
+    }
+
+    // Forget the births no live cell descends from, then the oldest of the
+    // rest while there are too many.
+    fn collect(&mut self) {
+        let mut kept = BTreeMap::new();
+        let mut stack: Vec<u32> = self.ids.iter().copied().filter(|&id| id != 0).collect();
+        while let Some(id) = stack.pop() {
+            if let Some(parents) = self.parents.remove(&id) {
+                stack.extend_from_slice(&parents);
+                kept.insert(id, parents);
+            }
+        }
+        while kept.len() > MAX_RECORDS / 2 {
+            kept.pop_first();
+        }
+        self.parents = kept;
+        self.kept = self.parents.len();
+    }
+
+    // Give the ids still in use the numbers from 1 up, in the same order.
+    fn renumber(&mut self) {
+        self.collect();
+        let mut used: Vec<u32> = self.ids.iter().copied().filter(|&id| id != 0).collect();
+        for (&id, parents) in &self.parents {
+            used.push(id);
+            used.extend_from_slice(parents);
+        }
+        used.sort_unstable();
+        used.dedup();
+
+        let new = |id: u32| used.binary_search(&id).map_or(0, |n| n as u32 + 1);
+        for id in &mut self.ids {
+            *id = new(*id);
+        }
+        let parents = std::mem::take(&mut self.parents);
+        self.parents = parents
+            .into_iter()
+            .map(|(id, parents)| (new(id), parents.iter().map(|&p| new(p)).collect()))
+            .collect();
+        self.last_id = used.len() as u32;
+    }
+
+    // Carry the ids over from `cells` to `next`, one generation later.
+    pub(crate) fn step(
+        &mut self,
+        cells: &[Cell],
+        next: &[Cell],
+        width: u32,
+        height: u32,
+        boundary: Boundary,
+    ) {
+        if self.ids.len() != cells.len() {
+            self.ids = vec![0; cells.len()];
+        }
+        // A step hands out at most two ids per cell: one for a cell an edit
+        // brought to life and one for a birth.
+        if u64::from(u32::MAX - self.last_id) < 2 * cells.len() as u64 {
+            self.renumber();
+        }
+        if self.parents.len() > (2 * self.kept).max(MIN_COLLECT) {
+            self.collect();
+        }
+        let mut old = std::mem::take(&mut self.ids);
+        for (id, &cell) in old.iter_mut().zip(cells) {
+            if cell == Cell::Alive && *id == 0 {
+                *id = self.new_id(&[]);
+            }
+        }
+        self.ids = old;
+
+        let mut ids = vec![0; next.len()];
+        let mut parents = Vec::with_capacity(8);
+        for (i, &cell) in next.iter().enumerate() {
+            if cell == Cell::Dead {
+                continue;
+            }
+            if cells[i] == Cell::Alive {
+                ids[i] = self.ids[i];
+                continue;
+            }
+            let (row, col) = (i as u32 / width, i as u32 % width);
+            parents.clear();
+            for (r, c) in neighbors(row, col, width, height, boundary) {
+                let n = (r * width + c) as usize;
+                if cells[n] == Cell::Alive {
+                    parents.push(self.ids[n]);
+                }
+            }
+            ids[i] = self.new_id(&parents);
+        }
+        self.ids = ids;
+    }
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Universe {
+    /// Start tracking lineage, with every live cell a root.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = enableLineage))]
+    pub fn enable_lineage(&mut self) {
+        let mut lineage = Lineage::default();
+        lineage.ids = self
+            .cells
+            .iter()
+            .map(|&cell| match cell {
+                Cell::Alive => lineage.new_id(&[]),
+                Cell::Dead => 0,
+            })
+            .collect();
+        self.lineage = Some(lineage);
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = disableLineage))]
+    pub fn disable_lineage(&mut self) {
+        self.lineage = None;
+    }
+
+    /// The lineage id of each cell, one `u32` per cell like `cells`, or
+    /// null while lineage is off. Ids are renumbered, in the same order,
+    /// when they would otherwise run out.
+    pub fn lineage(&self) -> *const u32 {
+        match &self.lineage {
+            Some(lineage) => lineage.ids.as_ptr(),
+            None => std::ptr::null(),
+        }
+    }
+
+    /// The ids of the cells that `id` was born from, empty for a root or a
+    /// birth too old to be remembered.
+    pub fn parents(&self, id: u32) -> Vec<u32> {
+        self.lineage
+            .as_ref()
+            .map(|lineage| lineage.parents(id).to_vec())
+            .unwrap_or_default()
+    }
+}
+
+impl Universe {
+    /// The lineage id of each cell, while lineage is on.
+    pub fn lineage_ids(&self) -> Option<&[u32]> {
+        self.lineage.as_ref().map(|lineage| &lineage.ids[..])
+    }
+
+    // Called whenever the board changes shape, even to the same number of
+    // cells: the old ids no longer line up with the cells, so every family
+    // is forgotten and the next step takes the live cells as new roots.
+    pub(crate) fn resize_lineage(&mut self, len: usize) {
+        if let Some(lineage) = &mut self.lineage {
+            *lineage = Lineage {
+                ids: vec![0; len],
+                ..Lineage::default()
+            };
+        }
+    }
+}
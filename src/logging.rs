@@ -0,0 +1,87 @@
+// Runtime log level for the `log!` macro, so a deployed build can turn on
+// transition logging without being rebuilt:
+//
+//     set_log_level(LogLevel.Trace);
+//
+// Errors thrown to JavaScript and panics are logged at `Error`.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+static LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Off as u8);
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Off = 0,
+    /// Errors thrown to JavaScript, and panics.
+    Error = 1,
+    /// Phase timings.
+    Info = 2,
+    /// Every cell and its neighbour count, and the cells that change state,
+    /// on every tick.
+    Trace = 3,
+}
+
+/// Log messages at `level` and below from now on. Logging is off by default.
+#[cfg_attr(feature = "web", wasm_bindgen(js_name = setLogLevel))]
+pub fn set_log_level(level: LogLevel) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen(js_name = logLevel))]
+pub fn log_level() -> LogLevel {
+    match LEVEL.load(Ordering::Relaxed) {
+        0 => LogLevel::Off,
+        1 => LogLevel::Error,
+        2 => LogLevel::Info,
+        _ => LogLevel::Trace,
+    }
+}
+
+/// Whether messages at `level` are currently logged.
+#[inline]
+pub(crate) fn enabled(level: LogLevel) -> bool {
+    level != LogLevel::Off && level as u8 <= LEVEL.load(Ordering::Relaxed)
+}
+
+/// Log `message` as an error, to the console or natively to stderr.
+pub(crate) fn error(message: &str) {
+    if !enabled(LogLevel::Error) {
+        return;
+    }
+    cfg_if::cfg_if! {
+        if #[cfg(all(feature = "web", target_arch = "wasm32", target_os = "unknown"))] {
+            web_sys::console::error_1(&format!("convida: {}", message).into());
+        } else {
+            eprintln!("convida: {}", message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enabled() {
+        assert_eq!(log_level(), LogLevel::Off);
+        assert!(!enabled(LogLevel::Error));
+
+        set_log_level(LogLevel::Error);
+        assert!(enabled(LogLevel::Error));
+        assert!(!enabled(LogLevel::Info));
+
+        set_log_level(LogLevel::Trace);
+        assert_eq!(log_level(), LogLevel::Trace);
+        assert!(enabled(LogLevel::Info) && enabled(LogLevel::Trace));
+        // `Off` is a level to set, never one to log at.
+        assert!(!enabled(LogLevel::Off));
+
+        set_log_level(LogLevel::Off);
+        assert!(!enabled(LogLevel::Error));
+    }
+}
@@ -0,0 +1,141 @@
+// A per-cell metadata layer, for applications tagging cells with labels,
+// note targets or puzzle markers.
+//
+// The engine never reads the values. It keeps them lined up with the cells
+// instead: resizing keeps the values of the cells both sizes share, and
+// snapshots carry the layer along.
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::{ConvidaError, Universe};
+
+/// The size of each metadata value.
+#[cfg_attr(feature = "web", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MetadataWidth {
+    U8 = 1,
+    U16 = 2,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Metadata {
+    U8(Vec<u8>),
+    U16(Vec<u16>),
+}
+
+impl Metadata {
+    pub(crate) fn new(width: MetadataWidth, len: usize) -> Metadata {
+        match width {
+            MetadataWidth::U8 => Metadata::U8(vec![0; len]),
+            MetadataWidth::U16 => Metadata::U16(vec![0; len]),
+        }
+    }
+
+    pub(crate) fn width(&self) -> MetadataWidth {
+        match self {
+            Metadata::U8(_) => MetadataWidth::U8,
+            Metadata::U16(_) => MetadataWidth::U16,
+        }
+    }
+
+    pub(crate) fn get(&self, idx: usize) -> u16 {
+        match self {
+            Metadata::U8(values) => values[idx] as u16,
+            Metadata::U16(values) => values[idx],
+        }
+    }
+
+    fn set(&mut self, idx: usize, value: u16) {
+        match self {
+            Metadata::U8(values) => values[idx] = value as u8,
+            Metadata::U16(values) => values[idx] = value,
+        }
+    }
+
+    fn as_ptr(&self) -> *const u8 {
+        match self {
+            Metadata::U8(values) => values.as_ptr(),
+            Metadata::U16(values) => values.as_ptr() as *const u8,
+        }
+    }
+
+    // Move the values of a `width` x `height` board onto one of
+    // `new_width` x `new_height`, anchored at the top-left corner.
+    pub(crate) fn resize(&mut self, width: u32, height: u32, new_width: u32, new_height: u32) {
+        let mut resized = Metadata::new(self.width(), (new_width * new_height) as usize);
+        for row in 0..height.min(new_height) {
+            for col in 0..width.min(new_width) {
+                let value = self.get((row * width + col) as usize);
+                resized.set((row * new_width + col) as usize, value);
+            }
+        }
+        *self = resized;
+    }
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Universe {
+    /// Start a metadata layer of `width` values, all 0, replacing any
+    /// earlier one.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = enableMetadata))]
+    pub fn enable_metadata(&mut self, width: MetadataWidth) {
+        self.metadata = Some(Metadata::new(width, self.cells.len()));
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = disableMetadata))]
+    pub fn disable_metadata(&mut self) {
+        self.metadata = None;
+    }
+
+    /// The size of the layer's values, or `undefined` without one.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = metadataWidth))]
+    pub fn metadata_width(&self) -> Option<MetadataWidth> {
+        self.metadata.as_ref().map(Metadata::width)
+    }
+
+    /// The layer's values in reading order, one or two bytes per cell, or
+    /// null without one. View it as a `Uint8Array` or `Uint16Array` to
+    /// match `metadata_width`.
+    pub fn metadata(&self) -> *const u8 {
+        match &self.metadata {
+            Some(metadata) => metadata.as_ptr(),
+            None => std::ptr::null(),
+        }
+    }
+
+    /// The value of (`row`, `col`), 0 without a layer.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = getMetadata))]
+    pub fn get_metadata(&self, row: u32, col: u32) -> Result<u16, ConvidaError> {
+        ConvidaError::check_bounds(row, col, self.width, self.height)?;
+        let idx = self.get_index(row, col);
+        Ok(self
+            .metadata
+            .as_ref()
+            .map_or(0, |metadata| metadata.get(idx)))
+    }
+
+    /// Set the value of (`row`, `col`), starting a `u16` layer if there is
+    /// none. A `u8` layer keeps the low byte.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = setMetadata))]
+    pub fn set_metadata(&mut self, row: u32, col: u32, value: u16) -> Result<(), ConvidaError> {
+        ConvidaError::check_bounds(row, col, self.width, self.height)?;
+        let idx = self.get_index(row, col);
+        let len = self.cells.len();
+        self.metadata
+            .get_or_insert_with(|| Metadata::new(MetadataWidth::U16, len))
+            .set(idx, value);
+        Ok(())
+    }
+}
+
+impl Universe {
+    // Keep the layer lined up with the cells when the board is resized to
+    // `width` x `height`.
+    pub(crate) fn resize_metadata(&mut self, width: u32, height: u32) {
+        let (old_width, old_height) = (self.width, self.height);
+        if let Some(metadata) = &mut self.metadata {
+            metadata.resize(old_width, old_height, width, height);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+// Measurements of a universe's current state.
+//
+// In JavaScript these are plain objects with TypeScript declarations, so
+// `universe.metrics().boundingBox?.width` type-checks.
+
+#[cfg(feature = "web")]
+use serde::Serialize;
+#[cfg(feature = "web")]
+use tsify_next::Tsify;
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::Universe;
+
+/// The smallest rectangle holding every live cell.
+#[cfg_attr(feature = "web", derive(Serialize, Tsify))]
+#[cfg_attr(feature = "web", tsify(into_wasm_abi), serde(rename_all = "camelCase"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BoundingBox {
+    /// The top row.
+    pub row: u32,
+    /// The leftmost column.
+    pub col: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[cfg_attr(feature = "web", derive(Serialize, Tsify))]
+#[cfg_attr(feature = "web", tsify(into_wasm_abi), serde(rename_all = "camelCase"))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Metrics {
+    pub generation: u32,
+    pub width: u32,
+    pub height: u32,
+    /// The number of live cells.
+    pub population: u32,
+    /// The fraction of cells that are alive.
+    pub density: f64,
+    /// `None` when every cell is dead.
+    pub bounding_box: Option<BoundingBox>,
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Universe {
+    pub fn metrics(&self) -> Metrics {
+        let population = self.iter_live().count() as u32;
+        Metrics {
+            generation: self.generation,
+            width: self.width,
+            height: self.height,
+            population,
+            density: f64::from(population) / self.cells.len().max(1) as f64,
+            bounding_box: self.bounding_box(),
+        }
+    }
+
+    /// The smallest rectangle holding every live cell, or `undefined` when
+    /// there are none. On a torus a pattern that wraps around an edge
+    /// spans the whole width or height.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = boundingBox))]
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        let mut live = self.iter_live();
+        let (first_row, first_col) = live.next()?;
+        let (mut top, mut bottom, mut left, mut right) =
+            (first_row, first_row, first_col, first_col);
+        for (row, col) in live {
+            top = top.min(row);
+            bottom = bottom.max(row);
+            left = left.min(col);
+            right = right.max(col);
+        }
+        Some(BoundingBox {
+            row: top,
+            col: left,
+            width: right - left + 1,
+            height: bottom - top + 1,
+        })
+    }
+}
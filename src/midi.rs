@@ -0,0 +1,166 @@
+// Web MIDI output that turns the universe into a step sequencer.
+//
+// Trigger regions are horizontal runs of cells, usually whole rows, each
+// mapped to a note. Whenever cells are born inside a region its note is
+// played, louder the more cells were born, and released on the next
+// update unless it's triggered again.
+//
+// JavaScript obtains the output port with `navigator.requestMIDIAccess()`
+// and hands it over, which keeps the permission prompt in the page's
+// control.
+
+use js_sys::Uint8Array;
+use wasm_bindgen::prelude::*;
+use web_sys::MidiOutput;
+
+use crate::{Cell, Universe};
+
+const NOTE_ON: u8 = 0x90;
+const NOTE_OFF: u8 = 0x80;
+
+struct Trigger {
+    row: u32,
+    col_start: u32,
+    col_end: u32,
+    note: u8,
+    channel: u8,
+    previous: Vec<Cell>,
+    sounding: bool,
+}
+
+#[wasm_bindgen]
+pub struct MidiSequencer {
+    output: MidiOutput,
+    triggers: Vec<Trigger>,
+}
+
+#[wasm_bindgen]
+impl MidiSequencer {
+    pub fn new(output: MidiOutput) -> MidiSequencer {
+        MidiSequencer {
+            output,
+            triggers: Vec::new(),
+        }
+    }
+
+    /// Play `note` on `channel` (0 to 15) whenever cells are born anywhere
+    /// in `row`.
+    #[wasm_bindgen(js_name = addTriggerRow)]
+    pub fn add_trigger_row(&mut self, row: u32, note: u8, channel: u8) {
+        self.add_trigger_region(row, 0, u32::MAX, note, channel);
+    }
+
+    /// Play `note` on `channel` whenever cells are born in the columns
+    /// `col_start..col_end` of `row`.
+    #[wasm_bindgen(js_name = addTriggerRegion)]
+    pub fn add_trigger_region(
+        &mut self,
+        row: u32,
+        col_start: u32,
+        col_end: u32,
+        note: u8,
+        channel: u8,
+    ) {
+        self.triggers.push(Trigger {
+            row,
+            col_start,
+            col_end: col_end.max(col_start),
+            note: note.min(127),
+            channel: channel.min(15),
+            previous: Vec::new(),
+            sounding: false,
+        });
+    }
+
+    /// Remove every trigger region on `row`, releasing their notes.
+    #[wasm_bindgen(js_name = removeTriggerRow)]
+    pub fn remove_trigger_row(&mut self, row: u32) {
+        let (removed, kept) = self
+            .triggers
+            .drain(..)
+            .partition(|trigger| trigger.row == row);
+        self.triggers = kept;
+        for trigger in removed {
+            self.release(&trigger);
+        }
+    }
+
+    #[wasm_bindgen(js_name = clearTriggers)]
+    pub fn clear_triggers(&mut self) {
+        self.all_notes_off();
+        self.triggers.clear();
+    }
+
+    /// Release every note that is still sounding.
+    #[wasm_bindgen(js_name = allNotesOff)]
+    pub fn all_notes_off(&mut self) {
+        for i in 0..self.triggers.len() {
+            self.release(&self.triggers[i]);
+            self.triggers[i].sounding = false;
+        }
+    }
+
+    /// Compare the trigger regions of `universe` with the previous update
+    /// and send note-on for regions with births, note-off for the others.
+    pub fn update(&mut self, universe: &Universe) {
+        let (width, height) = (universe.width(), universe.height());
+        let cells = universe.get_cells();
+
+        for i in 0..self.triggers.len() {
+            let trigger = &self.triggers[i];
+            let (row, start, end) = (
+                trigger.row,
+                trigger.col_start.min(width),
+                trigger.col_end.min(width),
+            );
+            if row >= height {
+                continue;
+            }
+
+            let offset = (row * width) as usize;
+            let current = &cells[offset + start as usize..offset + end as usize];
+            let births = if trigger.previous.len() == current.len() {
+                trigger
+                    .previous
+                    .iter()
+                    .zip(current)
+                    .filter(|&(&before, &after)| before == Cell::Dead && after == Cell::Alive)
+                    .count()
+            } else {
+                0
+            };
+
+            if trigger.sounding {
+                self.release(trigger);
+            }
+            let sounding = births > 0;
+            if sounding {
+                let velocity = (births * 127 / current.len().max(1)).clamp(32, 127) as u8;
+                self.send(&[NOTE_ON | trigger.channel, trigger.note, velocity]);
+            }
+
+            let trigger = &mut self.triggers[i];
+            trigger.sounding = sounding;
+            trigger.previous.clear();
+            trigger.previous.extend_from_slice(current);
+        }
+    }
+}
+
+impl MidiSequencer {
+    fn release(&self, trigger: &Trigger) {
+        if trigger.sounding {
+            self.send(&[NOTE_OFF | trigger.channel, trigger.note, 0]);
+        }
+    }
+
+    fn send(&self, message: &[u8]) {
+        let _ = self.output.send(&Uint8Array::from(message));
+    }
+}
+
+impl Drop for MidiSequencer {
+    fn drop(&mut self) {
+        self.all_notes_off();
+    }
+}
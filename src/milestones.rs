@@ -0,0 +1,150 @@
+// Achievements: each milestone raises a `milestone` event the first time
+// the universe reaches it, through the same handler or queue as the other
+// events.
+//
+// Pattern milestones match every phase of the pattern in all eight
+// orientations, so a glider counts however it was produced. Like the other
+// detectors they only run while someone is listening, and stop once
+// reached.
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::search::{occurrences, orientations, phases};
+use crate::{Event, LifeLike, Rle, Universe};
+
+// Oscillators recognized by `Milestone::Oscillator`.
+const OSCILLATORS: &[&str] = &[
+    "3o!",
+    "b3o$3o!",
+    "2o$2o$2b2o$2b2o!",
+    "2b3o3b3o2$o4bobo4bo$o4bobo4bo$o4bobo4bo$2b3o3b3o2$2b3o3b3o$o4bobo4bo$\
+     o4bobo4bo$o4bobo4bo2$2b3o3b3o!",
+    "2bo4bo$2ob4ob2o$2bo4bo!",
+];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Milestone {
+    /// The population rose above this many cells.
+    PopulationAbove(u32),
+    /// The universe reached this generation with cells still alive.
+    Survived(u32),
+    /// A glider appeared, surrounded by dead cells.
+    Glider,
+    /// A blinker, toad, beacon, pulsar or pentadecathlon appeared,
+    /// surrounded by dead cells.
+    Oscillator,
+}
+
+struct Tracked {
+    name: String,
+    milestone: Milestone,
+    /// Every shape matching a pattern milestone.
+    shapes: Vec<Vec<(u32, u32)>>,
+    reached: bool,
+}
+
+#[derive(Default)]
+pub(crate) struct Milestones {
+    tracked: Vec<Tracked>,
+}
+
+impl Milestones {
+    pub(crate) fn detect(&mut self, universe: &Universe, population: u32, events: &mut Vec<Event>) {
+        let generation = universe.generation;
+        for tracked in self.tracked.iter_mut().filter(|tracked| !tracked.reached) {
+            tracked.reached = match tracked.milestone {
+                Milestone::PopulationAbove(cells) => population > cells,
+                Milestone::Survived(generations) => population > 0 && generation >= generations,
+                Milestone::Glider | Milestone::Oscillator => tracked
+                    .shapes
+                    .iter()
+                    .any(|shape| occurrences(universe, shape).next().is_some()),
+            };
+            if tracked.reached {
+                events.push(Event::Milestone {
+                    generation,
+                    name: tracked.name.clone(),
+                });
+            }
+        }
+    }
+
+    pub(crate) fn reset(&mut self) {
+        for tracked in &mut self.tracked {
+            tracked.reached = false;
+        }
+    }
+}
+
+impl Universe {
+    /// Raise a `milestone` event named `name` the first time the universe
+    /// reaches `milestone`. Replaces any milestone of the same name.
+    pub fn add_milestone(&mut self, name: &str, milestone: Milestone) {
+        let patterns: Vec<Rle> = match milestone {
+            Milestone::Glider => vec![Rle::parse("bo$2bo$3o!").expect("glider")],
+            Milestone::Oscillator => OSCILLATORS
+                .iter()
+                .map(|rle| Rle::parse(rle).expect("oscillator"))
+                .collect(),
+            _ => Vec::new(),
+        };
+        let mut shapes: Vec<Vec<(u32, u32)>> = patterns.iter().flat_map(shapes).collect();
+        shapes.sort();
+        shapes.dedup();
+
+        self.remove_milestone(name);
+        self.events.milestones.tracked.push(Tracked {
+            name: name.to_string(),
+            milestone,
+            shapes,
+            reached: false,
+        });
+    }
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Universe {
+    /// Raise the `name` milestone once the population rises above
+    /// `population`.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = addPopulationMilestone))]
+    pub fn add_population_milestone(&mut self, name: &str, population: u32) {
+        self.add_milestone(name, Milestone::PopulationAbove(population));
+    }
+
+    /// Raise the `name` milestone once the universe reaches `generation`
+    /// with cells still alive.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = addSurvivalMilestone))]
+    pub fn add_survival_milestone(&mut self, name: &str, generation: u32) {
+        self.add_milestone(name, Milestone::Survived(generation));
+    }
+
+    /// Raise the `name` milestone when the first glider appears.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = addGliderMilestone))]
+    pub fn add_glider_milestone(&mut self, name: &str) {
+        self.add_milestone(name, Milestone::Glider);
+    }
+
+    /// Raise the `name` milestone when the first blinker, toad, beacon,
+    /// pulsar or pentadecathlon appears.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = addOscillatorMilestone))]
+    pub fn add_oscillator_milestone(&mut self, name: &str) {
+        self.add_milestone(name, Milestone::Oscillator);
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = removeMilestone))]
+    pub fn remove_milestone(&mut self, name: &str) {
+        self.events
+            .milestones
+            .tracked
+            .retain(|tracked| tracked.name != name);
+    }
+}
+
+// Every phase of `pattern` under Conway's Life in all eight orientations.
+fn shapes(pattern: &Rle) -> Vec<Vec<(u32, u32)>> {
+    phases(pattern, LifeLike::CONWAY)
+        .iter()
+        .flat_map(|phase| orientations(phase))
+        .collect()
+}
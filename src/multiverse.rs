@@ -0,0 +1,229 @@
+// Several universes side by side, for comparing rules and seeds.
+//
+// A `Multiverse` owns its universes and ticks them together in one call,
+// so a page comparing them needs one module instance and one set of glue
+// code. JavaScript reaches each universe by its index: `cells(i)` points at
+// its cells like `Universe.cells`, and `universe(i)` returns a copy.
+//
+// `ab_compare` adds two universes seeded with the same soup, runs one
+// under each of two rules in lockstep, and reports how far apart they
+// drift each generation.
+
+#[cfg(feature = "web")]
+use serde::Serialize;
+#[cfg(feature = "web")]
+use tsify_next::Tsify;
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "web")]
+use crate::rule::parse_rule;
+use crate::{Cell, ConvidaError, LifeLike, SoupSpec, Universe};
+
+/// One generation of an A/B comparison.
+#[cfg_attr(feature = "web", derive(Serialize, Tsify))]
+#[cfg_attr(feature = "web", tsify(into_wasm_abi), serde(rename_all = "camelCase"))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AbGeneration {
+    pub generation: u32,
+    pub population_a: u32,
+    pub population_b: u32,
+    /// Cells alive in one universe but not the other.
+    pub differing: u32,
+    /// `differing` as a fraction of all cells.
+    pub divergence: f64,
+}
+
+#[cfg_attr(feature = "web", derive(Serialize, Tsify))]
+#[cfg_attr(feature = "web", tsify(into_wasm_abi), serde(rename_all = "camelCase"))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct AbReport {
+    /// The indices of the universes run under each rule.
+    pub index_a: u32,
+    pub index_b: u32,
+    /// The first generation at which the universes differ, if any.
+    pub diverged_at: Option<u32>,
+    /// The seed generation and every one after it.
+    pub generations: Vec<AbGeneration>,
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+#[derive(Default)]
+pub struct Multiverse {
+    universes: Vec<Universe>,
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Multiverse {
+    #[cfg_attr(feature = "web", wasm_bindgen(constructor))]
+    pub fn new() -> Multiverse {
+        Multiverse::default()
+    }
+
+    /// The number of universes.
+    #[cfg_attr(feature = "web", wasm_bindgen(getter))]
+    pub fn length(&self) -> u32 {
+        self.universes.len() as u32
+    }
+
+    /// Take ownership of `universe`, returning its index.
+    pub fn add(&mut self, universe: Universe) -> u32 {
+        self.universes.push(universe);
+        self.universes.len() as u32 - 1
+    }
+
+    /// Take universe `index` out, moving the ones after it down by one.
+    pub fn remove(&mut self, index: u32) -> Result<Universe, ConvidaError> {
+        self.check_index(index)?;
+        Ok(self.universes.remove(index as usize))
+    }
+
+    /// Put `universe` in the place of universe `index`.
+    pub fn replace(&mut self, index: u32, universe: Universe) -> Result<(), ConvidaError> {
+        self.check_index(index)?;
+        self.universes[index as usize] = universe;
+        Ok(())
+    }
+
+    /// A copy of universe `index`, without its callbacks.
+    pub fn universe(&self, index: u32) -> Result<Universe, ConvidaError> {
+        Ok(self.get_checked(index)?.clone())
+    }
+
+    /// Advance every universe one generation.
+    pub fn tick(&mut self) {
+        for universe in &mut self.universes {
+            universe.tick();
+        }
+    }
+
+    /// Advance every universe `generations` generations.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = tickMany))]
+    pub fn tick_many(&mut self, generations: u32) {
+        for _ in 0..generations {
+            self.tick();
+        }
+    }
+
+    pub fn cells(&self, index: u32) -> Result<*const Cell, ConvidaError> {
+        Ok(self.get_checked(index)?.cells())
+    }
+
+    pub fn width(&self, index: u32) -> Result<u32, ConvidaError> {
+        Ok(self.get_checked(index)?.width())
+    }
+
+    pub fn height(&self, index: u32) -> Result<u32, ConvidaError> {
+        Ok(self.get_checked(index)?.height())
+    }
+
+    pub fn generation(&self, index: u32) -> Result<u32, ConvidaError> {
+        Ok(self.get_checked(index)?.generation())
+    }
+
+    /// The live cells of universe `index`.
+    pub fn population(&self, index: u32) -> Result<u32, ConvidaError> {
+        Ok(self.get_checked(index)?.iter_live().count() as u32)
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = toggleCell))]
+    pub fn toggle_cell(&mut self, index: u32, row: u32, col: u32) -> Result<(), ConvidaError> {
+        self.check_index(index)?;
+        self.universes[index as usize].toggle_cell(row, col)
+    }
+}
+
+impl Multiverse {
+    /// Add two `width` x `height` universes seeded with the same soup made
+    /// to `spec`, run them for `generations` generations under `rule_a`
+    /// and `rule_b`, and compare them at each generation.
+    pub fn ab_compare(
+        &mut self,
+        width: u32,
+        height: u32,
+        spec: &SoupSpec,
+        rule_a: LifeLike,
+        rule_b: LifeLike,
+        generations: u32,
+    ) -> Result<AbReport, ConvidaError> {
+        let mut a = Universe::from_cells(width, height, spec.cells(width, height)?);
+        let mut b = a.clone();
+        a.set_rule(rule_a);
+        b.set_rule(rule_b);
+
+        let mut report = AbReport {
+            index_a: 0,
+            index_b: 0,
+            diverged_at: None,
+            generations: Vec::new(),
+        };
+        for generation in 0..=generations {
+            if generation > 0 {
+                a.tick();
+                b.tick();
+            }
+            let differing = a.cells.iter().zip(&b.cells).filter(|(x, y)| x != y).count() as u32;
+            if differing > 0 && report.diverged_at.is_none() {
+                report.diverged_at = Some(generation);
+            }
+            report.generations.push(AbGeneration {
+                generation,
+                population_a: a.iter_live().count() as u32,
+                population_b: b.iter_live().count() as u32,
+                differing,
+                divergence: f64::from(differing) / a.cells.len() as f64,
+            });
+        }
+
+        report.index_a = self.add(a);
+        report.index_b = self.add(b);
+        Ok(report)
+    }
+
+    pub fn get(&self, index: u32) -> Option<&Universe> {
+        self.universes.get(index as usize)
+    }
+
+    pub fn get_mut(&mut self, index: u32) -> Option<&mut Universe> {
+        self.universes.get_mut(index as usize)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Universe> {
+        self.universes.iter()
+    }
+
+    fn get_checked(&self, index: u32) -> Result<&Universe, ConvidaError> {
+        self.check_index(index)?;
+        Ok(&self.universes[index as usize])
+    }
+
+    fn check_index(&self, index: u32) -> Result<(), ConvidaError> {
+        if (index as usize) < self.universes.len() {
+            Ok(())
+        } else {
+            Err(ConvidaError::NoSuchUniverse {
+                index,
+                count: self.length(),
+            })
+        }
+    }
+}
+
+#[cfg(feature = "web")]
+#[wasm_bindgen]
+impl Multiverse {
+    /// `ab_compare` with rules in B/S notation.
+    #[wasm_bindgen(js_name = abCompare)]
+    pub fn ab_compare_js(
+        &mut self,
+        width: u32,
+        height: u32,
+        spec: &SoupSpec,
+        rule_a: &str,
+        rule_b: &str,
+        generations: u32,
+    ) -> Result<AbReport, ConvidaError> {
+        let (rule_a, rule_b) = (parse_rule(rule_a)?, parse_rule(rule_b)?);
+        self.ab_compare(width, height, spec, rule_a, rule_b, generations)
+    }
+}
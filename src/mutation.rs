@@ -0,0 +1,173 @@
+// Rule mutation experiments, for artificial life work on how robust
+// patterns are to small changes of rule.
+//
+// Every so many generations one birth or survival condition, chosen at
+// random, is flipped: in the universe's own rule, or in the rule of one of
+// the regions picked with `mutate_region`, each of which runs as a rule
+// zone. B0 is never flipped on, since a rule with it makes the empty board
+// flash. Each flip is logged with the rule it produced.
+
+#[cfg(feature = "web")]
+use serde::Serialize;
+#[cfg(feature = "web")]
+use tsify_next::Tsify;
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::rng::Rng;
+use crate::{BoundingBox, LifeLike, Universe};
+
+/// One flipped condition.
+#[cfg_attr(feature = "web", derive(Serialize, Tsify))]
+#[cfg_attr(feature = "web", tsify(into_wasm_abi), serde(rename_all = "camelCase"))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuleMutation {
+    /// The generation the flip took effect at.
+    pub generation: u32,
+    /// The region whose rule was mutated, or `None` for the universe's.
+    pub region: Option<BoundingBox>,
+    /// The condition flipped, such as `B6` or `S4`.
+    pub condition: String,
+    /// Whether the condition was turned on rather than off.
+    pub added: bool,
+    /// The rule after the flip.
+    pub rule: String,
+}
+
+#[cfg_attr(feature = "web", derive(Serialize, Tsify))]
+#[cfg_attr(feature = "web", tsify(into_wasm_abi), serde(rename_all = "camelCase"))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MutationLog {
+    pub mutations: Vec<RuleMutation>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Experiment {
+    every: u32,
+    rng: Rng,
+    /// The indices of the experiment's regions among the rule zones.
+    zones: Vec<usize>,
+    running: bool,
+    log: MutationLog,
+}
+
+// Flip one random condition of `rule`, returning its name and whether it
+// was turned on.
+fn flip(rule: &mut LifeLike, rng: &mut Rng) -> (String, bool) {
+    // B1..=B8 and S0..=S8.
+    let choice = rng.next_u64() % 17;
+    let (mask, kind, count) = if choice < 8 {
+        (&mut rule.birth, 'B', choice + 1)
+    } else {
+        (&mut rule.survival, 'S', choice - 8)
+    };
+    let bit = 1 << count;
+    *mask ^= bit;
+    (format!("{}{}", kind, count), *mask & bit != 0)
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Universe {
+    /// Flip a random condition every `every_n` generations, drawing the
+    /// choices from `seed`. Replaces any earlier experiment, keeping the
+    /// rules it left behind.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = startMutations))]
+    pub fn start_mutations(&mut self, every_n: u32, seed: u64) {
+        self.mutations = Some(Experiment {
+            every: every_n.max(1),
+            rng: Rng::new(seed),
+            zones: Vec::new(),
+            running: true,
+            log: MutationLog::default(),
+        });
+    }
+
+    /// Mutate the rule of the `width` x `height` region with its top-left
+    /// corner at (`row`, `col`) instead of the universe's. With several
+    /// regions each flip picks one at random. The region starts with the
+    /// universe's current rule. Does nothing before `start_mutations`.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = mutateRegion))]
+    pub fn mutate_region(&mut self, row: u32, col: u32, width: u32, height: u32) {
+        let region = BoundingBox {
+            row,
+            col,
+            width,
+            height,
+        };
+        if let Some(experiment) = &mut self.mutations {
+            experiment.zones.push(self.rule_zones.zones.len());
+            self.rule_zones.zones.push((region, self.rule));
+        }
+    }
+
+    /// Stop flipping conditions, keeping the mutated rules and the log.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = stopMutations))]
+    pub fn stop_mutations(&mut self) {
+        if let Some(experiment) = &mut self.mutations {
+            experiment.running = false;
+        }
+    }
+
+    /// Stop the experiment and remove its regions, so they go back to the
+    /// universe's rule.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = clearMutations))]
+    pub fn clear_mutations(&mut self) {
+        if let Some(experiment) = self.mutations.take() {
+            let mut index = 0;
+            self.rule_zones.zones.retain(|_| {
+                index += 1;
+                !experiment.zones.contains(&(index - 1))
+            });
+        }
+    }
+
+    /// Every condition flipped, oldest first.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = mutationLog))]
+    pub fn mutation_log(&self) -> MutationLog {
+        self.mutations
+            .as_ref()
+            .map(|experiment| experiment.log.clone())
+            .unwrap_or_default()
+    }
+}
+
+impl Universe {
+    // Stop mutating the experiment's regions once the zones are gone.
+    pub(crate) fn forget_mutation_regions(&mut self) {
+        if let Some(experiment) = &mut self.mutations {
+            experiment.zones.clear();
+        }
+    }
+
+    // Called after every step.
+    pub(crate) fn mutate(&mut self) {
+        let experiment = match &mut self.mutations {
+            Some(experiment)
+                if experiment.running && self.generation.is_multiple_of(experiment.every) =>
+            {
+                experiment
+            }
+            _ => return,
+        };
+
+        let zone = match experiment.zones.len() {
+            0 => None,
+            n => Some(experiment.zones[(experiment.rng.next_u64() % n as u64) as usize]),
+        };
+        let (region, rule) = match zone {
+            Some(zone) => {
+                let (region, rule) = &mut self.rule_zones.zones[zone];
+                (Some(*region), rule)
+            }
+            None => (None, &mut self.rule),
+        };
+        let (condition, added) = flip(rule, &mut experiment.rng);
+        experiment.log.mutations.push(RuleMutation {
+            generation: self.generation,
+            region,
+            condition,
+            added,
+            rule: rule.to_string(),
+        });
+    }
+}
@@ -0,0 +1,88 @@
+// Organic starting shapes from Perlin noise.
+//
+// Uniform soups settle into the same sparse ash everywhere. Thresholding
+// smooth noise gives solid blobs with ragged edges instead, which burn
+// inwards from their borders and make for better demos.
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::rng::Rng;
+use crate::{Cell, Universe};
+
+// Gradient noise over the plane, repeating every 256 units.
+struct Perlin {
+    permutation: [u8; 512],
+}
+
+impl Perlin {
+    fn new(seed: u64) -> Perlin {
+        let mut table: Vec<u8> = (0..=255).collect();
+        let mut rng = Rng::new(seed);
+        for i in (1..table.len()).rev() {
+            let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+            table.swap(i, j);
+        }
+        let mut permutation = [0; 512];
+        for (i, value) in permutation.iter_mut().enumerate() {
+            *value = table[i & 255];
+        }
+        Perlin { permutation }
+    }
+
+    // Roughly in [-1, 1], and 0 at every lattice point.
+    fn noise(&self, x: f64, y: f64) -> f64 {
+        let (x0, y0) = (x.floor(), y.floor());
+        let (dx, dy) = (x - x0, y - y0);
+        let (xi, yi) = ((x0 as i64 & 255) as usize, (y0 as i64 & 255) as usize);
+        let p = &self.permutation;
+        let corner = |i: usize, j: usize, x: f64, y: f64| {
+            let hash = p[p[xi + i] as usize + yi + j];
+            match hash & 7 {
+                0 => x + y,
+                1 => -x + y,
+                2 => x - y,
+                3 => -x - y,
+                4 => x,
+                5 => -x,
+                6 => y,
+                _ => -y,
+            }
+        };
+        let fade = |t: f64| t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+        let lerp = |a: f64, b: f64, t: f64| a + t * (b - a);
+
+        let (u, v) = (fade(dx), fade(dy));
+        let top = lerp(corner(0, 0, dx, dy), corner(1, 0, dx - 1.0, dy), u);
+        let bottom = lerp(
+            corner(0, 1, dx, dy - 1.0),
+            corner(1, 1, dx - 1.0, dy - 1.0),
+            u,
+        );
+        lerp(top, bottom, v)
+    }
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Universe {
+    /// Replace every cell with Perlin noise drawn from `seed`, alive where
+    /// the noise is above `threshold`. `scale` is roughly the size of a blob
+    /// in cells, and a `threshold` of 0 brings about half the cells to life.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = seedNoise))]
+    pub fn seed_noise(&mut self, scale: f64, threshold: f64, seed: u64) {
+        self.enter("Universe::seed_noise");
+        let perlin = Perlin::new(seed);
+        let width = self.width;
+        self.cells = (0..self.width * self.height)
+            .map(|i| {
+                // Sample cell centres, since the noise is 0 on the lattice.
+                let (row, col) = ((i / width) as f64 + 0.5, (i % width) as f64 + 0.5);
+                if perlin.noise(col / scale, row / scale) > threshold {
+                    Cell::Alive
+                } else {
+                    Cell::Dead
+                }
+            })
+            .collect();
+    }
+}
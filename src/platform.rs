@@ -0,0 +1,45 @@
+// Detection of what the JavaScript host provides.
+//
+// The module is loaded both by browsers and by Node.js. Node has no
+// `window` or `document`, so anything needing them checks here first and
+// the simulation itself only ever relies on plain data.
+
+use wasm_bindgen::prelude::*;
+
+/// Whether the module runs in a browser window, as opposed to Node.js or
+/// a worker.
+#[wasm_bindgen(js_name = hasWindow)]
+pub fn has_window() -> bool {
+    web_sys::window().is_some()
+}
+
+/// Whether `console.time` and `console.timeEnd` exist.
+#[cfg(all(
+    not(feature = "performance"),
+    target_arch = "wasm32",
+    target_os = "unknown"
+))]
+pub fn has_console_timers() -> bool {
+    use js_sys::Reflect;
+
+    let console = match Reflect::get(&js_sys::global(), &JsValue::from_str("console")) {
+        Ok(console) if console.is_object() => console,
+        _ => return false,
+    };
+    ["time", "timeEnd"].iter().all(|name| {
+        Reflect::get(&console, &JsValue::from_str(name))
+            .map(|f| f.is_function())
+            .unwrap_or(false)
+    })
+}
+
+/// The host's `performance`, in windows, workers and Node.js alike.
+#[cfg(all(feature = "performance", target_arch = "wasm32", target_os = "unknown"))]
+pub fn performance() -> Option<web_sys::Performance> {
+    use wasm_bindgen::JsCast;
+
+    js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("performance"))
+        .ok()?
+        .dyn_into()
+        .ok()
+}
@@ -0,0 +1,199 @@
+// Minimal PNG encoder for RGBA frames.
+//
+// Rows use the Sub filter, which turns the flat colour runs of a rendered
+// board into runs of zeros, and are deflated with the fixed Huffman code
+// using back references to the previous byte and the previous row only.
+// That is enough to shrink typical frames by two orders of magnitude
+// without pulling in a compression library.
+
+const SIGNATURE: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+const MAX_MATCH: usize = 258;
+const MIN_MATCH: usize = 3;
+const MAX_DISTANCE: usize = 32768;
+
+// Base lengths and extra bits of the length codes 257..=285.
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+// Base distances and extra bits of the distance codes 0..=29.
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// Encode `rgba`, `width * height * 4` bytes in row-major order, as a PNG.
+pub fn encode_png(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    let stride = width as usize * 4;
+    assert_eq!(rgba.len(), stride * height as usize, "bad RGBA buffer size");
+
+    let mut filtered = Vec::with_capacity((stride + 1) * height as usize);
+    for row in rgba.chunks(stride.max(1)).take(height as usize) {
+        filtered.push(1); // Sub
+        for i in 0..row.len() {
+            let left = if i >= 4 { row[i - 4] } else { 0 };
+            filtered.push(row[i].wrapping_sub(left));
+        }
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // 8 bits per channel, colour type 6 (RGBA), default compression,
+    // filtering and no interlacing.
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    let mut png = SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &zlib(&filtered, stride + 1));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in bytes.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+// zlib stream holding a single fixed Huffman deflate block.
+fn zlib(data: &[u8], row_length: usize) -> Vec<u8> {
+    let mut out = BitWriter::default();
+    out.bytes.extend_from_slice(&[0x78, 0x01]);
+    out.write(1, 1); // final block
+    out.write(1, 2); // fixed Huffman codes
+
+    let mut i = 0;
+    while i < data.len() {
+        let (length, distance) = [1, row_length]
+            .iter()
+            .filter(|&&distance| distance <= i && distance <= MAX_DISTANCE)
+            .map(|&distance| (match_length(data, i, distance), distance))
+            .max()
+            .unwrap_or((0, 0));
+
+        if length >= MIN_MATCH {
+            out.length(length);
+            out.distance(distance);
+            i += length;
+        } else {
+            out.literal(data[i] as u16);
+            i += 1;
+        }
+    }
+    out.literal(256); // end of block
+
+    let mut bytes = out.finish();
+    bytes.extend_from_slice(&adler32(data).to_be_bytes());
+    bytes
+}
+
+fn match_length(data: &[u8], at: usize, distance: usize) -> usize {
+    data[at..]
+        .iter()
+        .zip(&data[at - distance..])
+        .take(MAX_MATCH)
+        .take_while(|(a, b)| a == b)
+        .count()
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u32,
+}
+
+impl BitWriter {
+    // Append the low `count` bits of `value`, least significant first.
+    fn write(&mut self, value: u32, count: u32) {
+        self.buffer |= value << self.bits;
+        self.bits += count;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    // Huffman codes are packed most significant bit first.
+    fn code(&mut self, code: u32, count: u32) {
+        let reversed = code.reverse_bits() >> (32 - count);
+        self.write(reversed, count);
+    }
+
+    fn literal(&mut self, value: u16) {
+        let value = value as u32;
+        match value {
+            0..=143 => self.code(0x30 + value, 8),
+            144..=255 => self.code(0x190 + value - 144, 9),
+            256..=279 => self.code(value - 256, 7),
+            _ => self.code(0xc0 + value - 280, 8),
+        }
+    }
+
+    fn length(&mut self, length: usize) {
+        let index = LENGTH_BASE
+            .iter()
+            .rposition(|&base| base as usize <= length)
+            .unwrap();
+        self.literal(257 + index as u16);
+        let extra = LENGTH_EXTRA[index] as u32;
+        self.write((length - LENGTH_BASE[index] as usize) as u32, extra);
+    }
+
+    fn distance(&mut self, distance: usize) {
+        let index = DISTANCE_BASE
+            .iter()
+            .rposition(|&base| base as usize <= distance)
+            .unwrap();
+        self.code(index as u32, 5);
+        let extra = DISTANCE_EXTRA[index] as u32;
+        self.write((distance - DISTANCE_BASE[index] as usize) as u32, extra);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
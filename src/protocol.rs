@@ -0,0 +1,239 @@
+// Binary wire format shared by everything that synchronizes a universe
+// between processes.
+//
+// Every message starts with a one byte tag followed by little-endian
+// fields. The server is authoritative: it stamps each edit with a sequence
+// number and the generation at which it takes effect, and clients apply
+// edits strictly in sequence order.
+
+use std::fmt;
+
+use crate::crdt::{CrdtEdit, Stamp};
+use crate::{Cell, Universe};
+
+const TAG_EDIT: u8 = 1;
+const TAG_CHECKSUM: u8 = 2;
+const TAG_RESYNC: u8 = 3;
+const TAG_SNAPSHOT: u8 = 4;
+const TAG_CRDT_EDIT: u8 = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Edit {
+    /// Order assigned by the server. Zero for an edit proposed by a client
+    /// that hasn't been sequenced yet.
+    pub seq: u32,
+    /// Generation before whose tick the edit is applied.
+    pub generation: u32,
+    pub row: u32,
+    pub col: u32,
+    pub state: Cell,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Message {
+    Edit(Edit),
+    /// The checksum of the board at `generation`.
+    Checksum {
+        generation: u32,
+        checksum: u64,
+    },
+    /// Ask for the board to be sent again after a checksum mismatch.
+    Resync {
+        generation: u32,
+    },
+    /// The full board in the snapshot format, including every edit up to
+    /// and including `seq`.
+    Snapshot {
+        seq: u32,
+        data: Vec<u8>,
+    },
+    /// An unsequenced edit merged as a last-writer-wins register.
+    CrdtEdit(CrdtEdit),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    Empty,
+    UnknownTag(u8),
+    Truncated,
+    InvalidCell(u8),
+    BadMagic,
+    UnsupportedVersion(u8),
+    InvalidMetadataWidth(u8),
+    /// A snapshot header with no cells, or too many to index.
+    InvalidSize {
+        width: u32,
+        height: u32,
+    },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::Empty => write!(f, "empty message"),
+            DecodeError::UnknownTag(tag) => write!(f, "unknown message tag {}", tag),
+            DecodeError::Truncated => write!(f, "message is truncated"),
+            DecodeError::InvalidCell(value) => write!(f, "invalid cell state {}", value),
+            DecodeError::BadMagic => write!(f, "not a convida snapshot"),
+            DecodeError::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version {}", version)
+            }
+            DecodeError::InvalidMetadataWidth(width) => {
+                write!(f, "invalid metadata width {}", width)
+            }
+            DecodeError::InvalidSize { width, height } => {
+                write!(f, "invalid snapshot size {}x{}", width, height)
+            }
+        }
+    }
+}
+
+impl Message {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        match self {
+            Message::Edit(edit) => {
+                bytes.push(TAG_EDIT);
+                bytes.extend_from_slice(&edit.seq.to_le_bytes());
+                bytes.extend_from_slice(&edit.generation.to_le_bytes());
+                bytes.extend_from_slice(&edit.row.to_le_bytes());
+                bytes.extend_from_slice(&edit.col.to_le_bytes());
+                bytes.push(edit.state as u8);
+            }
+            Message::Checksum {
+                generation,
+                checksum,
+            } => {
+                bytes.push(TAG_CHECKSUM);
+                bytes.extend_from_slice(&generation.to_le_bytes());
+                bytes.extend_from_slice(&checksum.to_le_bytes());
+            }
+            Message::Resync { generation } => {
+                bytes.push(TAG_RESYNC);
+                bytes.extend_from_slice(&generation.to_le_bytes());
+            }
+            Message::Snapshot { seq, data } => {
+                bytes.push(TAG_SNAPSHOT);
+                bytes.extend_from_slice(&seq.to_le_bytes());
+                bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+                bytes.extend_from_slice(data);
+            }
+            Message::CrdtEdit(edit) => {
+                bytes.push(TAG_CRDT_EDIT);
+                bytes.extend_from_slice(&edit.stamp.counter.to_le_bytes());
+                bytes.extend_from_slice(&edit.stamp.replica.to_le_bytes());
+                bytes.extend_from_slice(&edit.row.to_le_bytes());
+                bytes.extend_from_slice(&edit.col.to_le_bytes());
+                bytes.push(edit.state as u8);
+            }
+        }
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Message, DecodeError> {
+        let mut bytes = bytes;
+        Message::decode_prefix(&mut bytes)
+    }
+
+    /// Decode one message from the front of `bytes` and advance past it,
+    /// for reading several messages stored back to back.
+    pub fn decode_prefix(bytes: &mut &[u8]) -> Result<Message, DecodeError> {
+        let (&tag, mut rest) = bytes.split_first().ok_or(DecodeError::Empty)?;
+        let message = match tag {
+            TAG_EDIT => Message::Edit(Edit {
+                seq: read_u32(&mut rest)?,
+                generation: read_u32(&mut rest)?,
+                row: read_u32(&mut rest)?,
+                col: read_u32(&mut rest)?,
+                state: read_cell(&mut rest)?,
+            }),
+            TAG_CHECKSUM => Message::Checksum {
+                generation: read_u32(&mut rest)?,
+                checksum: read_u64(&mut rest)?,
+            },
+            TAG_RESYNC => Message::Resync {
+                generation: read_u32(&mut rest)?,
+            },
+            TAG_SNAPSHOT => {
+                let seq = read_u32(&mut rest)?;
+                let len = read_u32(&mut rest)? as usize;
+                Message::Snapshot {
+                    seq,
+                    data: take(&mut rest, len)?.to_vec(),
+                }
+            }
+            TAG_CRDT_EDIT => Message::CrdtEdit(CrdtEdit {
+                stamp: Stamp {
+                    counter: read_u64(&mut rest)?,
+                    replica: read_u32(&mut rest)?,
+                },
+                row: read_u32(&mut rest)?,
+                col: read_u32(&mut rest)?,
+                state: read_cell(&mut rest)?,
+            }),
+            other => return Err(DecodeError::UnknownTag(other)),
+        };
+        *bytes = rest;
+        Ok(message)
+    }
+}
+
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8], DecodeError> {
+    if bytes.len() < len {
+        return Err(DecodeError::Truncated);
+    }
+    let (head, tail) = bytes.split_at(len);
+    *bytes = tail;
+    Ok(head)
+}
+
+fn read_u32(bytes: &mut &[u8]) -> Result<u32, DecodeError> {
+    let mut buf = [0; 4];
+    buf.copy_from_slice(take(bytes, 4)?);
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(bytes: &mut &[u8]) -> Result<u64, DecodeError> {
+    let mut buf = [0; 8];
+    buf.copy_from_slice(take(bytes, 8)?);
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_cell(bytes: &mut &[u8]) -> Result<Cell, DecodeError> {
+    match take(bytes, 1)?[0] {
+        0 => Ok(Cell::Dead),
+        1 => Ok(Cell::Alive),
+        other => Err(DecodeError::InvalidCell(other)),
+    }
+}
+
+impl Universe {
+    /// FNV-1a hash of the dimensions and cells. Unlike `std`'s hasher it is
+    /// stable across builds, so peers running different binaries agree.
+    pub fn checksum(&self) -> u64 {
+        const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+        let mut hash = OFFSET;
+        let mut feed = |byte: u8| {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(PRIME);
+        };
+
+        self.width.to_le_bytes().iter().for_each(|&b| feed(b));
+        self.height.to_le_bytes().iter().for_each(|&b| feed(b));
+        self.cells.iter().for_each(|&cell| feed(cell as u8));
+        hash
+    }
+
+    /// Apply an edit from the sync protocol, ignoring edits outside the
+    /// grid.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(self)))]
+    pub fn apply_edit(&mut self, edit: &Edit) {
+        self.enter("Universe::apply_edit");
+        if edit.row < self.height && edit.col < self.width {
+            let idx = self.get_index(edit.row, edit.col);
+            self.cells[idx] = edit.state;
+        }
+    }
+}
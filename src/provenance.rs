@@ -0,0 +1,131 @@
+// Region provenance, for showing which initial blob conquered which
+// territory.
+//
+// The caller labels the board once, one `u32` per cell. From then on a
+// surviving cell keeps its label and a newborn cell takes the label most of
+// its live parents have, the smaller label winning a tie. Dead cells, and
+// cells brought to life by an edit rather than a birth, have label 0.
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::grid::neighbors;
+use crate::{Boundary, Cell, ConvidaError, Universe};
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Provenance {
+    labels: Vec<u32>,
+}
+
+impl Provenance {
+    // Carry the labels over from `cells` to `next`, one generation later.
+    pub(crate) fn step(
+        &mut self,
+        cells: &[Cell],
+        next: &[Cell],
+        width: u32,
+        height: u32,
+        boundary: Boundary,
+    ) {
+        if self.labels.len() != cells.len() {
+            self.labels = vec![0; cells.len()];
+        }
+        let mut labels = vec![0; next.len()];
+        let mut parents = Vec::with_capacity(8);
+        for (i, &cell) in next.iter().enumerate() {
+            if cell == Cell::Dead {
+                continue;
+            }
+            if cells[i] == Cell::Alive {
+                labels[i] = self.labels[i];
+                continue;
+            }
+            let (row, col) = (i as u32 / width, i as u32 % width);
+            parents.clear();
+            parents.extend(
+                neighbors(row, col, width, height, boundary)
+                    .map(|(r, c)| (r * width + c) as usize)
+                    .filter(|&n| cells[n] == Cell::Alive)
+                    .map(|n| self.labels[n]),
+            );
+            labels[i] = majority(&mut parents);
+        }
+        self.labels = labels;
+    }
+}
+
+// The most common of `labels`, the smallest on a tie.
+fn majority(labels: &mut [u32]) -> u32 {
+    labels.sort_unstable();
+    let mut best = (0, 0);
+    for run in labels.chunk_by(|a, b| a == b) {
+        if run.len() > best.0 {
+            best = (run.len(), run[0]);
+        }
+    }
+    best.1
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Universe {
+    /// Start tracking provenance from `labels`, one per cell in reading
+    /// order. The labels of dead cells are ignored.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = setProvenance))]
+    pub fn set_provenance(&mut self, labels: &[u32]) -> Result<(), ConvidaError> {
+        if labels.len() != self.cells.len() {
+            return Err(ConvidaError::BufferLength {
+                expected: self.cells.len() as u32,
+                actual: labels.len() as u32,
+            });
+        }
+        let labels = labels
+            .iter()
+            .zip(&self.cells)
+            .map(|(&label, &cell)| if cell == Cell::Alive { label } else { 0 })
+            .collect();
+        self.provenance = Some(Provenance { labels });
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = clearProvenance))]
+    pub fn clear_provenance(&mut self) {
+        self.provenance = None;
+    }
+
+    /// The label of each cell, one `u32` per cell like `cells`, or null
+    /// while provenance is off.
+    pub fn provenance(&self) -> *const u32 {
+        match &self.provenance {
+            Some(provenance) => provenance.labels.as_ptr(),
+            None => std::ptr::null(),
+        }
+    }
+
+    /// The number of live cells labelled `label`.
+    pub fn territory(&self, label: u32) -> u32 {
+        self.provenance_labels().map_or(0, |labels| {
+            labels
+                .iter()
+                .zip(&self.cells)
+                .filter(|&(&l, &cell)| l == label && cell == Cell::Alive)
+                .count() as u32
+        })
+    }
+}
+
+impl Universe {
+    /// The label of each cell, while provenance is on.
+    pub fn provenance_labels(&self) -> Option<&[u32]> {
+        self.provenance
+            .as_ref()
+            .map(|provenance| &provenance.labels[..])
+    }
+
+    // Called when the board is resized to `len` cells, whose cells are
+    // edits rather than births.
+    pub(crate) fn resize_provenance(&mut self, len: usize) {
+        if let Some(provenance) = &mut self.provenance {
+            provenance.labels = vec![0; len];
+        }
+    }
+}
@@ -0,0 +1,251 @@
+// Puzzles: reach a target pattern from a given board within a budget.
+//
+// A puzzle is an RLE file whose `#puzzle` lines, which other RLE readers
+// skip as comments, describe the goal:
+//
+//     #N Glider factory
+//     #C Build something that produces a glider in 50 generations.
+//     #puzzle target bo$2bo$3o!
+//     #puzzle generations 50
+//     #puzzle budget 6
+//     #puzzle region 8 8 16 16
+//     x = 32, y = 32, rule = B3/S23
+//     !
+//
+// The player may bring at most `budget` cells to life inside the editable
+// `region` (row, col, width, height; the whole board by default) but not
+// touch the cells of the given board. The puzzle is solved if the target
+// appears, surrounded by dead cells, within `generations` generations.
+
+#[cfg(feature = "web")]
+use serde::Serialize;
+#[cfg(feature = "web")]
+use tsify_next::Tsify;
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::{BoundingBox, Cell, ConvidaError, Event, Rle, Universe};
+
+/// How a run of a puzzle ended.
+#[cfg_attr(feature = "web", derive(Serialize, Tsify))]
+#[cfg_attr(feature = "web", tsify(into_wasm_abi), serde(rename_all = "camelCase"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PuzzleResult {
+    pub solved: bool,
+    /// The generation at which the target first appeared.
+    pub generation: Option<u32>,
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub struct Puzzle {
+    name: String,
+    description: String,
+    target: Rle,
+    generations: u32,
+    budget: u32,
+    region: BoundingBox,
+    /// The given board, which the player can't edit.
+    given: Vec<Cell>,
+    /// The given board plus the player's cells.
+    universe: Universe,
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Puzzle {
+    pub fn parse(text: &str) -> Result<Puzzle, ConvidaError> {
+        let bad = |message: String| ConvidaError::ParseError(message);
+
+        let mut name = String::new();
+        let mut description = Vec::new();
+        let mut target = None;
+        let mut generations = None;
+        let mut budget = None;
+        let mut region = None;
+        for line in text.lines().map(str::trim) {
+            if let Some(rest) = line.strip_prefix("#N") {
+                name = rest.trim().to_string();
+            } else if let Some(rest) = line.strip_prefix("#C") {
+                description.push(rest.trim());
+            } else if let Some(rest) = line.strip_prefix("#puzzle") {
+                let mut parts = rest.split_whitespace();
+                let key = parts.next().unwrap_or("");
+                let values: Vec<&str> = parts.collect();
+                let number = |value: &str| {
+                    value
+                        .parse::<u32>()
+                        .map_err(|_| bad(format!("puzzle {} expects a number, got {}", key, value)))
+                };
+                match (key, &values[..]) {
+                    ("target", [pattern]) => target = Some(Rle::parse(pattern)?),
+                    ("generations", [value]) => generations = Some(number(value)?),
+                    ("budget", [value]) => budget = Some(number(value)?),
+                    ("region", [row, col, width, height]) => {
+                        region = Some(BoundingBox {
+                            row: number(row)?,
+                            col: number(col)?,
+                            width: number(width)?,
+                            height: number(height)?,
+                        })
+                    }
+                    _ => return Err(bad(format!("malformed puzzle line \"{}\"", line))),
+                }
+            }
+        }
+
+        let missing = |key: &str| bad(format!("puzzle has no {}", key));
+        let target = target.ok_or_else(|| missing("target"))?;
+        if target.cells.is_empty() {
+            return Err(missing("target"));
+        }
+        let universe = Rle::parse(text)?.to_universe();
+        if universe.width == 0 || universe.height == 0 {
+            return Err(ConvidaError::EmptySize);
+        }
+        let region = region.unwrap_or(BoundingBox {
+            row: 0,
+            col: 0,
+            width: universe.width,
+            height: universe.height,
+        });
+        if region.width == 0 || region.height == 0 {
+            return Err(ConvidaError::EmptySize);
+        }
+        ConvidaError::check_bounds(
+            region.row.saturating_add(region.height - 1),
+            region.col.saturating_add(region.width - 1),
+            universe.width,
+            universe.height,
+        )?;
+
+        Ok(Puzzle {
+            name,
+            description: description.join("\n"),
+            target,
+            generations: generations.ok_or_else(|| missing("generations"))?,
+            budget: budget.ok_or_else(|| missing("budget"))?,
+            region,
+            given: universe.cells.clone(),
+            universe,
+        })
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(getter))]
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(getter))]
+    pub fn description(&self) -> String {
+        self.description.clone()
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(getter))]
+    pub fn width(&self) -> u32 {
+        self.universe.width
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(getter))]
+    pub fn height(&self) -> u32 {
+        self.universe.height
+    }
+
+    /// The generations within which the target has to appear.
+    #[cfg_attr(feature = "web", wasm_bindgen(getter))]
+    pub fn generations(&self) -> u32 {
+        self.generations
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(getter))]
+    pub fn region(&self) -> BoundingBox {
+        self.region
+    }
+
+    /// The cells the player may still bring to life.
+    #[cfg_attr(feature = "web", wasm_bindgen(getter))]
+    pub fn remaining(&self) -> u32 {
+        self.budget - self.placed()
+    }
+
+    /// The given board plus the player's cells, one byte per cell.
+    pub fn cells(&self) -> *const Cell {
+        self.universe.cells.as_ptr()
+    }
+
+    /// Bring a cell in the editable region to life, or take back one the
+    /// player placed earlier.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = toggleCell))]
+    pub fn toggle_cell(&mut self, row: u32, col: u32) -> Result<(), ConvidaError> {
+        ConvidaError::check_bounds(row, col, self.width(), self.height())?;
+        let idx = self.universe.get_index(row, col);
+        let region = self.region;
+        let editable = (region.row..region.row + region.height).contains(&row)
+            && (region.col..region.col + region.width).contains(&col)
+            && self.given[idx] == Cell::Dead;
+        if !editable {
+            return Err(ConvidaError::NotEditable { row, col });
+        }
+        if self.universe.cells[idx] == Cell::Dead && self.remaining() == 0 {
+            return Err(ConvidaError::BudgetSpent {
+                budget: self.budget,
+            });
+        }
+        self.universe.cells[idx].toggle();
+        Ok(())
+    }
+
+    /// Take back every cell the player placed.
+    pub fn reset(&mut self) {
+        self.universe.cells.clone_from(&self.given);
+    }
+
+    /// Run the board with the player's cells and judge it. The edits are
+    /// kept, so the player can adjust them and run again.
+    pub fn run(&self) -> PuzzleResult {
+        let mut universe = self.universe.clone();
+        let target: Vec<u32> = self
+            .target
+            .cells
+            .iter()
+            .flat_map(|&(row, col)| vec![row, col])
+            .collect();
+        universe.watch_pattern("target", &target);
+        universe.queue_events(true);
+
+        for _ in 0..self.generations {
+            universe.tick();
+            let found = universe
+                .take_events()
+                .into_iter()
+                .find_map(|event| match event {
+                    Event::PatternDetected { generation, .. } => Some(generation),
+                    _ => None,
+                });
+            if found.is_some() {
+                return PuzzleResult {
+                    solved: true,
+                    generation: found,
+                };
+            }
+        }
+        PuzzleResult {
+            solved: false,
+            generation: None,
+        }
+    }
+}
+
+impl Puzzle {
+    /// The number of cells the player has brought to life.
+    pub fn placed(&self) -> u32 {
+        self.universe
+            .cells
+            .iter()
+            .zip(&self.given)
+            .filter(|&(&cell, &given)| cell != given)
+            .count() as u32
+    }
+
+    pub fn universe(&self) -> &Universe {
+        &self.universe
+    }
+}
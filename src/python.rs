@@ -0,0 +1,170 @@
+// Python bindings for the simulation core, built with the `pyo3` feature.
+//
+//     import convida
+//
+//     universe = convida.Universe(128, 128, seed=7)
+//     universe.tick(1000)
+//     print(universe.population(), universe.to_rle())
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use crate::rle::Rle;
+use crate::rng::Rng;
+use crate::rule::parse_rule;
+use crate::{Cell, Universe};
+
+#[pyclass(name = "Universe", unsendable)]
+struct PyUniverse {
+    inner: Universe,
+}
+
+#[pymethods]
+impl PyUniverse {
+    /// A `width` x `height` random soup, reproducible when `seed` is given.
+    #[new]
+    #[pyo3(signature = (width = 64, height = 64, seed = None))]
+    fn new(width: u32, height: u32, seed: Option<u64>) -> PyUniverse {
+        let mut rng = match seed {
+            Some(seed) => Rng::new(seed),
+            None => Rng::from_entropy(),
+        };
+        let cells = crate::random((width * height) as usize, 0.5, &mut rng);
+        PyUniverse {
+            inner: Universe::from_cells(width, height, cells),
+        }
+    }
+
+    /// A universe exactly the size of an RLE pattern.
+    #[staticmethod]
+    fn from_rle(text: &str) -> PyResult<PyUniverse> {
+        let rle = Rle::parse(text).map_err(|err| PyValueError::new_err(err.to_string()))?;
+        Ok(PyUniverse {
+            inner: rle.to_universe(),
+        })
+    }
+
+    #[staticmethod]
+    fn from_snapshot(bytes: &[u8]) -> PyResult<PyUniverse> {
+        Universe::decode_snapshot(bytes)
+            .map(|inner| PyUniverse { inner })
+            .map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+
+    #[getter]
+    fn width(&self) -> u32 {
+        self.inner.width()
+    }
+
+    #[getter]
+    fn height(&self) -> u32 {
+        self.inner.height()
+    }
+
+    #[getter]
+    fn generation(&self) -> u32 {
+        self.inner.generation()
+    }
+
+    /// The rule the universe evolves under, in B/S notation.
+    #[getter]
+    fn rule(&self) -> String {
+        self.inner.rule()
+    }
+
+    #[setter]
+    fn set_rule(&mut self, rule: &str) -> PyResult<()> {
+        let rule = parse_rule(rule).map_err(|err| PyValueError::new_err(err.to_string()))?;
+        self.inner.set_rule(rule);
+        Ok(())
+    }
+
+    #[pyo3(signature = (generations = 1))]
+    fn tick(&mut self, py: Python, generations: u32) {
+        let inner = &mut self.inner;
+        py.allow_threads(|| {
+            for _ in 0..generations {
+                inner.tick();
+            }
+        });
+    }
+
+    fn population(&self) -> usize {
+        self.inner
+            .get_cells()
+            .iter()
+            .filter(|&&cell| cell == Cell::Alive)
+            .count()
+    }
+
+    fn get(&self, row: u32, col: u32) -> PyResult<bool> {
+        self.check(row, col)?;
+        Ok(self.inner.cells[self.inner.get_index(row, col)] == Cell::Alive)
+    }
+
+    fn set(&mut self, row: u32, col: u32, alive: bool) -> PyResult<()> {
+        self.check(row, col)?;
+        let idx = self.inner.get_index(row, col);
+        self.inner.cells[idx] = if alive { Cell::Alive } else { Cell::Dead };
+        Ok(())
+    }
+
+    /// One byte per cell, 1 for alive, in row-major order. Wrap it with
+    /// `numpy.frombuffer(...).reshape(height, width)` for array work.
+    fn cells<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        let bytes: Vec<u8> = self.inner.cells.iter().map(|&cell| cell as u8).collect();
+        PyBytes::new(py, &bytes)
+    }
+
+    /// Bring an RLE pattern to life with its top-left corner at
+    /// (`row`, `col`).
+    #[pyo3(signature = (text, row = 0, col = 0))]
+    fn stamp_rle(&mut self, text: &str, row: u32, col: u32) -> PyResult<()> {
+        let rle = Rle::parse(text).map_err(|err| PyValueError::new_err(err.to_string()))?;
+        rle.place(&mut self.inner, row, col);
+        Ok(())
+    }
+
+    fn to_rle(&self) -> String {
+        self.inner.to_rle()
+    }
+
+    fn to_snapshot<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.inner.encode_snapshot())
+    }
+
+    fn __str__(&self) -> String {
+        self.inner.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Universe(width={}, height={}, generation={})",
+            self.inner.width(),
+            self.inner.height(),
+            self.inner.generation()
+        )
+    }
+}
+
+impl PyUniverse {
+    fn check(&self, row: u32, col: u32) -> PyResult<()> {
+        if row < self.inner.height() && col < self.inner.width() {
+            Ok(())
+        } else {
+            Err(PyValueError::new_err(format!(
+                "cell ({}, {}) is outside the {}x{} universe",
+                row,
+                col,
+                self.inner.width(),
+                self.inner.height()
+            )))
+        }
+    }
+}
+
+#[pymodule]
+fn convida(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyUniverse>()
+}
@@ -0,0 +1,422 @@
+// RGBA renderer that draws the universe into a pixel buffer which
+// JavaScript can copy straight into an `ImageData`.
+
+use crate::camera::Camera;
+use crate::grid::Grid;
+use crate::{encode_png, Cell, ConvidaError, Universe};
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+#[cfg(feature = "web")]
+use web_sys::{Blob, BlobPropertyBag};
+
+const ALIVE_COLOR: [u8; 4] = [0xFF, 0xFF, 0xFF, 0xFF];
+const DEAD_COLOR: [u8; 4] = [0x00, 0x00, 0x00, 0xFF];
+const WALL_COLOR: [u8; 4] = [0x80, 0x60, 0x40, 0xFF];
+
+// The length of a `width` x `height` RGBA buffer, unless it can't be
+// addressed.
+fn rgba_len(width: u32, height: u32) -> Result<usize, ConvidaError> {
+    (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(4))
+        .ok_or(ConvidaError::SizeOverflow { width, height })
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub struct Renderer {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    camera: Camera,
+    alive_color: [u8; 4],
+    dead_color: [u8; 4],
+    trails: u8,
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Renderer {
+    /// Create a renderer for a viewport of `width` x `height` pixels, or
+    /// fail with `SizeOverflow` when its pixels wouldn't fit in memory.
+    pub fn new(width: u32, height: u32) -> Result<Renderer, ConvidaError> {
+        Ok(Renderer {
+            width,
+            height,
+            pixels: vec![0; rgba_len(width, height)?],
+            camera: Camera::default(),
+            alive_color: ALIVE_COLOR,
+            dead_color: DEAD_COLOR,
+            trails: 0,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Resize the viewport. The camera is left untouched, and so is the
+    /// viewport when the new one fails with `SizeOverflow`.
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<(), ConvidaError> {
+        self.pixels = vec![0; rgba_len(width, height)?];
+        self.width = width;
+        self.height = height;
+        Ok(())
+    }
+
+    pub fn camera(&self) -> Camera {
+        self.camera
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = setCamera))]
+    pub fn set_camera(&mut self, camera: Camera) {
+        self.camera = camera;
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = zoomAt))]
+    pub fn zoom_at(&mut self, x: f64, y: f64, factor: f64) {
+        self.camera.zoom_at(x, y, factor);
+    }
+
+    pub fn pan(&mut self, dx: f64, dy: f64) {
+        self.camera.pan(dx, dy);
+    }
+
+    /// Set the colors as packed `0xRRGGBB` values.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = setColors))]
+    pub fn set_colors(&mut self, alive: u32, dead: u32) {
+        self.alive_color = unpack_color(alive);
+        self.dead_color = unpack_color(dead);
+    }
+
+    /// Fade cells that died in the last `generations` generations from the
+    /// alive color to the dead one, for universes with trails enabled.
+    /// 0 turns trails off.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = setTrails))]
+    pub fn set_trails(&mut self, generations: u8) {
+        self.trails = generations.min(u8::MAX - 1);
+    }
+
+    pub fn pixels(&self) -> *const u8 {
+        self.pixels.as_ptr()
+    }
+
+    /// The last rendered frame as a PNG, framed by the camera as it was
+    /// drawn.
+    pub fn screenshot(&self) -> Vec<u8> {
+        encode_png(self.width, self.height, &self.pixels)
+    }
+
+    /// Draw the part of `universe` visible through the camera.
+    ///
+    /// Only the cells inside the visible range are visited, so the cost
+    /// depends on the zoom level rather than the size of the universe.
+    /// When cells are smaller than a pixel, each pixel is shaded by the
+    /// density of the cells it covers instead. Walls, and trails when set,
+    /// are drawn while cells are at least a pixel wide.
+    pub fn render(&mut self, universe: &Universe) {
+        self.render_grid(universe);
+        if self.camera.scale() < 1.0 {
+            return;
+        }
+
+        let trails = if universe.trail_ages().is_null() {
+            0
+        } else {
+            self.trails
+        };
+        let walls = !universe.wall_mask().is_null();
+        if trails == 0 && !walls {
+            return;
+        }
+        let range =
+            self.camera
+                .visible_range(self.width, self.height, universe.width(), universe.height());
+        for row in range.row_start..range.row_end {
+            for col in range.col_start..range.col_end {
+                if walls && universe.is_wall(row, col) {
+                    self.fill_cell(row, col, WALL_COLOR);
+                    continue;
+                }
+                match universe.trail_age(row, col) {
+                    Some(age) if age > 0 && age <= trails => {
+                        let fade = 1.0 - f64::from(age) / f64::from(trails + 1);
+                        let color = blend(self.dead_color, self.alive_color, fade);
+                        self.fill_cell(row, col, color);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// `render` part of the way from the previous generation to the current
+    /// one, for universes with interpolation enabled: at `alpha` 0 cells
+    /// born in the last tick are still dead and cells that died still
+    /// alive, and at 1 the frame is the same as `render`'s.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = renderInterpolated))]
+    pub fn render_interpolated(&mut self, universe: &Universe, alpha: f64) {
+        self.render(universe);
+        if self.camera.scale() < 1.0 || universe.previous_cells().is_null() {
+            return;
+        }
+
+        let alpha = alpha.clamp(0.0, 1.0);
+        let range =
+            self.camera
+                .visible_range(self.width, self.height, universe.width(), universe.height());
+        for row in range.row_start..range.row_end {
+            for col in range.col_start..range.col_end {
+                let (previous, cell) = match universe.previous_cell(row, col) {
+                    Some(previous) => (previous, universe.cells[universe.get_index(row, col)]),
+                    None => return,
+                };
+                if previous == cell || universe.is_wall(row, col) {
+                    continue;
+                }
+                let color = match cell {
+                    Cell::Alive => blend(self.dead_color, self.alive_color, alpha),
+                    Cell::Dead => blend(self.alive_color, self.dead_color, alpha),
+                };
+                self.fill_cell(row, col, color);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "web")]
+#[wasm_bindgen]
+impl Renderer {
+    /// `screenshot` as an `image/png` `Blob`, ready for a download link.
+    #[wasm_bindgen(js_name = screenshotBlob)]
+    pub fn screenshot_blob(&self) -> Result<Blob, JsValue> {
+        png_blob(&self.screenshot())
+    }
+}
+
+/// `png` as an `image/png` `Blob`.
+#[cfg(feature = "web")]
+fn png_blob(png: &[u8]) -> Result<Blob, JsValue> {
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(png));
+    let options = BlobPropertyBag::new();
+    options.set_type("image/png");
+    Blob::new_with_u8_array_sequence_and_options(&parts, &options)
+}
+
+impl Renderer {
+    pub fn get_pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// `render` for any engine's grid.
+    pub fn render_grid(&mut self, grid: &dyn Grid) {
+        let dead = self.dead_color;
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&dead);
+        }
+
+        if self.camera.scale() < 1.0 {
+            self.render_downsampled(grid);
+            return;
+        }
+
+        let (width, height) = grid.dims();
+        let range = self
+            .camera
+            .visible_range(self.width, self.height, width, height);
+
+        for (row, col) in grid.iter_region(
+            range.row_start..range.row_end,
+            range.col_start..range.col_end,
+        ) {
+            self.fill_cell(row, col, self.alive_color);
+        }
+    }
+
+    // Level-of-detail path: one density sample per pixel.
+    fn render_downsampled(&mut self, grid: &dyn Grid) {
+        let (width, height) = grid.dims();
+        let scale = self.camera.scale();
+        let cell_at = |offset: f64, px: u32, max: u32| {
+            let cell = (offset + px as f64 / scale).floor();
+            cell.clamp(0.0, max as f64) as u32
+        };
+
+        for py in 0..self.height {
+            let row_start = cell_at(self.camera.offset_y(), py, height);
+            let row_end = cell_at(self.camera.offset_y(), py + 1, height);
+            if row_start >= row_end {
+                continue;
+            }
+
+            for px in 0..self.width {
+                let col_start = cell_at(self.camera.offset_x(), px, width);
+                let col_end = cell_at(self.camera.offset_x(), px + 1, width);
+                if col_start >= col_end {
+                    continue;
+                }
+
+                let density = block_density(grid, row_start, row_end, col_start, col_end);
+                let idx = ((py * self.width + px) * 4) as usize;
+                self.pixels[idx..idx + 4].copy_from_slice(&blend(
+                    self.dead_color,
+                    self.alive_color,
+                    density,
+                ));
+            }
+        }
+    }
+
+    /// Draw a one pixel frame around a cell, e.g. to show a cursor.
+    #[cfg(feature = "web")]
+    pub(crate) fn outline_cell(&mut self, row: u32, col: u32, color: [u8; 4]) {
+        let (x, y) = self.camera.cell_to_screen(row, col);
+        let size = self.camera.scale().max(1.0);
+        let (x0, y0) = (x.floor() as i64, y.floor() as i64);
+        let (x1, y1) = ((x + size).ceil() as i64 - 1, (y + size).ceil() as i64 - 1);
+
+        for py in y0..=y1 {
+            for px in x0..=x1 {
+                let edge = py == y0 || py == y1 || px == x0 || px == x1;
+                if !edge || px < 0 || py < 0 || px >= self.width as i64 || py >= self.height as i64
+                {
+                    continue;
+                }
+                let idx = ((py as u32 * self.width + px as u32) * 4) as usize;
+                self.pixels[idx..idx + 4].copy_from_slice(&color);
+            }
+        }
+    }
+
+    // Fill the on-screen square of a cell, clipped to the viewport.
+    fn fill_cell(&mut self, row: u32, col: u32, color: [u8; 4]) {
+        let (x, y) = self.camera.cell_to_screen(row, col);
+        let size = self.camera.scale();
+
+        let x0 = (x.floor().max(0.0) as u32).min(self.width);
+        let y0 = (y.floor().max(0.0) as u32).min(self.height);
+        let x1 = ((x + size).ceil().max(0.0) as u32).min(self.width);
+        let y1 = ((y + size).ceil().max(0.0) as u32).min(self.height);
+        if x0 >= x1 {
+            return;
+        }
+
+        for py in y0..y1 {
+            let start = ((py * self.width + x0) * 4) as usize;
+            let end = ((py * self.width + x1) * 4) as usize;
+            for pixel in self.pixels[start..end].chunks_exact_mut(4) {
+                pixel.copy_from_slice(&color);
+            }
+        }
+    }
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Universe {
+    /// Render the whole universe as an RGBA image with `cell_size` pixels
+    /// per cell, without needing a canvas. The image is
+    /// `width * cell_size` pixels wide and `height * cell_size` tall.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = renderRgba))]
+    pub fn render_rgba(&self, cell_size: u32) -> Vec<u8> {
+        render_grid_rgba(self, cell_size)
+    }
+
+    /// The whole universe as a PNG with `cell_size` pixels per cell.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = toPng))]
+    pub fn to_png(&self, cell_size: u32) -> Vec<u8> {
+        let cell_size = cell_size.max(1);
+        let rgba = render_grid_rgba(self, cell_size);
+        encode_png(self.width() * cell_size, self.height() * cell_size, &rgba)
+    }
+
+    /// Render a `target_width` x `target_height` RGBA thumbnail of the
+    /// whole universe.
+    ///
+    /// Each pixel covers a block of cells and is shaded by the fraction of
+    /// live cells in that block, so an overview map stays readable even
+    /// when the universe is much larger than the thumbnail. Fails with
+    /// `SizeOverflow` when the thumbnail wouldn't fit in memory.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = renderMinimap))]
+    pub fn render_minimap(
+        &self,
+        target_width: u32,
+        target_height: u32,
+    ) -> Result<Vec<u8>, ConvidaError> {
+        let mut pixels = Vec::with_capacity(rgba_len(target_width, target_height)?);
+
+        for ty in 0..target_height {
+            let (row_start, row_end) = block_bounds(ty, target_height, self.height);
+            for tx in 0..target_width {
+                let (col_start, col_end) = block_bounds(tx, target_width, self.width);
+                let density = block_density(self, row_start, row_end, col_start, col_end);
+                pixels.extend_from_slice(&blend(DEAD_COLOR, ALIVE_COLOR, density));
+            }
+        }
+
+        Ok(pixels)
+    }
+}
+
+/// `Universe::render_rgba` for any engine's grid.
+pub fn render_grid_rgba(grid: &dyn Grid, cell_size: u32) -> Vec<u8> {
+    let cell_size = cell_size.max(1);
+    let (width, height) = grid.dims();
+    let image_width = width * cell_size;
+    let mut pixels = DEAD_COLOR.repeat((image_width * height * cell_size) as usize);
+
+    for (row, col) in grid.iter_region(0..height, 0..width) {
+        for y in row * cell_size..(row + 1) * cell_size {
+            let start = ((y * image_width + col * cell_size) * 4) as usize;
+            let end = start + (cell_size * 4) as usize;
+            for pixel in pixels[start..end].chunks_exact_mut(4) {
+                pixel.copy_from_slice(&ALIVE_COLOR);
+            }
+        }
+    }
+
+    pixels
+}
+
+// The range of cells covered by pixel `i` when `cells` cells are squeezed
+// into `pixels` pixels. Always covers at least one cell.
+fn block_bounds(i: u32, pixels: u32, cells: u32) -> (u32, u32) {
+    let start = (u64::from(i) * u64::from(cells) / u64::from(pixels)) as u32;
+    let end = (u64::from(i + 1) * u64::from(cells) / u64::from(pixels)) as u32;
+    (
+        start.min(cells.saturating_sub(1)),
+        end.max(start + 1).min(cells),
+    )
+}
+
+// The fraction of live cells in the given block.
+fn block_density(
+    grid: &dyn Grid,
+    row_start: u32,
+    row_end: u32,
+    col_start: u32,
+    col_end: u32,
+) -> f64 {
+    if row_start >= row_end || col_start >= col_end {
+        return 0.0;
+    }
+
+    let alive = grid.count_region(row_start..row_end, col_start..col_end);
+    let total = (row_end - row_start) as usize * (col_end - col_start) as usize;
+    alive as f64 / total as f64
+}
+
+fn blend(from: [u8; 4], to: [u8; 4], t: f64) -> [u8; 4] {
+    let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+    [
+        mix(from[0], to[0]),
+        mix(from[1], to[1]),
+        mix(from[2], to[2]),
+        mix(from[3], to[3]),
+    ]
+}
+
+fn unpack_color(rgb: u32) -> [u8; 4] {
+    [(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 0xFF]
+}
@@ -0,0 +1,246 @@
+// Run Length Encoded patterns, the format used by most pattern collections
+// and by Golly.
+//
+//     #N Glider
+//     x = 3, y = 3, rule = B3/S23
+//     bob$2bo$3o!
+//
+// `b` is a dead cell, `o` a live one and `$` ends a row. Each may be
+// preceded by a repeat count, and `!` ends the pattern.
+
+use std::fmt;
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::bump;
+use crate::grid::Grid;
+#[cfg(feature = "web")]
+use crate::ConvidaError;
+use crate::{Cell, LifeLike, Universe};
+
+// Golly and most other writers keep lines at most this long.
+const LINE_LENGTH: usize = 70;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RleError {
+    BadHeader(String),
+    UnsupportedRule(String),
+    UnexpectedChar(char),
+}
+
+impl fmt::Display for RleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RleError::BadHeader(line) => write!(f, "malformed RLE header \"{}\"", line),
+            RleError::UnsupportedRule(rule) => write!(f, "unsupported rule {}", rule),
+            RleError::UnexpectedChar(c) => write!(f, "unexpected character '{}' in RLE", c),
+        }
+    }
+}
+
+/// A pattern as live cells relative to its top-left corner.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Rle {
+    pub width: u32,
+    pub height: u32,
+    /// (row, col) pairs in reading order.
+    pub cells: Vec<(u32, u32)>,
+    pub rule: LifeLike,
+}
+
+impl Rle {
+    pub fn parse(text: &str) -> Result<Rle, RleError> {
+        let mut rle = Rle::default();
+        let cells = bump::collect_cells(|cells| {
+            let (mut row, mut col) = (0u32, 0u32);
+            let mut count: Option<u32> = None;
+
+            'lines: for line in text.lines() {
+                let line = line.trim();
+                if line.starts_with('#') || line.is_empty() {
+                    continue;
+                }
+                if line.starts_with('x') {
+                    parse_header(line, &mut rle)?;
+                    continue;
+                }
+
+                for c in line.chars() {
+                    if let Some(digit) = c.to_digit(10) {
+                        count = Some(count.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+                        continue;
+                    }
+                    if c.is_whitespace() {
+                        continue;
+                    }
+
+                    let run = count.take().unwrap_or(1);
+                    match c {
+                        'b' | '.' => col = col.saturating_add(run),
+                        'o' => {
+                            for i in 0..run {
+                                cells.push((row, col.saturating_add(i)));
+                            }
+                            col = col.saturating_add(run);
+                            rle.width = rle.width.max(col);
+                            rle.height = rle.height.max(row + 1);
+                        }
+                        '$' => {
+                            row = row.saturating_add(run);
+                            col = 0;
+                        }
+                        '!' => break 'lines,
+                        c => return Err(RleError::UnexpectedChar(c)),
+                    }
+                }
+            }
+            Ok(())
+        })?;
+        rle.cells = cells;
+
+        Ok(rle)
+    }
+
+    pub fn from_universe(universe: &Universe) -> Rle {
+        Rle {
+            rule: universe.rule,
+            ..Rle::from_grid(universe)
+        }
+    }
+
+    /// The live cells of any engine's grid, under the default rule.
+    pub fn from_grid(grid: &dyn Grid) -> Rle {
+        let (width, height) = grid.dims();
+        Rle {
+            width,
+            height,
+            cells: grid.iter_region(0..height, 0..width).collect(),
+            rule: LifeLike::default(),
+        }
+    }
+
+    /// A universe exactly the size of the pattern.
+    pub fn to_universe(&self) -> Universe {
+        let mut universe = Universe::from_cells(
+            self.width,
+            self.height,
+            vec![Cell::Dead; (self.width * self.height) as usize],
+        );
+        universe.rule = self.rule;
+        self.place(&mut universe, 0, 0);
+        universe
+    }
+
+    /// Bring the pattern to life in `universe` with its top-left corner at
+    /// (`row`, `col`), wrapping around the edges.
+    pub fn place(&self, universe: &mut Universe, row: u32, col: u32) {
+        self.place_grid(universe, row, col);
+    }
+
+    /// `place` for any engine's grid.
+    pub fn place_grid(&self, grid: &mut dyn Grid, row: u32, col: u32) {
+        let (width, height) = grid.dims();
+        if width == 0 || height == 0 {
+            return;
+        }
+        let wrap = |start: u32, offset: u32, len: u32| {
+            ((u64::from(start) + u64::from(offset)) % u64::from(len)) as u32
+        };
+        for &(r, c) in &self.cells {
+            grid.set(wrap(row, r, height), wrap(col, c, width), Cell::Alive);
+        }
+    }
+}
+
+impl fmt::Display for Rle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "x = {}, y = {}, rule = {}",
+            self.width, self.height, self.rule
+        )?;
+
+        let mut grid = vec![false; (self.width * self.height) as usize];
+        for &(row, col) in &self.cells {
+            grid[(row * self.width + col) as usize] = true;
+        }
+
+        let mut tokens = Vec::new();
+        let mut rows_ended = 0;
+        for line in grid.chunks(self.width.max(1) as usize) {
+            if let Some(last) = line.iter().rposition(|&alive| alive) {
+                if rows_ended > 0 {
+                    tokens.push((rows_ended, '$'));
+                    rows_ended = 0;
+                }
+                let mut i = 0;
+                while i <= last {
+                    let alive = line[i];
+                    let run = line[i..=last].iter().take_while(|&&a| a == alive).count();
+                    tokens.push((run as u32, if alive { 'o' } else { 'b' }));
+                    i += run;
+                }
+            }
+            rows_ended += 1;
+        }
+        tokens.push((1, '!'));
+
+        let mut length = 0;
+        for (run, tag) in tokens {
+            let token = if run == 1 {
+                tag.to_string()
+            } else {
+                format!("{}{}", run, tag)
+            };
+            if length + token.len() > LINE_LENGTH {
+                writeln!(f)?;
+                length = 0;
+            }
+            length += token.len();
+            write!(f, "{}", token)?;
+        }
+        writeln!(f)
+    }
+}
+
+fn parse_header(line: &str, rle: &mut Rle) -> Result<(), RleError> {
+    let bad = || RleError::BadHeader(line.to_string());
+
+    for field in line.split(',') {
+        let mut parts = field.splitn(2, '=');
+        let key = parts.next().ok_or_else(bad)?.trim();
+        let value = parts.next().ok_or_else(bad)?.trim();
+        match key {
+            "x" => rle.width = value.parse().map_err(|_| bad())?,
+            "y" => rle.height = value.parse().map_err(|_| bad())?,
+            "rule" => {
+                rle.rule = value
+                    .parse()
+                    .map_err(|_| RleError::UnsupportedRule(value.to_string()))?
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Universe {
+    /// The whole board as an RLE pattern.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = toRle))]
+    pub fn to_rle(&self) -> String {
+        Rle::from_universe(self).to_string()
+    }
+}
+
+#[cfg(feature = "web")]
+#[wasm_bindgen]
+impl Universe {
+    /// Build a universe the size of an RLE pattern.
+    #[wasm_bindgen(js_name = fromRle)]
+    pub fn from_rle(text: &str) -> Result<Universe, ConvidaError> {
+        Ok(Rle::parse(text)?.to_universe())
+    }
+}
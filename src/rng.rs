@@ -0,0 +1,81 @@
+// Small, fast pseudo-random number generator (xorshift64*).
+//
+// Not suitable for anything security related, but deterministic for a given
+// seed and free of platform dependencies, so the same seed produces the
+// same soup in the browser and in native builds.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // Scramble the seed so that small seeds like 0, 1, 2 still give
+        // unrelated sequences. The state must never be zero.
+        let mut state = splitmix64(seed);
+        if state == 0 {
+            state = 0x9e37_79b9_7f4a_7c15;
+        }
+        Rng { state }
+    }
+
+    /// A generator seeded from whatever entropy the platform offers.
+    pub fn from_entropy() -> Rng {
+        Rng::new(entropy())
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// A float uniformly distributed in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+fn splitmix64(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+// Mixed into every entropy seed so generators created in quick succession
+// still differ.
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+fn entropy() -> u64 {
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    splitmix64(platform_entropy() ^ count.wrapping_mul(0x9e37_79b9_7f4a_7c15))
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(feature = "web", target_arch = "wasm32", target_os = "unknown"))] {
+        fn platform_entropy() -> u64 {
+            let high = (js_sys::Math::random() * u32::MAX as f64) as u64;
+            let low = (js_sys::Math::random() * u32::MAX as f64) as u64;
+            (high << 32) | low
+        }
+    } else if #[cfg(all(target_arch = "wasm32", target_os = "unknown"))] {
+        // Bare wasm has no clock or entropy source to ask.
+        fn platform_entropy() -> u64 {
+            0
+        }
+    } else {
+        fn platform_entropy() -> u64 {
+            use std::time::{SystemTime, UNIX_EPOCH};
+
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_nanos() as u64)
+                .unwrap_or(0)
+        }
+    }
+}
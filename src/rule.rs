@@ -0,0 +1,32 @@
+// Rules deciding the next state of a cell from its neighbourhood.
+//
+// `Rule`, `Conway` and `LifeLike` live in the `convida-engine` crate.
+// `Universe::step` runs a generation under any `Rule` and is monomorphised
+// for each, so custom rules written in Rust cost no more than the built-in
+// ones. A universe's own rule is a `LifeLike` rule in B/S notation, with
+// Conway's Life taking a dedicated fast path.
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::error::ConvidaError;
+use crate::LifeLike;
+#[cfg(feature = "web")]
+use crate::Universe;
+
+/// Parse a rule in B/S or S/B notation, keeping the text for the error.
+pub(crate) fn parse_rule(text: &str) -> Result<LifeLike, ConvidaError> {
+    text.parse()
+        .map_err(|_| ConvidaError::UnsupportedRule(text.to_string()))
+}
+
+#[cfg(feature = "web")]
+#[wasm_bindgen]
+impl Universe {
+    /// Switch to a rule in B/S notation, such as `B36/S23`.
+    #[wasm_bindgen(js_name = setRule)]
+    pub fn set_rule_js(&mut self, rule: &str) -> Result<(), ConvidaError> {
+        self.set_rule(parse_rule(rule)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,195 @@
+// Finding small patterns on the board.
+//
+// A pattern matches where its live cells are alive and every other cell of
+// its bounding box, and of the one-cell border around it, is dead. The
+// border check keeps a glider inside a larger blob from counting as a
+// glider.
+//
+// Phase-tolerant searches also match every phase the pattern goes through
+// under the universe's rule, so a blinker is found standing or lying down.
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "web")]
+use crate::ConvidaError;
+use crate::{Boundary, Cell, LifeLike, Rle, Universe};
+
+// The longest period looked for when collecting the phases of a pattern.
+const MAX_PERIOD: u32 = 15;
+
+/// The top-left corners of every occurrence of `cells`, live cell offsets
+/// from the pattern's top-left corner, in reading order.
+pub(crate) fn occurrences<'a>(
+    universe: &'a Universe,
+    cells: &'a [(u32, u32)],
+) -> impl Iterator<Item = (u32, u32)> + 'a {
+    let height = cells.iter().map(|&(row, _)| row + 1).max().unwrap_or(0);
+    let width = cells.iter().map(|&(_, col)| col + 1).max().unwrap_or(0);
+    let fits = !cells.is_empty() && height <= universe.height && width <= universe.width;
+    let (rows, cols) = if fits {
+        (universe.height - height + 1, universe.width - width + 1)
+    } else {
+        (0, 0)
+    };
+
+    let (board_width, board_height) = (universe.width as i64, universe.height as i64);
+    let alive = move |row: i64, col: i64| {
+        let row = row.rem_euclid(board_height) as u32;
+        let col = col.rem_euclid(board_width) as u32;
+        universe.cells[universe.get_index(row, col)] == Cell::Alive
+    };
+    let matches = move |top: u32, left: u32| {
+        (-1..=height as i64).all(|dr| {
+            (-1..=width as i64).all(|dc| {
+                let expected = dr >= 0 && dc >= 0 && cells.contains(&(dr as u32, dc as u32));
+                alive(top as i64 + dr, left as i64 + dc) == expected
+            })
+        })
+    };
+
+    (0..rows)
+        .flat_map(move |top| (0..cols).map(move |left| (top, left)))
+        .filter(move |&(top, left)| matches(top, left))
+}
+
+/// `cells` in each of the eight orientations given by rotating and
+/// reflecting it, without repeats.
+pub(crate) fn orientations(cells: &[(u32, u32)]) -> Vec<Vec<(u32, u32)>> {
+    let height = cells.iter().map(|&(row, _)| row).max().unwrap_or(0);
+    let width = cells.iter().map(|&(_, col)| col).max().unwrap_or(0);
+    let mut shapes: Vec<Vec<(u32, u32)>> = (0..8)
+        .map(|orientation| {
+            let oriented = cells.iter().map(|&(row, col)| {
+                let (row, col) = match orientation & 3 {
+                    0 => (row, col),
+                    1 => (row, width - col),
+                    2 => (height - row, col),
+                    _ => (height - row, width - col),
+                };
+                if orientation & 4 == 0 {
+                    (row, col)
+                } else {
+                    (col, row)
+                }
+            });
+            normalized(oriented.collect())
+        })
+        .collect();
+    shapes.sort();
+    shapes.dedup();
+    shapes
+}
+
+/// The live cells of each phase `pattern` goes through under `rule`,
+/// normalized, until it repeats or dies out.
+pub(crate) fn phases(pattern: &Rle, rule: LifeLike) -> Vec<Vec<(u32, u32)>> {
+    let pad = MAX_PERIOD / 4 + 2;
+    let (width, height) = (pattern.width + 2 * pad, pattern.height + 2 * pad);
+    let mut universe =
+        Universe::from_cells(width, height, vec![Cell::Dead; (width * height) as usize]);
+    universe.boundary = Boundary::Dead;
+    universe.rule = rule;
+    pattern.place(&mut universe, pad, pad);
+
+    let mut phases: Vec<Vec<(u32, u32)>> = Vec::new();
+    for _ in 0..MAX_PERIOD {
+        let phase = normalized(universe.iter_live().collect());
+        if phases.first() == Some(&phase) || phase.is_empty() {
+            break;
+        }
+        phases.push(phase);
+        universe.tick();
+    }
+    phases
+}
+
+/// Sorted cells moved so that the topmost and leftmost are at 0.
+pub(crate) fn normalized(mut cells: Vec<(u32, u32)>) -> Vec<(u32, u32)> {
+    let top = cells.iter().map(|&(row, _)| row).min().unwrap_or(0);
+    let left = cells.iter().map(|&(_, col)| col).min().unwrap_or(0);
+    for cell in &mut cells {
+        *cell = (cell.0 - top, cell.1 - left);
+    }
+    cells.sort_unstable();
+    cells
+}
+
+impl Universe {
+    /// The top-left corner of the live cells of every occurrence of
+    /// `pattern`, surrounded by dead cells, in reading order. With
+    /// `include_rotations` the pattern also matches rotated and reflected.
+    pub fn find_pattern(&self, pattern: &Rle, include_rotations: bool) -> Vec<(u32, u32)> {
+        let shapes = if include_rotations {
+            orientations(&pattern.cells)
+        } else {
+            vec![normalized(pattern.cells.clone())]
+        };
+        self.find_shapes(shapes)
+    }
+
+    /// `find_pattern`, also matching the pattern in any of the phases it
+    /// goes through under this universe's rule.
+    pub fn find_pattern_any_phase(
+        &self,
+        pattern: &Rle,
+        include_rotations: bool,
+    ) -> Vec<(u32, u32)> {
+        let phases = phases(pattern, self.rule);
+        let shapes = if include_rotations {
+            phases
+                .iter()
+                .flat_map(|phase| orientations(phase))
+                .collect()
+        } else {
+            phases
+        };
+        self.find_shapes(shapes)
+    }
+
+    fn find_shapes(&self, mut shapes: Vec<Vec<(u32, u32)>>) -> Vec<(u32, u32)> {
+        shapes.sort();
+        shapes.dedup();
+        let mut found: Vec<(u32, u32)> = shapes
+            .iter()
+            .flat_map(|shape| occurrences(self, shape))
+            .collect();
+        found.sort_unstable();
+        found
+    }
+}
+
+#[cfg(feature = "web")]
+#[wasm_bindgen]
+impl Universe {
+    /// `find_pattern` for an RLE pattern, as flat `[row, col, ...]` pairs.
+    #[wasm_bindgen(js_name = findPattern)]
+    pub fn find_pattern_js(
+        &self,
+        rle: &str,
+        include_rotations: bool,
+    ) -> Result<Vec<u32>, ConvidaError> {
+        let pattern = Rle::parse(rle)?;
+        Ok(self
+            .find_pattern(&pattern, include_rotations)
+            .into_iter()
+            .flat_map(|(row, col)| vec![row, col])
+            .collect())
+    }
+
+    /// `find_pattern_any_phase` for an RLE pattern, as flat
+    /// `[row, col, ...]` pairs.
+    #[wasm_bindgen(js_name = findPatternAnyPhase)]
+    pub fn find_pattern_any_phase_js(
+        &self,
+        rle: &str,
+        include_rotations: bool,
+    ) -> Result<Vec<u32>, ConvidaError> {
+        let pattern = Rle::parse(rle)?;
+        Ok(self
+            .find_pattern_any_phase(&pattern, include_rotations)
+            .into_iter()
+            .flat_map(|(row, col)| vec![row, col])
+            .collect())
+    }
+}
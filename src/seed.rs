@@ -0,0 +1,192 @@
+// Initial contents of a universe.
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::builder::UniverseBuilder;
+use crate::error::ConvidaError;
+use crate::rle::Rle;
+use crate::rng::Rng;
+use crate::{Cell, Universe};
+
+// Patterns for `Seed::Pattern`, placed in the centre of the universe.
+const PATTERNS: &[(&str, &str)] = &[
+    ("blinker", "3o!"),
+    ("glider", "bo$2bo$3o!"),
+    ("lwss", "bo2bo$o$o3bo$4o!"),
+    ("r-pentomino", "b2o$2o$bo!"),
+    ("acorn", "bo$3bo$2o2b3o!"),
+    ("diehard", "6bo$2o$bo3b3o!"),
+    (
+        "pulsar",
+        "2b3o3b3o2$o4bobo4bo$o4bobo4bo$o4bobo4bo$2b3o3b3o2$2b3o3b3o$o4bobo4bo$\
+         o4bobo4bo$o4bobo4bo2$2b3o3b3o!",
+    ),
+    (
+        "gosper-gun",
+        "24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4b\
+         obo$10bo5bo7bo$11bo3bo$12b2o!",
+    ),
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Seed {
+    /// Every cell dead.
+    Empty,
+    /// Each cell alive with probability `density`, from 0 to 1.
+    Random { density: f64 },
+    /// Cells at even positions and multiples of 7 alive.
+    Default,
+    /// A single glider in the top-left corner.
+    Glider,
+    /// A single pulsar in the centre.
+    Pulsar,
+    /// One of the named patterns in `Seed::patterns()`, in the centre.
+    Pattern(String),
+}
+
+impl Seed {
+    /// The names accepted by `Seed::Pattern`.
+    pub fn patterns() -> Vec<&'static str> {
+        PATTERNS.iter().map(|&(name, _)| name).collect()
+    }
+
+    pub(crate) fn cells(
+        &self,
+        width: u32,
+        height: u32,
+        rng: &mut Rng,
+    ) -> Result<Vec<Cell>, ConvidaError> {
+        let size = ConvidaError::check_size(width, height)?;
+        let cells = match self {
+            Seed::Empty => vec![Cell::Dead; size],
+            Seed::Random { density } => {
+                crate::random(size, ConvidaError::check_density(*density)?, rng)
+            }
+            Seed::Default => crate::default(size),
+            Seed::Glider => {
+                let mut cells = vec![Cell::Dead; size];
+                place(&mut cells, width, height, &pattern("glider")?, 0, 0);
+                cells
+            }
+            Seed::Pulsar => centered(width, height, "pulsar")?,
+            Seed::Pattern(name) => centered(width, height, name)?,
+        };
+        Ok(cells)
+    }
+}
+
+/// One of the built-in patterns.
+pub(crate) fn pattern(name: &str) -> Result<Rle, ConvidaError> {
+    PATTERNS
+        .iter()
+        .find(|&&(pattern, _)| pattern == name)
+        .map(|&(_, rle)| Rle::parse(rle).expect("built-in pattern"))
+        .ok_or_else(|| ConvidaError::UnknownPattern(name.to_string()))
+}
+
+fn centered(width: u32, height: u32, name: &str) -> Result<Vec<Cell>, ConvidaError> {
+    let rle = pattern(name)?;
+    let mut cells = vec![Cell::Dead; (width * height) as usize];
+    let top = height.saturating_sub(rle.height) / 2;
+    let left = width.saturating_sub(rle.width) / 2;
+    place(&mut cells, width, height, &rle, top, left);
+    Ok(cells)
+}
+
+// Bring `rle` to life with its top-left corner at (`top`, `left`),
+// wrapping around the edges.
+fn place(cells: &mut [Cell], width: u32, height: u32, rle: &Rle, top: u32, left: u32) {
+    for &(row, col) in &rle.cells {
+        let row = (top + row) % height;
+        let col = (left + col) % width;
+        cells[(row * width + col) as usize] = Cell::Alive;
+    }
+}
+
+impl Universe {
+    /// A `width` x `height` universe filled with `seed`, drawing random
+    /// cells from `rng_seed` so the result can be reproduced.
+    pub fn new_with_seed(
+        width: u32,
+        height: u32,
+        seed: &Seed,
+        rng_seed: u64,
+    ) -> Result<Universe, ConvidaError> {
+        UniverseBuilder::new()
+            .width(width)
+            .height(height)
+            .seed(seed.clone())
+            .rng_seed(rng_seed)
+            .build()
+    }
+
+    /// Replace every cell with a fresh `seed`, keeping the size.
+    pub fn reset_with(&mut self, seed: &Seed) -> Result<(), ConvidaError> {
+        self.cells = seed.cells(self.width, self.height, &mut Rng::from_entropy())?;
+        Ok(())
+    }
+}
+
+/// `Seed` for JavaScript, which can't see enums with data:
+/// `universe.reset_with(Seed.pattern("gosper-gun"))`.
+#[cfg(feature = "web")]
+#[wasm_bindgen(js_name = Seed)]
+pub struct JsSeed(pub(crate) Seed);
+
+#[cfg(feature = "web")]
+#[wasm_bindgen(js_class = Seed)]
+impl JsSeed {
+    pub fn empty() -> JsSeed {
+        JsSeed(Seed::Empty)
+    }
+
+    /// Fails with `InvalidDensity` unless `density` is from 0 to 1.
+    pub fn random(density: f64) -> Result<JsSeed, ConvidaError> {
+        let density = ConvidaError::check_density(density)?;
+        Ok(JsSeed(Seed::Random { density }))
+    }
+
+    #[wasm_bindgen(js_name = default)]
+    pub fn default_cells() -> JsSeed {
+        JsSeed(Seed::Default)
+    }
+
+    pub fn glider() -> JsSeed {
+        JsSeed(Seed::Glider)
+    }
+
+    pub fn pulsar() -> JsSeed {
+        JsSeed(Seed::Pulsar)
+    }
+
+    pub fn pattern(name: &str) -> JsSeed {
+        JsSeed(Seed::Pattern(name.to_string()))
+    }
+
+    pub fn patterns() -> js_sys::Array {
+        Seed::patterns()
+            .into_iter()
+            .map(JsValue::from_str)
+            .collect()
+    }
+}
+
+#[cfg(feature = "web")]
+#[wasm_bindgen]
+impl Universe {
+    #[wasm_bindgen(js_name = newWithSeed)]
+    pub fn new_with_seed_js(
+        width: u32,
+        height: u32,
+        seed: &JsSeed,
+        rng_seed: u64,
+    ) -> Result<Universe, ConvidaError> {
+        Universe::new_with_seed(width, height, &seed.0, rng_seed)
+    }
+
+    #[wasm_bindgen(js_name = resetWith)]
+    pub fn reset_with_js(&mut self, seed: &JsSeed) -> Result<(), ConvidaError> {
+        self.reset_with(&seed.0)
+    }
+}
@@ -0,0 +1,112 @@
+// Set operations on the live cells of two universes of the same size, for
+// analysis such as which cells survived in one run but not in another.
+//
+// The operations return a new universe at generation zero with the first
+// universe's rule and boundary, and `diff_report` the cells on each side.
+
+#[cfg(feature = "web")]
+use serde::Serialize;
+#[cfg(feature = "web")]
+use tsify_next::Tsify;
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::{Cell, ConvidaError, Universe};
+
+#[cfg_attr(feature = "web", derive(Serialize, Tsify))]
+#[cfg_attr(feature = "web", tsify(into_wasm_abi), serde(rename_all = "camelCase"))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DiffCells {
+    pub count: u32,
+    /// (row, col) pairs in reading order.
+    pub cells: Vec<(u32, u32)>,
+}
+
+/// The live cells of two universes, compared.
+#[cfg_attr(feature = "web", derive(Serialize, Tsify))]
+#[cfg_attr(feature = "web", tsify(into_wasm_abi), serde(rename_all = "camelCase"))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DiffReport {
+    /// Alive in the universe the report was made on only.
+    pub only_self: DiffCells,
+    /// Alive in the other universe only.
+    pub only_other: DiffCells,
+    pub both: DiffCells,
+}
+
+impl DiffReport {
+    /// Whether the two universes have the same live cells.
+    pub fn is_identical(&self) -> bool {
+        self.only_self.count == 0 && self.only_other.count == 0
+    }
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Universe {
+    /// Cells alive in either universe.
+    pub fn union(&self, other: &Universe) -> Result<Universe, ConvidaError> {
+        self.combine(other, |a, b| a || b)
+    }
+
+    /// Cells alive in both universes.
+    pub fn intersection(&self, other: &Universe) -> Result<Universe, ConvidaError> {
+        self.combine(other, |a, b| a && b)
+    }
+
+    /// Cells alive in this universe but not in `other`.
+    pub fn difference(&self, other: &Universe) -> Result<Universe, ConvidaError> {
+        self.combine(other, |a, b| a && !b)
+    }
+
+    /// Cells alive in exactly one of the universes.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = symmetricDifference))]
+    pub fn symmetric_difference(&self, other: &Universe) -> Result<Universe, ConvidaError> {
+        self.combine(other, |a, b| a != b)
+    }
+
+    /// The cells alive only in this universe, only in `other`, and in
+    /// both, with their counts.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = diffReport))]
+    pub fn diff_report(&self, other: &Universe) -> Result<DiffReport, ConvidaError> {
+        ConvidaError::check_same_size(self, other)?;
+        let mut report = DiffReport::default();
+        for (i, (&a, &b)) in self.cells.iter().zip(&other.cells).enumerate() {
+            let side = match (a, b) {
+                (Cell::Alive, Cell::Dead) => &mut report.only_self,
+                (Cell::Dead, Cell::Alive) => &mut report.only_other,
+                (Cell::Alive, Cell::Alive) => &mut report.both,
+                (Cell::Dead, Cell::Dead) => continue,
+            };
+            side.count += 1;
+            let i = i as u32;
+            side.cells.push((i / self.width, i % self.width));
+        }
+        Ok(report)
+    }
+}
+
+impl Universe {
+    fn combine(
+        &self,
+        other: &Universe,
+        alive: impl Fn(bool, bool) -> bool,
+    ) -> Result<Universe, ConvidaError> {
+        ConvidaError::check_same_size(self, other)?;
+        let cells = self
+            .cells
+            .iter()
+            .zip(&other.cells)
+            .map(|(&a, &b)| {
+                if alive(a == Cell::Alive, b == Cell::Alive) {
+                    Cell::Alive
+                } else {
+                    Cell::Dead
+                }
+            })
+            .collect();
+        let mut universe = Universe::from_cells(self.width, self.height, cells);
+        universe.rule = self.rule;
+        universe.boundary = self.boundary;
+        Ok(universe)
+    }
+}
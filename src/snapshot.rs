@@ -0,0 +1,158 @@
+// Compact binary snapshot of a universe.
+//
+// Layout (little-endian):
+//
+//     "CVDA"      magic
+//     u8          format version
+//     u32         width
+//     u32         height
+//     u32         generation
+//     [u8]        cells, one bit per cell in row-major order, LSB first
+//
+// followed, if the universe has a metadata layer, by
+//
+//     u8          metadata width in bytes, 1 or 2
+//     [u8]        one value per cell in row-major order
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::metadata::{Metadata, MetadataWidth};
+use crate::protocol::DecodeError;
+use crate::{Cell, ConvidaError, Universe};
+
+const MAGIC: &[u8; 4] = b"CVDA";
+const VERSION: u8 = 1;
+const HEADER_LEN: usize = 4 + 1 + 4 * 3;
+
+impl Universe {
+    pub fn encode_snapshot(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.cells.len().div_ceil(8));
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.width.to_le_bytes());
+        bytes.extend_from_slice(&self.height.to_le_bytes());
+        bytes.extend_from_slice(&self.generation.to_le_bytes());
+
+        for chunk in self.cells.chunks(8) {
+            let mut byte = 0;
+            for (bit, &cell) in chunk.iter().enumerate() {
+                if cell == Cell::Alive {
+                    byte |= 1 << bit;
+                }
+            }
+            bytes.push(byte);
+        }
+
+        match &self.metadata {
+            Some(Metadata::U8(values)) => {
+                bytes.push(MetadataWidth::U8 as u8);
+                bytes.extend_from_slice(values);
+            }
+            Some(Metadata::U16(values)) => {
+                bytes.push(MetadataWidth::U16 as u8);
+                bytes.extend(values.iter().flat_map(|value| value.to_le_bytes()));
+            }
+            None => {}
+        }
+
+        bytes
+    }
+
+    pub fn decode_snapshot(bytes: &[u8]) -> Result<Universe, DecodeError> {
+        if bytes.len() < HEADER_LEN {
+            return Err(DecodeError::Truncated);
+        }
+        if &bytes[..4] != MAGIC {
+            return Err(DecodeError::BadMagic);
+        }
+        if bytes[4] != VERSION {
+            return Err(DecodeError::UnsupportedVersion(bytes[4]));
+        }
+
+        let field = |i: usize| {
+            let start = 5 + i * 4;
+            let mut buf = [0; 4];
+            buf.copy_from_slice(&bytes[start..start + 4]);
+            u32::from_le_bytes(buf)
+        };
+        let (width, height, generation) = (field(0), field(1), field(2));
+
+        let size = ConvidaError::check_size(width, height)
+            .map_err(|_| DecodeError::InvalidSize { width, height })?;
+        let packed = &bytes[HEADER_LEN..];
+        if packed.len() < size.div_ceil(8) {
+            return Err(DecodeError::Truncated);
+        }
+
+        let cells = (0..size)
+            .map(|i| {
+                if packed[i / 8] & (1 << (i % 8)) != 0 {
+                    Cell::Alive
+                } else {
+                    Cell::Dead
+                }
+            })
+            .collect();
+
+        let mut universe = Universe::from_cells(width, height, cells);
+        universe.generation = generation;
+        universe.metadata = decode_metadata(&packed[size.div_ceil(8)..], size)?;
+        Ok(universe)
+    }
+
+    /// Replace the size, cells and generation with those of a snapshot,
+    /// keeping registered callbacks.
+    pub fn restore_snapshot(&mut self, bytes: &[u8]) -> Result<(), DecodeError> {
+        let snapshot = Universe::decode_snapshot(bytes)?;
+        self.resize_layers(snapshot.width, snapshot.height);
+        self.width = snapshot.width;
+        self.height = snapshot.height;
+        self.cells = snapshot.cells;
+        self.generation = snapshot.generation;
+        self.metadata = snapshot.metadata;
+        Ok(())
+    }
+}
+
+// The metadata layer of `size` cells following the packed cells, if any.
+fn decode_metadata(bytes: &[u8], size: usize) -> Result<Option<Metadata>, DecodeError> {
+    let (&width, values) = match bytes.split_first() {
+        Some(split) => split,
+        None => return Ok(None),
+    };
+    let metadata = match width {
+        1 if values.len() >= size => Metadata::U8(values[..size].to_vec()),
+        2 if values.len() >= size * 2 => Metadata::U16(
+            values[..size * 2]
+                .chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                .collect(),
+        ),
+        1 | 2 => return Err(DecodeError::Truncated),
+        width => return Err(DecodeError::InvalidMetadataWidth(width)),
+    };
+    Ok(Some(metadata))
+}
+
+#[cfg(feature = "web")]
+#[wasm_bindgen]
+impl Universe {
+    /// Serialize the universe into the binary snapshot format.
+    #[wasm_bindgen(js_name = toSnapshot)]
+    pub fn to_snapshot(&self) -> Vec<u8> {
+        self.encode_snapshot()
+    }
+
+    /// Build a universe from bytes produced by `to_snapshot`.
+    #[wasm_bindgen(js_name = fromSnapshot)]
+    pub fn from_snapshot(bytes: &[u8]) -> Result<Universe, ConvidaError> {
+        Ok(Universe::decode_snapshot(bytes)?)
+    }
+
+    /// Load a snapshot into this universe.
+    #[wasm_bindgen(js_name = loadSnapshot)]
+    pub fn load_snapshot(&mut self, bytes: &[u8]) -> Result<(), ConvidaError> {
+        Ok(self.restore_snapshot(bytes)?)
+    }
+}
@@ -0,0 +1,189 @@
+// Random soups, for searches and census runs.
+//
+//     universe.seed_soup(&SoupSpec::new().density(0.3).region(0, 0, 16, 16).rng_seed(7))?;
+//
+// A soup fills its region, the whole universe unless given, and kills every
+// other cell. Cells only have two states, so `density` is the whole state
+// distribution. `SoupSpec::new()` is the soup `new_with_size` and `reset`
+// have always used: half the cells alive, drawn from entropy.
+//
+// Symmetric soups draw one part of the region and copy it onto the rest,
+// like the C2, C4, D4 and D8 soups of a Catagolue census. The symmetries
+// are about the region's centre, so an odd-sized region is centred on a
+// cell and an even-sized one on a corner between four cells.
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::rng::Rng;
+use crate::{BoundingBox, Cell, ConvidaError, Universe};
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Symmetry {
+    None = 0,
+    /// The right half of the region mirrors the left.
+    Horizontal = 1,
+    /// The bottom half of the region mirrors the top.
+    Vertical = 2,
+    /// Unchanged by a half turn.
+    C2 = 3,
+    /// Unchanged by a quarter turn. Needs a square region.
+    C4 = 4,
+    /// Mirrored both left to right and top to bottom.
+    D4 = 5,
+    /// Unchanged by any rotation or reflection of the square. Needs a
+    /// square region.
+    D8 = 6,
+}
+
+impl Symmetry {
+    // The cell of a `width` x `height` region that (`row`, `col`) copies:
+    // the first of its images in reading order.
+    fn source(self, row: u32, col: u32, width: u32, height: u32) -> (u32, u32) {
+        let (r, c) = (row, col);
+        let (mr, mc) = (height - 1 - row, width - 1 - col);
+        let images: &[(u32, u32)] = match self {
+            Symmetry::None => &[(r, c)],
+            Symmetry::Horizontal => &[(r, c), (r, mc)],
+            Symmetry::Vertical => &[(r, c), (mr, c)],
+            Symmetry::C2 => &[(r, c), (mr, mc)],
+            Symmetry::C4 => &[(r, c), (c, mr), (mr, mc), (mc, r)],
+            Symmetry::D4 => &[(r, c), (r, mc), (mr, c), (mr, mc)],
+            Symmetry::D8 => &[
+                (r, c),
+                (r, mc),
+                (mr, c),
+                (mr, mc),
+                (c, r),
+                (c, mr),
+                (mc, r),
+                (mc, mr),
+            ],
+        };
+        images
+            .iter()
+            .copied()
+            .min()
+            .expect("at least the cell itself")
+    }
+
+    fn needs_square(self) -> bool {
+        matches!(self, Symmetry::C4 | Symmetry::D8)
+    }
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SoupSpec {
+    density: f64,
+    region: Option<BoundingBox>,
+    symmetry: Symmetry,
+    rng_seed: Option<u64>,
+}
+
+impl Default for SoupSpec {
+    fn default() -> SoupSpec {
+        SoupSpec {
+            density: 0.5,
+            region: None,
+            symmetry: Symmetry::None,
+            rng_seed: None,
+        }
+    }
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl SoupSpec {
+    #[cfg_attr(feature = "web", wasm_bindgen(constructor))]
+    pub fn new() -> SoupSpec {
+        SoupSpec::default()
+    }
+
+    /// Bring each cell to life with probability `density`.
+    pub fn density(mut self, density: f64) -> SoupSpec {
+        self.density = density;
+        self
+    }
+
+    /// Only fill the `width` x `height` rectangle with its top-left corner
+    /// at (`row`, `col`).
+    pub fn region(mut self, row: u32, col: u32, width: u32, height: u32) -> SoupSpec {
+        self.region = Some(BoundingBox {
+            row,
+            col,
+            width,
+            height,
+        });
+        self
+    }
+
+    pub fn symmetry(mut self, symmetry: Symmetry) -> SoupSpec {
+        self.symmetry = symmetry;
+        self
+    }
+
+    /// Draw the cells from `seed`, so the soup can be reproduced.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = rngSeed))]
+    pub fn rng_seed(mut self, seed: u64) -> SoupSpec {
+        self.rng_seed = Some(seed);
+        self
+    }
+}
+
+impl SoupSpec {
+    pub(crate) fn cells(&self, width: u32, height: u32) -> Result<Vec<Cell>, ConvidaError> {
+        let size = ConvidaError::check_size(width, height)?;
+        let region = self.region.unwrap_or(BoundingBox {
+            row: 0,
+            col: 0,
+            width,
+            height,
+        });
+        if region.width > 0 && region.height > 0 {
+            ConvidaError::check_bounds(
+                region.row.saturating_add(region.height - 1),
+                region.col.saturating_add(region.width - 1),
+                width,
+                height,
+            )?;
+        }
+        if self.symmetry.needs_square() && region.width != region.height {
+            return Err(ConvidaError::NotSquare {
+                width: region.width,
+                height: region.height,
+            });
+        }
+
+        let mut rng = match self.rng_seed {
+            Some(seed) => Rng::new(seed),
+            None => Rng::from_entropy(),
+        };
+        let index = |row: u32, col: u32| ((region.row + row) * width + region.col + col) as usize;
+        let mut cells = vec![Cell::Dead; size];
+        for row in 0..region.height {
+            for col in 0..region.width {
+                let source = self.symmetry.source(row, col, region.width, region.height);
+                cells[index(row, col)] = if source != (row, col) {
+                    cells[index(source.0, source.1)]
+                } else if rng.next_f64() < self.density {
+                    Cell::Alive
+                } else {
+                    Cell::Dead
+                };
+            }
+        }
+        Ok(cells)
+    }
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Universe {
+    /// Replace every cell with a soup made to `spec`, keeping the size.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = seedSoup))]
+    pub fn seed_soup(&mut self, spec: &SoupSpec) -> Result<(), ConvidaError> {
+        self.enter("Universe::seed_soup");
+        self.cells = spec.cells(self.width, self.height)?;
+        Ok(())
+    }
+}
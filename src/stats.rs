@@ -0,0 +1,94 @@
+// Per-generation statistics, for spreadsheets and plotting tools.
+//
+// Once `start_stats` is called every step appends a row, and
+// `export_stats_csv` writes the rows out with a header:
+//
+//     generation,population,births,deaths,density,changed
+//     0,412,0,0,0.1005859375,0
+//     1,398,87,101,0.09716796875,188
+
+use std::fmt::Write;
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::Universe;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StatsRow {
+    pub generation: u32,
+    pub population: u32,
+    /// Cells that came to life in the step to this generation.
+    pub births: u32,
+    pub deaths: u32,
+    pub density: f64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Stats {
+    recording: bool,
+    rows: Vec<StatsRow>,
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Universe {
+    /// Record a row of statistics for the current generation and every
+    /// one after it, dropping any rows recorded before.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = startStats))]
+    pub fn start_stats(&mut self) {
+        self.stats = Stats {
+            recording: true,
+            rows: Vec::new(),
+        };
+        self.record_stats(0, 0);
+    }
+
+    /// Stop recording statistics, keeping the rows recorded so far.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = stopStats))]
+    pub fn stop_stats(&mut self) {
+        self.stats.recording = false;
+    }
+
+    /// The recorded rows as CSV.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = exportStatsCsv))]
+    pub fn export_stats_csv(&self) -> String {
+        let mut csv = String::from("generation,population,births,deaths,density,changed\n");
+        for row in self.stats_history() {
+            writeln!(
+                csv,
+                "{},{},{},{},{},{}",
+                row.generation,
+                row.population,
+                row.births,
+                row.deaths,
+                row.density,
+                row.births + row.deaths
+            )
+            .expect("writing to a String");
+        }
+        csv
+    }
+}
+
+impl Universe {
+    /// The rows recorded since `start_stats`, oldest first.
+    pub fn stats_history(&self) -> &[StatsRow] {
+        &self.stats.rows
+    }
+
+    // Called after every step with the cells it changed.
+    pub(crate) fn record_stats(&mut self, births: u32, deaths: u32) {
+        if !self.stats.recording {
+            return;
+        }
+        let population = self.iter_live().count() as u32;
+        let row = StatsRow {
+            generation: self.generation,
+            population,
+            births,
+            deaths,
+            density: f64::from(population) / self.cells.len().max(1) as f64,
+        };
+        self.stats.rows.push(row);
+    }
+}
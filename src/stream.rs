@@ -0,0 +1,235 @@
+// The simulation as an asynchronous sequence of generations.
+//
+// A `GenerationStream` owns a universe and ticks it once for every item a
+// consumer asks for, so a slow consumer holds the simulation back instead
+// of items piling up. Each item is the new generation's changes, the same
+// (row, col, state) triples as `onCellChange` in batch mode, or a whole
+// snapshot:
+//
+//     const stream = new GenerationStream(universe, false, 1000);
+//     for await (const { generation, changes } of stream.iterator()) {
+//         // ...
+//     }
+//
+// `readable()` offers the same items as a `ReadableStream`, which only
+// ticks while its queue has room for `highWaterMark` items, and `frames()`
+// the run as RLE or delta frames for archiving.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use futures::future;
+use js_sys::{Object, Promise, Reflect, Symbol, Uint8Array};
+use serde::Serialize;
+use tsify_next::Tsify;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::future_to_promise;
+use web_sys::{QueuingStrategy, ReadableStream, ReadableStreamDefaultController, UnderlyingSource};
+
+use crate::{Cell, FrameEncoder, FrameFormat, Universe};
+
+/// One generation of a `GenerationStream`.
+#[derive(Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationItem {
+    pub generation: u32,
+    /// Flat (row, col, state) triples of the cells the tick changed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changes: Option<Vec<u32>>,
+    /// The whole universe as `toSnapshot` would encode it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<Vec<u8>>,
+}
+
+struct Source {
+    universe: Universe,
+    snapshots: bool,
+    remaining: Option<u32>,
+    previous: Vec<Cell>,
+}
+
+impl Source {
+    // Tick once, unless the stream has given all the generations it was
+    // asked for.
+    fn tick(&mut self) -> bool {
+        match &mut self.remaining {
+            Some(0) => return false,
+            Some(remaining) => *remaining -= 1,
+            None => {}
+        }
+        self.universe.tick();
+        true
+    }
+
+    // Tick once and describe the result.
+    fn advance(&mut self) -> Option<GenerationItem> {
+        if !self.tick() {
+            return None;
+        }
+
+        let cells = &self.universe.cells;
+        let (changes, snapshot) = if self.snapshots {
+            (None, Some(self.universe.to_snapshot()))
+        } else {
+            let width = self.universe.width() as usize;
+            let mut changes = Vec::new();
+            if self.previous.len() == cells.len() {
+                for (idx, (&before, &after)) in self.previous.iter().zip(cells).enumerate() {
+                    if before != after {
+                        let (row, col) = (idx / width, idx % width);
+                        changes.extend_from_slice(&[row as u32, col as u32, after as u32]);
+                    }
+                }
+            }
+            (Some(changes), None)
+        };
+        self.previous.clone_from(cells);
+
+        Some(GenerationItem {
+            generation: self.universe.generation(),
+            changes,
+            snapshot,
+        })
+    }
+}
+
+#[wasm_bindgen]
+pub struct GenerationStream {
+    source: Rc<RefCell<Source>>,
+}
+
+#[wasm_bindgen]
+impl GenerationStream {
+    /// Stream the generations after `universe`'s current one, as whole
+    /// snapshots when `snapshots` is set and as changes otherwise, ending
+    /// after `generations` of them or running until the consumer stops.
+    #[wasm_bindgen(constructor)]
+    pub fn new(universe: Universe, snapshots: bool, generations: Option<u32>) -> GenerationStream {
+        let previous = universe.cells.clone();
+        GenerationStream {
+            source: Rc::new(RefCell::new(Source {
+                universe,
+                snapshots,
+                remaining: generations,
+                previous,
+            })),
+        }
+    }
+
+    /// A copy of the universe as far as the stream has got.
+    pub fn universe(&self) -> Universe {
+        self.source.borrow().universe.clone()
+    }
+
+    /// Tick once and resolve to the new generation as an iterator result,
+    /// `{ value, done }`. Nothing ticks until the next item is asked for.
+    pub fn next(&self) -> Promise {
+        let source = Rc::clone(&self.source);
+        future_to_promise(future::lazy(move || {
+            let item = source.borrow_mut().advance();
+            iterator_result(item)
+        }))
+    }
+
+    /// An object for `for await`, whose `next` is this stream's.
+    pub fn iterator(&self) -> Result<Object, JsValue> {
+        let source = Rc::clone(&self.source);
+        let next = Closure::wrap(Box::new(move || {
+            GenerationStream {
+                source: Rc::clone(&source),
+            }
+            .next()
+        }) as Box<dyn FnMut() -> Promise>);
+        let iterator = Object::new();
+        Reflect::set(&iterator, &"next".into(), &next.into_js_value())?;
+        let itself = {
+            let iterator = iterator.clone();
+            Closure::wrap(Box::new(move || iterator.clone()) as Box<dyn FnMut() -> Object>)
+        };
+        Reflect::set(
+            &iterator,
+            &Symbol::async_iterator(),
+            &itself.into_js_value(),
+        )?;
+        Ok(iterator)
+    }
+
+    /// The generations as a `ReadableStream`, ticking only while fewer
+    /// than `high_water_mark` items, 1 unless given, are waiting to be read.
+    pub fn readable(&self, high_water_mark: Option<f64>) -> Result<ReadableStream, JsValue> {
+        let source = Rc::clone(&self.source);
+        pull_stream(high_water_mark, move || {
+            match source.borrow_mut().advance() {
+                Some(item) => item_value(&item).map(Some),
+                None => Ok(None),
+            }
+        })
+    }
+
+    /// The universe as it is now and then every generation as a
+    /// `Uint8Array` frame in `format`, for `pipeTo` a file or an upload.
+    /// Like `readable`, it only ticks while the queue has room.
+    pub fn frames(
+        &self,
+        format: FrameFormat,
+        high_water_mark: Option<f64>,
+    ) -> Result<ReadableStream, JsValue> {
+        let source = Rc::clone(&self.source);
+        let mut encoder = FrameEncoder::new(format);
+        let mut started = false;
+        pull_stream(high_water_mark, move || {
+            let mut source = source.borrow_mut();
+            if started && !source.tick() {
+                return Ok(None);
+            }
+            started = true;
+            let frame = encoder.frame(&source.universe);
+            Ok(Some(Uint8Array::from(&frame[..]).into()))
+        })
+    }
+}
+
+// A `ReadableStream` that enqueues whatever `next` returns on each pull,
+// closing once it returns `None`.
+fn pull_stream<F>(high_water_mark: Option<f64>, next: F) -> Result<ReadableStream, JsValue>
+where
+    F: FnMut() -> Result<Option<JsValue>, JsValue> + 'static,
+{
+    let next = Rc::new(RefCell::new(next));
+    let pull = Closure::wrap(
+        Box::new(move |controller: ReadableStreamDefaultController| {
+            let next = Rc::clone(&next);
+            future_to_promise(future::lazy(move || {
+                match (next.borrow_mut())()? {
+                    Some(chunk) => controller.enqueue_with_chunk(&chunk)?,
+                    None => controller.close()?,
+                }
+                Ok(JsValue::UNDEFINED)
+            }))
+        }) as Box<dyn FnMut(ReadableStreamDefaultController) -> Promise>,
+    );
+
+    let underlying = UnderlyingSource::new();
+    underlying.set_pull(pull.into_js_value().unchecked_ref());
+    let strategy = QueuingStrategy::new();
+    strategy.set_high_water_mark(high_water_mark.unwrap_or(1.0));
+    ReadableStream::new_with_underlying_source_and_strategy(&underlying, &strategy)
+}
+
+fn item_value(item: &GenerationItem) -> Result<JsValue, JsValue> {
+    Ok(item.into_js()?.into())
+}
+
+fn iterator_result(item: Option<GenerationItem>) -> Result<JsValue, JsValue> {
+    let result = Object::new();
+    let done = item.is_none();
+    let value = match item {
+        Some(item) => item_value(&item)?,
+        None => JsValue::UNDEFINED,
+    };
+    Reflect::set(&result, &"value".into(), &value)?;
+    Reflect::set(&result, &"done".into(), &JsValue::from(done))?;
+    Ok(result.into())
+}
@@ -0,0 +1,460 @@
+// Live synchronization of a universe between browsers.
+//
+// Local edits are only proposed to the authority; they take effect once
+// the authority echoes them back with a sequence number, so every peer
+// applies the same edits in the same order at the same generation.
+// Checksums are exchanged periodically to detect peers that drifted apart.
+//
+// In CRDT mode edits skip the authority altogether: they apply locally at
+// once and merge as last-writer-wins registers, so peers that kept editing
+// while disconnected converge once they exchange registers again.
+//
+// Two transports speak the same protocol: a WebSocket to a sync server,
+// which is the authority, and a WebRTC data channel, where the host peer
+// is the authority and starts by sending a full snapshot.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::rc::Rc;
+
+use js_sys::{ArrayBuffer, Uint8Array};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{
+    BinaryType, MessageEvent, RtcDataChannel, RtcDataChannelState, RtcDataChannelType, WebSocket,
+};
+
+use crate::crdt::{CrdtEdit, LwwGrid};
+use crate::protocol::{Edit, Message};
+use crate::{Cell, Universe};
+
+// How many local checksums are kept to compare against late reports.
+const CHECKSUM_HISTORY: usize = 64;
+
+type Inbox = Rc<RefCell<VecDeque<Message>>>;
+
+enum Transport {
+    WebSocket(WebSocket),
+    DataChannel(RtcDataChannel),
+}
+
+impl Transport {
+    fn is_open(&self) -> bool {
+        match self {
+            Transport::WebSocket(socket) => socket.ready_state() == WebSocket::OPEN,
+            Transport::DataChannel(channel) => channel.ready_state() == RtcDataChannelState::Open,
+        }
+    }
+
+    fn send(&self, bytes: &[u8]) -> Result<(), JsValue> {
+        match self {
+            Transport::WebSocket(socket) => socket.send_with_u8_array(bytes),
+            Transport::DataChannel(channel) => channel.send_with_u8_array(bytes),
+        }
+    }
+
+    fn set_onmessage(&self, callback: Option<&js_sys::Function>) {
+        match self {
+            Transport::WebSocket(socket) => socket.set_onmessage(callback),
+            Transport::DataChannel(channel) => channel.set_onmessage(callback),
+        }
+    }
+
+    fn close(&self) -> Result<(), JsValue> {
+        match self {
+            Transport::WebSocket(socket) => socket.close(),
+            Transport::DataChannel(channel) => {
+                channel.close();
+                Ok(())
+            }
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub struct SyncSession {
+    transport: Transport,
+    inbox: Inbox,
+    _on_message: Closure<dyn FnMut(MessageEvent)>,
+    // Whether this side assigns sequence numbers.
+    authority: bool,
+    // Authority: whether the initial snapshot went out.
+    // Others: whether it has arrived.
+    snapshot_exchanged: bool,
+    pending: BTreeMap<u32, Edit>,
+    last_seq: u32,
+    next_seq: u32,
+    checksum_interval: u32,
+    checksums: HashMap<u32, u64>,
+    reported: HashMap<u32, u64>,
+    last_checked: Option<u32>,
+    desynced: bool,
+    crdt: Option<LwwGrid>,
+    // Cells whose register changed since the last `sync`.
+    dirty: Vec<(u32, u32)>,
+    // Whether the full register state was sent since the transport opened.
+    announced: bool,
+}
+
+#[wasm_bindgen]
+impl SyncSession {
+    /// Open a WebSocket to the sync server at `url`.
+    pub fn connect(url: &str) -> Result<SyncSession, JsValue> {
+        let socket = WebSocket::new(url)?;
+        socket.set_binary_type(BinaryType::Arraybuffer);
+
+        // The server sends a snapshot only when asked for a resync.
+        Ok(SyncSession::new(Transport::WebSocket(socket), false, true))
+    }
+
+    /// Synchronize over an already negotiated WebRTC data channel.
+    ///
+    /// Signaling is left to the page. Exactly one side must pass
+    /// `host = true`; the host orders all edits and sends its board to the
+    /// other side as soon as the channel opens.
+    #[wasm_bindgen(js_name = overDataChannel)]
+    pub fn over_data_channel(channel: RtcDataChannel, host: bool) -> SyncSession {
+        channel.set_binary_type(RtcDataChannelType::Arraybuffer);
+        SyncSession::new(Transport::DataChannel(channel), host, false)
+    }
+
+    #[wasm_bindgen(js_name = isAuthority)]
+    pub fn is_authority(&self) -> bool {
+        self.authority
+    }
+
+    /// Switch to CRDT edits. `replica` must be unique among the peers.
+    #[wasm_bindgen(js_name = enableCrdt)]
+    pub fn enable_crdt(&mut self, replica: u32) {
+        self.crdt = Some(LwwGrid::new(replica));
+    }
+
+    /// All CRDT registers, for carrying offline edits over to a new
+    /// session with `import_crdt`.
+    #[wasm_bindgen(js_name = exportCrdt)]
+    pub fn export_crdt(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        if let Some(crdt) = &self.crdt {
+            for edit in crdt.edits() {
+                bytes.extend(Message::CrdtEdit(edit).encode());
+            }
+        }
+        bytes
+    }
+
+    /// Merge registers produced by `export_crdt`. CRDT mode must be
+    /// enabled first.
+    #[wasm_bindgen(js_name = importCrdt)]
+    pub fn import_crdt(&mut self, bytes: &[u8]) -> Result<(), JsValue> {
+        let mut bytes = bytes;
+        while !bytes.is_empty() {
+            match Message::decode_prefix(&mut bytes) {
+                Ok(Message::CrdtEdit(edit)) => self.merge_crdt(&edit),
+                Ok(_) => return Err(JsValue::from_str("expected a CRDT edit")),
+                Err(err) => return Err(JsValue::from_str(&err.to_string())),
+            }
+        }
+        self.announced = false;
+        Ok(())
+    }
+
+    /// Ask for a cell to be set. The change is applied by `sync` once the
+    /// authority has ordered it, or at the next `sync` in CRDT mode.
+    #[wasm_bindgen(js_name = proposeEdit)]
+    pub fn propose_edit(
+        &mut self,
+        universe: &Universe,
+        row: u32,
+        col: u32,
+        alive: bool,
+    ) -> Result<(), JsValue> {
+        let state = if alive { Cell::Alive } else { Cell::Dead };
+        if let Some(crdt) = &mut self.crdt {
+            crdt.set(row, col, state);
+            self.dirty.push((row, col));
+            return Ok(());
+        }
+
+        let edit = Edit {
+            seq: 0,
+            generation: universe.generation(),
+            row,
+            col,
+            state,
+        };
+
+        if self.authority {
+            self.sequence(edit, universe.generation())
+        } else {
+            self.send(&Message::Edit(edit))
+        }
+    }
+
+    /// Apply ordered edits that are due and exchange snapshots and
+    /// checksums. Call this before every tick.
+    pub fn sync(&mut self, universe: &mut Universe) -> Result<(), JsValue> {
+        if self.authority && !self.snapshot_exchanged && self.transport.is_open() {
+            self.send_snapshot(universe)?;
+            self.snapshot_exchanged = true;
+        }
+
+        let messages: Vec<Message> = self.inbox.borrow_mut().drain(..).collect();
+        for message in messages {
+            self.receive(message, universe)?;
+        }
+
+        // Until the initial snapshot arrives there is no board to apply
+        // edits to.
+        if !self.snapshot_exchanged {
+            return Ok(());
+        }
+
+        if self.crdt.is_some() {
+            self.sync_crdt(universe)?;
+        }
+
+        // Apply edits in sequence order, stopping at a gap or at an edit
+        // meant for a later generation.
+        while let Some((&seq, edit)) = self.pending.iter().next() {
+            if seq != self.last_seq + 1 || edit.generation > universe.generation() {
+                break;
+            }
+            universe.apply_edit(edit);
+            self.last_seq = seq;
+            self.pending.remove(&seq);
+        }
+
+        let generation = universe.generation();
+        if self.checksum_interval > 0
+            && generation.is_multiple_of(self.checksum_interval)
+            && self.last_checked != Some(generation)
+        {
+            let checksum = universe.checksum();
+            remember(&mut self.checksums, generation, checksum);
+            self.last_checked = Some(generation);
+            self.send(&Message::Checksum {
+                generation,
+                checksum,
+            })?;
+            self.compare_checksums(generation, generation);
+        }
+
+        Ok(())
+    }
+
+    /// Send a checksum every `generations` generations. Zero disables
+    /// checksums.
+    #[wasm_bindgen(js_name = setChecksumInterval)]
+    pub fn set_checksum_interval(&mut self, generations: u32) {
+        self.checksum_interval = generations;
+    }
+
+    /// The sequence number of the last edit applied.
+    #[wasm_bindgen(js_name = lastSeq)]
+    pub fn last_seq(&self) -> u32 {
+        self.last_seq
+    }
+
+    /// Whether a peer reported a different checksum for a generation we
+    /// also checksummed. A resync has been requested when this is set.
+    #[wasm_bindgen(js_name = isDesynced)]
+    pub fn is_desynced(&self) -> bool {
+        self.desynced
+    }
+
+    /// Clear the desync flag, e.g. once a fresh board was loaded.
+    #[wasm_bindgen(js_name = clearDesync)]
+    pub fn clear_desync(&mut self) {
+        self.desynced = false;
+        self.checksums.clear();
+        self.reported.clear();
+    }
+
+    pub fn close(&self) -> Result<(), JsValue> {
+        self.transport.close()
+    }
+}
+
+impl SyncSession {
+    fn new(transport: Transport, authority: bool, snapshot_exchanged: bool) -> SyncSession {
+        let inbox: Inbox = Rc::new(RefCell::new(VecDeque::new()));
+        let on_message = {
+            let inbox = Rc::clone(&inbox);
+            Closure::wrap(Box::new(move |event: MessageEvent| {
+                if let Ok(buffer) = event.data().dyn_into::<ArrayBuffer>() {
+                    let bytes = Uint8Array::new(&buffer).to_vec();
+                    if let Ok(message) = Message::decode(&bytes) {
+                        inbox.borrow_mut().push_back(message);
+                    }
+                }
+            }) as Box<dyn FnMut(MessageEvent)>)
+        };
+        transport.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+
+        SyncSession {
+            transport,
+            inbox,
+            _on_message: on_message,
+            authority,
+            snapshot_exchanged,
+            pending: BTreeMap::new(),
+            last_seq: 0,
+            next_seq: 1,
+            checksum_interval: 60,
+            checksums: HashMap::new(),
+            reported: HashMap::new(),
+            last_checked: None,
+            desynced: false,
+            crdt: None,
+            dirty: Vec::new(),
+            announced: false,
+        }
+    }
+
+    fn merge_crdt(&mut self, edit: &CrdtEdit) {
+        if let Some(crdt) = &mut self.crdt {
+            if crdt.merge(edit) {
+                self.dirty.push((edit.row, edit.col));
+            }
+        }
+    }
+
+    fn sync_crdt(&mut self, universe: &mut Universe) -> Result<(), JsValue> {
+        let crdt = match &mut self.crdt {
+            Some(crdt) => crdt,
+            None => return Ok(()),
+        };
+
+        for (row, col) in self.dirty.drain(..) {
+            if let Some(state) = crdt.get(row, col) {
+                universe.apply_edit(&Edit {
+                    seq: 0,
+                    generation: universe.generation(),
+                    row,
+                    col,
+                    state,
+                });
+            }
+        }
+
+        if !self.transport.is_open() {
+            self.announced = false;
+            return Ok(());
+        }
+
+        // On (re)connection send every register, since the peer may have
+        // missed edits made while we were apart.
+        let edits = if self.announced {
+            crdt.take_unsent()
+        } else {
+            crdt.take_unsent();
+            crdt.edits()
+        };
+        self.announced = true;
+        for edit in edits {
+            self.send(&Message::CrdtEdit(edit))?;
+        }
+        Ok(())
+    }
+
+    fn send(&self, message: &Message) -> Result<(), JsValue> {
+        if !self.transport.is_open() {
+            return Err(JsValue::from_str("sync transport is not open"));
+        }
+        self.transport.send(&message.encode())
+    }
+
+    fn send_snapshot(&self, universe: &Universe) -> Result<(), JsValue> {
+        // Edits that are sequenced but not applied yet are not part of the
+        // board, so they are resent after the snapshot.
+        self.send(&Message::Snapshot {
+            seq: self.last_seq,
+            data: universe.encode_snapshot(),
+        })?;
+        for edit in self.pending.values() {
+            self.send(&Message::Edit(*edit))?;
+        }
+        Ok(())
+    }
+
+    // Authority only: order an edit and share it with the other side.
+    fn sequence(&mut self, mut edit: Edit, generation: u32) -> Result<(), JsValue> {
+        edit.seq = self.next_seq;
+        edit.generation = edit.generation.max(generation);
+        self.next_seq += 1;
+        self.pending.insert(edit.seq, edit);
+        self.send(&Message::Edit(edit))
+    }
+
+    fn receive(&mut self, message: Message, universe: &mut Universe) -> Result<(), JsValue> {
+        let generation = universe.generation();
+        match message {
+            Message::Edit(edit) => {
+                if self.authority && edit.seq == 0 {
+                    self.sequence(edit, generation)?;
+                } else if edit.seq > self.last_seq {
+                    self.pending.insert(edit.seq, edit);
+                }
+            }
+            Message::Checksum {
+                generation: at,
+                checksum,
+            } => {
+                remember(&mut self.reported, at, checksum);
+                self.compare_checksums(at, generation);
+            }
+            Message::CrdtEdit(edit) => self.merge_crdt(&edit),
+            Message::Resync { .. } => {
+                if self.authority {
+                    self.send_snapshot(universe)?;
+                }
+            }
+            Message::Snapshot { seq, data } => {
+                if !self.authority {
+                    universe
+                        .restore_snapshot(&data)
+                        .map_err(|err| JsValue::from_str(&err.to_string()))?;
+                    self.last_seq = seq;
+                    self.pending = self.pending.split_off(&(seq + 1));
+                    self.snapshot_exchanged = true;
+                    self.clear_desync();
+                    // Keep the local edits the snapshot can't know of.
+                    if let Some(crdt) = &self.crdt {
+                        crdt.apply_unsent_to(universe);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Compare our checksum for generation `at` with the reported one, if
+    // both are known, and ask for a resync on mismatch.
+    fn compare_checksums(&mut self, at: u32, generation: u32) {
+        let ours = self.checksums.get(&at);
+        let theirs = self.reported.get(&at);
+        if let (Some(ours), Some(theirs)) = (ours, theirs) {
+            if ours != theirs && !self.desynced {
+                self.desynced = true;
+                if !self.authority {
+                    let _ = self.send(&Message::Resync { generation });
+                }
+            }
+        }
+    }
+}
+
+fn remember(checksums: &mut HashMap<u32, u64>, generation: u32, checksum: u64) {
+    checksums.insert(generation, checksum);
+    if checksums.len() > CHECKSUM_HISTORY {
+        if let Some(oldest) = checksums.keys().min().copied() {
+            checksums.remove(&oldest);
+        }
+    }
+}
+
+impl Drop for SyncSession {
+    fn drop(&mut self) {
+        self.transport.set_onmessage(None);
+    }
+}
@@ -0,0 +1,100 @@
+// Reading back the text grid written by `Universe`'s `Display` impl.
+//
+//     ◻◼◻        .#.
+//     ◻◻◼   or   ..#
+//     ◼◼◼        ###
+
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "web")]
+use crate::ConvidaError;
+use crate::{Cell, Universe};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TextError {
+    Empty,
+    /// A row whose length differs from the first row.
+    RaggedRow {
+        row: u32,
+        expected: u32,
+        found: u32,
+    },
+    UnexpectedChar(char),
+}
+
+impl fmt::Display for TextError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TextError::Empty => write!(f, "no cells in text"),
+            TextError::RaggedRow {
+                row,
+                expected,
+                found,
+            } => write!(f, "row {} has {} cells, expected {}", row, found, expected),
+            TextError::UnexpectedChar(c) => write!(f, "unexpected character {:?}", c),
+        }
+    }
+}
+
+impl Universe {
+    /// Parse a grid of `◻`/`◼` or `.`/`#`, one line per row. Blank lines
+    /// before and after the grid are ignored.
+    pub fn from_string(text: &str) -> Result<Universe, TextError> {
+        let mut width = None;
+        let mut height = 0;
+        let mut cells = Vec::new();
+
+        for line in text.trim_matches(|c| c == '\n' || c == '\r').lines() {
+            let line = line.trim_end_matches('\r');
+            let before = cells.len();
+            for c in line.chars() {
+                cells.push(match c {
+                    '◻' | '.' => Cell::Dead,
+                    '◼' | '#' => Cell::Alive,
+                    c => return Err(TextError::UnexpectedChar(c)),
+                });
+            }
+
+            let found = (cells.len() - before) as u32;
+            match width {
+                None => width = Some(found),
+                Some(expected) if expected != found => {
+                    return Err(TextError::RaggedRow {
+                        row: height,
+                        expected,
+                        found,
+                    })
+                }
+                Some(_) => {}
+            }
+            height += 1;
+        }
+
+        match width {
+            Some(width) if width > 0 => Ok(Universe::from_cells(width, height, cells)),
+            _ => Err(TextError::Empty),
+        }
+    }
+}
+
+impl FromStr for Universe {
+    type Err = TextError;
+
+    fn from_str(text: &str) -> Result<Universe, TextError> {
+        Universe::from_string(text)
+    }
+}
+
+#[cfg(feature = "web")]
+#[wasm_bindgen]
+impl Universe {
+    /// Build a universe from the output of `render()`.
+    #[wasm_bindgen(js_name = fromString)]
+    pub fn from_string_js(text: &str) -> Result<Universe, ConvidaError> {
+        Ok(Universe::from_string(text)?)
+    }
+}
@@ -0,0 +1,140 @@
+// Time-lapse capture of a run.
+//
+// Every so many generations, as the universe steps, a thumbnail of it is
+// rendered like `render_minimap` and kept in the crate, so recording a long
+// run doesn't copy the whole board out to JavaScript each frame. Frames are
+// at most `MAX_FRAME_SIZE` pixels on a side, and recording stops once
+// `max_frames` have been taken. Rows of pixels are interned, so a row that
+// looks the same in many frames is only stored once.
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::intern::{Interned, Store};
+use crate::{encode_png, Universe};
+
+const MAX_FRAME_SIZE: u32 = 256;
+
+/// Frames captured by `Universe::start_timelapse`, all the same size.
+#[cfg_attr(feature = "web", wasm_bindgen)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Timelapse {
+    width: u32,
+    height: u32,
+    generations: Vec<u32>,
+    frames: Vec<Interned>,
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Timelapse {
+    /// The width of each frame in pixels.
+    #[cfg_attr(feature = "web", wasm_bindgen(getter))]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(getter))]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(getter))]
+    pub fn length(&self) -> u32 {
+        self.frames.len() as u32
+    }
+
+    /// The generation of each frame.
+    pub fn generations(&self) -> Vec<u32> {
+        self.generations.clone()
+    }
+
+    /// Frame `i` as RGBA pixels.
+    pub fn frame(&self, i: u32) -> Option<Vec<u8>> {
+        self.frames.get(i as usize).map(Interned::to_vec)
+    }
+
+    /// Frame `i` as a PNG.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = framePng))]
+    pub fn frame_png(&self, i: u32) -> Option<Vec<u8>> {
+        let frame = self.frames.get(i as usize)?;
+        Some(encode_png(self.width, self.height, &frame.to_vec()))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Recorder {
+    every: u32,
+    max_frames: usize,
+    timelapse: Timelapse,
+    store: Store,
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Universe {
+    /// Capture a frame now and every `every_n` generations after, up to
+    /// `max_frames` frames. Replaces any time-lapse being recorded.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = startTimelapse))]
+    pub fn start_timelapse(&mut self, every_n: u32, max_frames: u32) {
+        let scale = self.width.max(self.height).div_ceil(MAX_FRAME_SIZE).max(1);
+        self.timelapse = Some(Recorder {
+            every: every_n.max(1),
+            max_frames: max_frames as usize,
+            timelapse: Timelapse {
+                width: self.width.div_ceil(scale),
+                height: self.height.div_ceil(scale),
+                ..Timelapse::default()
+            },
+            store: Store::default(),
+        });
+        self.capture_frame();
+    }
+
+    /// Stop recording, returning the frames captured.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = stopTimelapse))]
+    pub fn stop_timelapse(&mut self) -> Timelapse {
+        self.timelapse
+            .take()
+            .map(|recorder| recorder.timelapse)
+            .unwrap_or_default()
+    }
+
+    /// The frames captured so far, while recording carries on.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = exportTimelapse))]
+    pub fn export_timelapse(&self) -> Timelapse {
+        self.timelapse
+            .as_ref()
+            .map(|recorder| recorder.timelapse.clone())
+            .unwrap_or_default()
+    }
+}
+
+impl Universe {
+    // Called after every step.
+    pub(crate) fn record_timelapse(&mut self) {
+        let due = match &self.timelapse {
+            Some(recorder) => self.generation.is_multiple_of(recorder.every),
+            None => false,
+        };
+        if due {
+            self.capture_frame();
+        }
+    }
+
+    fn capture_frame(&mut self) {
+        let (width, height) = match &self.timelapse {
+            Some(recorder) if recorder.timelapse.frames.len() < recorder.max_frames => {
+                (recorder.timelapse.width, recorder.timelapse.height)
+            }
+            _ => return,
+        };
+        let frame = self
+            .render_minimap(width, height)
+            .expect("a frame is at most MAX_FRAME_SIZE pixels on a side");
+        let generation = self.generation;
+        if let Some(recorder) = &mut self.timelapse {
+            recorder.timelapse.generations.push(generation);
+            let frame = recorder.store.intern(&frame, width as usize * 4);
+            recorder.timelapse.frames.push(frame);
+        }
+    }
+}
@@ -0,0 +1,83 @@
+// Trails, for drawing where moving patterns have just been.
+//
+// While trails are on the universe remembers, for every cell, how many
+// generations ago it was last alive, saturating at 255. One byte per cell
+// stands in for a buffer per generation: the renderer fades each dead cell
+// by its age, so only the current frame ever reaches JavaScript.
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::{Cell, Universe};
+
+// The age of a cell that hasn't been alive since trails were turned on.
+const NEVER: u8 = u8::MAX;
+
+fn fresh_ages(cells: &[Cell]) -> Vec<u8> {
+    cells
+        .iter()
+        .map(|&cell| match cell {
+            Cell::Alive => 0,
+            Cell::Dead => NEVER,
+        })
+        .collect()
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Universe {
+    /// Start remembering how long ago each cell was alive, for
+    /// `Renderer.set_trails`.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = enableTrails))]
+    pub fn enable_trails(&mut self) {
+        self.trails = Some(fresh_ages(&self.cells));
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = disableTrails))]
+    pub fn disable_trails(&mut self) {
+        self.trails = None;
+    }
+
+    /// The generations since each cell was last alive, one byte per cell
+    /// like `cells` and 0 for live ones, or null while trails are off.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = trailAges))]
+    pub fn trail_ages(&self) -> *const u8 {
+        match &self.trails {
+            Some(ages) => ages.as_ptr(),
+            None => std::ptr::null(),
+        }
+    }
+}
+
+impl Universe {
+    /// The age of (`row`, `col`), while trails are on.
+    pub fn trail_age(&self, row: u32, col: u32) -> Option<u8> {
+        if row >= self.height || col >= self.width {
+            return None;
+        }
+        let idx = self.get_index(row, col);
+        self.trails.as_ref().and_then(|ages| ages.get(idx).copied())
+    }
+
+    // Called when the board is resized to `len` cells, forgetting the ages.
+    pub(crate) fn resize_trails(&mut self, len: usize) {
+        if let Some(ages) = &mut self.trails {
+            *ages = vec![NEVER; len];
+        }
+    }
+
+    // Called after every step.
+    pub(crate) fn age_trails(&mut self) {
+        if let Some(ages) = &mut self.trails {
+            if ages.len() != self.cells.len() {
+                *ages = fresh_ages(&self.cells);
+                return;
+            }
+            for (age, &cell) in ages.iter_mut().zip(&self.cells) {
+                *age = match cell {
+                    Cell::Alive => 0,
+                    Cell::Dead => age.saturating_add(1),
+                };
+            }
+        }
+    }
+}
@@ -0,0 +1,432 @@
+// Two-player competitive Life.
+//
+// Every live cell belongs to a player. A cell that is born takes the
+// colour of most of its three parents, as in the Immigration variant, and
+// survivors keep theirs. The players take turns placing cells from a
+// per-turn budget; once both have moved the board runs for a few
+// generations and each scores the live cells they still own.
+//
+// The win conditions are checked after every tick, and the first one met
+// ends the game with a `game-over` event.
+
+#[cfg(feature = "web")]
+use serde::Serialize;
+#[cfg(feature = "web")]
+use tsify_next::Tsify;
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "web")]
+use js_sys::Function;
+
+use crate::{Boundary, BoundingBox, Cell, ConvidaError, Event, Universe};
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Player {
+    One = 1,
+    Two = 2,
+}
+
+impl Player {
+    pub fn other(self) -> Player {
+        match self {
+            Player::One => Player::Two,
+            Player::Two => Player::One,
+        }
+    }
+}
+
+// Reports hold a `Player` as its number, matching the exported enum.
+#[cfg(feature = "web")]
+impl Serialize for Player {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(*self as u8)
+    }
+}
+
+/// How a `Versus` game was decided.
+#[cfg_attr(feature = "web", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WinCondition {
+    /// The other player has no live cells left.
+    Elimination = 0,
+    /// The player held more cells at the final generation.
+    Generation = 1,
+    /// The player held enough cells in the target region.
+    Capture = 2,
+}
+
+impl WinCondition {
+    pub fn name(self) -> &'static str {
+        match self {
+            WinCondition::Elimination => "elimination",
+            WinCondition::Generation => "generation",
+            WinCondition::Capture => "capture",
+        }
+    }
+}
+
+/// The number of live cells each player owns.
+#[cfg_attr(feature = "web", derive(Serialize, Tsify))]
+#[cfg_attr(feature = "web", tsify(into_wasm_abi), serde(rename_all = "camelCase"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Score {
+    pub player_one: u32,
+    pub player_two: u32,
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+pub struct Versus {
+    universe: Universe,
+    /// The owning `Player` of each live cell, 0 for dead cells.
+    owners: Vec<u8>,
+    budget: u32,
+    remaining: u32,
+    player: Player,
+    round: u32,
+    generations_per_round: u32,
+    elimination: bool,
+    final_generation: Option<u32>,
+    /// The target region and how many of its cells a player must hold.
+    target: Option<(BoundingBox, u32)>,
+    /// The winner, or `None` on a draw, once the game is over.
+    outcome: Option<(Option<Player>, WinCondition)>,
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Versus {
+    /// An empty `width` x `height` board on which each player may place
+    /// `budget` cells per turn, followed by `generations_per_round`
+    /// generations once both have moved.
+    #[cfg_attr(feature = "web", wasm_bindgen(constructor))]
+    pub fn new(
+        width: u32,
+        height: u32,
+        budget: u32,
+        generations_per_round: u32,
+    ) -> Result<Versus, ConvidaError> {
+        crate::diagnostics::set_panic_hook();
+        let size = ConvidaError::check_size(width, height)?;
+        Ok(Versus {
+            universe: Universe::from_cells(width, height, vec![Cell::Dead; size]),
+            owners: vec![0; size],
+            budget,
+            remaining: budget,
+            player: Player::One,
+            round: 0,
+            generations_per_round,
+            elimination: false,
+            final_generation: None,
+            target: None,
+            outcome: None,
+        })
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(getter))]
+    pub fn width(&self) -> u32 {
+        self.universe.width
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(getter))]
+    pub fn height(&self) -> u32 {
+        self.universe.height
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(getter))]
+    pub fn generation(&self) -> u32 {
+        self.universe.generation
+    }
+
+    /// The player whose turn it is.
+    #[cfg_attr(feature = "web", wasm_bindgen(getter))]
+    pub fn player(&self) -> Player {
+        self.player
+    }
+
+    /// The number of rounds both players have finished.
+    #[cfg_attr(feature = "web", wasm_bindgen(getter))]
+    pub fn round(&self) -> u32 {
+        self.round
+    }
+
+    /// The cells the current player may still place this turn.
+    #[cfg_attr(feature = "web", wasm_bindgen(getter))]
+    pub fn remaining(&self) -> u32 {
+        self.remaining
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = setBoundary))]
+    pub fn set_boundary(&mut self, boundary: Boundary) {
+        self.universe.boundary = boundary;
+    }
+
+    pub fn cells(&self) -> *const Cell {
+        self.universe.cells.as_ptr()
+    }
+
+    /// The owner of each cell as bytes in reading order: 1 or 2 for a
+    /// player's live cell, 0 for a dead one.
+    pub fn owners(&self) -> *const u8 {
+        self.owners.as_ptr()
+    }
+
+    /// The player owning the cell, or `undefined` if it is dead.
+    pub fn owner(&self, row: u32, col: u32) -> Result<Option<Player>, ConvidaError> {
+        ConvidaError::check_bounds(row, col, self.width(), self.height())?;
+        Ok(match self.owners[self.universe.get_index(row, col)] {
+            1 => Some(Player::One),
+            2 => Some(Player::Two),
+            _ => None,
+        })
+    }
+
+    /// Place a live cell for the current player, spending one of their
+    /// placements for this turn.
+    pub fn place(&mut self, row: u32, col: u32) -> Result<(), ConvidaError> {
+        ConvidaError::check_bounds(row, col, self.width(), self.height())?;
+        if self.outcome.is_some() {
+            return Err(ConvidaError::GameOver);
+        }
+        let idx = self.universe.get_index(row, col);
+        if self.universe.cells[idx] == Cell::Alive {
+            return Err(ConvidaError::CellOccupied { row, col });
+        }
+        if self.remaining == 0 {
+            return Err(ConvidaError::BudgetSpent {
+                budget: self.budget,
+            });
+        }
+        self.remaining -= 1;
+        self.universe.cells[idx] = Cell::Alive;
+        self.owners[idx] = self.player as u8;
+        Ok(())
+    }
+
+    /// Pass the turn to the other player. After the second player's turn
+    /// the board runs for the round's generations.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = endTurn))]
+    pub fn end_turn(&mut self) {
+        if self.player == Player::Two {
+            for _ in 0..self.generations_per_round {
+                self.tick();
+            }
+            self.round += 1;
+        }
+        self.player = self.player.other();
+        self.remaining = self.budget;
+    }
+
+    /// Run one generation of Conway's Life, passing ownership on to
+    /// survivors and newborn cells, then check the win conditions.
+    pub fn tick(&mut self) {
+        let cells = self.universe.cells.clone();
+        self.universe.tick();
+
+        let mut owners = vec![0; self.owners.len()];
+        for row in 0..self.height() {
+            for col in 0..self.width() {
+                let idx = self.universe.get_index(row, col);
+                if self.universe.cells[idx] == Cell::Dead {
+                    continue;
+                }
+                owners[idx] = if cells[idx] == Cell::Alive {
+                    self.owners[idx]
+                } else {
+                    self.majority_owner(row, col)
+                };
+            }
+        }
+        self.owners = owners;
+
+        if self.outcome.is_none() {
+            self.judge();
+        }
+    }
+
+    /// End the game as soon as a player has no live cells left, with a
+    /// draw if neither has.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = winByElimination))]
+    pub fn win_by_elimination(&mut self, enabled: bool) {
+        self.elimination = enabled;
+    }
+
+    /// End the game at `generation`, won by whoever holds more cells.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = winAtGeneration))]
+    pub fn win_at_generation(&mut self, generation: u32) {
+        self.final_generation = Some(generation);
+    }
+
+    /// End the game once a player holds at least `cells` live cells in the
+    /// `width` x `height` region with its top-left corner at (`row`,
+    /// `col`). If both do, the one holding more wins.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = winByCapture))]
+    pub fn win_by_capture(
+        &mut self,
+        row: u32,
+        col: u32,
+        width: u32,
+        height: u32,
+        cells: u32,
+    ) -> Result<(), ConvidaError> {
+        if width == 0 || height == 0 {
+            return Err(ConvidaError::EmptySize);
+        }
+        ConvidaError::check_bounds(
+            row.saturating_add(height - 1),
+            col.saturating_add(width - 1),
+            self.width(),
+            self.height(),
+        )?;
+        let region = BoundingBox {
+            row,
+            col,
+            width,
+            height,
+        };
+        self.target = Some((region, cells));
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = isOver))]
+    pub fn is_over(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    /// The player who won, or `undefined` while the game is running or
+    /// after a draw.
+    #[cfg_attr(feature = "web", wasm_bindgen(getter))]
+    pub fn winner(&self) -> Option<Player> {
+        self.outcome.and_then(|(winner, _)| winner)
+    }
+
+    /// Deliver events, including `game-over`, to `handler` as with
+    /// `Universe.onEvent`. Pass `null` to unregister.
+    #[cfg(feature = "web")]
+    #[wasm_bindgen(js_name = onEvent)]
+    pub fn on_event(&mut self, handler: Option<Function>) {
+        self.universe.on_event(handler);
+    }
+
+    pub fn score(&self) -> Score {
+        let count = |player: Player| self.owners.iter().filter(|&&o| o == player as u8).count();
+        Score {
+            player_one: count(Player::One) as u32,
+            player_two: count(Player::Two) as u32,
+        }
+    }
+
+    /// The player with the higher score, or `undefined` on a draw.
+    pub fn leader(&self) -> Option<Player> {
+        let score = self.score();
+        match score.player_one.cmp(&score.player_two) {
+            std::cmp::Ordering::Greater => Some(Player::One),
+            std::cmp::Ordering::Less => Some(Player::Two),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+}
+
+impl Versus {
+    pub fn universe(&self) -> &Universe {
+        &self.universe
+    }
+
+    /// Bring a cell to life for `player`, outside the turns and budget.
+    pub(crate) fn claim(&mut self, player: Player, row: u32, col: u32) {
+        let idx = self.universe.get_index(row, col);
+        self.universe.cells[idx] = Cell::Alive;
+        self.owners[idx] = player as u8;
+    }
+
+    /// How the game was decided, once it is over.
+    pub fn outcome(&self) -> Option<(Option<Player>, WinCondition)> {
+        self.outcome
+    }
+
+    /// Collect events in a queue drained by `take_events`.
+    pub fn queue_events(&mut self, enabled: bool) {
+        self.universe.queue_events(enabled);
+    }
+
+    pub fn take_events(&mut self) -> Vec<Event> {
+        self.universe.take_events()
+    }
+
+    fn judge(&mut self) {
+        let score = self.score();
+        let outcome = if self.elimination && (score.player_one == 0 || score.player_two == 0) {
+            Some((self.leader(), WinCondition::Elimination))
+        } else if let Some(player) = self.capturer() {
+            Some((Some(player), WinCondition::Capture))
+        } else if self
+            .final_generation
+            .is_some_and(|generation| self.generation() >= generation)
+        {
+            Some((self.leader(), WinCondition::Generation))
+        } else {
+            None
+        };
+
+        if let Some((winner, condition)) = outcome {
+            self.outcome = outcome;
+            self.universe.events.emit(Event::GameOver {
+                generation: self.generation(),
+                winner,
+                condition,
+            });
+        }
+    }
+
+    // The player holding enough of the target region, if any.
+    fn capturer(&self) -> Option<Player> {
+        let (region, cells) = self.target?;
+        let (mut ones, mut twos) = (0, 0);
+        for row in region.row..region.row + region.height {
+            for col in region.col..region.col + region.width {
+                match self.owners[self.universe.get_index(row, col)] {
+                    1 => ones += 1,
+                    2 => twos += 1,
+                    _ => {}
+                }
+            }
+        }
+        match (ones >= cells, twos >= cells) {
+            (true, false) => Some(Player::One),
+            (false, true) => Some(Player::Two),
+            (true, true) if ones > twos => Some(Player::One),
+            (true, true) if twos > ones => Some(Player::Two),
+            _ => None,
+        }
+    }
+
+    // The owner of most of the live neighbours of (`row`, `col`) before
+    // the last tick. A birth has three parents, so there is never a tie.
+    fn majority_owner(&self, row: u32, col: u32) -> u8 {
+        let (width, height) = (i64::from(self.width()), i64::from(self.height()));
+        let mut ones = 0;
+        let mut twos = 0;
+        for dr in -1..=1 {
+            for dc in -1..=1 {
+                let (r, c) = (i64::from(row) + dr, i64::from(col) + dc);
+                let (r, c) = match self.universe.boundary {
+                    _ if (dr, dc) == (0, 0) => continue,
+                    Boundary::Torus => (r.rem_euclid(height), c.rem_euclid(width)),
+                    Boundary::Dead if r < 0 || r >= height || c < 0 || c >= width => continue,
+                    Boundary::Dead => (r, c),
+                };
+                match self.owners[(r * width + c) as usize] {
+                    1 => ones += 1,
+                    2 => twos += 1,
+                    _ => {}
+                }
+            }
+        }
+        if ones > twos {
+            Player::One as u8
+        } else {
+            Player::Two as u8
+        }
+    }
+}
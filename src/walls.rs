@@ -0,0 +1,120 @@
+// Wall cells, for mazes, containers and circuit boards within Life.
+//
+// A wall never changes. Its cell stays dead, so walls count as dead
+// neighbours, unless `set_walls_alive` makes every wall a permanently live
+// cell instead. Edits can't change a wall's cell; walls themselves are
+// drawn with `set_wall` and `toggle_wall`, or with the `GameLoop`'s wall
+// tool.
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::{Cell, ConvidaError, Universe};
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Walls {
+    /// 1 for each wall cell, empty while there are none.
+    mask: Vec<u8>,
+    alive: bool,
+}
+
+impl Walls {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.mask.is_empty()
+    }
+
+    pub(crate) fn contains(&self, idx: usize) -> bool {
+        self.mask.get(idx) == Some(&1)
+    }
+
+    // Forget every wall, for a board of a different size.
+    pub(crate) fn clear(&mut self) {
+        self.mask.clear();
+    }
+
+    /// The cell every wall holds.
+    pub(crate) fn cell(&self) -> Cell {
+        if self.alive {
+            Cell::Alive
+        } else {
+            Cell::Dead
+        }
+    }
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Universe {
+    /// Make (`row`, `col`) a wall, or turn a wall back into an ordinary dead
+    /// cell. Any other cell is left as it is.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = setWall))]
+    pub fn set_wall(&mut self, row: u32, col: u32, wall: bool) -> Result<(), ConvidaError> {
+        ConvidaError::check_bounds(row, col, self.width, self.height)?;
+        let idx = self.get_index(row, col);
+        let was_wall = self.walls.contains(idx);
+        if self.walls.mask.len() != self.cells.len() {
+            self.walls.mask = vec![0; self.cells.len()];
+        }
+        self.walls.mask[idx] = wall as u8;
+        if wall {
+            self.cells[idx] = self.walls.cell();
+        } else if was_wall {
+            self.cells[idx] = Cell::Dead;
+        }
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = toggleWall))]
+    pub fn toggle_wall(&mut self, row: u32, col: u32) -> Result<(), ConvidaError> {
+        let wall = self.is_wall(row, col);
+        self.set_wall(row, col, !wall)
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = isWall))]
+    pub fn is_wall(&self, row: u32, col: u32) -> bool {
+        row < self.height && col < self.width && self.walls.contains(self.get_index(row, col))
+    }
+
+    /// Whether walls count as live neighbours rather than dead ones.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = setWallsAlive))]
+    pub fn set_walls_alive(&mut self, alive: bool) {
+        self.walls.alive = alive;
+        let cell = self.walls.cell();
+        for (idx, &wall) in self.walls.mask.iter().enumerate() {
+            if wall == 1 {
+                self.cells[idx] = cell;
+            }
+        }
+    }
+
+    /// Turn every wall back into a dead cell.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = clearWalls))]
+    pub fn clear_walls(&mut self) {
+        for (idx, &wall) in self.walls.mask.iter().enumerate() {
+            if wall == 1 {
+                self.cells[idx] = Cell::Dead;
+            }
+        }
+        self.walls.mask.clear();
+    }
+
+    /// One byte per cell, 1 for walls, or null while there are none.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = wallMask))]
+    pub fn wall_mask(&self) -> *const u8 {
+        if self.walls.is_empty() {
+            std::ptr::null()
+        } else {
+            self.walls.mask.as_ptr()
+        }
+    }
+}
+
+impl Universe {
+    // Refuse an edit of a wall's cell.
+    pub(crate) fn check_not_wall(&self, row: u32, col: u32) -> Result<(), ConvidaError> {
+        if self.is_wall(row, col) {
+            Err(ConvidaError::NotEditable { row, col })
+        } else {
+            Ok(())
+        }
+    }
+}
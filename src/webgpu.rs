@@ -0,0 +1,390 @@
+// A WebGPU compute engine, for boards too large to tick on the CPU every
+// frame.
+//
+//     const gpu = await GpuUniverse.create(universe);
+//     gpu.tick(10);                      // queued on the GPU, returns at once
+//     const current = await gpu.sync();  // read the board back
+//
+// The cells live in two storage buffers, one `u32` per cell, and a compute
+// pass per generation reads one and writes the other. Nothing comes back
+// to the CPU until `sync`, so many generations can run between readbacks.
+// The shader only knows the plain outer-totalistic rules; when WebGPU is
+// missing, no adapter is available or the universe uses walls, frozen
+// cells, rule zones or a kernel, `create` falls back to ticking the
+// universe on the CPU behind the same interface. Per-cell `u32`s leave
+// room for continuous states later.
+//
+// Generations run on the GPU skip the CPU tick's bookkeeping: hooks,
+// events, statistics and history only see the board as of each `sync`.
+// `webgpu_model` does what the shader and the fallback do on the CPU, for
+// the differential harness.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use futures::future::{self, Either};
+use futures::Future;
+use js_sys::{Promise, Uint32Array};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::{future_to_promise, JsFuture};
+use web_sys::{
+    gpu_buffer_usage, gpu_map_mode, Gpu as GpuApi, GpuAdapter, GpuAutoLayoutMode, GpuBindGroup,
+    GpuBindGroupDescriptor, GpuBindGroupEntry, GpuBuffer, GpuBufferBinding, GpuBufferDescriptor,
+    GpuComputePipeline, GpuComputePipelineDescriptor, GpuDevice, GpuProgrammableStage,
+    GpuShaderModuleDescriptor,
+};
+
+use crate::webgpu_model;
+use crate::{Cell, Universe};
+
+// Cells per side of a workgroup, matching `@workgroup_size` below.
+const WORKGROUP_SIZE: u32 = 8;
+// The uniform block: width, height, torus, birth and survival masks,
+// padded to a multiple of 16 bytes.
+const PARAMS_SIZE: u32 = 32;
+
+const SHADER: &str = r#"
+struct Params {
+    width: u32,
+    height: u32,
+    torus: u32,
+    birth: u32,
+    survival: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> current: array<u32>;
+@group(0) @binding(2) var<storage, read_write> next: array<u32>;
+
+fn alive(row: i32, col: i32) -> u32 {
+    let height = i32(params.height);
+    let width = i32(params.width);
+    var r = row;
+    var c = col;
+    if (params.torus != 0u) {
+        r = (r + height) % height;
+        c = (c + width) % width;
+    } else if (r < 0 || r >= height || c < 0 || c >= width) {
+        return 0u;
+    }
+    return current[u32(r) * params.width + u32(c)];
+}
+
+@compute @workgroup_size(8, 8)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= params.width || id.y >= params.height) {
+        return;
+    }
+    let row = i32(id.y);
+    let col = i32(id.x);
+    var neighbors = 0u;
+    for (var dr = -1; dr <= 1; dr++) {
+        for (var dc = -1; dc <= 1; dc++) {
+            if (dr != 0 || dc != 0) {
+                neighbors += alive(row + dr, col + dc);
+            }
+        }
+    }
+    let index = id.y * params.width + id.x;
+    let mask = select(params.birth, params.survival, current[index] != 0u);
+    next[index] = (mask >> neighbors) & 1u;
+}
+"#;
+
+// The device and buffers of an accelerated universe.
+struct Gpu {
+    device: GpuDevice,
+    pipeline: GpuComputePipeline,
+    params: GpuBuffer,
+    buffers: [GpuBuffer; 2],
+    // `bind_groups[i]` reads `buffers[i]` and writes the other one.
+    bind_groups: [GpuBindGroup; 2],
+    // The buffer holding the newest generation.
+    current: usize,
+    // Generations run since the universe was last read back.
+    pending: u32,
+    // Counts the boards uploaded, so a readback of a board since replaced
+    // is thrown away.
+    uploads: u32,
+}
+
+impl Gpu {
+    fn new(device: GpuDevice, universe: &Universe) -> Result<Gpu, JsValue> {
+        let size = universe.cells.len() as f64 * 4.0;
+        let usage =
+            gpu_buffer_usage::STORAGE | gpu_buffer_usage::COPY_SRC | gpu_buffer_usage::COPY_DST;
+        let cells = || device.create_buffer(&GpuBufferDescriptor::new_with_f64(size, usage));
+        let buffers = [cells()?, cells()?];
+        let params = device.create_buffer(&GpuBufferDescriptor::new(
+            PARAMS_SIZE,
+            gpu_buffer_usage::UNIFORM | gpu_buffer_usage::COPY_DST,
+        ))?;
+
+        let module = device.create_shader_module(&GpuShaderModuleDescriptor::new(SHADER));
+        let stage = GpuProgrammableStage::new(&module);
+        stage.set_entry_point("main");
+        let pipeline = device.create_compute_pipeline(
+            &GpuComputePipelineDescriptor::new_with_gpu_auto_layout_mode(
+                GpuAutoLayoutMode::Auto,
+                &stage,
+            ),
+        );
+        let layout = pipeline.get_bind_group_layout(0);
+        let bind_group = |from: &GpuBuffer, to: &GpuBuffer| {
+            let entries = [
+                GpuBindGroupEntry::new_with_gpu_buffer_binding(0, &GpuBufferBinding::new(&params)),
+                GpuBindGroupEntry::new_with_gpu_buffer_binding(1, &GpuBufferBinding::new(from)),
+                GpuBindGroupEntry::new_with_gpu_buffer_binding(2, &GpuBufferBinding::new(to)),
+            ];
+            device.create_bind_group(&GpuBindGroupDescriptor::new(&entries, &layout))
+        };
+        let bind_groups = [
+            bind_group(&buffers[0], &buffers[1]),
+            bind_group(&buffers[1], &buffers[0]),
+        ];
+
+        let mut gpu = Gpu {
+            device,
+            pipeline,
+            params,
+            buffers,
+            bind_groups,
+            current: 0,
+            pending: 0,
+            uploads: 0,
+        };
+        gpu.upload(universe)?;
+        Ok(gpu)
+    }
+
+    // Copy the cells and rule of `universe` to the GPU.
+    fn upload(&mut self, universe: &Universe) -> Result<(), JsValue> {
+        let params: Vec<u8> = webgpu_model::params(universe)
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        let cells: Vec<u8> = universe
+            .cells
+            .iter()
+            .flat_map(|&cell| (cell as u32).to_le_bytes())
+            .collect();
+
+        let queue = self.device.queue();
+        queue.write_buffer_with_u32_and_u8_slice(&self.params, 0, &params)?;
+        queue.write_buffer_with_u32_and_u8_slice(&self.buffers[0], 0, &cells)?;
+        self.current = 0;
+        self.pending = 0;
+        self.uploads = self.uploads.wrapping_add(1);
+        Ok(())
+    }
+
+    // Queue `generations` compute passes for a `width` x `height` board.
+    fn tick(&mut self, generations: u32, width: u32, height: u32) {
+        let encoder = self.device.create_command_encoder();
+        for _ in 0..generations {
+            let pass = encoder.begin_compute_pass();
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, Some(&self.bind_groups[self.current]));
+            pass.dispatch_workgroups_with_workgroup_count_y(
+                width.div_ceil(WORKGROUP_SIZE),
+                height.div_ceil(WORKGROUP_SIZE),
+            );
+            pass.end();
+            self.current = 1 - self.current;
+        }
+        self.device.queue().submit(&[encoder.finish()]);
+        self.pending += generations;
+    }
+
+    // Copy the newest generation into a buffer the CPU can map.
+    fn read_back(&self, cells: usize) -> Result<GpuBuffer, JsValue> {
+        let size = cells as u32 * 4;
+        let staging = self.device.create_buffer(&GpuBufferDescriptor::new(
+            size,
+            gpu_buffer_usage::MAP_READ | gpu_buffer_usage::COPY_DST,
+        ))?;
+        let encoder = self.device.create_command_encoder();
+        encoder.copy_buffer_to_buffer_with_u32_and_u32_and_u32(
+            &self.buffers[self.current],
+            0,
+            &staging,
+            0,
+            size,
+        )?;
+        self.device.queue().submit(&[encoder.finish()]);
+        Ok(staging)
+    }
+}
+
+// Whether the shader can tick `universe` the way `Universe::tick` would.
+fn supported(universe: &Universe) -> bool {
+    !universe.cells.is_empty()
+        && universe.walls.is_empty()
+        && universe.frozen.is_empty()
+        && universe.rule_zones.is_empty()
+        && universe.kernel.is_none()
+}
+
+// `navigator.gpu`, where the browser has it.
+fn gpu_api() -> Option<GpuApi> {
+    let gpu = web_sys::window()?.navigator().gpu();
+    if gpu.is_undefined() {
+        None
+    } else {
+        Some(gpu)
+    }
+}
+
+struct State {
+    universe: Universe,
+    gpu: Option<Gpu>,
+}
+
+#[wasm_bindgen]
+pub struct GpuUniverse {
+    state: Rc<RefCell<State>>,
+}
+
+#[wasm_bindgen]
+impl GpuUniverse {
+    /// Resolve to a `GpuUniverse` ticking `universe` on the GPU when
+    /// WebGPU can run it, and on the CPU otherwise. Never rejects.
+    pub fn create(universe: Universe) -> Promise {
+        let api = match gpu_api() {
+            Some(api) if supported(&universe) => api,
+            _ => return Promise::resolve(&JsValue::from(GpuUniverse::on_cpu(universe))),
+        };
+        let device =
+            JsFuture::from(api.request_adapter().unchecked_into::<Promise>()).and_then(|adapter| {
+                if adapter.is_null() {
+                    return Either::A(future::ok(JsValue::NULL));
+                }
+                let adapter = adapter.unchecked_into::<GpuAdapter>();
+                Either::B(JsFuture::from(
+                    adapter.request_device().unchecked_into::<Promise>(),
+                ))
+            });
+        future_to_promise(device.then(move |device| {
+            let gpu = device
+                .ok()
+                .filter(|device| !device.is_null())
+                .and_then(|device| Gpu::new(device.unchecked_into(), &universe).ok());
+            Ok(GpuUniverse {
+                state: Rc::new(RefCell::new(State { universe, gpu })),
+            }
+            .into())
+        }))
+    }
+
+    /// Whether the generations run on the GPU.
+    #[wasm_bindgen(getter)]
+    pub fn accelerated(&self) -> bool {
+        self.state.borrow().gpu.is_some()
+    }
+
+    /// The generation reached, including the generations not read back
+    /// yet.
+    #[wasm_bindgen(getter)]
+    pub fn generation(&self) -> u32 {
+        let state = self.state.borrow();
+        let pending = state.gpu.as_ref().map_or(0, |gpu| gpu.pending);
+        state.universe.generation() + pending
+    }
+
+    /// Run `generations` generations. On the GPU they are only queued and
+    /// this returns straight away.
+    pub fn tick(&mut self, generations: u32) {
+        let mut state = self.state.borrow_mut();
+        let State { universe, gpu } = &mut *state;
+        match gpu {
+            Some(gpu) => gpu.tick(generations, universe.width(), universe.height()),
+            None => webgpu_model::cpu_tick(universe, generations),
+        }
+    }
+
+    /// Resolve to a copy of the universe once the GPU's generations have
+    /// been read back into it. A readback finishing after a newer one is
+    /// thrown away.
+    pub fn sync(&self) -> Promise {
+        let (staging, target, upload) = {
+            let state = self.state.borrow();
+            match &state.gpu {
+                Some(gpu) if gpu.pending > 0 => match gpu.read_back(state.universe.cells.len()) {
+                    Ok(staging) => (
+                        staging,
+                        state.universe.generation() + gpu.pending,
+                        gpu.uploads,
+                    ),
+                    Err(err) => return Promise::reject(&err),
+                },
+                _ => return Promise::resolve(&JsValue::from(state.universe.clone())),
+            }
+        };
+
+        let state = Rc::clone(&self.state);
+        let mapped = JsFuture::from(
+            staging
+                .map_async(gpu_map_mode::READ)
+                .unchecked_into::<Promise>(),
+        );
+        future_to_promise(mapped.and_then(move |_| {
+            let range = staging.get_mapped_range()?;
+            let cells = Uint32Array::new(&range).to_vec();
+            staging.unmap();
+            staging.destroy();
+
+            let mut state = state.borrow_mut();
+            let State { universe, gpu } = &mut *state;
+            match gpu {
+                Some(gpu) if gpu.uploads == upload && target > universe.generation => {
+                    for (cell, &value) in universe.cells.iter_mut().zip(&cells) {
+                        *cell = if value == 0 { Cell::Dead } else { Cell::Alive };
+                    }
+                    // Generations queued since stay pending.
+                    let reached = universe.generation + gpu.pending;
+                    universe.generation = target;
+                    gpu.pending = reached - target;
+                }
+                _ => {}
+            }
+            Ok(universe.clone().into())
+        }))
+    }
+
+    /// A copy of the universe as of the last `sync`.
+    pub fn universe(&self) -> Universe {
+        self.state.borrow().universe.clone()
+    }
+
+    /// Carry on from `universe` instead, e.g. after editing a copy. Any
+    /// generations not read back are dropped. A universe that has fallen
+    /// back to the CPU stays there, even if `universe` could run on the
+    /// GPU; `create` a new one to try again.
+    #[wasm_bindgen(js_name = setUniverse)]
+    pub fn set_universe(&mut self, universe: Universe) -> Result<(), JsValue> {
+        let mut state = self.state.borrow_mut();
+        let fits = state.universe.cells.len() == universe.cells.len();
+        state.gpu = match state.gpu.take() {
+            Some(mut gpu) if fits && supported(&universe) => {
+                gpu.upload(&universe)?;
+                Some(gpu)
+            }
+            Some(gpu) if supported(&universe) => Some(Gpu::new(gpu.device, &universe)?),
+            _ => None,
+        };
+        state.universe = universe;
+        Ok(())
+    }
+}
+
+impl GpuUniverse {
+    fn on_cpu(universe: Universe) -> GpuUniverse {
+        GpuUniverse {
+            state: Rc::new(RefCell::new(State {
+                universe,
+                gpu: None,
+            })),
+        }
+    }
+}
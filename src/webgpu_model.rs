@@ -0,0 +1,63 @@
+// What the WebGPU engine computes, on the CPU.
+//
+// `GpuUniverse` only builds for browsers with the unstable WebGPU
+// bindings, so the parts of it that decide the result live here, where the
+// differential harness can run them on any target: the uniform block
+// uploaded with each board, a step doing what the compute shader does with
+// it, and the tick used when there is no GPU. `step` has to be kept in line
+// with `SHADER` in `webgpu.rs`.
+
+use crate::{Boundary, Universe};
+
+/// The uniform block for `universe`: width, height, torus, birth and
+/// survival masks, padded to 32 bytes.
+pub(crate) fn params(universe: &Universe) -> [u32; 8] {
+    [
+        universe.width,
+        universe.height,
+        u32::from(universe.boundary == Boundary::Torus),
+        u32::from(universe.rule.birth),
+        u32::from(universe.rule.survival),
+        0,
+        0,
+        0,
+    ]
+}
+
+/// One compute pass: the generation after `current`, one `u32` per cell,
+/// into `next`.
+pub(crate) fn step(params: &[u32; 8], current: &[u32], next: &mut [u32]) {
+    let [width, height, torus, birth, survival, ..] = *params;
+    let (w, h) = (width as i32, height as i32);
+    let alive = |row: i32, col: i32| {
+        let (r, c) = if torus != 0 {
+            ((row + h) % h, (col + w) % w)
+        } else if row < 0 || row >= h || col < 0 || col >= w {
+            return 0;
+        } else {
+            (row, col)
+        };
+        current[r as usize * width as usize + c as usize]
+    };
+
+    for row in 0..h {
+        for col in 0..w {
+            let mut neighbors = 0;
+            for dr in -1..=1 {
+                for dc in -1..=1 {
+                    if dr != 0 || dc != 0 {
+                        neighbors += alive(row + dr, col + dc);
+                    }
+                }
+            }
+            let index = row as usize * width as usize + col as usize;
+            let mask = if current[index] != 0 { survival } else { birth };
+            next[index] = (mask >> neighbors) & 1;
+        }
+    }
+}
+
+/// How `GpuUniverse` runs `generations` generations without a GPU.
+pub(crate) fn cpu_tick(universe: &mut Universe, generations: u32) {
+    universe.tick_many(generations);
+}
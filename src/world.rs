@@ -0,0 +1,128 @@
+// World coordinates, for a plane larger than the board.
+//
+// The board is a window onto a plane addressed by signed 64-bit (row, col)
+// coordinates, with its top-left cell at the universe's origin. On a torus
+// the plane repeats the board in every direction, so any coordinate maps to
+// a cell; with dead boundaries only those inside the window do. Unlike the
+// board's own `u32` coordinates, world coordinates never wrap around on
+// overflow and can go negative, which is what the unbounded engines will
+// need.
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+use crate::{Boundary, Cell, ConvidaError, Rle, Universe};
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Universe {
+    /// The world row of the board's top row.
+    #[cfg_attr(feature = "web", wasm_bindgen(getter, js_name = originRow))]
+    pub fn origin_row(&self) -> i64 {
+        self.origin.0
+    }
+
+    /// The world column of the board's left column.
+    #[cfg_attr(feature = "web", wasm_bindgen(getter, js_name = originCol))]
+    pub fn origin_col(&self) -> i64 {
+        self.origin.1
+    }
+
+    /// Move the board's window so its top-left cell is at world (`row`,
+    /// `col`). The cells stay as they are.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = setOrigin))]
+    pub fn set_origin(&mut self, row: i64, col: i64) {
+        self.origin = (row, col);
+    }
+
+    /// The cell at world (`row`, `col`), or `undefined` outside the window
+    /// with dead boundaries.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = cellAt))]
+    pub fn cell_at(&self, row: i64, col: i64) -> Option<Cell> {
+        let (row, col) = self.to_local(row, col).ok()?;
+        Some(self.cells[self.get_index(row, col)])
+    }
+
+    /// Set the cell at world (`row`, `col`) like `toggleCell` would, to
+    /// `cell`.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = setCellAt))]
+    pub fn set_cell_at(&mut self, row: i64, col: i64, cell: Cell) -> Result<(), ConvidaError> {
+        let (row, col) = self.to_local(row, col)?;
+        if self.cells[self.get_index(row, col)] != cell {
+            self.toggle_cell(row, col)?;
+        }
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = toggleCellAt))]
+    pub fn toggle_cell_at(&mut self, row: i64, col: i64) -> Result<(), ConvidaError> {
+        let (row, col) = self.to_local(row, col)?;
+        self.toggle_cell(row, col)
+    }
+}
+
+impl Universe {
+    pub fn origin(&self) -> (i64, i64) {
+        self.origin
+    }
+
+    /// The board cell at world (`row`, `col`).
+    pub fn to_local(&self, row: i64, col: i64) -> Result<(u32, u32), ConvidaError> {
+        let (origin_row, origin_col) = self.origin;
+        // i128 so that no two i64s are too far apart to subtract.
+        let r = i128::from(row) - i128::from(origin_row);
+        let c = i128::from(col) - i128::from(origin_col);
+        let (height, width) = (i128::from(self.height), i128::from(self.width));
+        match self.boundary {
+            Boundary::Torus if width > 0 && height > 0 => {
+                Ok((r.rem_euclid(height) as u32, c.rem_euclid(width) as u32))
+            }
+            _ if (0..height).contains(&r) && (0..width).contains(&c) => Ok((r as u32, c as u32)),
+            _ => Err(ConvidaError::OutsideWindow { row, col }),
+        }
+    }
+
+    /// The world coordinates of board cell (`row`, `col`).
+    pub fn to_world(&self, row: u32, col: u32) -> (i64, i64) {
+        (
+            self.origin.0.saturating_add(i64::from(row)),
+            self.origin.1.saturating_add(i64::from(col)),
+        )
+    }
+
+    /// Stamp `pattern` with its top-left corner at world (`row`, `col`),
+    /// if the limits allow it. On a torus the pattern wraps around the
+    /// edges; with dead boundaries the cells outside the window are left
+    /// out.
+    pub fn stamp_at(&mut self, pattern: &Rle, row: i64, col: i64) -> Result<(), ConvidaError> {
+        let indices = pattern
+            .cells
+            .iter()
+            .filter_map(|&(r, c)| {
+                let (r, c) = (
+                    row.checked_add(i64::from(r))?,
+                    col.checked_add(i64::from(c))?,
+                );
+                let (r, c) = self.to_local(r, c).ok()?;
+                Some(self.get_index(r, c))
+            })
+            .collect();
+        self.bring_to_life(indices, true)
+    }
+}
+
+impl Rle {
+    /// Bring the pattern to life in `universe` with its top-left corner at
+    /// world (`row`, `col`), ignoring the limits like `place`.
+    pub fn place_at(&self, universe: &mut Universe, row: i64, col: i64) {
+        for &(r, c) in &self.cells {
+            let cell = row
+                .checked_add(i64::from(r))
+                .zip(col.checked_add(i64::from(c)))
+                .and_then(|(r, c)| universe.to_local(r, c).ok());
+            if let Some((r, c)) = cell {
+                let idx = universe.get_index(r, c);
+                universe.cells[idx] = Cell::Alive;
+            }
+        }
+    }
+}
@@ -0,0 +1,92 @@
+// Rectangular zones of the board running under their own rule.
+//
+// A cell inside a zone steps under the zone's rule, the latest zone winning
+// where they overlap, and every other cell under the universe's rule.
+// Neighbours are counted the same way everywhere, so cells on a zone's
+// border see the cells across it. Rule mutation experiments add zones of
+// their own.
+//
+//     universe.set_rule_zone(BoundingBox { row: 0, col: 0, width: 64, height: 128 }, seeds)?;
+
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "web")]
+use crate::rule::parse_rule;
+use crate::{BoundingBox, ConvidaError, LifeLike, Universe};
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct RuleZones {
+    pub(crate) zones: Vec<(BoundingBox, LifeLike)>,
+}
+
+impl RuleZones {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.zones.is_empty()
+    }
+
+    /// The rule of the latest zone holding (`row`, `col`).
+    pub(crate) fn rule_at(&self, row: u32, col: u32) -> Option<LifeLike> {
+        self.zones
+            .iter()
+            .rev()
+            .find(|(region, _)| {
+                (region.row..region.row + region.height).contains(&row)
+                    && (region.col..region.col + region.width).contains(&col)
+            })
+            .map(|&(_, rule)| rule)
+    }
+}
+
+impl Universe {
+    /// Run the cells of `region` under `rule`, on top of any earlier zones.
+    pub fn set_rule_zone(
+        &mut self,
+        region: BoundingBox,
+        rule: LifeLike,
+    ) -> Result<(), ConvidaError> {
+        ConvidaError::check_region(&region, self.width, self.height)?;
+        self.rule_zones.zones.push((region, rule));
+        Ok(())
+    }
+
+    /// The zones in the order they were added, later ones on top.
+    pub fn rule_zones(&self) -> &[(BoundingBox, LifeLike)] {
+        &self.rule_zones.zones
+    }
+}
+
+#[cfg_attr(feature = "web", wasm_bindgen)]
+impl Universe {
+    /// Remove every rule zone, including those of a mutation experiment, so
+    /// the whole board runs under the universe's rule again.
+    #[cfg_attr(feature = "web", wasm_bindgen(js_name = clearRuleZones))]
+    pub fn clear_rule_zones(&mut self) {
+        self.rule_zones.zones.clear();
+        self.forget_mutation_regions();
+    }
+}
+
+#[cfg(feature = "web")]
+#[wasm_bindgen]
+impl Universe {
+    /// Run the `width` x `height` region with its top-left corner at
+    /// (`row`, `col`) under a rule in B/S notation.
+    #[wasm_bindgen(js_name = setRuleZone)]
+    pub fn set_rule_zone_js(
+        &mut self,
+        row: u32,
+        col: u32,
+        width: u32,
+        height: u32,
+        rule: &str,
+    ) -> Result<(), ConvidaError> {
+        let region = BoundingBox {
+            row,
+            col,
+            width,
+            height,
+        };
+        self.set_rule_zone(region, parse_rule(rule)?)
+    }
+}
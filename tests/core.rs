@@ -0,0 +1,1859 @@
+//! Tests of the simulation core, run natively with `cargo test`.
+
+use convida::{
+    fuzz_engines, reference_step, Arena, Boundary, BoundingBox, Camera, Cell, ConvidaError, Conway,
+    Coupled, DecodeError, Edit, Event, FrameEncoder, FrameFormat, Kernel, KernelRule,
+    LayeredUniverse, LifeLike, Limits, LwwGrid, Message, MetadataWidth, Milestone, Multiverse,
+    OverlayMode, PatternFile, Player, Puzzle, PuzzleResult, Renderer, Rle, Rule, Score, Seed,
+    SideReport, SoupSpec, Symmetry, TextError, Universe, UniverseBuilder, Versus, WinCondition,
+};
+
+fn input_spaceship() -> Universe {
+    let mut universe = Universe::new();
+    universe.set_width(6).unwrap();
+    universe.set_height(6).unwrap();
+    universe
+        .set_cells(&[(1, 2), (2, 3), (3, 1), (3, 2), (3, 3)])
+        .unwrap();
+    universe
+}
+
+#[test]
+pub fn test_blinker_oscillates() {
+    let mut universe = Universe::new();
+    universe.set_width(5).unwrap();
+    universe.set_height(5).unwrap();
+    universe.set_cells(&[(2, 1), (2, 2), (2, 3)]).unwrap();
+    let horizontal = universe.get_cells().to_vec();
+
+    universe.tick();
+    assert_ne!(universe.get_cells(), &horizontal[..]);
+    universe.tick();
+    assert_eq!(universe.get_cells(), &horizontal[..]);
+}
+
+#[test]
+pub fn test_event_queue() {
+    let mut universe = Universe::new();
+    universe.set_width(5).unwrap();
+    universe.set_height(5).unwrap();
+    universe.set_cells(&[(2, 2)]).unwrap();
+    universe.queue_events(true);
+
+    universe.tick();
+    assert_eq!(
+        universe.take_events(),
+        vec![Event::Extinction { generation: 1 }]
+    );
+    assert!(universe.take_events().is_empty());
+}
+
+#[test]
+pub fn test_camera_zoom_at() {
+    let mut camera = Camera::new(10.0);
+    camera.pan(-50.0, -30.0);
+
+    // The cell under the cursor must stay under the cursor while zooming.
+    let (row, col) = (camera.row_at(200.0), camera.col_at(100.0));
+    camera.zoom_at(100.0, 200.0, 4.0);
+    assert_eq!(camera.scale(), 40.0);
+    assert_eq!((camera.row_at(200.0), camera.col_at(100.0)), (row, col));
+
+    let range = camera.visible_range(400, 400, 128, 128);
+    assert_eq!((range.col_start, range.col_end), (12, 23));
+    assert_eq!((range.row_start, range.row_end), (18, 28));
+}
+
+#[test]
+pub fn test_protocol_round_trip() {
+    let messages = [
+        Message::Edit(Edit {
+            seq: 7,
+            generation: 42,
+            row: 3,
+            col: 5,
+            state: Cell::Alive,
+        }),
+        Message::Checksum {
+            generation: 60,
+            checksum: 0xdead_beef_cafe,
+        },
+        Message::Resync { generation: 61 },
+    ];
+
+    for message in messages.iter() {
+        assert_eq!(Message::decode(&message.encode()).as_ref(), Ok(message));
+    }
+    assert_eq!(Message::decode(&[1, 0, 0]), Err(DecodeError::Truncated));
+}
+
+#[test]
+pub fn test_snapshot_round_trip() {
+    let universe = input_spaceship();
+    let restored = Universe::decode_snapshot(&universe.encode_snapshot()).unwrap();
+
+    assert_eq!(restored.width(), universe.width());
+    assert_eq!(restored.height(), universe.height());
+    assert_eq!(restored.get_cells(), universe.get_cells());
+
+    // Headers whose cells couldn't be indexed are refused before decoding.
+    for &(width, height) in &[(0, 6), (u32::MAX, 2)] {
+        let mut crafted = universe.encode_snapshot();
+        crafted[5..9].copy_from_slice(&u32::to_le_bytes(width));
+        crafted[9..13].copy_from_slice(&u32::to_le_bytes(height));
+        assert_eq!(
+            Universe::decode_snapshot(&crafted).err(),
+            Some(DecodeError::InvalidSize { width, height })
+        );
+    }
+}
+
+#[test]
+pub fn test_crdt_converges() {
+    let mut a = LwwGrid::new(1);
+    let mut b = LwwGrid::new(2);
+
+    // Concurrent edits of the same cell while disconnected.
+    let from_a = a.set(4, 4, Cell::Alive);
+    let from_b = b.set(4, 4, Cell::Dead);
+    let only_b = b.set(1, 2, Cell::Alive);
+
+    a.merge(&from_b);
+    a.merge(&only_b);
+    b.merge(&from_a);
+
+    // Equal counters are broken by replica id, so b wins on both sides.
+    assert_eq!(a.get(4, 4), Some(Cell::Dead));
+    assert_eq!(b.get(4, 4), Some(Cell::Dead));
+    assert_eq!(a.edits(), b.edits());
+}
+
+#[test]
+pub fn test_crdt_snapshot_keeps_evolved_edits() {
+    let mut grid = LwwGrid::new(1);
+    let mut universe = Universe::from_string(&"......\n".repeat(6)).unwrap();
+
+    // A lone cell, sent to the peer, dies on the next tick.
+    grid.set(1, 1, Cell::Alive);
+    assert_eq!(grid.take_unsent().len(), 1);
+    grid.apply_to(&mut universe);
+    universe.tick();
+    let snapshot = universe.encode_snapshot();
+
+    grid.set(4, 4, Cell::Alive);
+    universe.restore_snapshot(&snapshot).unwrap();
+    grid.apply_unsent_to(&mut universe);
+
+    assert_eq!(universe.get_cells()[6 + 1], Cell::Dead);
+    assert_eq!(universe.get_cells()[4 * 6 + 4], Cell::Alive);
+}
+
+#[test]
+pub fn test_rle_round_trip() {
+    let glider = Rle::parse("#N Glider\nx = 3, y = 3, rule = B3/S23\nbob$2bo$3o!\n").unwrap();
+    assert_eq!(glider.cells, vec![(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)]);
+
+    let universe = input_spaceship();
+    let rle = universe.to_rle();
+    assert_eq!(rle, "x = 6, y = 6, rule = B3/S23\n$2bo$3bo$b3o!\n");
+    assert_eq!(
+        Rle::parse(&rle).unwrap().to_universe().get_cells(),
+        universe.get_cells()
+    );
+}
+
+#[test]
+pub fn test_builder_boundary() {
+    let build = |boundary| {
+        let mut universe = UniverseBuilder::new()
+            .width(5)
+            .height(5)
+            .seed(Seed::Empty)
+            .boundary(boundary)
+            .build()
+            .unwrap();
+        universe.set_cells(&[(0, 1), (0, 2), (0, 3)]).unwrap();
+        universe.tick();
+        universe
+    };
+    let alive = |universe: &Universe| {
+        universe
+            .get_cells()
+            .iter()
+            .filter(|&&cell| cell == Cell::Alive)
+            .count()
+    };
+
+    assert_eq!(alive(&build(Boundary::Torus)), 3);
+    assert_eq!(alive(&build(Boundary::Dead)), 2);
+    assert_eq!(
+        UniverseBuilder::new().rule("B9/S23").build().err(),
+        Some(ConvidaError::UnsupportedRule("B9/S23".to_string()))
+    );
+}
+
+#[test]
+pub fn test_seed_patterns() {
+    let mut universe = UniverseBuilder::new()
+        .width(40)
+        .height(20)
+        .seed(Seed::Pattern("gosper-gun".to_string()))
+        .build()
+        .unwrap();
+    let alive = |universe: &Universe| {
+        universe
+            .get_cells()
+            .iter()
+            .filter(|&&cell| cell == Cell::Alive)
+            .count()
+    };
+    assert_eq!(alive(&universe), 36);
+
+    universe.reset_with(&Seed::Pulsar).unwrap();
+    assert_eq!(alive(&universe), 48);
+    universe.reset_with(&Seed::Random { density: 0.0 }).unwrap();
+    assert_eq!(alive(&universe), 0);
+    assert_eq!(
+        universe.reset_with(&Seed::Pattern("spaceship".to_string())),
+        Err(ConvidaError::UnknownPattern("spaceship".to_string()))
+    );
+    for density in [f64::NAN, -0.1, 1.5] {
+        let seed = Seed::Random { density };
+        assert!(matches!(
+            universe.reset_with(&seed),
+            Err(ConvidaError::InvalidDensity(_))
+        ));
+        assert!(UniverseBuilder::new().seed(seed).build().is_err());
+    }
+    assert_eq!(alive(&universe), 0);
+}
+
+#[test]
+pub fn test_new_with_seed_is_reproducible() {
+    let seed = Seed::Random { density: 0.5 };
+    let a = Universe::new_with_seed(32, 16, &seed, 7).unwrap();
+    let b = Universe::new_with_seed(32, 16, &seed, 7).unwrap();
+    assert_eq!(a.get_cells(), b.get_cells());
+    assert_eq!((a.width(), a.height()), (32, 16));
+
+    let mut resized = Universe::new_with_size(8, 8).unwrap();
+    resized.set_size(4, 2).unwrap();
+    assert_eq!(resized.get_cells().len(), 8);
+}
+
+#[test]
+pub fn test_text_round_trip() {
+    let mut universe = input_spaceship();
+    universe.tick();
+    let text = universe.to_string();
+    let parsed = Universe::from_string(&text).unwrap();
+    assert_eq!(parsed.get_cells(), universe.get_cells());
+    assert_eq!(parsed.to_string(), text);
+
+    let ascii: Universe = "\n.#.\r\n..#\n###\n\n".parse().unwrap();
+    assert_eq!((ascii.width(), ascii.height()), (3, 3));
+    assert_eq!(
+        Universe::from_string(".#\n#").err(),
+        Some(TextError::RaggedRow {
+            row: 1,
+            expected: 2,
+            found: 1
+        })
+    );
+    assert_eq!(
+        Universe::from_string(".x").err(),
+        Some(TextError::UnexpectedChar('x'))
+    );
+}
+
+#[test]
+pub fn test_live_cells() {
+    let universe = input_spaceship();
+    assert_eq!(universe.live_cells(), vec![1, 2, 2, 3, 3, 1, 3, 2, 3, 3]);
+    assert_eq!(universe.iter_live().count(), 5);
+}
+
+#[test]
+pub fn test_clone_and_diff() {
+    let original = input_spaceship();
+    let mut copy = original.duplicate();
+    assert_eq!(copy, original);
+
+    copy.tick();
+    assert_ne!(copy, original);
+    let changed = original.diff(&copy);
+    assert_eq!(changed, vec![8, 13, 19, 26]);
+
+    let mut undo = copy.clone();
+    for &idx in &changed {
+        let (row, col) = (idx / undo.width(), idx % undo.width());
+        undo.toggle_cell(row, col).unwrap();
+    }
+    assert_eq!(undo, original);
+}
+
+#[test]
+pub fn test_life_like_rules() {
+    let highlife: LifeLike = "b36/s23".parse().unwrap();
+    assert_eq!(highlife.to_string(), "B36/S23");
+    assert_eq!("23/36".parse::<LifeLike>(), Ok(highlife));
+    assert_eq!("B3/S23".parse::<LifeLike>(), Ok(LifeLike::CONWAY));
+    assert!("B3/S29".parse::<LifeLike>().is_err());
+
+    for neighbors in 0..=8 {
+        for &cell in &[Cell::Dead, Cell::Alive] {
+            assert_eq!(
+                LifeLike::CONWAY.next_state(cell, neighbors),
+                Conway.next_state(cell, neighbors)
+            );
+        }
+    }
+
+    // Seeds, B2/S: every live cell dies and cells with two neighbours are
+    // born.
+    struct Seeds;
+    impl Rule for Seeds {
+        fn next_state(&self, current: Cell, neighborhood: u8) -> Cell {
+            match (current, neighborhood) {
+                (Cell::Dead, 2) => Cell::Alive,
+                _ => Cell::Dead,
+            }
+        }
+    }
+    let mut universe = Universe::from_string(".....\n.##..\n.....\n.....").unwrap();
+    universe.step(&Seeds);
+    assert_eq!(
+        universe.to_string(),
+        Universe::from_string(".##..\n.....\n.##..\n.....")
+            .unwrap()
+            .to_string()
+    );
+    assert_eq!(universe.rule(), "B3/S23");
+}
+
+#[test]
+pub fn test_engines_agree() {
+    let builder = || {
+        UniverseBuilder::new()
+            .width(70)
+            .height(40)
+            .rng_seed(11)
+            .boundary(Boundary::Dead)
+    };
+    let mut universe = builder().build().unwrap();
+    for _ in 0..30 {
+        universe.tick();
+    }
+    let expected = universe.to_rle();
+
+    for engine in convida::ENGINES {
+        let mut grid = builder().engine(engine).build_grid().unwrap();
+        for _ in 0..30 {
+            convida::step(grid.as_mut(), &LifeLike::CONWAY, Boundary::Dead);
+        }
+        assert_eq!(
+            Rle::from_grid(grid.as_ref()).to_string(),
+            expected,
+            "{}",
+            engine
+        );
+        assert_eq!(grid.population(), universe.iter_live().count());
+    }
+    assert_eq!(
+        builder().engine("sparse").build().err(),
+        Some(ConvidaError::NotDense("sparse".to_string()))
+    );
+}
+
+#[test]
+pub fn test_errors_instead_of_panics() {
+    let mut universe = Universe::from_string("...\n...\n...").unwrap();
+    assert_eq!(
+        universe.toggle_cell(3, 0),
+        Err(ConvidaError::OutOfBounds {
+            row: 3,
+            col: 0,
+            width: 3,
+            height: 3
+        })
+    );
+    assert!(universe.set_cells(&[(0, 0), (0, 9)]).is_err());
+    assert_eq!(
+        universe.set_width(u32::MAX),
+        Err(ConvidaError::SizeOverflow {
+            width: u32::MAX,
+            height: 3
+        })
+    );
+    assert_eq!(universe.width(), 3);
+    assert_eq!(universe.set_width(0), Err(ConvidaError::EmptySize));
+    assert_eq!(universe.set_height(0), Err(ConvidaError::EmptySize));
+    assert_eq!(universe.set_size(5, 0), Err(ConvidaError::EmptySize));
+    assert_eq!((universe.width(), universe.height()), (3, 3));
+    assert_eq!(
+        Universe::new_with_size(0, 5).err(),
+        Some(ConvidaError::EmptySize)
+    );
+
+    // A pulsar wraps around a universe smaller than itself.
+    universe.pulsar(1, 1).unwrap();
+    universe.glider(2, 2).unwrap();
+    assert!(universe.pulsar(0, 3).is_err());
+    assert_eq!(
+        UniverseBuilder::new().rule("B3/S9").build().err(),
+        Some(ConvidaError::UnsupportedRule("B3/S9".to_string()))
+    );
+}
+
+#[test]
+pub fn test_panic_context() {
+    struct Unruly;
+
+    impl Rule for Unruly {
+        fn next_state(&self, _: Cell, _: u8) -> Cell {
+            panic!("no rule for this cell")
+        }
+    }
+
+    let mut universe = UniverseBuilder::new().width(5).height(4).build().unwrap();
+    universe.tick();
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| universe.step(&Unruly)));
+    assert!(result.is_err());
+    let message = convida::last_error().unwrap();
+    assert!(message.contains("no rule for this cell"), "{}", message);
+    assert!(
+        message.contains("in Universe::tick at generation 1 of a 5x4 universe"),
+        "{}",
+        message
+    );
+}
+
+#[test]
+pub fn test_metrics() {
+    let mut universe = Universe::from_string("......\n..#...\n...#..\n.###..").unwrap();
+    let metrics = universe.metrics();
+    assert_eq!(metrics.population, 5);
+    assert_eq!(
+        metrics.bounding_box,
+        Some(BoundingBox {
+            row: 1,
+            col: 1,
+            width: 3,
+            height: 3
+        })
+    );
+    universe.clear();
+    assert_eq!(universe.bounding_box(), None);
+    assert_eq!(universe.metrics().density, 0.0);
+}
+
+#[test]
+pub fn test_engine_matches_universe() {
+    for &boundary in &[Boundary::Torus, Boundary::Dead] {
+        let mut universe = UniverseBuilder::new()
+            .width(23)
+            .height(17)
+            .rng_seed(5)
+            .boundary(boundary)
+            .build()
+            .unwrap();
+        let mut cells = universe.get_cells().to_vec();
+        let mut next = cells.clone();
+        for _ in 0..20 {
+            universe.tick();
+            convida_engine::step(23, 17, boundary, &LifeLike::CONWAY, &cells, &mut next);
+            std::mem::swap(&mut cells, &mut next);
+        }
+        assert_eq!(universe.get_cells(), &cells[..]);
+    }
+}
+
+#[test]
+pub fn test_engines_match_reference() {
+    if let Err(divergence) = fuzz_engines(661, 40, 12) {
+        panic!("{}", divergence);
+    }
+
+    // And the reference itself, on a blinker.
+    let mut cells = vec![Cell::Dead; 25];
+    for &i in &[11, 12, 13] {
+        cells[i] = Cell::Alive;
+    }
+    let next = reference_step(5, 5, Boundary::Dead, &LifeLike::CONWAY, &cells);
+    let alive: Vec<usize> = (0..25).filter(|&i| next[i] == Cell::Alive).collect();
+    assert_eq!(alive, vec![7, 12, 17]);
+}
+
+#[test]
+pub fn test_versus() {
+    let mut game = Versus::new(8, 8, 3, 1).unwrap();
+    game.set_boundary(Boundary::Dead);
+    game.place(1, 1).unwrap();
+    game.place(1, 2).unwrap();
+    assert_eq!(
+        game.place(1, 2),
+        Err(ConvidaError::CellOccupied { row: 1, col: 2 })
+    );
+    game.place(6, 6).unwrap();
+    assert_eq!(
+        game.place(6, 5),
+        Err(ConvidaError::BudgetSpent { budget: 3 })
+    );
+    game.end_turn();
+
+    assert_eq!(game.player(), Player::Two);
+    assert_eq!(game.remaining(), 3);
+    game.place(2, 1).unwrap();
+    game.end_turn();
+
+    // (2, 2) is born from two cells of player one and one of player two,
+    // completing a block, and the lone cell at (6, 6) dies.
+    assert_eq!(game.round(), 1);
+    assert_eq!(game.generation(), 1);
+    assert_eq!(game.owner(2, 2), Ok(Some(Player::One)));
+    assert_eq!(game.owner(2, 1), Ok(Some(Player::Two)));
+    assert_eq!(game.owner(6, 6), Ok(None));
+    assert_eq!(
+        game.score(),
+        Score {
+            player_one: 3,
+            player_two: 1
+        }
+    );
+    assert_eq!(game.leader(), Some(Player::One));
+    assert_eq!(game.player(), Player::One);
+}
+
+#[test]
+pub fn test_versus_win_conditions() {
+    let block = |game: &mut Versus, row: u32, col: u32| {
+        for &(r, c) in &[(0, 0), (0, 1), (1, 0), (1, 1)] {
+            game.place(row + r, col + c).unwrap();
+        }
+    };
+
+    let mut game = Versus::new(8, 8, 4, 1).unwrap();
+    game.win_by_elimination(true);
+    game.queue_events(true);
+    block(&mut game, 1, 1);
+    game.end_turn();
+    game.place(6, 6).unwrap();
+    game.end_turn();
+    assert_eq!(game.winner(), Some(Player::One));
+    assert_eq!(
+        game.take_events().last(),
+        Some(&Event::GameOver {
+            generation: 1,
+            winner: Some(Player::One),
+            condition: WinCondition::Elimination
+        })
+    );
+    assert_eq!(game.place(5, 5), Err(ConvidaError::GameOver));
+
+    let mut game = Versus::new(8, 8, 4, 1).unwrap();
+    game.win_at_generation(2);
+    block(&mut game, 1, 1);
+    game.end_turn();
+    block(&mut game, 5, 5);
+    game.end_turn();
+    assert!(!game.is_over());
+    game.end_turn();
+    game.end_turn();
+    assert_eq!(game.outcome(), Some((None, WinCondition::Generation)));
+
+    let mut game = Versus::new(8, 8, 4, 1).unwrap();
+    game.win_by_capture(4, 4, 4, 4, 4).unwrap();
+    assert!(game.win_by_capture(6, 6, 4, 4, 4).is_err());
+    game.end_turn();
+    block(&mut game, 5, 5);
+    game.end_turn();
+    assert_eq!(
+        game.outcome(),
+        Some((Some(Player::Two), WinCondition::Capture))
+    );
+}
+
+#[test]
+pub fn test_puzzle() {
+    let mut puzzle = Puzzle::parse(
+        "#N Blinker\n\
+         #C Make a vertical blinker.\n\
+         #puzzle target o$o$o!\n\
+         #puzzle generations 2\n\
+         #puzzle budget 3\n\
+         #puzzle region 1 1 6 6\n\
+         x = 7, y = 7\n\
+         o!\n",
+    )
+    .unwrap();
+    assert_eq!(puzzle.name(), "Blinker");
+    assert_eq!(
+        puzzle.toggle_cell(0, 3),
+        Err(ConvidaError::NotEditable { row: 0, col: 3 })
+    );
+    for col in 2..5 {
+        puzzle.toggle_cell(3, col).unwrap();
+    }
+    assert_eq!(
+        puzzle.toggle_cell(5, 5),
+        Err(ConvidaError::BudgetSpent { budget: 3 })
+    );
+    assert_eq!(
+        puzzle.run(),
+        PuzzleResult {
+            solved: true,
+            generation: Some(1)
+        }
+    );
+
+    puzzle.toggle_cell(3, 4).unwrap();
+    assert_eq!(puzzle.remaining(), 1);
+    assert!(!puzzle.run().solved);
+    puzzle.reset();
+    assert_eq!(puzzle.remaining(), 3);
+
+    assert!(Puzzle::parse("#puzzle target o!\nx = 3, y = 3\n!").is_err());
+}
+
+#[test]
+pub fn test_limits() {
+    let mut universe = UniverseBuilder::new()
+        .width(12)
+        .height(12)
+        .seed(Seed::Empty)
+        .build()
+        .unwrap();
+    universe.set_limits(Limits::new().edit_budget(12).mana(5, 2));
+    universe.toggle_cell(0, 0).unwrap();
+    universe.toggle_cell(0, 0).unwrap();
+    assert_eq!(universe.remaining_budget(), Some(11));
+
+    universe.glider(1, 1).unwrap();
+    assert_eq!(universe.mana(), Some(0));
+    assert_eq!(
+        universe.glider(6, 6),
+        Err(ConvidaError::NotEnoughMana {
+            needed: 5,
+            available: 0
+        })
+    );
+    universe.tick();
+    universe.tick();
+    assert_eq!(universe.mana(), Some(4));
+
+    assert_eq!(
+        universe.set_cells(&[(9, 0), (9, 1), (9, 2), (9, 3), (9, 4), (9, 5), (9, 6)]),
+        Err(ConvidaError::BudgetSpent { budget: 12 })
+    );
+    assert_eq!(universe.remaining_budget(), Some(6));
+    assert_eq!(universe.get_cells()[9 * 12], Cell::Dead);
+
+    universe.set_limits(Limits::new().population_cap(6));
+    assert_eq!(
+        universe.set_cells(&[(9, 0), (9, 1)]),
+        Err(ConvidaError::PopulationCap { cap: 6 })
+    );
+    universe.set_cells(&[(9, 0)]).unwrap();
+    assert_eq!(universe.remaining_budget(), None);
+}
+
+#[test]
+pub fn test_arena() {
+    let mut arena = Arena::new(20, 12, 8).unwrap();
+    arena.place(Player::One, "2o$2o!", 2, 2).unwrap();
+    assert_eq!(
+        arena.place(Player::One, "2o$2o!", 2, 9),
+        Err(ConvidaError::NotEditable { row: 2, col: 10 })
+    );
+    // A lone cell dies at once, and the glider flies out of the right
+    // half without counting as a structure.
+    arena.place(Player::Two, "o!", 8, 15).unwrap();
+    arena.launch(Player::Two, "bo$2bo$3o!", 0, 12).unwrap();
+
+    let report = arena.run();
+    assert_eq!(report.generation, 8);
+    assert_eq!(
+        report.player_one,
+        SideReport {
+            structures: 1,
+            standing: 1,
+            cells: 4
+        }
+    );
+    assert_eq!(report.player_two.structures, 1);
+    assert_eq!(report.player_two.standing, 0);
+    assert_eq!(report.winner, Some(Player::One));
+    assert_eq!(
+        arena.place(Player::One, "o!", 0, 0),
+        Err(ConvidaError::BattleStarted)
+    );
+
+    arena.reset().unwrap();
+    assert_eq!(arena.round(), 1);
+    assert_eq!(arena.report().winner, None);
+}
+
+#[test]
+pub fn test_milestones() {
+    let mut universe = UniverseBuilder::new()
+        .width(24)
+        .height(24)
+        .seed(Seed::Empty)
+        .boundary(Boundary::Dead)
+        .build()
+        .unwrap();
+    // A glider heading north-west and a vertical blinker.
+    universe
+        .set_cells(&[(16, 16), (16, 17), (16, 18), (17, 16), (18, 17)])
+        .unwrap();
+    universe.set_cells(&[(2, 3), (3, 3), (4, 3)]).unwrap();
+    universe.queue_events(true);
+    universe.add_population_milestone("crowd", 7);
+    universe.add_survival_milestone("survivor", 4);
+    universe.add_glider_milestone("glider");
+    universe.add_oscillator_milestone("oscillator");
+    universe.add_milestone("huge", Milestone::PopulationAbove(100));
+
+    let mut reached = Vec::new();
+    for _ in 0..12 {
+        universe.tick();
+        for event in universe.take_events() {
+            if let Event::Milestone { generation, name } = event {
+                reached.push((generation, name));
+            }
+        }
+    }
+    let expected = [
+        (1, "crowd"),
+        (1, "glider"),
+        (1, "oscillator"),
+        (4, "survivor"),
+    ];
+    let expected: Vec<(u32, String)> = expected
+        .iter()
+        .map(|&(generation, name)| (generation, name.to_string()))
+        .collect();
+    assert_eq!(reached, expected);
+}
+
+#[test]
+pub fn test_overlay() {
+    let scene = || Universe::from_string(".....\n.....\n..##.\n.....\n").unwrap();
+    let part = Universe::from_string("##\n.#\n").unwrap();
+
+    let mut universe = scene();
+    universe.overlay(&part, 1, 2, OverlayMode::Or).unwrap();
+    assert_eq!(universe.to_string(), scene_text("..##.", "..##."));
+
+    let mut universe = scene();
+    universe.overlay(&part, 1, 2, OverlayMode::Xor).unwrap();
+    assert_eq!(universe.to_string(), scene_text("..##.", "..#.."));
+
+    let mut universe = scene();
+    universe.overlay(&part, 1, 2, OverlayMode::And).unwrap();
+    assert_eq!(universe.to_string(), scene_text(".....", "...#."));
+
+    let mut universe = scene();
+    universe
+        .overlay(&part, 1, 2, OverlayMode::Overwrite)
+        .unwrap();
+    assert_eq!(universe.to_string(), scene_text("..##.", "...#."));
+
+    // Patterns wrap around the edges like `glider`.
+    let mut universe = scene();
+    universe
+        .overlay_rle("2o$bo!", 3, 4, OverlayMode::Or)
+        .unwrap();
+    assert_eq!(
+        universe
+            .get_cells()
+            .iter()
+            .filter(|&&c| c == Cell::Alive)
+            .count(),
+        5
+    );
+    assert!(universe.overlay(&part, 4, 0, OverlayMode::Or).is_err());
+}
+
+fn scene_text(second: &str, third: &str) -> String {
+    Universe::from_string(&format!(".....\n{}\n{}\n.....\n", second, third))
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+pub fn test_set_operations() {
+    let a = Universe::from_string("##.\n...\n").unwrap();
+    let b = Universe::from_string(".##\n...\n").unwrap();
+    let live = |universe: Universe| universe.live_cells();
+
+    assert_eq!(live(a.union(&b).unwrap()), vec![0, 0, 0, 1, 0, 2]);
+    assert_eq!(live(a.intersection(&b).unwrap()), vec![0, 1]);
+    assert_eq!(live(a.difference(&b).unwrap()), vec![0, 0]);
+    assert_eq!(live(a.symmetric_difference(&b).unwrap()), vec![0, 0, 0, 2]);
+
+    let small = Universe::from_string("#\n").unwrap();
+    assert_eq!(
+        a.union(&small).err(),
+        Some(ConvidaError::SizeMismatch {
+            width: 3,
+            height: 2,
+            other_width: 1,
+            other_height: 1
+        })
+    );
+}
+
+#[test]
+pub fn test_diff_report() {
+    let a = Universe::from_string("##.\n..#\n").unwrap();
+    let b = Universe::from_string(".##\n..#\n").unwrap();
+    let report = a.diff_report(&b).unwrap();
+    assert_eq!(report.only_self.cells, vec![(0, 0)]);
+    assert_eq!(report.only_other.cells, vec![(0, 2)]);
+    assert_eq!(report.both.count, 2);
+    assert_eq!(report.both.cells, vec![(0, 1), (1, 2)]);
+    assert!(!report.is_identical());
+    assert!(a.diff_report(&a).unwrap().is_identical());
+}
+
+#[test]
+pub fn test_find_pattern() {
+    let universe = Universe::from_string(
+        "\
+.#..........
+..#.....#...
+###....#....
+.......###..
+............
+.##.........
+.###........
+..#.........
+............
+",
+    )
+    .unwrap();
+    let glider = Rle::parse("bo$2bo$3o!").unwrap();
+    assert_eq!(universe.find_pattern(&glider, false), vec![(0, 0)]);
+    assert_eq!(universe.find_pattern(&glider, true), vec![(0, 0), (1, 7)]);
+
+    // A glider touching other live cells is not a glider.
+    let blob = Universe::from_string(".#...\n..#..\n####.\n.....\n.....\n").unwrap();
+    assert!(blob.find_pattern(&glider, true).is_empty());
+}
+
+#[test]
+pub fn test_find_pattern_any_phase() {
+    let universe = Universe::from_string(
+        "\
+.......
+.#.....
+.#..###
+.#.....
+.......
+",
+    )
+    .unwrap();
+    let blinker = Rle::parse("3o!").unwrap();
+    assert_eq!(universe.find_pattern(&blinker, false), vec![(2, 4)]);
+    assert_eq!(
+        universe.find_pattern_any_phase(&blinker, false),
+        vec![(1, 1), (2, 4)]
+    );
+
+    // The second phase of a glider.
+    let universe = Universe::from_string("......\n.#.#..\n..##..\n..#...\n......\n").unwrap();
+    let glider = Rle::parse("bo$2bo$3o!").unwrap();
+    assert!(universe.find_pattern(&glider, true).is_empty());
+    assert_eq!(
+        universe.find_pattern_any_phase(&glider, false),
+        vec![(1, 1)]
+    );
+}
+
+#[test]
+pub fn test_seed_soup() {
+    let mut universe = Universe::new_with_seed(16, 12, &Seed::Empty, 0).unwrap();
+    universe.seed_soup(&SoupSpec::new().rng_seed(7)).unwrap();
+    let preset = Universe::new_with_seed(16, 12, &Seed::Random { density: 0.5 }, 7).unwrap();
+    assert_eq!(universe.to_string(), preset.to_string());
+
+    let spec = SoupSpec::new()
+        .density(0.4)
+        .region(2, 3, 6, 4)
+        .symmetry(Symmetry::Horizontal)
+        .rng_seed(1);
+    universe.seed_soup(&spec).unwrap();
+    let live: Vec<(u32, u32)> = universe.iter_live().collect();
+    assert!(!live.is_empty());
+    for &(row, col) in &live {
+        assert!((2..6).contains(&row) && (3..9).contains(&col));
+        assert!(live.contains(&(row, 11 - col)));
+    }
+
+    let mut again = universe.clone();
+    again.seed_soup(&spec).unwrap();
+    assert_eq!(again.to_string(), universe.to_string());
+
+    assert!(matches!(
+        universe.seed_soup(&SoupSpec::new().region(10, 10, 8, 8)),
+        Err(ConvidaError::OutOfBounds { .. })
+    ));
+}
+
+#[test]
+pub fn test_seed_noise() {
+    let mut universe = Universe::new_with_seed(64, 64, &Seed::Empty, 0).unwrap();
+    universe.seed_noise(12.0, 0.0, 3);
+    let live: Vec<(u32, u32)> = universe.iter_live().collect();
+    assert!((64 * 64 / 5..64 * 64 * 4 / 5).contains(&live.len()));
+
+    // Blobs rather than static: most live cells continue to the right.
+    let joined = live
+        .iter()
+        .filter(|&&(row, col)| live.contains(&(row, col + 1)))
+        .count();
+    assert!(joined * 10 > live.len() * 8);
+
+    let mut again = universe.clone();
+    again.seed_noise(12.0, 0.0, 3);
+    assert_eq!(again.to_string(), universe.to_string());
+    again.seed_noise(12.0, 0.0, 4);
+    assert_ne!(again.to_string(), universe.to_string());
+}
+
+#[test]
+pub fn test_symmetric_soups() {
+    let mut universe = Universe::new_with_seed(12, 12, &Seed::Empty, 0).unwrap();
+    for &n in &[9, 10] {
+        let m = n - 1;
+        for &symmetry in &[Symmetry::C2, Symmetry::C4, Symmetry::D4, Symmetry::D8] {
+            let spec = SoupSpec::new()
+                .region(0, 0, n, n)
+                .symmetry(symmetry)
+                .rng_seed(5);
+            universe.seed_soup(&spec).unwrap();
+            let live: Vec<(u32, u32)> = universe.iter_live().collect();
+            assert!(!live.is_empty());
+            for &(row, col) in &live {
+                let image = match symmetry {
+                    Symmetry::C2 => (m - row, m - col),
+                    Symmetry::C4 => (col, m - row),
+                    Symmetry::D4 => (m - row, col),
+                    _ => (col, row),
+                };
+                assert!(live.contains(&image), "{:?} at {:?}", symmetry, image);
+            }
+        }
+    }
+
+    let spec = SoupSpec::new().region(0, 0, 8, 6).symmetry(Symmetry::C4);
+    assert_eq!(
+        universe.seed_soup(&spec),
+        Err(ConvidaError::NotSquare {
+            width: 8,
+            height: 6
+        })
+    );
+}
+
+#[test]
+pub fn test_checkpoints() {
+    let mut universe = Universe::from_string("......\n..#...\n..#...\n..#...\n......\n").unwrap();
+    let start = universe.to_string();
+    universe.checkpoint("start");
+    universe.tick();
+    universe.checkpoint("later");
+    let later = universe.to_string();
+    universe.clear();
+
+    universe.restore("start").unwrap();
+    assert_eq!(universe.generation(), 0);
+    assert_eq!(universe.to_string(), start);
+    universe.restore("later").unwrap();
+    assert_eq!(universe.generation(), 1);
+    assert_eq!(universe.to_string(), later);
+
+    universe.checkpoint("start");
+    assert_eq!(universe.list_checkpoints(), vec!["start", "later"]);
+    universe.remove_checkpoint("later");
+    assert_eq!(
+        universe.restore("later"),
+        Err(ConvidaError::UnknownCheckpoint("later".to_string()))
+    );
+}
+
+#[test]
+pub fn test_autosave() {
+    let mut universe = Universe::from_string(".....\n.###.\n.....\n").unwrap();
+    universe.enable_autosave(3, 2);
+    assert_eq!(universe.autosaves(), vec![0]);
+    for _ in 0..7 {
+        universe.tick();
+    }
+    assert_eq!(universe.autosaves(), vec![3, 6]);
+
+    let blinker = universe.to_string();
+    universe.clear();
+    universe.restore_autosave(3).unwrap();
+    assert_eq!(universe.generation(), 3);
+    assert_ne!(universe.to_string(), blinker);
+    assert_eq!(
+        universe.restore_autosave(0),
+        Err(ConvidaError::NoAutosave { generation: 0 })
+    );
+
+    universe.disable_autosave();
+    assert!(universe.autosaves().is_empty());
+}
+
+#[test]
+pub fn test_timelapse() {
+    let mut universe =
+        Universe::new_with_seed(600, 300, &Seed::Random { density: 0.3 }, 1).unwrap();
+    universe.start_timelapse(2, 3);
+    for _ in 0..10 {
+        universe.tick();
+    }
+    let timelapse = universe.export_timelapse();
+    assert_eq!((timelapse.width(), timelapse.height()), (200, 100));
+    assert_eq!(timelapse.generations(), vec![0, 2, 4]);
+    assert_eq!(timelapse.frame(1).unwrap().len(), 200 * 100 * 4);
+    assert_ne!(timelapse.frame(0), timelapse.frame(2));
+    assert!(timelapse.frame(3).is_none());
+    assert!(timelapse.frame_png(0).unwrap().starts_with(b"\x89PNG"));
+
+    assert_eq!(universe.stop_timelapse(), timelapse);
+    assert_eq!(universe.export_timelapse().length(), 0);
+}
+
+#[test]
+pub fn test_stats_csv() {
+    let mut universe = Universe::from_string(".....\n.###.\n.....\n.....\n").unwrap();
+    universe.start_stats();
+    universe.tick();
+    universe.tick();
+    universe.stop_stats();
+    universe.tick();
+    assert_eq!(
+        universe.export_stats_csv(),
+        "generation,population,births,deaths,density,changed\n\
+         0,3,0,0,0.15,0\n\
+         1,3,2,2,0.15,4\n\
+         2,3,2,2,0.15,4\n"
+    );
+    assert_eq!(universe.stats_history().len(), 3);
+}
+
+#[test]
+pub fn test_multiverse() {
+    let mut multiverse = Multiverse::new();
+    let blinker = Universe::from_string(".....\n.....\n.###.\n.....\n.....\n").unwrap();
+    let mut highlife = blinker.clone();
+    highlife.set_rule("B36/S23".parse().unwrap());
+    assert_eq!(multiverse.add(blinker.clone()), 0);
+    assert_eq!(multiverse.add(highlife), 1);
+
+    multiverse.tick_many(3);
+    assert_eq!(multiverse.generation(1), Ok(3));
+    assert_eq!(multiverse.population(0), Ok(3));
+    let mut ticked = blinker.clone();
+    ticked.tick_many(3);
+    assert_eq!(multiverse.universe(0).unwrap(), ticked);
+    let mut expected = blinker;
+    expected.tick();
+    assert_eq!(multiverse.universe(0).unwrap(), expected);
+
+    multiverse.toggle_cell(0, 0, 0).unwrap();
+    assert_eq!(multiverse.population(0), Ok(4));
+    assert_eq!(
+        multiverse.width(2),
+        Err(ConvidaError::NoSuchUniverse { index: 2, count: 2 })
+    );
+    multiverse.remove(0).unwrap();
+    assert_eq!(multiverse.length(), 1);
+}
+
+#[test]
+pub fn test_ab_compare() {
+    let mut multiverse = Multiverse::new();
+    let spec = SoupSpec::new().density(0.4).rng_seed(11);
+    let conway = LifeLike::CONWAY;
+    let highlife = "B36/S23".parse().unwrap();
+    let report = multiverse
+        .ab_compare(32, 32, &spec, conway, highlife, 20)
+        .unwrap();
+    assert_eq!((report.index_a, report.index_b), (0, 1));
+    assert_eq!(report.generations.len(), 21);
+    assert_eq!(report.generations[0].differing, 0);
+    let diverged = report.diverged_at.unwrap();
+    assert!(diverged > 0);
+    let last = report.generations[20];
+    assert_eq!(last.population_a, multiverse.population(0).unwrap());
+    assert_eq!(last.population_b, multiverse.population(1).unwrap());
+    assert!(last.divergence > 0.0);
+
+    let same = multiverse
+        .ab_compare(32, 32, &spec, conway, conway, 20)
+        .unwrap();
+    assert_eq!(same.diverged_at, None);
+}
+
+#[test]
+pub fn test_lineage() {
+    let mut universe = Universe::from_string(".....\n.....\n.###.\n.....\n.....\n").unwrap();
+    assert!(universe.lineage_ids().is_none());
+    universe.enable_lineage();
+    let ids = universe.lineage_ids().unwrap();
+    assert_eq!(&ids[11..14], &[1, 2, 3]);
+
+    universe.tick();
+    let ids = universe.lineage_ids().unwrap().to_vec();
+    assert_eq!((ids[7], ids[12], ids[17]), (4, 2, 5));
+    assert_eq!(ids.iter().filter(|&&id| id != 0).count(), 3);
+    assert_eq!(universe.parents(4), vec![1, 2, 3]);
+    assert_eq!(universe.parents(5), vec![1, 2, 3]);
+    assert!(universe.parents(2).is_empty());
+
+    universe.tick();
+    let ids = universe.lineage_ids().unwrap();
+    assert_eq!(&ids[11..14], &[6, 2, 7]);
+    assert_eq!(universe.parents(6), vec![4, 2, 5]);
+}
+
+#[test]
+pub fn test_lineage_after_reshape() {
+    let mut universe = Universe::from_string(".....\n.###.\n.....\n.....\n").unwrap();
+    universe.enable_lineage();
+    universe.tick();
+    let born = universe.lineage_ids().unwrap()[2];
+    assert!(!universe.parents(born).is_empty());
+
+    // Turned on its side the board has as many cells, none of them related.
+    let tall = Universe::from_string("....\n.#..\n.#..\n.#..\n....\n").unwrap();
+    universe.restore_snapshot(&tall.encode_snapshot()).unwrap();
+    assert_eq!(universe.lineage_ids().unwrap(), &[0; 20][..]);
+    assert!(universe.parents(born).is_empty());
+    universe.tick();
+    assert_eq!(&universe.lineage_ids().unwrap()[8..11], &[4, 2, 5]);
+}
+
+#[test]
+pub fn test_lineage_forgets_dead_families() {
+    let mut universe = Universe::new_with_seed(64, 64, &Seed::Empty, 0).unwrap();
+    universe.seed_soup(&SoupSpec::new().rng_seed(1)).unwrap();
+    universe.enable_lineage();
+    universe.tick();
+    let ids = universe.lineage_ids().unwrap();
+    let born = *ids
+        .iter()
+        .find(|&&id| !universe.parents(id).is_empty())
+        .unwrap();
+
+    // Kill the family, then run a new soup, which starts from new roots,
+    // long enough for the lineage to be collected.
+    universe.clear();
+    universe.tick();
+    universe.seed_soup(&SoupSpec::new().rng_seed(2)).unwrap();
+    for _ in 0..1000 {
+        universe.tick();
+    }
+    assert!(universe.parents(born).is_empty());
+    let ids = universe.lineage_ids().unwrap();
+    assert!(ids
+        .iter()
+        .any(|&id| id > born && !universe.parents(id).is_empty()));
+}
+
+#[test]
+pub fn test_provenance() {
+    let mut universe = Universe::from_string(".....\n.....\n.###.\n.....\n.....\n").unwrap();
+    let mut labels = vec![9; 25];
+    labels[11..14].copy_from_slice(&[2, 2, 1]);
+    universe.set_provenance(&labels).unwrap();
+    assert_eq!(universe.territory(9), 0);
+
+    universe.tick();
+    let labels = universe.provenance_labels().unwrap();
+    assert_eq!((labels[7], labels[12], labels[17]), (2, 2, 2));
+    assert_eq!(universe.territory(2), 3);
+    assert_eq!(universe.territory(1), 0);
+
+    let mut labels = vec![0; 25];
+    labels[11..14].copy_from_slice(&[3, 2, 1]);
+    universe.tick();
+    universe.set_provenance(&labels).unwrap();
+    universe.tick();
+    assert_eq!(universe.provenance_labels().unwrap()[7], 1);
+
+    assert_eq!(
+        universe.set_provenance(&[0; 3]),
+        Err(ConvidaError::BufferLength {
+            expected: 25,
+            actual: 3
+        })
+    );
+}
+
+#[test]
+pub fn test_rule_mutations() {
+    let mut universe = Universe::new_with_seed(24, 24, &Seed::Random { density: 0.3 }, 2).unwrap();
+    universe.start_mutations(5, 9);
+    for _ in 0..20 {
+        universe.tick();
+    }
+    let log = universe.mutation_log().mutations;
+    assert_eq!(log.len(), 4);
+    assert_eq!(log[0].generation, 5);
+    assert!(log.iter().all(|mutation| mutation.region.is_none()));
+    assert_eq!(universe.rule(), log[3].rule);
+    assert_ne!(log[0].condition, "B0");
+
+    let mut universe = Universe::new_with_seed(24, 24, &Seed::Random { density: 0.3 }, 2).unwrap();
+    let rule = universe.rule();
+    universe.start_mutations(2, 9);
+    universe.mutate_region(0, 0, 12, 24);
+    universe.tick();
+    universe.tick();
+    universe.stop_mutations();
+    universe.tick();
+    universe.tick();
+    let log = universe.mutation_log().mutations;
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].region.unwrap().width, 12);
+    assert_ne!(log[0].rule, rule);
+    assert_eq!(universe.rule(), rule);
+
+    universe.clear_mutations();
+    assert!(universe.mutation_log().mutations.is_empty());
+}
+
+#[test]
+pub fn test_rule_zones() {
+    let mut universe = Universe::from_string(
+        "\
+............
+............
+.##.....##..
+............
+............
+",
+    )
+    .unwrap();
+    let seeds = "B2/S".parse().unwrap();
+    let right = BoundingBox {
+        row: 0,
+        col: 6,
+        width: 6,
+        height: 5,
+    };
+    universe.set_rule_zone(right, seeds).unwrap();
+    assert_eq!(universe.rule_zones(), &[(right, seeds)]);
+    universe.tick();
+    let live: Vec<(u32, u32)> = universe.iter_live().collect();
+    assert_eq!(live, vec![(1, 8), (1, 9), (3, 8), (3, 9)]);
+
+    let outside = BoundingBox { col: 8, ..right };
+    assert!(universe.set_rule_zone(outside, seeds).is_err());
+    universe.clear_rule_zones();
+    assert!(universe.rule_zones().is_empty());
+}
+
+#[test]
+pub fn test_walls() {
+    let mut universe = Universe::from_string(
+        "\
+......
+......
+.###..
+......
+......
+......
+",
+    )
+    .unwrap();
+    universe.set_wall(1, 2, true).unwrap();
+    assert!(universe.is_wall(1, 2));
+    assert!(!universe.wall_mask().is_null());
+    assert!(matches!(
+        universe.toggle_cell(1, 2),
+        Err(ConvidaError::NotEditable { row: 1, col: 2 })
+    ));
+    universe.tick();
+    let live: Vec<(u32, u32)> = universe.iter_live().collect();
+    assert_eq!(live, vec![(2, 2), (3, 2)]);
+
+    universe.clear();
+    universe.set_walls_alive(true);
+    universe.tick();
+    let live: Vec<(u32, u32)> = universe.iter_live().collect();
+    assert_eq!(live, vec![(1, 2)]);
+
+    universe.clear_walls();
+    assert!(universe.wall_mask().is_null());
+    assert_eq!(universe.iter_live().count(), 0);
+
+    universe.toggle_cell(0, 0).unwrap();
+    universe.set_wall(0, 0, false).unwrap();
+    assert_eq!(universe.iter_live().count(), 1);
+    universe.set_wall(0, 0, true).unwrap();
+    universe.set_wall(0, 0, false).unwrap();
+    assert_eq!(universe.iter_live().count(), 0);
+}
+
+#[test]
+pub fn test_frozen_regions() {
+    let mut universe = Universe::from_string(
+        "\
+......
+......
+.###..
+......
+......
+......
+",
+    )
+    .unwrap();
+    let row = BoundingBox {
+        row: 2,
+        col: 1,
+        width: 3,
+        height: 1,
+    };
+    universe.freeze_region(row).unwrap();
+    assert!(universe.is_frozen(2, 3));
+    universe.tick();
+    let live: Vec<(u32, u32)> = universe.iter_live().collect();
+    assert_eq!(live, vec![(1, 2), (2, 1), (2, 2), (2, 3), (3, 2)]);
+
+    assert!(universe
+        .freeze_region(BoundingBox { width: 0, ..row })
+        .is_err());
+    universe.thaw_region(row).unwrap();
+    assert!(!universe.is_frozen(2, 3));
+    assert!(universe.frozen_mask().is_null());
+}
+
+#[test]
+pub fn test_metadata() {
+    let mut universe = input_spaceship();
+    assert_eq!(universe.metadata_width(), None);
+    universe.enable_metadata(MetadataWidth::U16);
+    universe.set_metadata(1, 2, 700).unwrap();
+    assert!(universe.set_metadata(99, 0, 1).is_err());
+
+    let restored = Universe::decode_snapshot(&universe.encode_snapshot()).unwrap();
+    assert_eq!(restored.metadata_width(), Some(MetadataWidth::U16));
+    assert_eq!(restored.get_metadata(1, 2).unwrap(), 700);
+
+    universe.set_width(universe.width() + 3).unwrap();
+    assert_eq!(universe.get_metadata(1, 2).unwrap(), 700);
+    assert_eq!(universe.get_metadata(0, 2).unwrap(), 0);
+
+    universe.enable_metadata(MetadataWidth::U8);
+    universe.set_metadata(0, 0, 300).unwrap();
+    assert_eq!(universe.get_metadata(0, 0).unwrap(), 44);
+}
+
+#[test]
+pub fn test_heat_field() {
+    let mut universe = Universe::from_string(
+        "\
+......
+......
+..##..
+..##..
+......
+......
+",
+    )
+    .unwrap();
+    assert!(universe.heat().is_null());
+    universe.enable_heat(1.0, 0.5, 0.1);
+    universe.tick();
+    let heat = universe.heat_values().unwrap();
+    assert_eq!((heat[14], heat[8], heat[0]), (1.0, 0.0, 0.0));
+
+    universe.tick();
+    let heat = universe.heat_values().unwrap();
+    assert!(heat[14] > 1.0 && heat[14] < 2.0);
+    assert!(heat[8] > 0.0);
+    assert_eq!(heat[0], 0.0);
+
+    universe.disable_heat();
+    assert!(universe.heat_values().is_none());
+}
+
+#[test]
+pub fn test_layered_universe() {
+    let mut stack = LayeredUniverse::new(6, 6, 2).unwrap();
+    for layer in 0..2 {
+        for col in 1..4 {
+            stack.toggle_cell(layer, 2, col).unwrap();
+        }
+    }
+    // Layer 0 holds up the middle of layer 1 only.
+    stack.toggle_cell(0, 2, 1).unwrap();
+    stack.toggle_cell(0, 2, 3).unwrap();
+    let rule = Coupled {
+        alive: "B3/S23".parse().unwrap(),
+        dead: "B/S".parse().unwrap(),
+    };
+    stack.couple(1, 0, rule).unwrap();
+    assert!(stack.couple(2, 0, rule).is_err());
+
+    stack.tick();
+    let bottom: Vec<(u32, u32)> = stack.get(0).unwrap().iter_live().collect();
+    assert!(bottom.is_empty());
+    let top: Vec<(u32, u32)> = stack.get(1).unwrap().iter_live().collect();
+    assert_eq!(top, vec![(2, 2)]);
+
+    stack.decouple(1).unwrap();
+    stack.tick();
+    assert_eq!(stack.get(1).unwrap().iter_live().count(), 0);
+}
+
+#[test]
+pub fn test_active_region() {
+    let mut glider = Universe::new_with_size(40, 40).unwrap();
+    glider.clear();
+    glider
+        .set_cells(&[(1, 2), (2, 3), (3, 1), (3, 2), (3, 3)])
+        .unwrap();
+    let mut tracked = glider.clone();
+    tracked.enable_active_region(1);
+    assert_eq!(tracked.active_window(), None);
+    for _ in 0..60 {
+        glider.tick();
+        tracked.tick();
+        assert_eq!(tracked.get_cells(), glider.get_cells());
+    }
+    let window = tracked.active_window().unwrap();
+    assert!(window.width < 10 && window.height < 10);
+
+    tracked.toggle_cell(0, 0).unwrap();
+    assert_eq!(tracked.active_window(), None);
+
+    let mut block = Universe::from_string("......\n.##...\n.##...\n......\n").unwrap();
+    block.enable_active_region(1);
+    block.tick();
+    assert_eq!(block.active_window().map(|window| window.width), Some(0));
+    block.tick();
+    assert_eq!(block.iter_live().count(), 4);
+}
+
+#[test]
+pub fn test_kernel_rule() {
+    let mut conway = Universe::new_with_size(24, 24).unwrap();
+    let mut weighted = conway.clone();
+    let ones = vec![1.0, 1.0, 1.0, 1.0, 0.0, 1.0, 1.0, 1.0, 1.0];
+    let kernel = Kernel::new(ones, 3, 3).unwrap();
+    weighted.set_kernel_rule(KernelRule {
+        kernel,
+        birth: (3.0, 3.0),
+        survival: (2.0, 3.0),
+    });
+    for _ in 0..20 {
+        conway.tick();
+        weighted.tick();
+        assert_eq!(weighted.get_cells(), conway.get_cells());
+    }
+
+    // A cell only sees the cell two to its left, and copies it.
+    let mut shift = Universe::from_string("#.....\n......\n").unwrap();
+    let mut weights = vec![0.0; 5];
+    weights[0] = 1.0;
+    shift.set_kernel_rule(KernelRule {
+        kernel: Kernel::new(weights, 5, 1).unwrap(),
+        birth: (1.0, 1.0),
+        survival: (1.0, 1.0),
+    });
+    shift.tick();
+    let live: Vec<(u32, u32)> = shift.iter_live().collect();
+    assert_eq!(live, vec![(0, 2)]);
+
+    assert!(matches!(
+        Kernel::new(vec![1.0; 4], 2, 2),
+        Err(ConvidaError::KernelSize { .. })
+    ));
+    assert!(Kernel::new(vec![1.0; 4], 3, 3).is_err());
+    shift.clear_kernel();
+    assert!(shift.kernel_rule().is_none());
+}
+
+#[test]
+pub fn test_trails() {
+    let mut universe = Universe::from_string("......\n.#....\n......\n......\n").unwrap();
+    universe.enable_trails();
+    assert_eq!(universe.trail_age(1, 1), Some(0));
+    universe.tick();
+    universe.tick();
+    assert_eq!(universe.trail_age(1, 1), Some(2));
+    assert_eq!(universe.trail_age(0, 0), Some(255));
+
+    let mut renderer = Renderer::new(6, 4).unwrap();
+    let pixel = |renderer: &Renderer| renderer.get_pixels()[(6 + 1) * 4];
+    renderer.render(&universe);
+    assert_eq!(pixel(&renderer), 0x00);
+    renderer.set_trails(3);
+    renderer.render(&universe);
+    assert_eq!(pixel(&renderer), 0x80);
+    assert_eq!(renderer.get_pixels()[0], 0x00);
+}
+
+#[test]
+pub fn test_resized_layers() {
+    let mut universe = Universe::from_string(".#.\n.#.\n.#.\n").unwrap();
+    universe.enable_trails();
+    universe.enable_interpolation();
+    universe.set_provenance(&[1; 9]).unwrap();
+    universe.tick();
+    let bigger = Universe::new_with_seed(5, 4, &Seed::Empty, 0).unwrap();
+
+    // Every per-cell layer follows the board to its new size.
+    universe
+        .restore_snapshot(&bigger.encode_snapshot())
+        .unwrap();
+    assert_eq!(universe.trail_age(3, 4), Some(255));
+    assert_eq!(universe.previous_cell(3, 4), Some(Cell::Dead));
+    assert_eq!(universe.provenance_labels().unwrap(), &[0; 20][..]);
+    universe.set_width(2).unwrap();
+    assert_eq!(universe.provenance_labels().unwrap().len(), 8);
+    universe.tick();
+    assert_eq!(universe.trail_age(3, 1), Some(255));
+}
+
+#[test]
+pub fn test_minimap() {
+    let universe = Universe::from_string("#.##\n..##\n...#\n..#.\n").unwrap();
+    // Each pixel is shaded by the live fraction of the 2x2 block it covers.
+    let pixels = universe.render_minimap(2, 2).unwrap();
+    let shades: Vec<u8> = pixels.chunks_exact(4).map(|pixel| pixel[0]).collect();
+    assert_eq!(shades, vec![64, 255, 0, 128]);
+    assert!(pixels.chunks_exact(4).all(|pixel| pixel[3] == 0xFF));
+
+    assert!(matches!(
+        universe.render_minimap(u32::MAX, u32::MAX),
+        Err(ConvidaError::SizeOverflow { .. })
+    ));
+    assert!(Renderer::new(u32::MAX, u32::MAX).is_err());
+    let mut renderer = Renderer::new(4, 4).unwrap();
+    assert!(renderer.resize(u32::MAX, u32::MAX).is_err());
+    assert_eq!((renderer.width(), renderer.height()), (4, 4));
+    assert_eq!(renderer.get_pixels().len(), 64);
+}
+
+#[test]
+pub fn test_screenshots() {
+    let universe = Universe::from_string("#..\n.#.\n").unwrap();
+    let png = universe.to_png(4);
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    // The IHDR chunk holds the width and height.
+    assert_eq!(&png[16..24], &[0, 0, 0, 12, 0, 0, 0, 8]);
+
+    let mut renderer = Renderer::new(5, 7).unwrap();
+    renderer.render(&universe);
+    let png = renderer.screenshot();
+    assert_eq!(&png[16..24], &[0, 0, 0, 5, 0, 0, 0, 7]);
+}
+
+#[test]
+pub fn test_interned_checkpoints() {
+    let mut universe = Universe::new_with_size(64, 64).unwrap();
+    universe.clear();
+    universe
+        .set_cells(&[(10, 10), (10, 11), (11, 10), (11, 11)])
+        .unwrap();
+    universe.checkpoint("first");
+    let single = universe.checkpoint_memory();
+    assert!(single > 0);
+
+    universe.enable_autosave(1, 8);
+    for _ in 0..8 {
+        universe.tick();
+    }
+    universe.checkpoint("last");
+    // Ten snapshots of a still life only differ in their headers, and the
+    // empty stretches of one are a single block.
+    assert!(universe.checkpoint_memory() < 4 * single);
+
+    universe.toggle_cell(40, 40).unwrap();
+    universe.restore("first").unwrap();
+    assert_eq!(universe.generation(), 0);
+    universe.restore_autosave(8).unwrap();
+    assert_eq!(universe.iter_live().count(), 4);
+
+    universe.disable_autosave();
+    universe.remove_checkpoint("last");
+    assert_eq!(universe.checkpoint_memory(), single);
+}
+
+#[test]
+pub fn test_interpolated_rendering() {
+    let mut universe = Universe::from_string(".....\n.....\n.###.\n.....\n.....\n").unwrap();
+    assert!(universe.previous_cells().is_null());
+    universe.enable_interpolation();
+    universe.tick();
+    assert_eq!(universe.previous_cell(2, 1), Some(Cell::Alive));
+    assert_eq!(universe.previous_cell(1, 2), Some(Cell::Dead));
+
+    let mut renderer = Renderer::new(5, 5).unwrap();
+    let pixel =
+        |renderer: &Renderer, row: usize, col: usize| renderer.get_pixels()[(row * 5 + col) * 4];
+    renderer.render_interpolated(&universe, 0.25);
+    // Born, dying and surviving.
+    assert_eq!(pixel(&renderer, 1, 2), 0x40);
+    assert_eq!(pixel(&renderer, 2, 1), 0xBF);
+    assert_eq!(pixel(&renderer, 2, 2), 0xFF);
+    renderer.render_interpolated(&universe, 1.0);
+    assert_eq!(pixel(&renderer, 1, 2), 0xFF);
+    assert_eq!(pixel(&renderer, 2, 1), 0x00);
+}
+
+#[test]
+pub fn test_frame_encoder() {
+    let mut universe = Universe::from_string(".....\n.....\n.###.\n.....\n.....\n").unwrap();
+    let mut rle = FrameEncoder::new(FrameFormat::Rle);
+    let frame = String::from_utf8(rle.frame(&universe)).unwrap();
+    assert!(frame.starts_with("#C generation 0\n"));
+    assert_eq!(Rle::parse(&frame).unwrap().cells.len(), 3);
+
+    let mut delta = FrameEncoder::new(FrameFormat::Delta);
+    let first = delta.frame(&universe);
+    let mut replay = match Message::decode(&first).unwrap() {
+        Message::Snapshot { data, .. } => Universe::decode_snapshot(&data).unwrap(),
+        other => panic!("expected a snapshot, got {:?}", other),
+    };
+    for _ in 0..3 {
+        universe.tick();
+        let frame = delta.frame(&universe);
+        let mut bytes = &frame[..];
+        let mut edits = 0;
+        while !bytes.is_empty() {
+            match Message::decode_prefix(&mut bytes).unwrap() {
+                Message::Edit(edit) => replay.apply_edit(&edit),
+                other => panic!("expected an edit, got {:?}", other),
+            }
+            edits += 1;
+        }
+        // A blinker turns two cells off and two on each generation.
+        assert_eq!(edits, 4);
+        assert_eq!(replay.get_cells(), universe.get_cells());
+    }
+}
+
+#[test]
+pub fn test_world_coordinates() {
+    let mut universe = Universe::new_with_size(8, 8).unwrap();
+    universe.clear();
+    universe.set_origin(-100, -100);
+    universe.set_cell_at(-100, -99, Cell::Alive).unwrap();
+    assert_eq!(universe.iter_live().collect::<Vec<_>>(), vec![(0, 1)]);
+    assert_eq!(universe.to_world(0, 1), (-100, -99));
+    // The torus repeats the board across the plane.
+    assert_eq!(universe.cell_at(-92, -91), Some(Cell::Alive));
+    assert_eq!(universe.to_local(i64::MIN, i64::MAX).unwrap(), (4, 3));
+
+    // Offsets past the end of `i64` are left out rather than wrapping.
+    let glider = Rle::parse("bo$2bo$3o!").unwrap();
+    universe.stamp_at(&glider, i64::MAX, i64::MAX).unwrap();
+
+    universe.set_boundary(Boundary::Dead);
+    assert_eq!(universe.cell_at(-101, -100), None);
+    assert_eq!(
+        universe.toggle_cell_at(-92, -100),
+        Err(ConvidaError::OutsideWindow {
+            row: -92,
+            col: -100
+        })
+    );
+    universe.clear();
+    glider.place_at(&mut universe, -101, -101);
+    assert_eq!(
+        universe.iter_live().collect::<Vec<_>>(),
+        vec![(0, 1), (1, 0), (1, 1)]
+    );
+}
+
+#[test]
+pub fn test_pattern_files() {
+    let glider = Rle::parse("bo$2bo$3o!").unwrap();
+    let rle = PatternFile::parse(
+        "glider.rle",
+        "#N Glider\n#C The smallest spaceship.\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n",
+    )
+    .unwrap();
+    assert_eq!(rle.name.as_deref(), Some("Glider"));
+    assert_eq!(rle.comments, vec!["The smallest spaceship.".to_string()]);
+    assert_eq!(rle.pattern, glider);
+
+    let cells = PatternFile::parse("glider.cells", "!Name: Glider\n!\n.O\n..O\nOOO\n").unwrap();
+    assert_eq!(cells.name.as_deref(), Some("Glider"));
+    assert_eq!(cells.pattern, glider);
+
+    // Life 1.06 coordinates are (x, y) and may be negative.
+    let life106 = PatternFile::parse("glider.lif", "#Life 1.06\n0 -1\n1 0\n-1 1\n0 1\n1 1\n");
+    assert_eq!(life106.unwrap().pattern, glider);
+
+    let life105 = PatternFile::parse(
+        "GLIDER.LIF",
+        "#Life 1.05\n#D A glider\n#N\n#P -1 -1\n.*\n..*\n***\n",
+    )
+    .unwrap();
+    assert_eq!(life105.comments, vec!["A glider".to_string()]);
+    assert_eq!(life105.pattern, glider);
+
+    assert!(matches!(
+        PatternFile::parse("bad.cells", ".O\nxx\n"),
+        Err(ConvidaError::ParseError(_))
+    ));
+    assert!(PatternFile::parse("bad.lif", "#Life 1.06\n1 2 3\n").is_err());
+
+    // Imports are centred on the given cell and reported as an event.
+    let mut universe = Universe::new_with_size(10, 10).unwrap();
+    universe.clear();
+    universe.queue_events(true);
+    universe
+        .import_pattern("glider.cells", "!Name: Glider\n.O\n..O\nOOO\n", 5, 5)
+        .unwrap();
+    assert_eq!(
+        universe.iter_live().collect::<Vec<_>>(),
+        vec![(4, 5), (5, 6), (6, 4), (6, 5), (6, 6)]
+    );
+    assert_eq!(
+        universe.take_events(),
+        vec![Event::PatternImported {
+            generation: 0,
+            file: "glider.cells".to_string(),
+            name: Some("Glider".to_string()),
+            row: 5,
+            col: 5,
+            width: 3,
+            height: 3,
+            population: 5,
+        }]
+    );
+}
+
+#[test]
+pub fn test_describe() {
+    let mut universe = Universe::new_with_size(40, 40).unwrap();
+    universe.clear();
+    assert_eq!(universe.describe(), "No live cells");
+
+    let glider = Rle::parse("bo$2bo$3o!").unwrap();
+    let block = Rle::parse("2o$2o!").unwrap();
+    glider.place(&mut universe, 2, 2);
+    glider.place(&mut universe, 2, 10);
+    // Rotated a half turn, it heads northwest.
+    Rle::parse("3o$o$bo!").unwrap().place(&mut universe, 30, 30);
+    block.place(&mut universe, 20, 2);
+    Rle::parse("bo2bo$o4b$o3bo$4o!")
+        .unwrap()
+        .place(&mut universe, 12, 20);
+    assert_eq!(
+        universe.describe(),
+        "28 live cells, 2 gliders heading southeast, 1 glider heading northwest, \
+         1 lightweight spaceship heading west, 1 block"
+    );
+
+    // Recorded statistics give the trend.
+    universe.start_stats();
+    universe.clear();
+    block.place(&mut universe, 5, 5);
+    universe.tick();
+    assert_eq!(
+        universe.describe(),
+        "4 live cells, 1 block, population declining"
+    );
+
+    let mut big = Universe::new_with_size(64, 64).unwrap();
+    big.set_rule("B36/S23".parse::<LifeLike>().unwrap());
+    assert!(big.describe().ends_with(" live cells"));
+    assert!(big.describe().contains(','));
+}
+
+#[cfg(feature = "capi")]
+#[test]
+pub fn test_capi() {
+    use convida::capi::*;
+    use std::ffi::CString;
+    use std::ptr;
+
+    assert!(convida_universe_new(0, 4).is_null());
+    assert!(convida_universe_new(u32::MAX, u32::MAX).is_null());
+
+    unsafe {
+        let universe = convida_universe_new(6, 6);
+        assert!(!universe.is_null());
+        assert_eq!(convida_universe_width(universe), 6);
+        assert_eq!(convida_universe_height(universe), 6);
+
+        // A blinker, edited a cell at a time.
+        for col in 1..4 {
+            assert_eq!(convida_universe_set_cell(universe, 2, col, 1), CONVIDA_OK);
+        }
+        assert_eq!(
+            convida_universe_set_cell(universe, 6, 0, 1),
+            CONVIDA_ERR_BOUNDS
+        );
+        assert_eq!(
+            convida_universe_set_cell(universe, 0, 6, 1),
+            CONVIDA_ERR_BOUNDS
+        );
+        assert_eq!(
+            convida_universe_set_cell(ptr::null_mut(), 0, 0, 1),
+            CONVIDA_ERR_NULL
+        );
+        convida_universe_tick(universe, 1);
+        assert_eq!(convida_universe_generation(universe), 1);
+        let cells = std::slice::from_raw_parts(convida_universe_cells(universe), 36);
+        let alive: Vec<usize> = (0..36).filter(|&idx| cells[idx] == 1).collect();
+        assert_eq!(alive, vec![8, 14, 20]);
+
+        // Walls and limits refuse the edit, as they do from JS.
+        (*universe).set_wall(0, 0, true).unwrap();
+        assert_eq!(
+            convida_universe_set_cell(universe, 0, 0, 1),
+            CONVIDA_ERR_REFUSED
+        );
+        (*universe).set_limits(Limits::new().population_cap(3));
+        assert_eq!(
+            convida_universe_set_cell(universe, 5, 5, 1),
+            CONVIDA_ERR_REFUSED
+        );
+        assert_eq!(convida_universe_set_cell(universe, 1, 2, 0), CONVIDA_OK);
+        (*universe).set_limits(Limits::new());
+
+        let glider = CString::new("bo$2bo$3o!").unwrap();
+        assert_eq!(
+            convida_universe_stamp_rle(universe, glider.as_ptr(), 3, 3),
+            CONVIDA_OK
+        );
+        let cells = std::slice::from_raw_parts(convida_universe_cells(universe), 36);
+        assert_eq!(cells.iter().filter(|&&cell| cell == 1).count(), 7);
+        let bad = CString::new("3q!").unwrap();
+        assert_eq!(
+            convida_universe_stamp_rle(universe, bad.as_ptr(), 0, 0),
+            CONVIDA_ERR_PARSE
+        );
+        let invalid = [0xffu8, 0];
+        assert_eq!(
+            convida_universe_stamp_rle(universe, invalid.as_ptr().cast(), 0, 0),
+            CONVIDA_ERR_UTF8
+        );
+        assert_eq!(
+            convida_universe_stamp_rle(universe, ptr::null(), 0, 0),
+            CONVIDA_ERR_NULL
+        );
+        assert_eq!(
+            convida_universe_stamp_rle(ptr::null_mut(), glider.as_ptr(), 0, 0),
+            CONVIDA_ERR_NULL
+        );
+
+        convida_universe_free(universe);
+        convida_universe_free(ptr::null_mut());
+        assert_eq!(convida_universe_width(ptr::null()), 0);
+        assert!(convida_universe_cells(ptr::null()).is_null());
+    }
+}
@@ -2,8 +2,8 @@
 
 #![cfg(target_arch = "wasm32")]
 
-extern crate wasm_game_of_life;
-use wasm_game_of_life::Universe;
+extern crate convida;
+use convida::Universe;
 extern crate wasm_bindgen_test;
 use wasm_bindgen_test::*;
 
@@ -12,18 +12,22 @@ wasm_bindgen_test_configure!(run_in_browser);
 #[cfg(test)]
 pub fn input_spaceship() -> Universe {
     let mut universe = Universe::new();
-    universe.set_width(6);
-    universe.set_height(6);
-    universe.set_cells(&[(1, 2), (2, 3), (3, 1), (3, 2), (3, 3)]);
+    universe.set_width(6).unwrap();
+    universe.set_height(6).unwrap();
+    universe
+        .set_cells(&[(1, 2), (2, 3), (3, 1), (3, 2), (3, 3)])
+        .unwrap();
     universe
 }
 
 #[cfg(test)]
 pub fn expected_spaceship() -> Universe {
     let mut universe = Universe::new();
-    universe.set_width(6);
-    universe.set_height(6);
-    universe.set_cells(&[(2, 1), (2, 3), (3, 2), (3, 3), (4, 2)]);
+    universe.set_width(6).unwrap();
+    universe.set_height(6).unwrap();
+    universe
+        .set_cells(&[(2, 1), (2, 3), (3, 2), (3, 3), (4, 2)])
+        .unwrap();
     universe
 }
 
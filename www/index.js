@@ -9,8 +9,8 @@ const ALIVE_COLOR = "#FFFFFF";
 
 //Construct the universe, and get its width and height.
 const universe = Universe.new();
-const width = universe.width();
-const height = universe.height();
+const width = universe.width;
+const height = universe.height;
 
 // Give the canvas room for all of our cells and a 1px border
 // around each of them.
@@ -121,7 +121,7 @@ canvas.addEventListener("click", event => {
     }
 
     else {
-        universe.toggle_cell(row, col);
+        universe.toggleCell(row, col);
     }
 
     drawGrid();